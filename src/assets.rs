@@ -0,0 +1,243 @@
+/**
+ * Assets
+ *
+ * Copies the files a stylesheet refers to with url() next to its output, with a hash of their contents in the name
+ * (img/logo.png becomes img/logo.3b7e2c1a.png), so they can be cached for as long as they don't change. References
+ * are rewritten to the copies. With a CDN, they point there instead, at the path of the copy relative to the output
+ * directory (or the directory of the minify.toml when there's no output directory).
+ *
+ * With an inline limit, files up to that many bytes are inlined as data: URIs instead, saving a request. The MIME type
+ * comes from the extension, and files with one that isn't known are never inlined. SVG files are percent-encoded or
+ * base64 encoded, whichever is shorter, other files are always base64 encoded. References to a #fragment of a file
+ * aren't inlined, as the fragment can't point into a data: URI.
+ *
+ * Relative urls are resolved against the stylesheet (bundled stylesheets are already rebased onto it). Data URIs,
+ * absolute and remote urls and references to a #fragment are left alone, and the query string or fragment of a url is
+ * kept (font.svg#icon).
+ */
+use crate::bundle::{self, normalize};
+use crate::config::Config;
+use crate::css::{self, parse::tree::Stylesheet, token::tokens::CSSToken};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// A hash of the contents of a file (FNV-1a), as 8 hex digits
+fn hash(contents: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in contents {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    format!("{:08x}", (hash ^ (hash >> 32)) as u32)
+}
+
+// Puts the hash before the extension (logo.png => logo.3b7e2c1a.png)
+fn hashed(path: &str, hash: &str) -> String {
+    let (directory, name) = match path.rfind('/') {
+        Some(slash) => path.split_at(slash + 1),
+        None => ("", path),
+    };
+
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => format!("{}{}.{}{}", directory, &name[..dot], hash, &name[dot..]),
+        _ => format!("{}{}.{}", directory, name, hash),
+    }
+}
+
+// The MIME types of the files worth inlining
+const MIME_TYPES: [(&str, &str); 15] = [
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("cur", "image/x-icon"),
+    ("eot", "application/vnd.ms-fontobject"),
+    ("gif", "image/gif"),
+    ("ico", "image/x-icon"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("otf", "font/otf"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("ttf", "font/ttf"),
+    ("webp", "image/webp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+];
+
+fn base64(contents: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(contents.len().div_ceil(3) * 4);
+
+    for chunk in contents.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - index * 6) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+// Percent-encodes what can't be in a data: URI as it is: % and # (which would start the fragment), control characters
+// and everything outside ASCII
+fn percent(contents: &str) -> String {
+    let mut encoded = String::with_capacity(contents.len());
+
+    for byte in contents.bytes() {
+        if byte == b'%' || byte == b'#' || !(0x20..0x7f).contains(&byte) {
+            encoded.push_str(&format!("%{:02X}", byte));
+        } else {
+            encoded.push(byte as char);
+        }
+    }
+
+    encoded
+}
+
+// The shortest data: URI for a file, None if its type isn't known
+fn data_uri(path: &str, contents: &[u8]) -> Option<String> {
+    let extension = path.rsplit('.').next()?.to_ascii_lowercase();
+    let (_, mime) = MIME_TYPES.iter().find(|(known, _)| *known == extension)?;
+
+    let base64 = format!("data:{};base64,{}", mime, base64(contents));
+
+    // Text is only worth percent-encoding for SVG, and only when it's UTF-8
+    let text = match std::str::from_utf8(contents) {
+        Ok(text) if extension == "svg" => format!("data:{},{}", mime, percent(text.trim())),
+        _ => return Some(base64),
+    };
+
+    // Compare them as they'll be written, quotes and escapes included
+    let length = |url: &str| CSSToken::URL(url.to_string()).to_string().len();
+    if length(&text) < length(&base64) {
+        Some(text)
+    } else {
+        Some(base64)
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    let current = env::current_dir().unwrap_or_default();
+    normalize(&current.join(path))
+}
+
+// Where urls on the CDN start from
+fn root(config: &Config) -> PathBuf {
+    match (&config.out_dir, &config.file) {
+        (Some(out_dir), _) => absolute(out_dir),
+        (None, Some(file)) => absolute(file.parent().unwrap_or(Path::new(""))),
+        (None, None) => absolute(Path::new("")),
+    }
+}
+
+// Inlines an asset or copies it next to the output, returning the url to use for it
+fn asset(
+    url: &str,
+    input: &Path,
+    output: &Path,
+    config: &Config,
+) -> Result<Option<String>, String> {
+    // The query string or fragment isn't part of the file
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    let (path, suffix) = url.split_at(end);
+
+    let source = normalize(&input.parent().unwrap_or(Path::new("")).join(path));
+    let contents =
+        fs::read(&source).map_err(|e| format!("Could not read {}: {}", source.display(), e))?;
+
+    if config
+        .inline_limit
+        .is_some_and(|limit| contents.len() <= limit && !suffix.contains('#'))
+    {
+        if let Some(data) = data_uri(path, &contents) {
+            return Ok(Some(data));
+        }
+    }
+
+    if !config.assets {
+        return Ok(None);
+    }
+
+    let path = hashed(path, &hash(&contents));
+    let destination = normalize(&output.parent().unwrap_or(Path::new("")).join(&path));
+
+    // The hash is in the name, so a file that's already there is the same
+    if !destination.exists() {
+        if let Some(directory) = destination.parent() {
+            fs::create_dir_all(directory)
+                .map_err(|e| format!("Could not create {}: {}", directory.display(), e))?;
+        }
+        fs::write(&destination, &contents)
+            .map_err(|e| format!("Could not write {}: {}", destination.display(), e))?;
+    }
+
+    let cdn = match &config.cdn {
+        Some(cdn) => cdn,
+        None => return Ok(Some(format!("{}{}", path, suffix))),
+    };
+
+    let root = root(config);
+    let relative = absolute(&destination)
+        .strip_prefix(&root)
+        .map_err(|_| {
+            format!(
+                "{} is outside {}, so it has no url on the CDN",
+                destination.display(),
+                root.display()
+            )
+        })?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    Ok(Some(format!(
+        "{}/{}{}",
+        cdn.trim_end_matches('/'),
+        relative,
+        suffix
+    )))
+}
+
+/**
+ * Inlines the small assets a stylesheet refers to and copies the others next to its output with hashed names, and
+ * rewrites the references
+ */
+pub fn stylesheet(
+    stylesheet: &mut Stylesheet,
+    input: &Path,
+    output: &Path,
+    config: &Config,
+) -> Result<(), String> {
+    let mut error: Option<String> = None;
+
+    css::url::rules(&mut stylesheet.rules, &mut |url| {
+        if error.is_some() || !bundle::is_relative(url) {
+            return None;
+        }
+
+        match asset(url, input, output, config) {
+            Ok(url) => url,
+            Err(e) => {
+                error = Some(e);
+                None
+            }
+        }
+    });
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
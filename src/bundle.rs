@@ -0,0 +1,592 @@
+/**
+ * @import bundling
+ * https://www.w3.org/TR/css-cascade-5/#at-import
+ *
+ * Local imports are replaced by the rules of the imported stylesheet, recursively. Import conditions are kept by
+ * wrapping the inlined rules: media queries in @media, supports() in @supports and layer / layer() in @layer.
+ * Relative url()s in imported stylesheets are rebased onto the directory of the entry stylesheet.
+ *
+ * Remote imports (http://, https://, //) can't be inlined, so they're kept and moved to the top of the bundle, where
+ * @import rules have to be. A local import before a remote one is kept as an @import too, as inlining it would put its
+ * rules after the remote stylesheet and reverse their cascade order. When hoisting would change the order of layers (a
+ * remote import into layer(b) after a local one into layer(a)), an @layer statement before the imports declares them in
+ * their original order.
+ */
+use crate::config::Config;
+use crate::css::layer;
+use crate::css::parse::{self, tree::*};
+use crate::css::token::tokens::CSSToken;
+use crate::css::token::{self, error::ParseError};
+use crate::css::url;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+pub enum Error {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, ParseError),
+
+    // An import that couldn't be read, with the file and position of the @import
+    Import(PathBuf, i32, String, io::Error),
+
+    // The chain of files leading back to a file that's already being imported
+    Cycle(Vec<PathBuf>),
+
+    // A lint rule set to "error"
+    Lint(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Read(file, e) => write!(f, "Could not read {}: {}", file.display(), e),
+            Error::Parse(file, e) => write!(f, "Error parsing file {}!\n{}", file.display(), e),
+            Error::Import(file, at, url, e) => write!(
+                f,
+                "Could not import \"{}\" at position {} in {}: {}",
+                url,
+                at,
+                file.display(),
+                e
+            ),
+            Error::Cycle(chain) => write!(
+                f,
+                "Import cycle detected: {}",
+                chain
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect::<Vec<String>>()
+                    .join(" -> ")
+            ),
+            Error::Lint(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+// The parts of an @import prelude: url, then optional layer, supports() and media query list
+struct Import {
+    url: String,
+    layer: Option<Vec<ComponentValue>>,
+    supports: Option<Vec<ComponentValue>>,
+    media: Vec<ComponentValue>,
+}
+
+fn is_whitespace(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Whitespace))
+}
+
+fn import(rule: &AtRule) -> Option<Import> {
+    let mut values = rule.prelude.iter().filter(|value| !is_whitespace(value));
+    let url = url::value(values.next()?)?;
+
+    let mut import = Import {
+        url,
+        layer: None,
+        supports: None,
+        media: Vec::new(),
+    };
+
+    // Everything after the url, layer and supports() is the media query list
+    let mut rest = rule
+        .prelude
+        .iter()
+        .skip_while(|value| url::value(value).is_none());
+    rest.next();
+
+    let mut rest = rest.skip_while(|value| is_whitespace(value)).peekable();
+
+    if let Some(value) = rest.peek() {
+        match value {
+            ComponentValue::Token(CSSToken::Ident(name)) if name.eq_ignore_ascii_case("layer") => {
+                import.layer = Some(Vec::new());
+                rest.next();
+            }
+            ComponentValue::Function(function) if function.name.eq_ignore_ascii_case("layer") => {
+                import.layer = Some(function.value.clone());
+                rest.next();
+            }
+            _ => (),
+        }
+    }
+
+    let mut rest = rest.skip_while(|value| is_whitespace(value)).peekable();
+
+    if let Some(ComponentValue::Function(function)) = rest.peek() {
+        if function.name.eq_ignore_ascii_case("supports") {
+            import.supports = Some(function.value.clone());
+            rest.next();
+        }
+    }
+
+    import.media = rest.cloned().collect();
+    parse::consume::trim(&mut import.media);
+
+    Some(import)
+}
+
+// Where a position is in a stylesheet, as file:line:column
+fn location(file: &Path, contents: &str, at: i32) -> String {
+    let (line, column) = token::location(contents, at);
+    format!("{}:{}:{}", file.display(), line, column)
+}
+
+// The file a local import points at, ignoring any query string or fragment
+fn local(directory: &Path, url: &str) -> PathBuf {
+    let path = url.split(['?', '#']).next().unwrap_or("");
+    normalize(&directory.join(path))
+}
+
+// Whether a stylesheet imports a remote one, itself or through the local stylesheets it imports
+fn imports_remote(path: &Path, visited: &mut Vec<PathBuf>) -> bool {
+    if visited.iter().any(|visited| visited == path) {
+        return false;
+    }
+    visited.push(path.to_path_buf());
+
+    let stylesheet = match fs::read_to_string(path).map(parse::stylesheet) {
+        Ok(Ok(stylesheet)) => stylesheet,
+        _ => return false,
+    };
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    stylesheet
+        .rules
+        .iter()
+        .filter_map(|rule| match rule {
+            Rule::At(rule) if rule.name.eq_ignore_ascii_case("import") => import(rule),
+            _ => None,
+        })
+        .any(|import| {
+            !is_relative(&import.url) || imports_remote(&local(directory, &import.url), visited)
+        })
+}
+
+// Points an @import kept in the bundle at its file from the bundle's directory
+fn reimport(rule: &mut Rule, url: String) {
+    if let Rule::At(rule) = rule {
+        if let Some(value) = rule
+            .prelude
+            .iter_mut()
+            .find(|value| url::value(value).is_some())
+        {
+            *value = ComponentValue::Token(CSSToken::String(url));
+        }
+    }
+}
+
+fn is_remote(url: &str) -> bool {
+    url.starts_with("//") || url.contains("://")
+}
+
+// URLs that point at a file relative to the stylesheet
+pub fn is_relative(url: &str) -> bool {
+    !(url.is_empty()
+        || url.starts_with('/')
+        || url.starts_with('#')
+        || url.starts_with("data:")
+        || is_remote(url))
+}
+
+// Resolves . and .. without touching the filesystem
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+// The path to get from one directory to a file, both relative to the same place
+fn relative(from: &Path, to: &Path) -> String {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut parts: Vec<String> = vec![String::from(".."); from.len() - common];
+    parts.extend(
+        to[common..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy().to_string()),
+    );
+
+    parts.join("/")
+}
+
+// Rewrites the relative urls in a rule written in one directory so they work from another
+fn rebase_rule(rule: &mut Rule, from: &Path, to: &Path) {
+    if from == to {
+        return;
+    }
+
+    url::rules(std::slice::from_mut(rule), &mut |url| {
+        is_relative(url).then(|| relative(to, &normalize(&from.join(url))))
+    });
+}
+
+/**
+ * Rewrites the relative urls in rules written in one directory, so they work from another
+ */
+pub fn relocate(rules: &mut [Rule], from: &Path, to: &Path) {
+    for rule in rules.iter_mut() {
+        rebase_rule(rule, from, to);
+    }
+}
+
+// Wraps rules in an at-rule
+pub fn wrap(name: &str, prelude: Vec<ComponentValue>, rules: Vec<Rule>) -> Rule {
+    Rule::At(AtRule {
+        name: String::from(name),
+        prelude,
+        block: Some(rules.into_iter().map(BlockItem::Rule).collect()),
+        at: 0,
+    })
+}
+
+// Applies the import conditions, so @import "a.css" layer(x) supports(y) z becomes @media z { @supports y { @layer x
+fn conditions(import: Import, mut rules: Vec<Rule>) -> Vec<Rule> {
+    if let Some(layer) = import.layer {
+        rules = vec![wrap("layer", layer, rules)];
+    }
+
+    if let Some(mut supports) = import.supports {
+        parse::consume::trim(&mut supports);
+
+        // supports(display: grid) holds a declaration, which has to be written as (display: grid)
+        let mut values = supports.iter().filter(|value| !is_whitespace(value));
+        let declaration = matches!(
+            (values.next(), values.next()),
+            (
+                Some(ComponentValue::Token(CSSToken::Ident(_))),
+                Some(ComponentValue::Token(CSSToken::Colon))
+            )
+        );
+
+        if declaration {
+            supports = vec![ComponentValue::Block(SimpleBlock {
+                token: CSSToken::LeftParentheses,
+                value: supports,
+            })];
+        }
+
+        rules = vec![wrap("supports", supports, rules)];
+    }
+
+    if !import.media.is_empty() {
+        rules = vec![wrap("media", import.media, rules)];
+    }
+
+    rules
+}
+
+struct Bundler<'a> {
+    config: &'a Config,
+
+    // The directory urls are rebased onto
+    root: PathBuf,
+
+    // The files currently being imported, to detect cycles
+    stack: Vec<PathBuf>,
+
+    // Remote imports, hoisted to the top of the bundle
+    remote: Vec<Rule>,
+
+    // How many of the imports being inlined have conditions
+    conditional: usize,
+
+    // License comments from every file
+    comments: Vec<String>,
+}
+
+impl Bundler<'_> {
+    fn load(&mut self, file: &Path, contents: String) -> Result<Vec<Rule>, Error> {
+        let stylesheet = match parse::stylesheet(contents.clone()) {
+            Ok(stylesheet) => stylesheet,
+            Err(e) => return Err(Error::Parse(file.to_path_buf(), e)),
+        };
+
+        let directory = file.parent().unwrap_or(Path::new("")).to_path_buf();
+        let mut rules: Vec<Rule> = Vec::new();
+
+        self.comments.extend(stylesheet.comments);
+
+        self.stack.push(file.to_path_buf());
+
+        // Local imports before the last one that brings in a remote stylesheet have to stay imports
+        let last_remote = stylesheet.rules.iter().rposition(|rule| match rule {
+            Rule::At(rule) if rule.name.eq_ignore_ascii_case("import") => {
+                import(rule).is_some_and(|import| {
+                    !is_relative(&import.url)
+                        || imports_remote(&local(&directory, &import.url), &mut self.stack.clone())
+                })
+            }
+            _ => false,
+        });
+
+        // @import is only allowed before any other rules (except @charset and @layer statements)
+        let mut preamble = true;
+
+        for (index, mut rule) in stylesheet.rules.into_iter().enumerate() {
+            let at_rule = match &rule {
+                Rule::At(at_rule) => at_rule,
+                Rule::Qualified(_) => {
+                    preamble = false;
+                    rebase_rule(&mut rule, &directory, &self.root);
+                    rules.push(rule);
+                    continue;
+                }
+            };
+
+            // Only the entry stylesheet's @charset means anything
+            if at_rule.name.eq_ignore_ascii_case("charset") && self.stack.len() > 1 {
+                continue;
+            }
+
+            let import = match import(at_rule) {
+                Some(import) if preamble && at_rule.name.eq_ignore_ascii_case("import") => import,
+                _ => {
+                    if at_rule.name.eq_ignore_ascii_case("import") && !preamble {
+                        self.config
+                            .report(
+                                "late-import",
+                                format!(
+                                    "@import comes after other rules in {}",
+                                    location(file, &contents, at_rule.at)
+                                ),
+                            )
+                            .map_err(Error::Lint)?;
+                    }
+
+                    let statement = at_rule.block.is_none()
+                        && (at_rule.name.eq_ignore_ascii_case("layer")
+                            || at_rule.name.eq_ignore_ascii_case("charset"));
+                    preamble = preamble && statement;
+
+                    rebase_rule(&mut rule, &directory, &self.root);
+                    rules.push(rule);
+                    continue;
+                }
+            };
+
+            let at = at_rule.at;
+            let path = local(&directory, &import.url);
+            let before_remote =
+                is_relative(&import.url) && last_remote.is_some_and(|last| index < last);
+
+            if before_remote {
+                self.config
+                    .report(
+                        "late-import",
+                        format!(
+                            "\"{}\" is kept as an @import, a remote @import comes after it in {}",
+                            import.url,
+                            location(file, &contents, at)
+                        ),
+                    )
+                    .map_err(Error::Lint)?;
+
+                reimport(&mut rule, relative(&self.root, &path));
+            }
+
+            if !is_relative(&import.url) || before_remote {
+                if self.conditional > 0 {
+                    self.config
+                        .report(
+                            "conditional-remote-import",
+                            format!(
+                                "\"{}\" is imported with conditions that can't be applied to it after bundling in {}",
+                                import.url,
+                                location(file, &contents, at)
+                            ),
+                        )
+                        .map_err(Error::Lint)?;
+                }
+
+                // Kept in place too, to know the order of layers before it's hoisted
+                if self.conditional == 0 {
+                    rules.push(rule.clone());
+                }
+
+                self.remote.push(rule);
+                continue;
+            }
+
+            if self.stack.contains(&path) {
+                let mut chain = self.stack.clone();
+                chain.push(path);
+
+                return Err(Error::Cycle(chain));
+            }
+
+            let contents = match fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(e) => return Err(Error::Import(file.to_path_buf(), at, import.url, e)),
+            };
+
+            let conditional =
+                import.layer.is_some() || import.supports.is_some() || !import.media.is_empty();
+            if conditional {
+                self.conditional += 1;
+            }
+
+            let imported = self.load(&path, contents)?;
+            rules.extend(conditions(import, imported));
+
+            if conditional {
+                self.conditional -= 1;
+            }
+        }
+
+        self.stack.pop();
+
+        Ok(rules)
+    }
+}
+
+/**
+ * Bundles a stylesheet and everything it imports
+ */
+pub fn stylesheet(file: &str, config: &Config) -> Result<Stylesheet, Error> {
+    let path = normalize(Path::new(file));
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) => return Err(Error::Read(path, e)),
+    };
+
+    let mut bundler = Bundler {
+        config,
+        root: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+        stack: Vec::new(),
+        remote: Vec::new(),
+        conditional: 0,
+        comments: Vec::new(),
+    };
+
+    let mut rules = bundler.load(&path, contents)?;
+
+    let order = layer::order(&rules);
+    let remote: Vec<String> = bundler.remote.iter().map(Rule::to_string).collect();
+    rules.retain(|rule| !remote.contains(&rule.to_string()));
+
+    // @charset has to stay first, followed by the remote imports
+    let charset =
+        matches!(rules.first(), Some(Rule::At(rule)) if rule.name.eq_ignore_ascii_case("charset"));
+    let position = if charset { 1 } else { 0 };
+
+    rules.splice(position..position, bundler.remote);
+
+    if layer::order(&rules) != order {
+        rules.insert(position, layer::statement(&order));
+    }
+
+    Ok(Stylesheet {
+        rules,
+        comments: bundler.comments,
+    })
+}
+
+// Collects the local files a list of component values points at
+fn urls(values: &[ComponentValue], directory: &Path, files: &mut Vec<PathBuf>) {
+    for value in values {
+        match value {
+            ComponentValue::Function(function) if !function.name.eq_ignore_ascii_case("url") => {
+                urls(&function.value, directory, files)
+            }
+            ComponentValue::Block(block) => urls(&block.value, directory, files),
+            _ => {
+                let url = match value {
+                    ComponentValue::Token(CSSToken::String(_)) => None,
+                    value => url::value(value),
+                };
+
+                if let Some(url) = url.filter(|url| is_relative(url)) {
+                    let path = url.split(['?', '#']).next().unwrap_or("");
+                    files.push(normalize(&directory.join(path)));
+                }
+            }
+        }
+    }
+}
+
+fn item_urls(items: &[BlockItem], directory: &Path, files: &mut Vec<PathBuf>) {
+    for item in items {
+        match item {
+            BlockItem::Declaration(declaration) => urls(&declaration.value, directory, files),
+            BlockItem::Rule(Rule::Qualified(rule)) => item_urls(&rule.block, directory, files),
+            BlockItem::Rule(Rule::At(rule)) => {
+                urls(&rule.prelude, directory, files);
+
+                if let Some(block) = &rule.block {
+                    item_urls(block, directory, files);
+                }
+            }
+        }
+    }
+}
+
+/**
+ * The files a stylesheet depends on: everything it imports (recursively) and every local url() in them
+ *
+ * Files that can't be read or parsed are still listed, so they can be watched until they can.
+ */
+pub fn dependencies(file: &str) -> Vec<PathBuf> {
+    let entry = normalize(Path::new(file));
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut queue: Vec<PathBuf> = vec![entry.clone()];
+    let mut visited: Vec<PathBuf> = Vec::new();
+
+    while let Some(path) = queue.pop() {
+        visited.push(path.clone());
+
+        let stylesheet = match fs::read_to_string(&path).map(parse::stylesheet) {
+            Ok(Ok(stylesheet)) => stylesheet,
+            _ => continue,
+        };
+
+        let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let mut found: Vec<PathBuf> = Vec::new();
+
+        for rule in &stylesheet.rules {
+            match rule {
+                Rule::At(rule) if rule.name.eq_ignore_ascii_case("import") => {
+                    // Imported stylesheets are followed
+                    if let Some(import) = import(rule).filter(|import| is_relative(&import.url)) {
+                        let import = local(&directory, &import.url);
+
+                        if !visited.contains(&import) && !queue.contains(&import) {
+                            queue.push(import.clone());
+                        }
+
+                        found.push(import);
+                    }
+                }
+                Rule::At(rule) => {
+                    urls(&rule.prelude, &directory, &mut found);
+
+                    if let Some(block) = &rule.block {
+                        item_urls(block, &directory, &mut found);
+                    }
+                }
+                Rule::Qualified(rule) => item_urls(&rule.block, &directory, &mut found),
+            }
+        }
+
+        for dependency in found {
+            if dependency != entry && !files.contains(&dependency) {
+                files.push(dependency);
+            }
+        }
+    }
+
+    files
+}
@@ -0,0 +1,1055 @@
+/**
+ * Configuration (minify.toml)
+ *
+ * The configuration for an input is read from the closest minify.toml, looking in the input's directory and then each
+ * parent directory in turn. Command line options are applied on top of it, so they always win. Relative paths in the
+ * file (the output directory and excluded paths) are relative to the directory the file is in.
+ *
+ * Only the parts of TOML the settings need are understood: [tables], key = value pairs (with dotted and quoted keys),
+ * strings, integers, booleans, arrays and # comments.
+ *
+ *  bundle = true
+ *  comments = "license"
+ *  color-fallback = "both"
+ *  targets = ["chrome 100", "safari 15"]
+ *  exclude = ["vendor", "*.test.css"]
+ *
+ *  [output]
+ *  dir = "dist"
+ *  suffix = "min"
+ *
+ *  [purge]
+ *  content = ["templates/**/*.html"]
+ *  safelist = ["is-open", "/^js-/"]
+ *
+ *  [mangle]
+ *  enabled = true
+ *  reserved = ["active", "js-*"]
+ *  seed = 42
+ *
+ *  [modules]
+ *  enabled = true
+ *
+ *  [keyframes]
+ *  remove-unused = true
+ *
+ *  [keyframes.rename]
+ *  fade-in = "in"
+ *
+ *  [assets]
+ *  enabled = true
+ *  cdn = "https://cdn.example.com/static"
+ *  inline-limit = 2048
+ *
+ *  [passes]
+ *  svg-groups = false
+ *
+ *  [lint]
+ *  late-import = "error"
+ */
+use crate::css::{self, compat::Target};
+use crate::regex::Regex;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const FILE_NAME: &str = "minify.toml";
+
+// Optimizations that can be turned off, all are on by default
+pub const PASSES: [(&str, &str); 18] = [
+    ("css-background-none", "Write background: none as background: 0 0"),
+    (
+        "css-colors",
+        "Convert colors the targets don't support to hex or rgba()",
+    ),
+    (
+        "css-container",
+        "Shorten @container queries with the range syntax and without extra parentheses",
+    ),
+    (
+        "css-fallbacks",
+        "Replace properties the targets don't support with ones they do",
+    ),
+    (
+        "css-font-face",
+        "Drop invalid @font-face descriptors, merge duplicate faces and order src by format",
+    ),
+    (
+        "css-font-weight",
+        "Write the font weights normal and bold as 400 and 700",
+    ),
+    (
+        "css-initial",
+        "Replace initial with the property's initial value when that's shorter",
+    ),
+    (
+        "css-keyframes",
+        "Shorten keyframe selectors, merge identical keyframes and drop overridden @keyframes",
+    ),
+    (
+        "css-keyword-case",
+        "Lowercase keywords and units",
+    ),
+    (
+        "css-layers",
+        "Unwrap @layer rules for targets without cascade layers, keeping their order with specificity",
+    ),
+    (
+        "css-nesting",
+        "Flatten nested style rules for targets without CSS Nesting",
+    ),
+    (
+        "css-prefixes",
+        "Add and remove vendor prefixes for the targets",
+    ),
+    (
+        "css-transparent",
+        "Write transparent as #0000 for targets supporting #rgba colors",
+    ),
+    (
+        "css-zero-units",
+        "Drop the unit of zero lengths and angles where it's optional, and zero times transitions and animations don't need",
+    ),
+    (
+        "html-attributes",
+        "Unquote attribute values and shorten boolean attributes",
+    ),
+    ("html-optional-tags", "Omit optional end tags"),
+    ("svg-groups", "Collapse redundant groups"),
+    ("svg-numbers", "Shorten path data, transforms and numbers"),
+];
+
+// Problems worth reporting, with their default level
+pub const LINT_RULES: [(&str, Level, &str); 7] = [
+    (
+        "conditional-remote-import",
+        Level::Warn,
+        "A remote @import inside a conditional @import loses its conditions when bundling",
+    ),
+    (
+        "invalid-custom-property",
+        Level::Warn,
+        "A custom property's value doesn't match the syntax its @property rule gives it",
+    ),
+    (
+        "invalid-declaration",
+        Level::Warn,
+        "A declaration's value doesn't match the grammar of its property, so browsers ignore it",
+    ),
+    (
+        "invalid-font-face",
+        Level::Warn,
+        "An @font-face rule has descriptors or src entries browsers ignore, they're dropped",
+    ),
+    (
+        "invalid-property-rule",
+        Level::Warn,
+        "A @property rule browsers ignore, like one without an initial-value",
+    ),
+    (
+        "late-import",
+        Level::Warn,
+        "An @import after other rules is ignored by browsers, so it isn't bundled",
+    ),
+    (
+        "undeclared-container",
+        Level::Warn,
+        "An @container query names a container, or queries a size, no container in the stylesheet has",
+    ),
+];
+
+// Which comments survive minification
+#[derive(Clone, Copy, PartialEq)]
+pub enum Comments {
+    None,
+
+    // /*! ... */ comments, which usually hold a license
+    License,
+}
+
+// What's written for a color the targets don't support
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorFallback {
+    // Only the converted color
+    Replace,
+
+    // The converted color, then the original for browsers that understand it (color: #f00; color: oklch(...))
+    Both,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Off,
+    Warn,
+    Error,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Off => "off",
+            Level::Warn => "warn",
+            Level::Error => "error",
+        }
+    }
+}
+
+// A value from the file, or from the command line
+#[derive(Clone)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Integer(_) => "an integer",
+            Value::Boolean(_) => "a boolean",
+            Value::Array(_) => "an array",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::String(string) => write!(f, "{}", quote(string)),
+            Value::Integer(integer) => write!(f, "{}", integer),
+            Value::Boolean(boolean) => write!(f, "{}", boolean),
+            Value::Array(values) => {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+        }
+    }
+}
+
+// Writes a TOML basic string
+fn quote(string: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for ch in string.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            ch if ch.is_control() => quoted.push_str(&format!("\\u{:04X}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+pub struct Error {
+    // The file, or "command line"
+    pub source: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "{}:{}: {}", self.source, self.line, self.message)
+        } else {
+            write!(f, "{}: {}", self.source, self.message)
+        }
+    }
+}
+
+// What went wrong while parsing, and on which line
+type Failure = (usize, String);
+
+// Parses the TOML subset into a list of (line, dotted key, value)
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek();
+        self.index += 1;
+
+        if ch == Some('\n') {
+            self.line += 1;
+        }
+
+        ch
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, Failure> {
+        Err((self.line, message.to_string()))
+    }
+
+    // Skips spaces and tabs, and newlines and comments too if asked
+    fn skip(&mut self, newlines: bool) {
+        while let Some(ch) = self.peek() {
+            match ch {
+                ' ' | '\t' => (),
+                '\r' | '\n' if newlines => (),
+                '#' => {
+                    while self.peek().is_some_and(|ch| ch != '\n') {
+                        self.next();
+                    }
+                    continue;
+                }
+                _ => return,
+            }
+
+            self.next();
+        }
+    }
+
+    // Nothing but a comment may follow a key/value pair or a table header
+    fn end_of_line(&mut self) -> Result<(), Failure> {
+        self.skip(false);
+
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.chars.get(self.index + 1) == Some(&'\n') => Ok(()),
+            Some(_) => self.error("Expected the end of the line"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Failure> {
+        let quote = self.next();
+        let mut string = String::new();
+
+        if self.peek() == quote && self.chars.get(self.index + 1).copied() == quote {
+            return self.error("Multi-line strings aren't supported");
+        }
+
+        loop {
+            match self.next() {
+                None | Some('\n') => return self.error("Unterminated string"),
+                ch if ch == quote => return Ok(string),
+
+                // Literal strings ('...') have no escapes
+                Some('\\') if quote == Some('"') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some(ch @ ('u' | 'U')) => {
+                            let length = if ch == 'u' { 4 } else { 8 };
+                            let hex: String = (0..length).filter_map(|_| self.next()).collect();
+
+                            match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                                Some(ch) => ch,
+                                None => return self.error("Invalid unicode escape"),
+                            }
+                        }
+                        _ => return self.error("Invalid escape"),
+                    };
+
+                    string.push(escaped);
+                }
+                Some(ch) => string.push(ch),
+            }
+        }
+    }
+
+    // A dotted key, made of bare (a-z, 0-9, - and _) and quoted parts
+    fn key(&mut self) -> Result<String, Failure> {
+        let mut parts: Vec<String> = Vec::new();
+
+        loop {
+            self.skip(false);
+
+            let part = match self.peek() {
+                Some('"' | '\'') => self.string()?,
+                _ => {
+                    let mut part = String::new();
+                    while let Some(ch) = self.peek() {
+                        if !(ch.is_ascii_alphanumeric() || ch == '-' || ch == '_') {
+                            break;
+                        }
+                        part.push(ch);
+                        self.next();
+                    }
+
+                    if part.is_empty() {
+                        return self.error("Expected a key");
+                    }
+                    part
+                }
+            };
+
+            parts.push(part);
+            self.skip(false);
+
+            if self.peek() != Some('.') {
+                return Ok(parts.join("."));
+            }
+            self.next();
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, Failure> {
+        match self.peek() {
+            Some('"' | '\'') => Ok(Value::String(self.string()?)),
+            Some('[') => {
+                self.next();
+                let mut values: Vec<Value> = Vec::new();
+
+                loop {
+                    self.skip(true);
+                    if self.peek() == Some(']') {
+                        self.next();
+                        return Ok(Value::Array(values));
+                    }
+
+                    values.push(self.value()?);
+                    self.skip(true);
+
+                    match self.next() {
+                        Some(',') => (),
+                        Some(']') => return Ok(Value::Array(values)),
+                        _ => return self.error("Expected , or ] in array"),
+                    }
+                }
+            }
+            Some('{') => self.error("Inline tables aren't supported"),
+            _ => {
+                let mut word = String::new();
+                while let Some(ch) = self.peek() {
+                    if !(ch.is_ascii_alphanumeric() || "+-_.:".contains(ch)) {
+                        break;
+                    }
+                    word.push(ch);
+                    self.next();
+                }
+
+                match word.as_str() {
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    _ => match word.replace('_', "").parse::<i64>() {
+                        Ok(integer) if !word.is_empty() => Ok(Value::Integer(integer)),
+                        _ => self.error("Expected a string, integer, boolean or array"),
+                    },
+                }
+            }
+        }
+    }
+
+    fn document(&mut self) -> Result<Vec<(usize, String, Value)>, Failure> {
+        let mut pairs: Vec<(usize, String, Value)> = Vec::new();
+        let mut table = String::new();
+
+        loop {
+            self.skip(true);
+
+            match self.peek() {
+                None => return Ok(pairs),
+                Some('[') => {
+                    self.next();
+                    if self.peek() == Some('[') {
+                        return self.error("Arrays of tables aren't supported");
+                    }
+
+                    table = self.key()?;
+                    if self.next() != Some(']') {
+                        return self.error("Expected ] after the table name");
+                    }
+                    self.end_of_line()?;
+                }
+                Some(_) => {
+                    let line = self.line;
+                    let key = self.key()?;
+
+                    if self.next() != Some('=') {
+                        return self.error("Expected = after the key");
+                    }
+                    self.skip(false);
+
+                    let value = self.value()?;
+                    self.end_of_line()?;
+
+                    let key = if table.is_empty() {
+                        key
+                    } else {
+                        format!("{}.{}", table, key)
+                    };
+
+                    if pairs.iter().any(|(_, other, _)| *other == key) {
+                        return Err((line, format!("\"{}\" is set more than once", key)));
+                    }
+
+                    pairs.push((line, key, value));
+                }
+            }
+        }
+    }
+}
+
+/**
+ * Parses a value given on the command line, anything that isn't valid TOML is taken as a string
+ */
+pub fn value(text: &str) -> Value {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        index: 0,
+        line: 1,
+    };
+
+    match parser.value() {
+        Ok(value) if parser.index >= parser.chars.len() => value,
+        _ => Value::String(text.to_string()),
+    }
+}
+
+#[derive(Clone)]
+pub struct Config {
+    // The file the configuration was read from
+    pub file: Option<PathBuf>,
+
+    // Where minimized files go, mirroring the input directories (see inputs.rs)
+    pub out_dir: Option<PathBuf>,
+    suffix: Option<String>,
+
+    // Inline local @import rules into CSS files
+    pub bundle: bool,
+    pub comments: Comments,
+    pub color_fallback: ColorFallback,
+
+    // The browsers to generate CSS for ("chrome 100", "safari 15")
+    pub targets: Vec<Target>,
+
+    // Patterns for files and directories that aren't minimized when found by walking or matching, relative to the
+    // directory of the file
+    pub exclude: Vec<String>,
+    pub root: PathBuf,
+
+    // HTML and template files, CSS rules that can't match anything in them are removed (see css/purge.rs). Safelisted
+    // names are always kept, /slashes/ make a regular expression
+    pub content: Vec<String>,
+    pub safelist: Vec<String>,
+
+    // Rename classes and IDs in CSS files (see css/mangle.rs), except the reserved ones
+    pub mangle: bool,
+    pub reserved: Vec<String>,
+    pub seed: u64,
+
+    // Make the classes and IDs of CSS files local to them, as CSS Modules (see css/modules.rs)
+    pub modules: bool,
+
+    // Remove @keyframes rules nothing refers to, and the new names of animations (see css/keyframes.rs)
+    pub unused_keyframes: bool,
+    pub animations: Vec<(String, String)>,
+
+    // Copy the files CSS refers to next to the output with a hash in their names (see assets.rs), and the url their
+    // references start with instead, if any
+    pub assets: bool,
+    pub cdn: Option<String>,
+
+    // Files up to this many bytes are inlined as data: URIs instead
+    pub inline_limit: Option<usize>,
+
+    // Only the passes and rules set somewhere, the rest use their defaults
+    passes: Vec<(String, bool)>,
+    lint: Vec<(String, Level)>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            file: None,
+            out_dir: None,
+            suffix: None,
+            bundle: false,
+            comments: Comments::None,
+            color_fallback: ColorFallback::Replace,
+            targets: Vec::new(),
+            exclude: Vec::new(),
+            root: PathBuf::new(),
+            content: Vec::new(),
+            safelist: Vec::new(),
+            mangle: false,
+            reserved: Vec::new(),
+            seed: 0,
+            modules: false,
+            unused_keyframes: false,
+            animations: Vec::new(),
+            assets: false,
+            cdn: None,
+            inline_limit: None,
+            passes: Vec::new(),
+            lint: Vec::new(),
+        }
+    }
+}
+
+fn strings(value: Value) -> Result<Vec<String>, String> {
+    match value {
+        // A single comma separated string ("chrome 100, safari 15")
+        Value::String(string) => Ok(string
+            .split(',')
+            .map(|part| part.trim().to_string())
+            .filter(|part| !part.is_empty())
+            .collect()),
+        Value::Array(values) => values
+            .into_iter()
+            .map(|value| match value {
+                Value::String(string) => Ok(string),
+                value => Err(format!("expected strings, found {}", value.kind())),
+            })
+            .collect(),
+        value => Err(format!(
+            "expected an array of strings, found {}",
+            value.kind()
+        )),
+    }
+}
+
+/**
+ * The regular expression in a safelist entry written as /pattern/
+ */
+pub fn safelist_pattern(entry: &str) -> Option<&str> {
+    entry
+        .strip_prefix('/')
+        .and_then(|entry| entry.strip_suffix('/'))
+}
+
+impl Config {
+    /**
+     * Changes a setting, relative paths in the value are relative to base
+     */
+    pub fn set(&mut self, key: &str, value: Value, base: &Path) -> Result<(), String> {
+        let expected = |kind: &str, value: &Value| {
+            Err(format!(
+                "\"{}\" should be {}, found {}",
+                key,
+                kind,
+                value.kind()
+            ))
+        };
+
+        match (key, value) {
+            ("bundle", Value::Boolean(bundle)) => self.bundle = bundle,
+            ("comments", Value::String(comments)) => {
+                self.comments = match comments.as_str() {
+                    "none" => Comments::None,
+                    "license" => Comments::License,
+                    _ => {
+                        return Err(format!(
+                            "\"comments\" should be \"none\" or \"license\", found \"{}\"",
+                            comments
+                        ))
+                    }
+                }
+            }
+            ("color-fallback", Value::String(fallback)) => {
+                self.color_fallback = match fallback.as_str() {
+                    "replace" => ColorFallback::Replace,
+                    "both" => ColorFallback::Both,
+                    _ => {
+                        return Err(format!(
+                            "\"color-fallback\" should be \"replace\" or \"both\", found \"{}\"",
+                            fallback
+                        ))
+                    }
+                }
+            }
+            ("targets", value) => {
+                self.targets = strings(value)
+                    .map_err(|e| format!("\"targets\": {}", e))?
+                    .iter()
+                    .map(|target| Target::parse(target))
+                    .collect::<Result<Vec<Target>, String>>()?
+            }
+            ("exclude", value) => {
+                self.exclude = strings(value).map_err(|e| format!("\"exclude\": {}", e))?;
+                self.root = base.to_path_buf();
+            }
+            ("purge.content", value) => {
+                self.content = strings(value)
+                    .map_err(|e| format!("\"purge.content\": {}", e))?
+                    .iter()
+                    .map(|pattern| base.join(pattern).to_string_lossy().to_string())
+                    .collect()
+            }
+            ("purge.safelist", value) => {
+                let safelist = strings(value).map_err(|e| format!("\"purge.safelist\": {}", e))?;
+
+                for entry in &safelist {
+                    if let Some(pattern) = safelist_pattern(entry) {
+                        Regex::new(pattern)
+                            .map_err(|e| format!("\"purge.safelist\": {} in {}", e, entry))?;
+                    }
+                }
+
+                self.safelist = safelist;
+            }
+            ("mangle.enabled", Value::Boolean(mangle)) => self.mangle = mangle,
+            ("mangle.reserved", value) => {
+                self.reserved = strings(value).map_err(|e| format!("\"mangle.reserved\": {}", e))?
+            }
+            ("mangle.seed", Value::Integer(seed)) => self.seed = seed as u64,
+            ("modules.enabled", Value::Boolean(modules)) => self.modules = modules,
+            ("keyframes.remove-unused", Value::Boolean(unused)) => self.unused_keyframes = unused,
+            (key, Value::String(renamed)) if key.starts_with("keyframes.rename.") => {
+                let name = &key[17..];
+                if !css::keyframes::is_identifier(&renamed) {
+                    return Err(format!(
+                        "\"{}\" should be an animation name, found \"{}\"",
+                        key, renamed
+                    ));
+                }
+
+                self.animations.retain(|(original, _)| original != name);
+                self.animations.push((name.to_string(), renamed));
+            }
+            ("assets.enabled", Value::Boolean(assets)) => self.assets = assets,
+            // An empty url goes back to relative references
+            ("assets.cdn", Value::String(cdn)) if cdn.is_empty() => self.cdn = None,
+            ("assets.cdn", Value::String(cdn)) => self.cdn = Some(cdn),
+            ("assets.inline-limit", Value::Integer(limit)) if limit >= 0 => {
+                self.inline_limit = Some(limit as usize)
+            }
+            // --inline-limit gives a string
+            ("assets.inline-limit", Value::String(limit)) => match limit.trim().parse::<usize>() {
+                Ok(limit) => self.inline_limit = Some(limit),
+                Err(_) => {
+                    return Err(format!(
+                        "\"assets.inline-limit\" should be a number of bytes, found \"{}\"",
+                        limit
+                    ))
+                }
+            },
+            // An empty directory writes next to the inputs again
+            ("output.dir", Value::String(dir)) if dir.is_empty() => self.out_dir = None,
+            ("output.dir", Value::String(dir)) => self.out_dir = Some(base.join(dir)),
+            ("output.suffix", Value::String(suffix)) => self.suffix = Some(suffix),
+            (key, value) if key.starts_with("passes.") => {
+                let name = &key[7..];
+                if !PASSES.iter().any(|(pass, _)| *pass == name) {
+                    return Err(format!("Unknown pass \"{}\"", name));
+                }
+
+                let enabled = match value {
+                    Value::Boolean(enabled) => enabled,
+                    value => return expected("a boolean", &value),
+                };
+
+                self.passes.retain(|(pass, _)| pass != name);
+                self.passes.push((name.to_string(), enabled));
+            }
+            (key, value) if key.starts_with("lint.") => {
+                let name = &key[5..];
+                if !LINT_RULES.iter().any(|(rule, _, _)| *rule == name) {
+                    return Err(format!("Unknown lint rule \"{}\"", name));
+                }
+
+                let level = match &value {
+                    Value::String(level) if level == "off" => Level::Off,
+                    Value::String(level) if level == "warn" => Level::Warn,
+                    Value::String(level) if level == "error" => Level::Error,
+                    value => {
+                        return Err(format!(
+                            "\"{}\" should be \"off\", \"warn\" or \"error\", found {}",
+                            key, value
+                        ))
+                    }
+                };
+
+                self.lint.retain(|(rule, _)| rule != name);
+                self.lint.push((name.to_string(), level));
+            }
+            (
+                "bundle"
+                | "mangle.enabled"
+                | "modules.enabled"
+                | "keyframes.remove-unused"
+                | "assets.enabled",
+                value,
+            ) => return expected("a boolean", &value),
+            (key, value) if key.starts_with("keyframes.rename.") => {
+                return expected("a string", &value)
+            }
+            ("mangle.seed", value) => return expected("an integer", &value),
+            ("assets.inline-limit", value) => return expected("a number of bytes", &value),
+            (
+                "comments" | "color-fallback" | "output.dir" | "output.suffix" | "assets.cdn",
+                value,
+            ) => return expected("a string", &value),
+            (key, _) => return Err(format!("Unknown setting \"{}\"", key)),
+        }
+
+        Ok(())
+    }
+
+    // What's inserted before the extension (main.css => main.min.css), nothing when writing to another directory
+    pub fn suffix(&self) -> &str {
+        match &self.suffix {
+            Some(suffix) => suffix,
+            None if self.out_dir.is_some() => "",
+            None => "min",
+        }
+    }
+
+    pub fn enabled(&self, pass: &str) -> bool {
+        self.passes
+            .iter()
+            .find(|(name, _)| name == pass)
+            .is_none_or(|(_, enabled)| *enabled)
+    }
+
+    pub fn level(&self, rule: &str) -> Level {
+        match self.lint.iter().find(|(name, _)| name == rule) {
+            Some((_, level)) => *level,
+            None => LINT_RULES
+                .iter()
+                .find(|(name, _, _)| *name == rule)
+                .map_or(Level::Warn, |(_, level, _)| *level),
+        }
+    }
+
+    /**
+     * Reports a problem at the level configured for its rule, an error fails the file
+     */
+    pub fn report(&self, rule: &str, message: String) -> Result<(), String> {
+        match self.level(rule) {
+            Level::Off => Ok(()),
+            Level::Warn => {
+                eprintln!("Warning: {} [{}]", message, rule);
+                Ok(())
+            }
+            Level::Error => Err(format!("{} [{}]", message, rule)),
+        }
+    }
+
+    fn load(file: &Path) -> Result<Config, Error> {
+        let source = file.display().to_string();
+        let error = |line: usize, message: String| Error {
+            source: source.clone(),
+            line,
+            message,
+        };
+
+        let text = fs::read_to_string(file).map_err(|e| error(0, e.to_string()))?;
+
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            index: 0,
+            line: 1,
+        };
+        let pairs = parser
+            .document()
+            .map_err(|(line, message)| error(line, message))?;
+
+        let base = file.parent().unwrap_or(Path::new(""));
+        let mut config = Config {
+            file: Some(file.to_path_buf()),
+            ..Config::default()
+        };
+
+        for (line, key, value) in pairs {
+            config
+                .set(&key, value, base)
+                .map_err(|message| error(line, message))?;
+        }
+
+        Ok(config)
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file {
+            Some(file) => writeln!(
+                f,
+                "# From {}, with command line options applied",
+                file.display()
+            )?,
+            None => writeln!(
+                f,
+                "# No {} found, defaults with command line options applied",
+                FILE_NAME
+            )?,
+        }
+
+        let strings =
+            |strings: &[String]| Value::Array(strings.iter().cloned().map(Value::String).collect());
+
+        writeln!(f, "bundle = {}", self.bundle)?;
+        writeln!(
+            f,
+            "comments = \"{}\"",
+            match self.comments {
+                Comments::None => "none",
+                Comments::License => "license",
+            }
+        )?;
+        writeln!(
+            f,
+            "color-fallback = \"{}\"",
+            match self.color_fallback {
+                ColorFallback::Replace => "replace",
+                ColorFallback::Both => "both",
+            }
+        )?;
+        let targets: Vec<String> = self.targets.iter().map(|t| t.to_string()).collect();
+        writeln!(f, "targets = {}", strings(&targets))?;
+        writeln!(f, "exclude = {}", strings(&self.exclude))?;
+
+        writeln!(f, "\n[output]")?;
+        if let Some(out_dir) = &self.out_dir {
+            writeln!(f, "dir = {}", quote(&out_dir.to_string_lossy()))?;
+        }
+        writeln!(f, "suffix = {}", quote(self.suffix()))?;
+
+        writeln!(f, "\n[purge]")?;
+        writeln!(f, "content = {}", strings(&self.content))?;
+        writeln!(f, "safelist = {}", strings(&self.safelist))?;
+
+        writeln!(f, "\n[mangle]")?;
+        writeln!(f, "enabled = {}", self.mangle)?;
+        writeln!(f, "reserved = {}", strings(&self.reserved))?;
+        writeln!(f, "seed = {}", self.seed as i64)?;
+
+        writeln!(f, "\n[modules]")?;
+        writeln!(f, "enabled = {}", self.modules)?;
+
+        writeln!(f, "\n[keyframes]")?;
+        writeln!(f, "remove-unused = {}", self.unused_keyframes)?;
+        if !self.animations.is_empty() {
+            writeln!(f, "\n[keyframes.rename]")?;
+            for (original, renamed) in &self.animations {
+                writeln!(f, "{} = {}", quote(original), quote(renamed))?;
+            }
+        }
+
+        writeln!(f, "\n[assets]")?;
+        writeln!(f, "enabled = {}", self.assets)?;
+        if let Some(cdn) = &self.cdn {
+            writeln!(f, "cdn = {}", quote(cdn))?;
+        }
+        if let Some(limit) = self.inline_limit {
+            writeln!(f, "inline-limit = {}", limit)?;
+        }
+
+        writeln!(f, "\n[passes]")?;
+        for (pass, description) in PASSES.iter() {
+            writeln!(f, "# {}", description)?;
+            writeln!(f, "{} = {}", pass, self.enabled(pass))?;
+        }
+
+        writeln!(f, "\n[lint]")?;
+        for (rule, _, description) in LINT_RULES.iter() {
+            writeln!(f, "# {}", description)?;
+            writeln!(f, "{} = \"{}\"", rule, self.level(rule).name())?;
+        }
+
+        Ok(())
+    }
+}
+
+// Makes a path relative to the current directory when it's inside it
+fn shorten(path: PathBuf, current: &Path) -> PathBuf {
+    match path.strip_prefix(current) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path,
+    }
+}
+
+/**
+ * Finds the configuration for each input, applying the command line options on top
+ */
+pub struct Loader {
+    // Settings from the command line
+    overrides: Vec<(String, Value)>,
+
+    // A file given with --config, used instead of looking for one
+    file: Option<PathBuf>,
+
+    // Configurations already found, by the directory they were looked up from
+    found: Vec<(PathBuf, Arc<Config>)>,
+}
+
+impl Loader {
+    pub fn new(overrides: Vec<(String, Value)>, file: Option<PathBuf>) -> Loader {
+        Loader {
+            overrides,
+            file,
+            found: Vec::new(),
+        }
+    }
+
+    // Looks for a minify.toml in a directory and its parents
+    fn discover(directory: &Path) -> Option<PathBuf> {
+        directory
+            .ancestors()
+            .map(|directory| directory.join(FILE_NAME))
+            .find(|file| file.is_file())
+    }
+
+    /**
+     * The configuration for a file or directory
+     */
+    pub fn config(&mut self, path: &Path) -> Result<Arc<Config>, Error> {
+        let current = env::current_dir().unwrap_or_default();
+        let absolute = crate::bundle::normalize(&current.join(path));
+
+        let directory = if absolute.is_dir() {
+            absolute
+        } else {
+            absolute.parent().unwrap_or(Path::new("/")).to_path_buf()
+        };
+
+        if let Some((_, config)) = self.found.iter().find(|(found, _)| *found == directory) {
+            return Ok(config.clone());
+        }
+
+        let file = match &self.file {
+            Some(file) => Some(file.clone()),
+            None => Loader::discover(&directory).map(|file| shorten(file, &current)),
+        };
+
+        let mut config = match &file {
+            Some(file) => Config::load(file)?,
+            None => Config::default(),
+        };
+
+        let error = |message: String| Error {
+            source: String::from("command line"),
+            line: 0,
+            message,
+        };
+
+        for (key, value) in &self.overrides {
+            config
+                .set(key, value.clone(), Path::new(""))
+                .map_err(error)?;
+        }
+
+        if config.suffix().is_empty() && config.out_dir.is_none() {
+            return Err(Error {
+                source: file.map_or(String::from("command line"), |file| file.display().to_string()),
+                line: 0,
+                message: String::from("output.suffix can only be empty when output.dir is set, or the inputs would be overwritten"),
+            });
+        }
+
+        let config = Arc::new(config);
+        self.found.push((directory, config.clone()));
+
+        Ok(config)
+    }
+}
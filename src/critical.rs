@@ -0,0 +1,79 @@
+/**
+ * Critical CSS extraction
+ *
+ * `minify critical page.html` splits the stylesheets a page links to (or the ones given with --css) into the rules
+ * needed to render the page (page.critical.css, to inline in a <style> element) and everything else (page.rest.css,
+ * to load afterwards). Both are minimized and written next to the page. See css/critical.rs for how rules are split.
+ *
+ * Stylesheets are read as page.rs describes, with their urls working from the page's directory as that's where the
+ * outputs go. <style> elements are already inline, so they're left alone.
+ */
+use crate::bundle;
+use crate::config::Loader;
+use crate::css::{self, media::Viewport};
+use crate::html::dom;
+use crate::minimize;
+use crate::page;
+use std::fs;
+use std::path::Path;
+
+/**
+ * Splits the stylesheets of a page into its critical CSS and the rest, and writes them next to it
+ */
+pub fn run(
+    file: &str,
+    stylesheets: &[String],
+    viewport: &Viewport,
+    loader: &mut Loader,
+) -> Result<(), String> {
+    let path = bundle::normalize(Path::new(file));
+    let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    let config = loader.config(&path).map_err(|e| e.to_string())?;
+
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let document = dom::parse(contents)
+        .map_err(|e| format!("Error parsing file {}!\n{}", path.display(), e))?;
+
+    let sources = if stylesheets.is_empty() {
+        page::sources(&document, &directory, false)
+    } else {
+        stylesheets
+            .iter()
+            .map(|file| page::Source::file(Path::new(file)))
+            .collect()
+    };
+
+    if sources.is_empty() {
+        return Err(format!(
+            "{} doesn't link to any local stylesheets, pass them with --css",
+            path.display()
+        ));
+    }
+
+    let stylesheet = page::load(sources, &directory, loader)?;
+    let (mut critical, mut rest) = css::critical::stylesheet(&stylesheet, &document, viewport);
+
+    let stem = path.file_stem().map_or(String::from("page"), |stem| {
+        stem.to_string_lossy().to_string()
+    });
+    let mut written: Vec<String> = Vec::new();
+
+    for (stylesheet, kind) in [(&mut critical, "critical"), (&mut rest, "rest")] {
+        css::optimize(stylesheet, &config);
+
+        let output = directory.join(format!("{}.{}.css", stem, kind));
+        let contents = stylesheet.to_string();
+        fs::write(&output, &contents)
+            .map_err(|e| format!("Could not write {}: {}", output.display(), e))?;
+
+        written.push(format!(
+            "{} ({})",
+            output.display(),
+            minimize::size(contents.len())
+        ));
+    }
+
+    println!("Wrote {}", written.join(" and "));
+    Ok(())
+}
@@ -0,0 +1,490 @@
+/**
+ * Canonical values
+ *
+ * Values are rewritten the shortest way that means the same thing, using the grammar of their property to know what
+ * each component value is:
+ *
+ *  - font-weight: normal and bold => 400 and 700, in the font shorthand too
+ *  - background: none => background: 0 0
+ *  - transparent => #0000, when there are targets and they all support #rgba colors
+ *  - Property names, keywords and units are lowercased (BLOCK => block, 10PX => 10px), names like animation-name: Fade
+ *    aren't
+ *  - Zero lengths lose their unit (0px => 0), as do zero angles in rotate() and skew(). Zero times keep it, they need
+ *    it, but a lone zero time in a transition or animation is left out, it's the default
+ *  - initial => the property's initial value, when that's shorter (margin: initial => margin: 0)
+ *
+ * Each rewrite is a pass of its own, so it can be turned off. Custom properties, declarations in at-rules like
+ * @font-face and values the grammars can't check (var(), vendor prefixes) are left alone.
+ */
+use super::cascade::INITIAL;
+use super::grammar::{self, Matched};
+use super::parse::{self, tree::*};
+use super::token::tokens::{CSSToken, HashFlag, NumericFlag};
+use super::types;
+
+// The initial values of shorthands whose longhands all start the same way
+const SHORTHANDS: [(&str, &str); 7] = [
+    ("border", "none"),
+    ("border-radius", "0"),
+    ("gap", "normal"),
+    ("inset", "auto"),
+    ("margin", "0"),
+    ("outline", "none"),
+    ("padding", "0"),
+];
+
+// Transform functions whose angles can be a unitless zero
+const ZERO_ANGLES: [&str; 8] = [
+    "rotate", "rotate3d", "rotatex", "rotatey", "rotatez", "skew", "skewx", "skewy",
+];
+
+/**
+ * Which rewrites are made
+ */
+#[derive(Clone, Copy)]
+pub struct Rewrites {
+    pub background_none: bool,
+    pub font_weight: bool,
+    pub initial: bool,
+    pub keyword_case: bool,
+    pub transparent: bool,
+    pub zero_units: bool,
+}
+
+fn is_zero(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Dimension(number, _, _)) if *number == 0.0)
+}
+
+fn is_keyword(matched: &Matched) -> bool {
+    !matched.term.starts_with('<')
+        && !matched.term.ends_with("()")
+        && matched.term != ","
+        && matched.term != "/"
+}
+
+// The idents in gradients are colors, or keywords like to and circle
+fn is_gradient(matched: &Matched, function: &Function) -> bool {
+    matched.term == "<image>" && function.name.to_ascii_lowercase().ends_with("-gradient")
+}
+
+fn initial(name: &str, declaration: &mut Declaration) {
+    match declaration.value.as_slice() {
+        [ComponentValue::Token(CSSToken::Ident(keyword))]
+            if keyword.eq_ignore_ascii_case("initial") => {}
+        _ => return,
+    }
+
+    let value = INITIAL
+        .iter()
+        .chain(SHORTHANDS.iter())
+        .find(|(property, _)| *property == name)
+        .map(|(_, value)| *value)
+        .filter(|value| value.len() < "initial".len());
+
+    if let Some(Ok(value)) = value.map(|value| parse::component_values(value.to_string())) {
+        declaration.value = value;
+    }
+}
+
+// Units are ASCII case-insensitive everywhere
+fn units(values: &mut [ComponentValue]) {
+    for value in values.iter_mut() {
+        match value {
+            ComponentValue::Token(CSSToken::Dimension(_, _, unit)) => unit.make_ascii_lowercase(),
+            ComponentValue::Function(function) => units(&mut function.value),
+            ComponentValue::Block(block) => units(&mut block.value),
+            ComponentValue::Token(_) => (),
+        }
+    }
+}
+
+fn lowercase(value: &mut ComponentValue) {
+    if let ComponentValue::Token(CSSToken::Ident(ident)) = value {
+        ident.make_ascii_lowercase();
+    }
+}
+
+fn keyword_case(matched: &Matched, value: &mut ComponentValue) {
+    match value {
+        // Named colors are keywords too
+        ComponentValue::Token(_) if is_keyword(matched) || matched.term == "<color>" => {
+            lowercase(value)
+        }
+        ComponentValue::Function(function) if is_gradient(matched, function) => {
+            function.value.iter_mut().for_each(lowercase);
+        }
+        _ => (),
+    }
+}
+
+fn font_weight(matched: &Matched, value: &mut ComponentValue) {
+    if !matched
+        .path
+        .iter()
+        .any(|name| name == "font-weight-absolute")
+    {
+        return;
+    }
+
+    let weight = match value {
+        ComponentValue::Token(CSSToken::Ident(ident)) if ident.eq_ignore_ascii_case("normal") => {
+            400.0
+        }
+        ComponentValue::Token(CSSToken::Ident(ident)) if ident.eq_ignore_ascii_case("bold") => {
+            700.0
+        }
+        _ => return,
+    };
+
+    *value = ComponentValue::Token(CSSToken::Number(weight, NumericFlag::Integer));
+}
+
+fn transparent_color(value: &mut ComponentValue) {
+    if matches!(value, ComponentValue::Token(CSSToken::Ident(ident)) if ident.eq_ignore_ascii_case("transparent"))
+    {
+        *value =
+            ComponentValue::Token(CSSToken::Hash(String::from("0000"), HashFlag::Unrestricted));
+    }
+}
+
+fn transparent(matched: &Matched, value: &mut ComponentValue) {
+    match value {
+        _ if matched.term == "<color>" => transparent_color(value),
+        ComponentValue::Function(function) if is_gradient(matched, function) => {
+            function.value.iter_mut().for_each(transparent_color);
+        }
+        _ => (),
+    }
+}
+
+fn zero_units(matched: &Matched, value: &mut ComponentValue) {
+    match value {
+        // flex: 1 0 would make the 0 a flex-shrink
+        _ if matches!(matched.term.as_str(), "<length>" | "<length-percentage>")
+            && is_zero(value)
+            && types::matches("length", value) == Some(true)
+            && !matched.path.iter().any(|name| name == "flex-basis") =>
+        {
+            *value = ComponentValue::Token(CSSToken::Number(0.0, NumericFlag::Integer));
+        }
+        ComponentValue::Function(function)
+            if matched.term == "<transform-function>"
+                && ZERO_ANGLES
+                    .iter()
+                    .any(|name| function.name.eq_ignore_ascii_case(name)) =>
+        {
+            for argument in function.value.iter_mut() {
+                if is_zero(argument) && types::matches("angle", argument) == Some(true) {
+                    *argument = ComponentValue::Token(CSSToken::Number(0.0, NumericFlag::Integer));
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+// The lone zero times of transitions and animations, which are their default duration
+fn zero_times(matched: &[Matched]) -> Vec<usize> {
+    let mut removed: Vec<usize> = Vec::new();
+
+    for layer in matched
+        .iter()
+        .enumerate()
+        .collect::<Vec<(usize, &Matched)>>()
+        .split(|(_, matched)| matched.term == ",")
+    {
+        let times: Vec<&(usize, &Matched)> = layer
+            .iter()
+            .filter(|(_, matched)| matched.term == "<time>")
+            .collect();
+
+        if let [(index, time)] = times.as_slice() {
+            if layer.len() > 1 && is_zero(&time.value) {
+                removed.push(*index);
+            }
+        }
+    }
+
+    removed
+}
+
+fn declaration(declaration: &mut Declaration, rewrites: Rewrites) {
+    if declaration.name.starts_with("--") {
+        return;
+    }
+    if rewrites.keyword_case {
+        declaration.name.make_ascii_lowercase();
+    }
+    let name = declaration.name.to_ascii_lowercase();
+
+    if rewrites.initial {
+        initial(&name, declaration);
+    }
+
+    if rewrites.keyword_case {
+        units(&mut declaration.value);
+    }
+
+    let matched = match grammar::check(declaration) {
+        Some(Ok(matched)) => matched,
+        _ => return,
+    };
+
+    // Where each matched component value is in the declaration
+    let positions: Vec<usize> = declaration
+        .value
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| !matches!(value, ComponentValue::Token(CSSToken::Whitespace)))
+        .map(|(index, _)| index)
+        .collect();
+
+    if positions.len() != matched.len() {
+        return;
+    }
+
+    for (position, matched) in positions.iter().zip(&matched) {
+        let value = &mut declaration.value[*position];
+
+        if rewrites.keyword_case {
+            keyword_case(matched, value);
+        }
+        if rewrites.font_weight {
+            font_weight(matched, value);
+        }
+        if rewrites.transparent {
+            transparent(matched, value);
+        }
+        if rewrites.zero_units {
+            zero_units(matched, value);
+        }
+    }
+
+    if rewrites.zero_units && (name == "transition" || name == "animation") {
+        let removed: Vec<usize> = zero_times(&matched)
+            .into_iter()
+            .map(|index| positions[index])
+            .collect();
+
+        let mut index = 0;
+        declaration.value.retain(|_| {
+            index += 1;
+            !removed.contains(&(index - 1))
+        });
+    }
+
+    // Everything else is reset along with the image, to the same values 0 0 resets it to
+    if rewrites.background_none && name == "background" {
+        if let [Matched { term, .. }] = matched.as_slice() {
+            if term == "none" {
+                declaration.value = vec![
+                    ComponentValue::Token(CSSToken::Number(0.0, NumericFlag::Integer)),
+                    ComponentValue::Token(CSSToken::Whitespace),
+                    ComponentValue::Token(CSSToken::Number(0.0, NumericFlag::Integer)),
+                ];
+            }
+        }
+    }
+}
+
+// Declarations are only rewritten in style rules, at-rules like @font-face have descriptors
+fn items(items: &mut [BlockItem], style: bool, rewrites: Rewrites) {
+    for item in items.iter_mut() {
+        match item {
+            BlockItem::Declaration(declaration) if style => {
+                self::declaration(declaration, rewrites)
+            }
+            BlockItem::Declaration(_) => (),
+            BlockItem::Rule(Rule::Qualified(rule)) => self::items(&mut rule.block, true, rewrites),
+            BlockItem::Rule(Rule::At(rule)) => {
+                if let Some(block) = &mut rule.block {
+                    self::items(block, style, rewrites);
+                }
+            }
+        }
+    }
+}
+
+/**
+ * Rewrites the values of a stylesheet the shortest canonical way
+ */
+pub fn stylesheet(stylesheet: &mut Stylesheet, rewrites: Rewrites) {
+    let mut rules: Vec<BlockItem> = std::mem::take(&mut stylesheet.rules)
+        .into_iter()
+        .map(BlockItem::Rule)
+        .collect();
+
+    items(&mut rules, false, rewrites);
+
+    stylesheet.rules = rules
+        .into_iter()
+        .filter_map(|item| match item {
+            BlockItem::Rule(rule) => Some(rule),
+            BlockItem::Declaration(_) => None,
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: Rewrites = Rewrites {
+        background_none: true,
+        font_weight: true,
+        initial: true,
+        keyword_case: true,
+        transparent: true,
+        zero_units: true,
+    };
+
+    const NONE: Rewrites = Rewrites {
+        background_none: false,
+        font_weight: false,
+        initial: false,
+        keyword_case: false,
+        transparent: false,
+        zero_units: false,
+    };
+
+    fn rewrite(css: &str, rewrites: Rewrites) -> String {
+        let mut parsed = parse::stylesheet(css.to_string()).unwrap();
+        stylesheet(&mut parsed, rewrites);
+        parsed.to_string()
+    }
+
+    // Each pair is rewritten as expected, and left as it was with only this rewrite turned off
+    fn check(pairs: &[(&str, &str)], off: Rewrites) {
+        for (before, after) in pairs {
+            assert_eq!(rewrite(before, ALL), *after, "rewriting {}", before);
+            assert_eq!(
+                rewrite(before, off),
+                rewrite(before, NONE),
+                "rewriting {} with the rewrite turned off",
+                before
+            );
+        }
+    }
+
+    #[test]
+    fn font_weight() {
+        check(
+            &[
+                (".a{font-weight:normal}", ".a{font-weight:400}"),
+                (".a{font-weight:bold}", ".a{font-weight:700}"),
+                (".a{font:bold 12px serif}", ".a{font:700 12px serif}"),
+                (".a{font-weight:bolder}", ".a{font-weight:bolder}"),
+            ],
+            Rewrites {
+                font_weight: false,
+                ..ALL
+            },
+        );
+    }
+
+    #[test]
+    fn background_none() {
+        check(
+            &[
+                (".a{background:none}", ".a{background:0 0}"),
+                (".a{background:none red}", ".a{background:none red}"),
+                (".a{background-image:none}", ".a{background-image:none}"),
+            ],
+            Rewrites {
+                background_none: false,
+                ..ALL
+            },
+        );
+    }
+
+    #[test]
+    fn transparent() {
+        check(
+            &[
+                (".a{color:transparent}", ".a{color:#0000}"),
+                (
+                    ".a{background:linear-gradient(red,transparent)}",
+                    ".a{background:linear-gradient(red,#0000)}",
+                ),
+                (
+                    ".a{animation-name:transparent}",
+                    ".a{animation-name:transparent}",
+                ),
+            ],
+            Rewrites {
+                transparent: false,
+                ..ALL
+            },
+        );
+    }
+
+    #[test]
+    fn keyword_case() {
+        check(
+            &[
+                (".a{DISPLAY:BLOCK}", ".a{display:block}"),
+                (".a{color:Red}", ".a{color:red}"),
+                (".a{width:10PX}", ".a{width:10px}"),
+                (
+                    ".a{background:linear-gradient(To Right,Red,Blue)}",
+                    ".a{background:linear-gradient(to right,red,blue)}",
+                ),
+                (".a{animation-name:Fade}", ".a{animation-name:Fade}"),
+            ],
+            Rewrites {
+                keyword_case: false,
+                ..ALL
+            },
+        );
+    }
+
+    #[test]
+    fn zero_units() {
+        check(
+            &[
+                (".a{margin:0px}", ".a{margin:0}"),
+                (".a{padding:0em 1px}", ".a{padding:0 1px}"),
+                (".a{transform:rotate(0deg)}", ".a{transform:rotate(0)}"),
+                (".a{transition:opacity 0s}", ".a{transition:opacity}"),
+                (
+                    ".a{transition:opacity 0s 1s}",
+                    ".a{transition:opacity 0s 1s}",
+                ),
+                (".a{transition-duration:0s}", ".a{transition-duration:0s}"),
+                (".a{flex:1 1 0px}", ".a{flex:1 1 0px}"),
+                (".a{rotate:0deg}", ".a{rotate:0deg}"),
+            ],
+            Rewrites {
+                zero_units: false,
+                ..ALL
+            },
+        );
+    }
+
+    #[test]
+    fn initial() {
+        check(
+            &[
+                (".a{margin:initial}", ".a{margin:0}"),
+                (".a{z-index:initial}", ".a{z-index:auto}"),
+                (".a{color:initial}", ".a{color:initial}"),
+            ],
+            Rewrites {
+                initial: false,
+                ..ALL
+            },
+        );
+    }
+
+    #[test]
+    fn left_alone() {
+        for css in [
+            ".a{--x:0PX}",
+            ".a{width:calc(0px + 10%)}",
+            ".a{font-weight:var(--bold)}",
+            "@font-face{font-family:A;font-weight:bold}",
+        ] {
+            assert_eq!(rewrite(css, ALL), rewrite(css, NONE), "rewriting {}", css);
+        }
+    }
+}
@@ -0,0 +1,937 @@
+/**
+ * The cascade
+ * https://www.w3.org/TR/css-cascade-5/
+ *
+ * Works out the value of each property for an element of a document, the way a browser would before layout. For every
+ * property, the declarations that apply to the element are sorted by (§ 6.1):
+ *
+ *  1. Origin and importance: user-agent, user and author declarations, then !important ones in the opposite order
+ *  2. Context: the style attribute beats rules
+ *  3. Layers: later layers win for normal declarations, earlier ones for !important ones, and declarations outside
+ *     layers are treated as the last layer
+ *  4. Specificity
+ *  5. Order of appearance
+ *
+ * The last one left wins, after revert and revert-layer roll back to the origin or layer before it. Shorthands like
+ * margin and border are split into their longhands first, so they override each other like browsers do, unless they
+ * use var() and what they set isn't known yet.
+ *
+ * Computed values (§ 7) add inheritance: properties like color take the parent's value when nothing is declared, and
+ * initial, inherit and unset are resolved. Custom properties are substituted into var() functions, with values that
+ * end up invalid falling back to unset. Values aren't converted any further, so 2em stays 2em.
+ *
+ * @media rules apply when they match the viewport (see media.rs). @supports and @container are taken to apply, while
+ * @scope and @starting-style rules are ignored. Nested rules are flattened first (see nesting.rs), and rules for
+ * pseudo-elements don't apply to the element itself.
+ */
+use super::layer::{self, Layer, Layers};
+use super::media::{self, Viewport};
+use super::nesting;
+use super::parse::{self, tree::*};
+use super::select::{self, Selector, Specificity};
+use super::serialize::{values, Context};
+use super::token::tokens::CSSToken;
+use crate::html::dom::{Document, NodeId};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+type Values = Vec<ComponentValue>;
+
+/**
+ * § 6.2 Where a declaration came from, from the weakest to the strongest for normal declarations
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Origin {
+    UserAgent,
+    User,
+    Author,
+}
+
+// § 7.3 Properties that take the parent's value when nothing else is declared
+const INHERITED: [&str; 62] = [
+    "-webkit-font-smoothing",
+    "-webkit-text-size-adjust",
+    "accent-color",
+    "border-collapse",
+    "border-spacing",
+    "caption-side",
+    "caret-color",
+    "color",
+    "color-scheme",
+    "cursor",
+    "direction",
+    "empty-cells",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "font",
+    "font-family",
+    "font-feature-settings",
+    "font-kerning",
+    "font-size",
+    "font-size-adjust",
+    "font-stretch",
+    "font-style",
+    "font-synthesis",
+    "font-variant",
+    "font-variation-settings",
+    "font-weight",
+    "hyphens",
+    "image-rendering",
+    "letter-spacing",
+    "line-height",
+    "list-style",
+    "list-style-image",
+    "list-style-position",
+    "list-style-type",
+    "orphans",
+    "overflow-wrap",
+    "paint-order",
+    "pointer-events",
+    "quotes",
+    "stroke",
+    "stroke-dasharray",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-opacity",
+    "stroke-width",
+    "tab-size",
+    "text-align",
+    "text-align-last",
+    "text-indent",
+    "text-justify",
+    "text-orientation",
+    "text-rendering",
+    "text-shadow",
+    "text-transform",
+    "text-underline-position",
+    "visibility",
+    "white-space",
+    "widows",
+    "word-break",
+    "word-spacing",
+    "writing-mode",
+];
+
+// § 7.1 The initial values of common properties, used when nothing is declared or inherited
+pub const INITIAL: [(&str, &str); 78] = [
+    ("accent-color", "auto"),
+    ("align-content", "normal"),
+    ("align-items", "normal"),
+    ("align-self", "auto"),
+    ("background-color", "transparent"),
+    ("background-image", "none"),
+    ("border-bottom-color", "currentcolor"),
+    ("border-bottom-left-radius", "0"),
+    ("border-bottom-right-radius", "0"),
+    ("border-bottom-style", "none"),
+    ("border-bottom-width", "medium"),
+    ("border-collapse", "separate"),
+    ("border-left-color", "currentcolor"),
+    ("border-left-style", "none"),
+    ("border-left-width", "medium"),
+    ("border-right-color", "currentcolor"),
+    ("border-right-style", "none"),
+    ("border-right-width", "medium"),
+    ("border-top-color", "currentcolor"),
+    ("border-top-left-radius", "0"),
+    ("border-top-right-radius", "0"),
+    ("border-top-style", "none"),
+    ("border-top-width", "medium"),
+    ("bottom", "auto"),
+    ("box-shadow", "none"),
+    ("box-sizing", "content-box"),
+    ("caret-color", "auto"),
+    ("clear", "none"),
+    ("color", "canvastext"),
+    ("column-gap", "normal"),
+    ("cursor", "auto"),
+    ("direction", "ltr"),
+    ("display", "inline"),
+    ("flex-basis", "auto"),
+    ("flex-direction", "row"),
+    ("flex-grow", "0"),
+    ("flex-shrink", "1"),
+    ("flex-wrap", "nowrap"),
+    ("float", "none"),
+    ("font-size", "medium"),
+    ("font-style", "normal"),
+    ("font-weight", "normal"),
+    ("height", "auto"),
+    ("justify-content", "normal"),
+    ("left", "auto"),
+    ("letter-spacing", "normal"),
+    ("line-height", "normal"),
+    ("list-style-position", "outside"),
+    ("list-style-type", "disc"),
+    ("margin-bottom", "0"),
+    ("margin-left", "0"),
+    ("margin-right", "0"),
+    ("margin-top", "0"),
+    ("max-height", "none"),
+    ("max-width", "none"),
+    ("min-height", "auto"),
+    ("min-width", "auto"),
+    ("opacity", "1"),
+    ("overflow-x", "visible"),
+    ("overflow-y", "visible"),
+    ("padding-bottom", "0"),
+    ("padding-left", "0"),
+    ("padding-right", "0"),
+    ("padding-top", "0"),
+    ("position", "static"),
+    ("right", "auto"),
+    ("row-gap", "normal"),
+    ("text-align", "start"),
+    ("text-decoration-line", "none"),
+    ("text-indent", "0"),
+    ("text-transform", "none"),
+    ("top", "auto"),
+    ("transform", "none"),
+    ("visibility", "visible"),
+    ("white-space", "normal"),
+    ("width", "auto"),
+    ("word-spacing", "normal"),
+    ("z-index", "auto"),
+];
+
+// § 7.3 The keywords every property accepts
+const KEYWORDS: [&str; 5] = ["initial", "inherit", "unset", "revert", "revert-layer"];
+
+// The style sheet browsers start from, reduced to what matters most for the elements of a page
+const USER_AGENT: &str = "
+address, article, aside, blockquote, body, dd, details, dialog, div, dl, dt, fieldset, figcaption, figure, footer, form,
+h1, h2, h3, h4, h5, h6, header, hgroup, hr, html, legend, main, menu, nav, ol, p, pre, search, section, summary, ul {
+    display: block
+}
+area, base, datalist, head, link, meta, noscript, script, style, template, title, [hidden]:not([hidden=until-found i]) {
+    display: none
+}
+li { display: list-item }
+table { display: table; border-collapse: separate; border-spacing: 2px }
+tr { display: table-row }
+td, th { display: table-cell; padding: 1px }
+th { font-weight: bold; text-align: center }
+body { margin: 8px }
+p, blockquote, dl, figure, pre { margin-top: 1em; margin-bottom: 1em }
+ol, ul, menu { margin-top: 1em; margin-bottom: 1em; padding-left: 40px }
+ol { list-style-type: decimal }
+h1 { font-size: 2em; margin-top: 0.67em; margin-bottom: 0.67em; font-weight: bold }
+h2 { font-size: 1.5em; margin-top: 0.83em; margin-bottom: 0.83em; font-weight: bold }
+h3 { font-size: 1.17em; margin-top: 1em; margin-bottom: 1em; font-weight: bold }
+h4 { margin-top: 1.33em; margin-bottom: 1.33em; font-weight: bold }
+h5 { font-size: 0.83em; margin-top: 1.67em; margin-bottom: 1.67em; font-weight: bold }
+h6 { font-size: 0.67em; margin-top: 2.33em; margin-bottom: 2.33em; font-weight: bold }
+b, strong { font-weight: bolder }
+i, em, cite, dfn, var { font-style: italic }
+code, kbd, pre, samp { font-family: monospace }
+pre { white-space: pre }
+a:link { color: #0000ee; text-decoration-line: underline; cursor: pointer }
+a:visited { color: #551a8b }
+";
+
+/**
+ * The user-agent stylesheet the cascade starts from
+ */
+pub fn user_agent() -> Stylesheet {
+    parse::stylesheet(USER_AGENT.to_string()).expect("the user-agent stylesheet parses")
+}
+
+// A style rule with the declarations it sets, after flattening
+struct StyleRule {
+    selectors: Vec<Selector>,
+    // The declarations as written, and split into longhands, with their order of appearance
+    written: Vec<(Declaration, usize)>,
+    declarations: Vec<(String, Values, bool, usize)>,
+    origin: Origin,
+    layer: Vec<u32>,
+}
+
+impl StyleRule {
+    // The specificity of the most specific selector matching an element, None if none match
+    fn specificity(&self, document: &Document, id: NodeId) -> Option<Specificity> {
+        self.selectors
+            .iter()
+            .filter(|selector| selector.matches(document, id))
+            .map(|selector| selector.specificity())
+            .max()
+    }
+}
+
+// The declarations in the style attribute of an element, as if it was a rule that only matches the element
+fn inline(document: &Document, id: NodeId) -> Vec<Declaration> {
+    document
+        .attribute(id, "style")
+        .and_then(|style| parse::declaration_list(style.to_string()).ok())
+        .unwrap_or_default()
+}
+
+// A declaration that applies to an element
+#[derive(Clone)]
+struct Candidate<'a> {
+    value: &'a [ComponentValue],
+    origin: Origin,
+    important: bool,
+    inline: bool,
+    layer: &'a [u32],
+    specificity: Specificity,
+    order: usize,
+}
+
+impl Candidate<'_> {
+    // § 6.2 Normal declarations from the user agent are the weakest, !important ones the strongest
+    fn rank(&self) -> u8 {
+        match (self.origin, self.important) {
+            (Origin::UserAgent, false) => 0,
+            (Origin::User, false) => 1,
+            (Origin::Author, false) => 2,
+            (Origin::Author, true) => 3,
+            (Origin::User, true) => 4,
+            (Origin::UserAgent, true) => 5,
+        }
+    }
+
+    fn precedence(&self, other: &Candidate) -> Ordering {
+        self.rank()
+            .cmp(&other.rank())
+            .then(self.inline.cmp(&other.inline))
+            .then_with(|| {
+                // § 6.4 !important declarations in earlier layers win
+                if self.important {
+                    other.layer.cmp(self.layer)
+                } else {
+                    self.layer.cmp(other.layer)
+                }
+            })
+            .then(self.specificity.cmp(&other.specificity))
+            .then(self.order.cmp(&other.order))
+    }
+}
+
+/**
+ * The stylesheets of a document, ready to find the style of its elements
+ */
+pub struct Cascade {
+    viewport: Viewport,
+    rules: Vec<StyleRule>,
+    layers: Layers,
+    order: usize,
+}
+
+/**
+ * The computed values of an element's properties
+ */
+#[derive(Clone, Default)]
+pub struct Style {
+    values: BTreeMap<String, Values>,
+}
+
+impl Style {
+    /**
+     * The value of a property, or its initial value when nothing sets it
+     */
+    pub fn get(&self, name: &str) -> Option<String> {
+        let name = if name.starts_with("--") {
+            name.to_string()
+        } else {
+            name.to_ascii_lowercase()
+        };
+
+        match self.values.get(&name) {
+            Some(value) => Some(values(value, Context::Value)),
+            None => INITIAL
+                .iter()
+                .find(|(property, _)| *property == name)
+                .map(|(_, initial)| initial.to_string()),
+        }
+    }
+
+    /**
+     * The properties something sets or inherits, with their values
+     */
+    pub fn properties(&self) -> Vec<(String, String)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.clone(), values(value, Context::Value)))
+            .collect()
+    }
+}
+
+fn is_inherited(name: &str) -> bool {
+    name.starts_with("--") || INHERITED.contains(&name)
+}
+
+// initial, inherit, unset, revert or revert-layer
+fn keyword(value: &[ComponentValue]) -> Option<&'static str> {
+    let mut value = value
+        .iter()
+        .filter(|value| !matches!(value, ComponentValue::Token(CSSToken::Whitespace)));
+
+    match (value.next(), value.next()) {
+        (Some(ComponentValue::Token(CSSToken::Ident(name))), None) => KEYWORDS
+            .iter()
+            .find(|keyword| name.eq_ignore_ascii_case(keyword))
+            .copied(),
+        _ => None,
+    }
+}
+
+fn has_var(value: &[ComponentValue]) -> bool {
+    value.iter().any(|value| match value {
+        ComponentValue::Function(function) => {
+            function.name.eq_ignore_ascii_case("var") || has_var(&function.value)
+        }
+        ComponentValue::Block(block) => has_var(&block.value),
+        ComponentValue::Token(_) => false,
+    })
+}
+
+// Splits a value on whitespace
+fn split(value: &[ComponentValue]) -> Vec<Values> {
+    let mut parts: Vec<Values> = Vec::new();
+    let mut part: Values = Vec::new();
+
+    for component in value {
+        match component {
+            ComponentValue::Token(CSSToken::Whitespace) => {
+                if !part.is_empty() {
+                    parts.push(std::mem::take(&mut part));
+                }
+            }
+            component => part.push(component.clone()),
+        }
+    }
+
+    if !part.is_empty() {
+        parts.push(part);
+    }
+
+    parts
+}
+
+fn ident(name: &str) -> Values {
+    vec![ComponentValue::Token(CSSToken::Ident(name.to_string()))]
+}
+
+const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+const BORDER_STYLES: [&str; 10] = [
+    "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset",
+];
+
+// The longhands of a shorthand declaration, or the declaration itself. Shorthands using var() are left alone, as
+// what they set isn't known until it's substituted.
+fn longhands(name: &str, value: &[ComponentValue]) -> Vec<(String, Values)> {
+    let one = || vec![(name.to_string(), value.to_vec())];
+    if has_var(value) {
+        return one();
+    }
+
+    // Names are built for four sides, or the corners of border-radius
+    let names: Vec<String> = match name {
+        "margin" | "padding" => SIDES
+            .iter()
+            .map(|side| format!("{}-{}", name, side))
+            .collect(),
+        "inset" => SIDES.iter().map(|side| side.to_string()).collect(),
+        "border-width" | "border-style" | "border-color" => SIDES
+            .iter()
+            .map(|side| format!("border-{}-{}", side, &name[7..]))
+            .collect(),
+        "border-radius" => ["top-left", "top-right", "bottom-right", "bottom-left"]
+            .iter()
+            .map(|corner| format!("border-{}-radius", corner))
+            .collect(),
+        "gap" => vec![String::from("row-gap"), String::from("column-gap")],
+        "overflow" => vec![String::from("overflow-x"), String::from("overflow-y")],
+        "border" | "border-top" | "border-right" | "border-bottom" | "border-left" => {
+            return border(name, value).unwrap_or_else(one)
+        }
+        _ => return one(),
+    };
+
+    // A keyword like inherit applies to every longhand
+    if keyword(value).is_some() {
+        return names
+            .into_iter()
+            .map(|name| (name, value.to_vec()))
+            .collect();
+    }
+
+    let parts = split(value);
+    let indices: &[usize] = match (names.len(), parts.len()) {
+        (2, 1) => &[0, 0],
+        (2, 2) => &[0, 1],
+        (4, 1) => &[0, 0, 0, 0],
+        (4, 2) => &[0, 1, 0, 1],
+        (4, 3) => &[0, 1, 2, 1],
+        (4, 4) => &[0, 1, 2, 3],
+        _ => return one(),
+    };
+
+    names
+        .into_iter()
+        .zip(indices)
+        .map(|(name, index)| (name, parts[*index].clone()))
+        .collect()
+}
+
+// border and border-<side> set a width, style and color, resetting the ones left out
+fn border(name: &str, value: &[ComponentValue]) -> Option<Vec<(String, Values)>> {
+    let sides: Vec<&str> = match name.strip_prefix("border-") {
+        Some(side) => vec![side],
+        None => SIDES.to_vec(),
+    };
+
+    let (mut width, mut style, mut color) = (None, None, None);
+
+    if keyword(value).is_some() {
+        width = Some(value.to_vec());
+        style = Some(value.to_vec());
+        color = Some(value.to_vec());
+    } else {
+        for part in split(value) {
+            let slot = match part.as_slice() {
+                [ComponentValue::Token(CSSToken::Dimension(..))]
+                | [ComponentValue::Token(CSSToken::Number(..))] => &mut width,
+                [ComponentValue::Token(CSSToken::Ident(word))]
+                    if ["thin", "medium", "thick"]
+                        .iter()
+                        .any(|keyword| word.eq_ignore_ascii_case(keyword)) =>
+                {
+                    &mut width
+                }
+                [ComponentValue::Function(function)]
+                    if ["calc", "min", "max", "clamp"]
+                        .iter()
+                        .any(|name| function.name.eq_ignore_ascii_case(name)) =>
+                {
+                    &mut width
+                }
+                [ComponentValue::Token(CSSToken::Ident(word))]
+                    if BORDER_STYLES
+                        .iter()
+                        .any(|keyword| word.eq_ignore_ascii_case(keyword)) =>
+                {
+                    &mut style
+                }
+                _ => &mut color,
+            };
+
+            // Each can only be given once
+            if slot.replace(part).is_some() {
+                return None;
+            }
+        }
+    }
+
+    let width = width.unwrap_or_else(|| ident("medium"));
+    let style = style.unwrap_or_else(|| ident("none"));
+    let color = color.unwrap_or_else(|| ident("currentcolor"));
+
+    Some(
+        sides
+            .iter()
+            .flat_map(|side| {
+                [
+                    (format!("border-{}-width", side), width.clone()),
+                    (format!("border-{}-style", side), style.clone()),
+                    (format!("border-{}-color", side), color.clone()),
+                ]
+            })
+            .collect(),
+    )
+}
+
+// The declared name, lowercased unless it's a custom property
+fn property(name: &str) -> String {
+    if name.starts_with("--") {
+        name.to_string()
+    } else {
+        name.to_ascii_lowercase()
+    }
+}
+
+impl Cascade {
+    pub fn new(viewport: Viewport) -> Cascade {
+        Cascade {
+            viewport,
+            rules: Vec::new(),
+            layers: Layers::default(),
+            order: 0,
+        }
+    }
+
+    /**
+     * Adds a stylesheet, after the ones already added
+     */
+    pub fn add(&mut self, stylesheet: &Stylesheet, origin: Origin) {
+        let mut flat = Stylesheet {
+            rules: stylesheet.rules.clone(),
+            comments: Vec::new(),
+        };
+        nesting::stylesheet(&mut flat, true);
+
+        // Each origin has its own layers
+        let root = vec![format!("{:?}", origin)];
+        self.layers.declare(&root);
+        self.walk(&flat.rules, origin, &root);
+    }
+
+    fn walk(&mut self, rules: &[Rule], origin: Origin, path: &[String]) {
+        for rule in rules {
+            let at = match rule {
+                Rule::Qualified(style) => {
+                    self.style_rule(style, origin, path);
+                    continue;
+                }
+                Rule::At(at) => at,
+            };
+
+            let block: Vec<Rule> = match &at.block {
+                Some(block) => block
+                    .iter()
+                    .filter_map(|item| match item {
+                        BlockItem::Rule(rule) => Some(rule.clone()),
+                        BlockItem::Declaration(_) => None,
+                    })
+                    .collect(),
+                None => Vec::new(),
+            };
+
+            match layer::layer(at) {
+                Some(Layer::Block(name)) => {
+                    let path = self.layers.block(path, name);
+                    self.walk(&block, origin, &path);
+                    continue;
+                }
+                // @layer a, b; sets the order of layers before they're used
+                Some(Layer::Statement(names)) => {
+                    for name in names {
+                        self.layers.block(path, Some(name));
+                    }
+                    continue;
+                }
+                None => (),
+            }
+
+            match at.name.to_ascii_lowercase().as_str() {
+                "media" if media::matches(&at.prelude, &self.viewport) => {
+                    self.walk(&block, origin, path)
+                }
+                "supports" | "container" => self.walk(&block, origin, path),
+                _ => (),
+            }
+        }
+    }
+
+    fn style_rule(&mut self, rule: &QualifiedRule, origin: Origin, path: &[String]) {
+        // Browsers drop rules with invalid selectors
+        let selectors: Vec<Selector> = match select::list(&rule.prelude) {
+            Ok(selectors) => selectors
+                .into_iter()
+                .filter(|selector| selector.pseudo_element().is_none())
+                .collect(),
+            Err(_) => return,
+        };
+        if selectors.is_empty() {
+            return;
+        }
+
+        let mut written = Vec::new();
+        let mut declarations = Vec::new();
+        for item in &rule.block {
+            if let BlockItem::Declaration(declaration) = item {
+                self.order += 1;
+                written.push((declaration.clone(), self.order));
+
+                for (name, value) in longhands(&property(&declaration.name), &declaration.value) {
+                    declarations.push((name, value, declaration.important, self.order));
+                }
+            }
+        }
+
+        let layer = self.layers.declare(path);
+        self.rules.push(StyleRule {
+            selectors,
+            written,
+            declarations,
+            origin,
+            layer,
+        });
+    }
+
+    /**
+     * § 6 The cascaded value of each property declared for an element, before inheritance. Properties left as
+     * revert with nothing to roll back to are unset.
+     */
+    pub fn cascaded(&self, document: &Document, id: NodeId) -> BTreeMap<String, Values> {
+        let mut candidates: HashMap<String, Vec<Candidate>> = HashMap::new();
+
+        for rule in &self.rules {
+            let specificity = match rule.specificity(document, id) {
+                Some(specificity) => specificity,
+                None => continue,
+            };
+
+            for (name, value, important, order) in &rule.declarations {
+                candidates.entry(name.clone()).or_default().push(Candidate {
+                    value,
+                    origin: rule.origin,
+                    important: *important,
+                    inline: false,
+                    layer: &rule.layer,
+                    specificity,
+                    order: *order,
+                });
+            }
+        }
+
+        let inline: Vec<(String, Values, bool)> = inline(document, id)
+            .into_iter()
+            .flat_map(|declaration| {
+                longhands(&property(&declaration.name), &declaration.value)
+                    .into_iter()
+                    .map(move |(name, value)| (name, value, declaration.important))
+            })
+            .collect();
+
+        for (order, (name, value, important)) in inline.iter().enumerate() {
+            candidates.entry(name.clone()).or_default().push(Candidate {
+                value,
+                origin: Origin::Author,
+                important: *important,
+                inline: true,
+                layer: &[],
+                specificity: Specificity::default(),
+                order,
+            });
+        }
+
+        // § 7.2 all sets every property except custom ones, direction and unicode-bidi
+        if let Some(all) = candidates.remove("all") {
+            let names: Vec<String> = candidates
+                .keys()
+                .cloned()
+                .chain(INITIAL.iter().map(|(name, _)| name.to_string()))
+                .filter(|name| {
+                    !name.starts_with("--") && name != "direction" && name != "unicode-bidi"
+                })
+                .collect();
+
+            for name in names {
+                candidates
+                    .entry(name)
+                    .or_default()
+                    .extend(all.iter().cloned());
+            }
+        }
+
+        candidates
+            .into_iter()
+            .filter_map(|(name, list)| Some((name, resolve(list)?)))
+            .collect()
+    }
+
+    /**
+     * § 6 The declarations that apply to an element, as they were written, from the weakest to the strongest. A later
+     * declaration overrides an earlier one for the properties both set.
+     */
+    pub fn declarations(&self, document: &Document, id: NodeId) -> Vec<Declaration> {
+        let inline = inline(document, id);
+        let mut candidates: Vec<(Candidate, &Declaration)> = Vec::new();
+
+        for rule in &self.rules {
+            let specificity = match rule.specificity(document, id) {
+                Some(specificity) => specificity,
+                None => continue,
+            };
+
+            for (declaration, order) in &rule.written {
+                let candidate = Candidate {
+                    value: &declaration.value,
+                    origin: rule.origin,
+                    important: declaration.important,
+                    inline: false,
+                    layer: &rule.layer,
+                    specificity,
+                    order: *order,
+                };
+                candidates.push((candidate, declaration));
+            }
+        }
+
+        for (order, declaration) in inline.iter().enumerate() {
+            let candidate = Candidate {
+                value: &declaration.value,
+                origin: Origin::Author,
+                important: declaration.important,
+                inline: true,
+                layer: &[],
+                specificity: Specificity::default(),
+                order,
+            };
+            candidates.push((candidate, declaration));
+        }
+
+        candidates.sort_by(|(a, _), (b, _)| a.precedence(b));
+        candidates
+            .into_iter()
+            .map(|(_, declaration)| declaration.clone())
+            .collect()
+    }
+
+    /**
+     * § 7 The computed value of each property of an element, with inheritance and custom properties resolved
+     */
+    pub fn computed(&self, document: &Document, id: NodeId) -> Style {
+        let parent = document
+            .parent_element(id)
+            .map(|parent| self.computed(document, parent));
+        let inherited = |name: &str| parent.as_ref().and_then(|parent| parent.values.get(name));
+
+        let mut style = Style::default();
+        if let Some(parent) = &parent {
+            for (name, value) in &parent.values {
+                if is_inherited(name) {
+                    style.values.insert(name.clone(), value.clone());
+                }
+            }
+        }
+
+        let cascaded = self.cascaded(document, id);
+        let mut substituted: Vec<String> = Vec::new();
+
+        for (name, value) in &cascaded {
+            // Initial values aren't stored, Style::get falls back on them
+            let inherit = match keyword(value) {
+                Some("inherit") => true,
+                Some("initial") => false,
+                Some(_) => is_inherited(name),
+                None => {
+                    if has_var(value) {
+                        substituted.push(name.clone());
+                    }
+                    style.values.insert(name.clone(), value.clone());
+                    continue;
+                }
+            };
+
+            style.values.remove(name);
+            if let Some(value) = inherit.then(|| inherited(name)).flatten() {
+                style.values.insert(name.clone(), value.clone());
+            }
+        }
+
+        // § 3 (css-variables) Custom properties are substituted first, as other properties use their results. Ones
+        // that refer to themselves, or to custom properties that don't exist, are invalid.
+        let customs: BTreeMap<String, Values> = style
+            .values
+            .iter()
+            .filter(|(name, _)| name.starts_with("--"))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+
+        substituted.sort_by_key(|name| !name.starts_with("--"));
+        for name in substituted {
+            let value = substitute(&style.values[&name], &customs, 0);
+            style.values.remove(&name);
+
+            match value {
+                Some(value) => {
+                    style.values.insert(name, value);
+                }
+                // Invalid at computed-value time, so the property is unset
+                None => {
+                    if let Some(value) = inherited(&name).filter(|_| is_inherited(&name)) {
+                        style.values.insert(name, value.clone());
+                    }
+                }
+            }
+        }
+
+        style
+    }
+}
+
+// The winner of the cascade for a property, after rolling back revert and revert-layer
+fn resolve(mut candidates: Vec<Candidate>) -> Option<Values> {
+    candidates.sort_by(|a, b| a.precedence(b));
+
+    loop {
+        let winner = match candidates.last() {
+            Some(winner) => winner.clone(),
+            None => return Some(ident("unset")),
+        };
+
+        match keyword(winner.value) {
+            // § 7.3.4 Back to the origin before this one
+            Some("revert") => candidates.retain(|candidate| candidate.origin < winner.origin),
+            // § 7.3.5 Back to the layer before this one
+            Some("revert-layer") => candidates.retain(|candidate| {
+                candidate.origin != winner.origin
+                    || candidate.important != winner.important
+                    || candidate.inline != winner.inline
+                    || candidate.layer != winner.layer
+            }),
+            _ => return Some(winner.value.to_vec()),
+        }
+    }
+}
+
+// Replaces the var() functions in a value, None if one can't be substituted
+fn substitute(
+    value: &[ComponentValue],
+    customs: &BTreeMap<String, Values>,
+    depth: usize,
+) -> Option<Values> {
+    // Custom properties that refer to each other in a loop never finish
+    if depth > 32 {
+        return None;
+    }
+
+    let mut substituted: Values = Vec::new();
+
+    for component in value {
+        match component {
+            ComponentValue::Function(function) if function.name.eq_ignore_ascii_case("var") => {
+                let mut arguments = function.value.splitn(2, |value| {
+                    matches!(value, ComponentValue::Token(CSSToken::Comma))
+                });
+
+                let name = arguments.next().and_then(|name| {
+                    name.iter().find_map(|value| match value {
+                        ComponentValue::Token(CSSToken::Ident(name)) if name.starts_with("--") => {
+                            Some(name)
+                        }
+                        _ => None,
+                    })
+                })?;
+
+                // The fallback is used when the custom property doesn't exist or is invalid itself
+                let value = customs
+                    .get(name)
+                    .and_then(|value| substitute(value, customs, depth + 1));
+                let mut replacement = match (value, arguments.next()) {
+                    (Some(value), _) => value,
+                    (None, Some(fallback)) => substitute(fallback, customs, depth + 1)?,
+                    (None, None) => return None,
+                };
+                parse::consume::trim(&mut replacement);
+                substituted.extend(replacement);
+            }
+            ComponentValue::Function(function) => {
+                substituted.push(ComponentValue::Function(Function {
+                    name: function.name.clone(),
+                    value: substitute(&function.value, customs, depth)?,
+                }))
+            }
+            ComponentValue::Block(block) => substituted.push(ComponentValue::Block(SimpleBlock {
+                token: block.token.clone(),
+                value: substitute(&block.value, customs, depth)?,
+            })),
+            component => substituted.push(component.clone()),
+        }
+    }
+
+    Some(substituted)
+}
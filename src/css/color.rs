@@ -0,0 +1,1394 @@
+/**
+ * Colors
+ * https://www.w3.org/TR/css-color-4/ and https://www.w3.org/TR/css-color-5/
+ *
+ * Colors the targets don't support are converted to hex, or rgba() when they're transparent, which every browser
+ * understands:
+ *
+ *  - rgb() and hsl() with spaces (rgb(0 0 0 / 50%)), hwb(), lab(), lch(), oklab(), oklch() and color()
+ *  - color-mix(in oklch, red 40%, blue)
+ *  - Relative colors (rgb(from red r g b / 50%)), including calc() on the channels
+ *  - #rgba and #rrggbbaa
+ *
+ * Colors outside of sRGB are gamut mapped the way CSS Color 4 describes, lowering the chroma in OKLCH until the color
+ * fits, rather than clipping each channel. Colors that depend on something only the browser knows (var(),
+ * currentcolor, system colors) are left alone.
+ */
+use super::compat::{self, Engines, Target};
+use super::parse::tree::*;
+use super::token::tokens::{CSSToken, HashFlag, NumericFlag};
+use crate::config::ColorFallback;
+
+type Matrix = [[f64; 3]; 3];
+
+// CSS Color 4 § 18 Sample code for color conversions, from linear light to XYZ (D65, or D50 for ProPhoto)
+const SRGB_TO_XYZ: Matrix = [
+    [0.41239079926595934, 0.357584339383878, 0.1804807884018343],
+    [0.21263900587151027, 0.715168678767756, 0.07219231536073371],
+    [0.01933081871559182, 0.11919477979462598, 0.9505321522496607],
+];
+
+const P3_TO_XYZ: Matrix = [
+    [0.4865709486482162, 0.26566769316909306, 0.1982172852343625],
+    [0.2289745640697488, 0.6917385218365064, 0.079286914093745],
+    [0.0, 0.04511338185890264, 1.043944368900976],
+];
+
+const A98_TO_XYZ: Matrix = [
+    [0.5766690429101305, 0.1855582379065463, 0.1882286462349947],
+    [0.29734497525053605, 0.6273635662554661, 0.07529145849399788],
+    [0.02703136138641234, 0.07068885253582723, 0.9913375368376388],
+];
+
+const PROPHOTO_TO_XYZ_D50: Matrix = [
+    [0.7977604896723027, 0.13518583717574031, 0.0313493495815248],
+    [
+        0.2880711282292934,
+        0.7118432178101014,
+        0.00008565396060525902,
+    ],
+    [0.0, 0.0, 0.8251046025104601],
+];
+
+const REC2020_TO_XYZ: Matrix = [
+    [0.6369580483012914, 0.14461690358620832, 0.1688809751641721],
+    [0.2627002120112671, 0.6779980715188708, 0.05930171646986196],
+    [0.0, 0.028072693049087428, 1.060985057710791],
+];
+
+// Bradford chromatic adaptation
+const D50_TO_D65: Matrix = [
+    [0.955473421488075, -0.02309845494876471, 0.06325924320057072],
+    [
+        -0.0283697093338637,
+        1.0099953980813041,
+        0.021041441191917323,
+    ],
+    [
+        0.012314014864481998,
+        -0.020507649298898964,
+        1.330365926242124,
+    ],
+];
+
+const XYZ_TO_LMS: Matrix = [
+    [0.819022437996703, 0.3619062600528904, -0.1288737815209879],
+    [0.0329836539323885, 0.9292868615863434, 0.0361446663506424],
+    [0.0481771893596242, 0.2642395317527308, 0.6335478284694309],
+];
+
+const LMS_TO_OKLAB: Matrix = [
+    [0.210454268309314, 0.7936177747023054, -0.0040720430116193],
+    [1.9779985324311684, -2.42859224204858, 0.450593709617411],
+    [0.0259040424655478, 0.7827717124575296, -0.8086757549230774],
+];
+
+// The D50 white point, for lab() and lch()
+const D50: [f64; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Space {
+    Srgb,
+    SrgbLinear,
+    DisplayP3,
+    A98Rgb,
+    ProphotoRgb,
+    Rec2020,
+    XyzD50,
+    XyzD65,
+    Lab,
+    Lch,
+    Oklab,
+    Oklch,
+    Hsl,
+    Hwb,
+}
+
+// The spaces color() takes, color-mix() takes these and the ones with functions of their own
+const PREDEFINED: [(&str, Space); 9] = [
+    ("srgb", Space::Srgb),
+    ("srgb-linear", Space::SrgbLinear),
+    ("display-p3", Space::DisplayP3),
+    ("a98-rgb", Space::A98Rgb),
+    ("prophoto-rgb", Space::ProphotoRgb),
+    ("rec2020", Space::Rec2020),
+    ("xyz", Space::XyzD65),
+    ("xyz-d50", Space::XyzD50),
+    ("xyz-d65", Space::XyzD65),
+];
+
+// How a channel is written
+#[derive(Clone, Copy)]
+enum Channel {
+    // A number, or a percentage of the given number
+    Number(f64),
+    Hue,
+    Alpha,
+}
+
+impl Space {
+    fn predefined(name: &str) -> Option<Space> {
+        PREDEFINED
+            .iter()
+            .find(|(space, _)| name.eq_ignore_ascii_case(space))
+            .map(|(_, space)| *space)
+    }
+
+    fn interpolation(name: &str) -> Option<Space> {
+        match name.to_ascii_lowercase().as_str() {
+            "hsl" => Some(Space::Hsl),
+            "hwb" => Some(Space::Hwb),
+            "lab" => Some(Space::Lab),
+            "lch" => Some(Space::Lch),
+            "oklab" => Some(Space::Oklab),
+            "oklch" => Some(Space::Oklch),
+            name => Space::predefined(name),
+        }
+    }
+
+    // Which channel is the hue, for polar spaces
+    fn hue(self) -> Option<usize> {
+        match self {
+            Space::Hsl | Space::Hwb => Some(0),
+            Space::Lch | Space::Oklch => Some(2),
+            _ => None,
+        }
+    }
+
+    // The names of the channels in relative colors
+    fn names(self) -> [&'static str; 3] {
+        match self {
+            Space::XyzD50 | Space::XyzD65 => ["x", "y", "z"],
+            Space::Lab | Space::Oklab => ["l", "a", "b"],
+            Space::Lch | Space::Oklch => ["l", "c", "h"],
+            Space::Hsl => ["h", "s", "l"],
+            Space::Hwb => ["h", "w", "b"],
+            _ => ["r", "g", "b"],
+        }
+    }
+
+    fn channels(self) -> [Channel; 3] {
+        match self {
+            Space::Hsl | Space::Hwb => {
+                [Channel::Hue, Channel::Number(100.0), Channel::Number(100.0)]
+            }
+            Space::Lab => [
+                Channel::Number(100.0),
+                Channel::Number(125.0),
+                Channel::Number(125.0),
+            ],
+            Space::Lch => [Channel::Number(100.0), Channel::Number(150.0), Channel::Hue],
+            Space::Oklab => [
+                Channel::Number(1.0),
+                Channel::Number(0.4),
+                Channel::Number(0.4),
+            ],
+            Space::Oklch => [Channel::Number(1.0), Channel::Number(0.4), Channel::Hue],
+            _ => [Channel::Number(1.0); 3],
+        }
+    }
+}
+
+fn multiply(matrix: &Matrix, vector: [f64; 3]) -> [f64; 3] {
+    matrix.map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+fn invert(m: &Matrix) -> Matrix {
+    let cofactor = |row: usize, column: usize| {
+        let (r1, r2) = ((row + 1) % 3, (row + 2) % 3);
+        let (c1, c2) = ((column + 1) % 3, (column + 2) % 3);
+        m[r1][c1] * m[r2][c2] - m[r1][c2] * m[r2][c1]
+    };
+    let determinant = (0..3)
+        .map(|column| m[0][column] * cofactor(0, column))
+        .sum::<f64>();
+
+    [0, 1, 2].map(|row| [0, 1, 2].map(|column| cofactor(column, row) / determinant))
+}
+
+// Transfer functions, between gamma encoded and linear light
+fn srgb_to_linear(c: f64) -> f64 {
+    if c.abs() <= 0.04045 {
+        c / 12.92
+    } else {
+        c.signum() * ((c.abs() + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn srgb_from_linear(c: f64) -> f64 {
+    if c.abs() > 0.0031308 {
+        c.signum() * (1.055 * c.abs().powf(1.0 / 2.4) - 0.055)
+    } else {
+        12.92 * c
+    }
+}
+
+fn a98_to_linear(c: f64) -> f64 {
+    c.signum() * c.abs().powf(563.0 / 256.0)
+}
+
+fn a98_from_linear(c: f64) -> f64 {
+    c.signum() * c.abs().powf(256.0 / 563.0)
+}
+
+fn prophoto_to_linear(c: f64) -> f64 {
+    if c.abs() <= 16.0 / 512.0 {
+        c / 16.0
+    } else {
+        c.signum() * c.abs().powf(1.8)
+    }
+}
+
+fn prophoto_from_linear(c: f64) -> f64 {
+    if c.abs() >= 1.0 / 512.0 {
+        c.signum() * c.abs().powf(1.0 / 1.8)
+    } else {
+        16.0 * c
+    }
+}
+
+const REC2020_ALPHA: f64 = 1.09929682680944;
+const REC2020_BETA: f64 = 0.018053968510807;
+
+fn rec2020_to_linear(c: f64) -> f64 {
+    if c.abs() < REC2020_BETA * 4.5 {
+        c / 4.5
+    } else {
+        c.signum() * ((c.abs() + REC2020_ALPHA - 1.0) / REC2020_ALPHA).powf(1.0 / 0.45)
+    }
+}
+
+fn rec2020_from_linear(c: f64) -> f64 {
+    if c.abs() > REC2020_BETA {
+        c.signum() * (REC2020_ALPHA * c.abs().powf(0.45) - (REC2020_ALPHA - 1.0))
+    } else {
+        4.5 * c
+    }
+}
+
+// CIE Lab, relative to D50
+const KAPPA: f64 = 24389.0 / 27.0;
+const EPSILON: f64 = 216.0 / 24389.0;
+
+fn lab_to_xyz([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let f1 = (l + 16.0) / 116.0;
+    let f0 = a / 500.0 + f1;
+    let f2 = f1 - b / 200.0;
+    let cube = |f: f64| {
+        if f.powi(3) > EPSILON {
+            f.powi(3)
+        } else {
+            (116.0 * f - 16.0) / KAPPA
+        }
+    };
+    let y = if l > KAPPA * EPSILON {
+        f1.powi(3)
+    } else {
+        l / KAPPA
+    };
+
+    [cube(f0) * D50[0], y * D50[1], cube(f2) * D50[2]]
+}
+
+fn xyz_to_lab(xyz: [f64; 3]) -> [f64; 3] {
+    let [f0, f1, f2] = [0, 1, 2].map(|index| {
+        let value = xyz[index] / D50[index];
+        if value > EPSILON {
+            value.cbrt()
+        } else {
+            (KAPPA * value + 16.0) / 116.0
+        }
+    });
+
+    [116.0 * f1 - 16.0, 500.0 * (f0 - f1), 200.0 * (f1 - f2)]
+}
+
+fn oklab_to_xyz(oklab: [f64; 3]) -> [f64; 3] {
+    let lms = multiply(&invert(&LMS_TO_OKLAB), oklab).map(|c| c.powi(3));
+    multiply(&invert(&XYZ_TO_LMS), lms)
+}
+
+fn xyz_to_oklab(xyz: [f64; 3]) -> [f64; 3] {
+    multiply(&LMS_TO_OKLAB, multiply(&XYZ_TO_LMS, xyz).map(f64::cbrt))
+}
+
+// Lab to LCH, the hue is missing (powerless) when there's (next to) no chroma
+fn polar([l, a, b]: [f64; 3], achromatic: f64) -> [f64; 3] {
+    let chroma = (a * a + b * b).sqrt();
+    let hue = if chroma < achromatic {
+        f64::NAN
+    } else {
+        b.atan2(a).to_degrees().rem_euclid(360.0)
+    };
+
+    [l, chroma, hue]
+}
+
+fn rectangular([l, chroma, hue]: [f64; 3]) -> [f64; 3] {
+    let hue = if hue.is_nan() { 0.0 } else { hue.to_radians() };
+    [l, chroma * hue.cos(), chroma * hue.sin()]
+}
+
+fn hsl_to_srgb([hue, saturation, lightness]: [f64; 3]) -> [f64; 3] {
+    let hue = hue.rem_euclid(360.0);
+    let (saturation, lightness) = (saturation / 100.0, lightness / 100.0);
+    let a = saturation * lightness.min(1.0 - lightness);
+
+    [0.0, 8.0, 4.0].map(|n: f64| {
+        let k = (n + hue / 30.0) % 12.0;
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    })
+}
+
+fn srgb_to_hsl([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+
+    let mut hue = f64::NAN;
+    let mut saturation = 0.0;
+
+    if delta > 1e-9 {
+        if lightness > 0.0 && lightness < 1.0 {
+            saturation = (max - lightness) / lightness.min(1.0 - lightness);
+        }
+
+        hue = 60.0
+            * if max == r {
+                (g - b) / delta + if g < b { 6.0 } else { 0.0 }
+            } else if max == g {
+                (b - r) / delta + 2.0
+            } else {
+                (r - g) / delta + 4.0
+            };
+    }
+
+    // Colors outside sRGB can end up with a negative saturation
+    if saturation < 0.0 {
+        hue += 180.0;
+        saturation = -saturation;
+    }
+
+    [hue.rem_euclid(360.0), saturation * 100.0, lightness * 100.0]
+}
+
+fn hwb_to_srgb([hue, white, black]: [f64; 3]) -> [f64; 3] {
+    let (white, black) = (white / 100.0, black / 100.0);
+
+    if white + black >= 1.0 {
+        return [white / (white + black); 3];
+    }
+
+    hsl_to_srgb([hue, 100.0, 50.0]).map(|c| c * (1.0 - white - black) + white)
+}
+
+fn srgb_to_hwb(rgb: [f64; 3]) -> [f64; 3] {
+    let [hue, _, _] = srgb_to_hsl(rgb);
+    let white = rgb[0].min(rgb[1]).min(rgb[2]);
+    let black = 1.0 - rgb[0].max(rgb[1]).max(rgb[2]);
+
+    [hue, white * 100.0, black * 100.0]
+}
+
+// A color in the space it was written in
+#[derive(Clone, Copy)]
+struct Color {
+    space: Space,
+
+    // Missing channels (none) are NaN
+    channels: [f64; 3],
+    alpha: f64,
+}
+
+impl Color {
+    fn srgb(channels: [f64; 3], alpha: f64) -> Color {
+        Color {
+            space: Space::Srgb,
+            channels,
+            alpha,
+        }
+    }
+
+    // In XYZ, relative to D65
+    fn xyz(&self) -> [f64; 3] {
+        let c = self.channels.map(|c| if c.is_nan() { 0.0 } else { c });
+
+        match self.space {
+            Space::Srgb => multiply(&SRGB_TO_XYZ, c.map(srgb_to_linear)),
+            Space::SrgbLinear => multiply(&SRGB_TO_XYZ, c),
+            Space::DisplayP3 => multiply(&P3_TO_XYZ, c.map(srgb_to_linear)),
+            Space::A98Rgb => multiply(&A98_TO_XYZ, c.map(a98_to_linear)),
+            Space::ProphotoRgb => multiply(
+                &D50_TO_D65,
+                multiply(&PROPHOTO_TO_XYZ_D50, c.map(prophoto_to_linear)),
+            ),
+            Space::Rec2020 => multiply(&REC2020_TO_XYZ, c.map(rec2020_to_linear)),
+            Space::XyzD50 => multiply(&D50_TO_D65, c),
+            Space::XyzD65 => c,
+            Space::Lab => multiply(&D50_TO_D65, lab_to_xyz(c)),
+            Space::Lch => multiply(&D50_TO_D65, lab_to_xyz(rectangular(c))),
+            Space::Oklab => oklab_to_xyz(c),
+            Space::Oklch => oklab_to_xyz(rectangular(c)),
+            Space::Hsl => multiply(&SRGB_TO_XYZ, hsl_to_srgb(c).map(srgb_to_linear)),
+            Space::Hwb => multiply(&SRGB_TO_XYZ, hwb_to_srgb(c).map(srgb_to_linear)),
+        }
+    }
+
+    fn convert(&self, space: Space) -> Color {
+        if space == self.space {
+            return *self;
+        }
+
+        let xyz = self.xyz();
+        let srgb = || multiply(&invert(&SRGB_TO_XYZ), xyz).map(srgb_from_linear);
+        let d50 = || multiply(&invert(&D50_TO_D65), xyz);
+
+        let channels = match space {
+            Space::Srgb => srgb(),
+            Space::SrgbLinear => multiply(&invert(&SRGB_TO_XYZ), xyz),
+            Space::DisplayP3 => multiply(&invert(&P3_TO_XYZ), xyz).map(srgb_from_linear),
+            Space::A98Rgb => multiply(&invert(&A98_TO_XYZ), xyz).map(a98_from_linear),
+            Space::ProphotoRgb => {
+                multiply(&invert(&PROPHOTO_TO_XYZ_D50), d50()).map(prophoto_from_linear)
+            }
+            Space::Rec2020 => multiply(&invert(&REC2020_TO_XYZ), xyz).map(rec2020_from_linear),
+            Space::XyzD50 => d50(),
+            Space::XyzD65 => xyz,
+            Space::Lab => xyz_to_lab(d50()),
+            Space::Lch => polar(xyz_to_lab(d50()), 0.0015),
+            Space::Oklab => xyz_to_oklab(xyz),
+            Space::Oklch => polar(xyz_to_oklab(xyz), 0.000004),
+            Space::Hsl => srgb_to_hsl(srgb()),
+            Space::Hwb => srgb_to_hwb(srgb()),
+        };
+
+        Color {
+            space,
+            channels,
+            alpha: self.alpha,
+        }
+    }
+
+    // Clamps channels to their valid ranges when parsed (CSS Color 4 § 4.3 Out of range values)
+    fn clamp(mut self) -> Color {
+        let c = &mut self.channels;
+
+        match self.space {
+            Space::Srgb => *c = c.map(|c| c.clamp(0.0, 1.0)),
+            Space::Hsl => {
+                c[1] = c[1].max(0.0);
+                c[2] = c[2].clamp(0.0, 100.0);
+            }
+            Space::Hwb => {
+                c[1] = c[1].clamp(0.0, 100.0);
+                c[2] = c[2].clamp(0.0, 100.0);
+            }
+            Space::Lab | Space::Lch => c[0] = c[0].clamp(0.0, 100.0),
+            Space::Oklab | Space::Oklch => c[0] = c[0].clamp(0.0, 1.0),
+            _ => (),
+        }
+
+        if matches!(self.space, Space::Lch | Space::Oklch) {
+            c[1] = c[1].max(0.0);
+        }
+
+        self.alpha = self.alpha.clamp(0.0, 1.0);
+        self
+    }
+
+    // In sRGB, gamut mapped (CSS Color 4 § 13.2.2 CSS gamut mapping to an RGB destination)
+    fn mapped(&self) -> [f64; 3] {
+        const JND: f64 = 0.02;
+        const MINIMUM: f64 = 0.0001;
+
+        let in_gamut = |c: &[f64; 3]| c.iter().all(|c| (-0.000001..=1.000001).contains(c));
+        let clip = |c: [f64; 3]| c.map(|c| c.clamp(0.0, 1.0));
+
+        // The color clipped to sRGB, and how far that is from it (deltaEOK)
+        let clipped = |color: &Color| {
+            let clipped = clip(color.convert(Space::Srgb).channels);
+            let [l1, a1, b1] = color.convert(Space::Oklab).channels;
+            let [l2, a2, b2] = Color::srgb(clipped, 1.0).convert(Space::Oklab).channels;
+            let delta = ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt();
+
+            (clipped, delta)
+        };
+
+        let rgb = self.convert(Space::Srgb).channels;
+        if in_gamut(&rgb) {
+            return clip(rgb);
+        }
+
+        let mut current = self.convert(Space::Oklch);
+        if current.channels[0] >= 1.0 {
+            return [1.0; 3];
+        }
+        if current.channels[0] <= 0.0 {
+            return [0.0; 3];
+        }
+
+        let (mut closest, delta) = clipped(&current);
+        if delta < JND {
+            return closest;
+        }
+
+        let mut min = 0.0;
+        let mut max = current.channels[1];
+        let mut min_in_gamut = true;
+
+        while max - min > MINIMUM {
+            let chroma = (min + max) / 2.0;
+            current.channels[1] = chroma;
+
+            if min_in_gamut && in_gamut(&current.convert(Space::Srgb).channels) {
+                min = chroma;
+                continue;
+            }
+
+            let (clip, delta) = clipped(&current);
+            closest = clip;
+
+            if delta < JND {
+                if JND - delta < MINIMUM {
+                    break;
+                }
+
+                min_in_gamut = false;
+                min = chroma;
+            } else {
+                max = chroma;
+            }
+        }
+
+        closest
+    }
+}
+
+// A function's arguments, without whitespace
+enum Argument<'a> {
+    Value(&'a ComponentValue),
+    Comma,
+    Slash,
+}
+
+fn arguments(values: &[ComponentValue]) -> Vec<Argument<'_>> {
+    values
+        .iter()
+        .filter_map(|value| match value {
+            ComponentValue::Token(CSSToken::Whitespace) => None,
+            ComponentValue::Token(CSSToken::Comma) => Some(Argument::Comma),
+            ComponentValue::Token(CSSToken::Delim('/')) => Some(Argument::Slash),
+            value => Some(Argument::Value(value)),
+        })
+        .collect()
+}
+
+fn is_ident(argument: &Argument, name: &str) -> bool {
+    matches!(argument, Argument::Value(ComponentValue::Token(CSSToken::Ident(ident))) if ident.eq_ignore_ascii_case(name))
+}
+
+// The channel keywords of a relative color
+type Keywords = Vec<(&'static str, f64)>;
+
+fn angle(value: f64, unit: &str) -> Option<f64> {
+    match unit.to_ascii_lowercase().as_str() {
+        "deg" => Some(value),
+        "rad" => Some(value.to_degrees()),
+        "grad" => Some(value * 0.9),
+        "turn" => Some(value * 360.0),
+        _ => None,
+    }
+}
+
+fn number(token: &CSSToken, channel: Channel, keywords: &Keywords) -> Option<f64> {
+    match (token, channel) {
+        (CSSToken::Number(value, _), _) => Some(*value),
+        (CSSToken::Percentage(value), Channel::Number(full)) => Some(value / 100.0 * full),
+        (CSSToken::Percentage(value), Channel::Alpha) => Some(value / 100.0),
+        (CSSToken::Dimension(value, _, unit), Channel::Hue) => angle(*value, unit),
+        (CSSToken::Ident(name), _) if name.eq_ignore_ascii_case("none") => Some(f64::NAN),
+        (CSSToken::Ident(name), _) => keywords
+            .iter()
+            .find(|(keyword, _)| name.eq_ignore_ascii_case(keyword))
+            .map(|(_, value)| *value),
+        _ => None,
+    }
+}
+
+// CSS Values 4 § 10 Mathematical Expressions, just the arithmetic relative colors need
+struct Calc<'a> {
+    values: Vec<&'a ComponentValue>,
+    index: usize,
+    channel: Channel,
+    keywords: &'a Keywords,
+}
+
+impl Calc<'_> {
+    fn evaluate(values: &[ComponentValue], channel: Channel, keywords: &Keywords) -> Option<f64> {
+        let mut calc = Calc {
+            values: values
+                .iter()
+                .filter(|value| !matches!(value, ComponentValue::Token(CSSToken::Whitespace)))
+                .collect(),
+            index: 0,
+            channel,
+            keywords,
+        };
+
+        let value = calc.sum()?;
+        if calc.index < calc.values.len() {
+            return None;
+        }
+
+        Some(value)
+    }
+
+    fn operator(&mut self, operators: [char; 2]) -> Option<char> {
+        match self.values.get(self.index) {
+            Some(ComponentValue::Token(CSSToken::Delim(delim))) if operators.contains(delim) => {
+                self.index += 1;
+                Some(*delim)
+            }
+            _ => None,
+        }
+    }
+
+    fn sum(&mut self) -> Option<f64> {
+        let mut value = self.product()?;
+
+        while let Some(operator) = self.operator(['+', '-']) {
+            let next = self.product()?;
+            value = if operator == '+' {
+                value + next
+            } else {
+                value - next
+            };
+        }
+
+        Some(value)
+    }
+
+    fn product(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+
+        while let Some(operator) = self.operator(['*', '/']) {
+            let next = self.term()?;
+            value = if operator == '*' {
+                value * next
+            } else {
+                value / next
+            };
+        }
+
+        Some(value)
+    }
+
+    fn term(&mut self) -> Option<f64> {
+        let value = self.values.get(self.index)?;
+        self.index += 1;
+
+        match value {
+            ComponentValue::Token(CSSToken::Ident(name)) if name.eq_ignore_ascii_case("pi") => {
+                Some(std::f64::consts::PI)
+            }
+            ComponentValue::Token(CSSToken::Ident(name)) if name.eq_ignore_ascii_case("e") => {
+                Some(std::f64::consts::E)
+            }
+            ComponentValue::Token(CSSToken::Ident(name)) if name.eq_ignore_ascii_case("none") => {
+                None
+            }
+            ComponentValue::Token(token) => number(token, self.channel, self.keywords),
+            ComponentValue::Block(block) if matches!(block.token, CSSToken::LeftParentheses) => {
+                Calc::evaluate(&block.value, self.channel, self.keywords)
+            }
+            ComponentValue::Function(function) if function.name.eq_ignore_ascii_case("calc") => {
+                Calc::evaluate(&function.value, self.channel, self.keywords)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn channel(argument: &Argument, channel: Channel, keywords: &Keywords) -> Option<f64> {
+    match argument {
+        Argument::Value(ComponentValue::Token(token)) => number(token, channel, keywords),
+        Argument::Value(ComponentValue::Function(function))
+            if function.name.eq_ignore_ascii_case("calc") =>
+        {
+            Calc::evaluate(&function.value, channel, keywords)
+        }
+        _ => None,
+    }
+}
+
+// The color a relative color is based on, and the arguments after it
+fn origin<'a, 'b>(arguments: &'b [Argument<'a>]) -> Option<(Option<Color>, &'b [Argument<'a>])> {
+    match arguments {
+        [from, Argument::Value(origin), rest @ ..] if is_ident(from, "from") => {
+            Some((Some(parse(origin)?), rest))
+        }
+        _ => Some((None, arguments)),
+    }
+}
+
+// The channels and alpha of a color function, scale is what 100% of an RGB channel is (255 for rgb())
+fn channels(
+    space: Space,
+    scale: f64,
+    origin: Option<Color>,
+    arguments: &[Argument],
+    legacy: bool,
+) -> Option<Color> {
+    let mut keywords: Keywords = Vec::new();
+    if let Some(origin) = origin {
+        let origin = origin.convert(space);
+        let zero = |c: f64| if c.is_nan() { 0.0 } else { c };
+
+        for (name, value) in space.names().iter().zip(origin.channels) {
+            let scale = if space == Space::Srgb { scale } else { 1.0 };
+            keywords.push((*name, zero(value) * scale));
+        }
+        keywords.push(("alpha", zero(origin.alpha)));
+    }
+
+    let (values, alpha): (Vec<&Argument>, Option<&Argument>) = match arguments {
+        [a, b, c] => (vec![a, b, c], None),
+        [a, b, c, Argument::Slash, alpha] => (vec![a, b, c], Some(alpha)),
+
+        // The legacy syntax, with commas: rgba(0, 0, 0, 0.5)
+        [a, Argument::Comma, b, Argument::Comma, c, rest @ ..] if legacy && origin.is_none() => {
+            match rest {
+                [] => (vec![a, b, c], None),
+                [Argument::Comma, alpha] => (vec![a, b, c], Some(alpha)),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    if values
+        .iter()
+        .chain(alpha.iter())
+        .any(|value| !matches!(value, Argument::Value(_)))
+    {
+        return None;
+    }
+
+    let mut channels = [0.0; 3];
+    for ((value, kind), channel) in values.iter().zip(space.channels()).zip(channels.iter_mut()) {
+        *channel = match kind {
+            Channel::Number(full) if space == Space::Srgb => {
+                self::channel(value, Channel::Number(full * scale), &keywords)? / scale
+            }
+            kind => self::channel(value, kind, &keywords)?,
+        };
+    }
+
+    let alpha = match alpha {
+        Some(alpha) => self::channel(alpha, Channel::Alpha, &keywords)?,
+        None => origin.map_or(1.0, |origin| origin.alpha),
+    };
+
+    Some(
+        Color {
+            space,
+            channels,
+            alpha,
+        }
+        .clamp(),
+    )
+}
+
+// CSS Color 5 § 2 Mixing Colors: the color-mix() Function
+fn mix(arguments: &[Argument]) -> Option<Color> {
+    let groups: Vec<&[Argument]> = arguments
+        .split(|argument| matches!(argument, Argument::Comma))
+        .collect();
+
+    let (method, first, second) = match groups.as_slice() {
+        [method, first, second] => (method, first, second),
+        _ => return None,
+    };
+
+    let name = |argument: &Argument| match argument {
+        Argument::Value(ComponentValue::Token(CSSToken::Ident(name))) => {
+            Some(name.to_ascii_lowercase())
+        }
+        _ => None,
+    };
+
+    let (space, hue) = match method {
+        [keyword, space] if is_ident(keyword, "in") => (name(space)?, String::from("shorter")),
+        [keyword, space, method, hue] if is_ident(keyword, "in") && is_ident(hue, "hue") => {
+            (name(space)?, name(method)?)
+        }
+        _ => return None,
+    };
+
+    let space = Space::interpolation(&space)?;
+    if !["shorter", "longer", "increasing", "decreasing"].contains(&hue.as_str())
+        || (hue != "shorter" && space.hue().is_none())
+    {
+        return None;
+    }
+
+    // A color and an optional percentage, in either order
+    let color = |group: &[Argument]| -> Option<(Color, Option<f64>)> {
+        let (color, percentage) = match group {
+            [Argument::Value(color)] => return Some((parse(color)?, None)),
+            [Argument::Value(color), Argument::Value(ComponentValue::Token(CSSToken::Percentage(p)))]
+            | [Argument::Value(ComponentValue::Token(CSSToken::Percentage(p))), Argument::Value(color)] => {
+                (color, *p)
+            }
+            _ => return None,
+        };
+
+        if !(0.0..=100.0).contains(&percentage) {
+            return None;
+        }
+
+        Some((parse(color)?, Some(percentage)))
+    };
+
+    let (a, p1) = color(first)?;
+    let (b, p2) = color(second)?;
+
+    let (p1, p2) = match (p1, p2) {
+        (None, None) => (50.0, 50.0),
+        (Some(p1), None) => (p1, 100.0 - p1),
+        (None, Some(p2)) => (100.0 - p2, p2),
+        (Some(p1), Some(p2)) => (p1, p2),
+    };
+
+    let total = p1 + p2;
+    if total == 0.0 {
+        return None;
+    }
+
+    let mut mixed = interpolate(a.convert(space), b.convert(space), p2 / total, &hue);
+
+    // Percentages adding up to less than 100% make the result transparent
+    if total < 100.0 {
+        mixed.alpha *= total / 100.0;
+    }
+
+    Some(mixed)
+}
+
+// CSS Color 4 § 12 Interpolation, with premultiplied alpha
+fn interpolate(a: Color, b: Color, t: f64, method: &str) -> Color {
+    // A missing channel takes the value from the other color
+    let fill = |x: f64, y: f64| match (x.is_nan(), y.is_nan()) {
+        (true, false) => (y, y),
+        (false, true) => (x, x),
+        _ => (x, y),
+    };
+    let lerp = |x: f64, y: f64| x + (y - x) * t;
+
+    let (alpha_a, alpha_b) = fill(a.alpha, b.alpha);
+    let alpha = lerp(alpha_a, alpha_b);
+
+    let mut channels = [0.0; 3];
+    for (index, channel) in channels.iter_mut().enumerate() {
+        let (mut x, mut y) = fill(a.channels[index], b.channels[index]);
+
+        if a.space.hue() == Some(index) {
+            // CSS Color 4 § 12.4 Hue Interpolation
+            let difference = y - x;
+            match method {
+                "longer" if difference > 0.0 && difference < 180.0 => x += 360.0,
+                "longer" if difference > -180.0 && difference <= 0.0 => y += 360.0,
+                "increasing" if y < x => y += 360.0,
+                "decreasing" if x < y => x += 360.0,
+                "shorter" if difference > 180.0 => x += 360.0,
+                "shorter" if difference < -180.0 => y += 360.0,
+                _ => (),
+            }
+
+            *channel = lerp(x, y).rem_euclid(360.0);
+        } else if alpha == 0.0 || alpha.is_nan() {
+            *channel = lerp(x, y);
+        } else {
+            *channel = lerp(x * alpha_a, y * alpha_b) / alpha;
+        }
+    }
+
+    Color {
+        space: a.space,
+        channels,
+        alpha,
+    }
+}
+
+fn hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digits: Vec<f64> = match hex.len() {
+        3 | 4 => hex
+            .chars()
+            .map(|ch| ch.to_digit(16).unwrap() as f64 * 17.0)
+            .collect(),
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap() as f64)
+            .collect(),
+        _ => return None,
+    };
+
+    Some(Color::srgb(
+        [digits[0] / 255.0, digits[1] / 255.0, digits[2] / 255.0],
+        digits.get(3).map_or(1.0, |alpha| alpha / 255.0),
+    ))
+}
+
+// CSS Color 4 § 6.1 Named Colors
+const NAMED: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+// CSS Color 4 § 6.2 System colors, and the deprecated ones § A.1 still keeps
+const SYSTEM: [&str; 42] = [
+    "accentcolor",
+    "accentcolortext",
+    "activetext",
+    "buttonborder",
+    "buttonface",
+    "buttontext",
+    "canvas",
+    "canvastext",
+    "field",
+    "fieldtext",
+    "graytext",
+    "highlight",
+    "highlighttext",
+    "linktext",
+    "mark",
+    "marktext",
+    "selecteditem",
+    "selecteditemtext",
+    "visitedtext",
+    "activeborder",
+    "activecaption",
+    "appworkspace",
+    "background",
+    "buttonhighlight",
+    "buttonshadow",
+    "captiontext",
+    "inactiveborder",
+    "inactivecaption",
+    "inactivecaptiontext",
+    "infobackground",
+    "infotext",
+    "menu",
+    "menutext",
+    "scrollbar",
+    "threeddarkshadow",
+    "threedface",
+    "threedhighlight",
+    "threedlightshadow",
+    "threedshadow",
+    "window",
+    "windowframe",
+    "windowtext",
+];
+
+fn named(name: &str) -> Option<Color> {
+    if name.eq_ignore_ascii_case("transparent") {
+        return Some(Color::srgb([0.0; 3], 0.0));
+    }
+
+    let (_, rgb) = NAMED
+        .iter()
+        .find(|(named, _)| name.eq_ignore_ascii_case(named))?;
+
+    Some(Color::srgb(
+        [16, 8, 0].map(|shift| ((rgb >> shift) & 0xff) as f64 / 255.0),
+        1.0,
+    ))
+}
+
+// Parses a color, None if it isn't one or depends on something only known in the browser
+fn parse(value: &ComponentValue) -> Option<Color> {
+    let function = match value {
+        ComponentValue::Token(CSSToken::Hash(hex, _)) => return self::hex(hex),
+        ComponentValue::Token(CSSToken::Ident(name)) => return named(name),
+        ComponentValue::Function(function) => function,
+        _ => return None,
+    };
+
+    let arguments = arguments(&function.value);
+    let name = function.name.to_ascii_lowercase();
+
+    if name == "color-mix" {
+        return mix(&arguments);
+    }
+
+    let (origin, arguments) = origin(&arguments)?;
+
+    match name.as_str() {
+        "rgb" | "rgba" => channels(Space::Srgb, 255.0, origin, arguments, true),
+        "hsl" | "hsla" => channels(Space::Hsl, 1.0, origin, arguments, true),
+        "hwb" => channels(Space::Hwb, 1.0, origin, arguments, false),
+        "lab" => channels(Space::Lab, 1.0, origin, arguments, false),
+        "lch" => channels(Space::Lch, 1.0, origin, arguments, false),
+        "oklab" => channels(Space::Oklab, 1.0, origin, arguments, false),
+        "oklch" => channels(Space::Oklch, 1.0, origin, arguments, false),
+        "color" => match arguments {
+            [Argument::Value(ComponentValue::Token(CSSToken::Ident(space))), rest @ ..] => {
+                channels(Space::predefined(space)?, 1.0, origin, rest, false)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Writes a color as hex, or rgba() when it's transparent
+fn serialize(color: &Color) -> ComponentValue {
+    let [r, g, b] = color.mapped().map(|c| (c * 255.0).round());
+    let alpha = if color.alpha.is_nan() {
+        0.0
+    } else {
+        color.alpha
+    };
+    let alpha = (alpha * 1000.0).round() / 1000.0;
+
+    if alpha < 1.0 {
+        let number =
+            |value: f64, flag: NumericFlag| ComponentValue::Token(CSSToken::Number(value, flag));
+
+        return ComponentValue::Function(Function {
+            name: String::from("rgba"),
+            value: vec![
+                number(r, NumericFlag::Integer),
+                ComponentValue::Token(CSSToken::Comma),
+                number(g, NumericFlag::Integer),
+                ComponentValue::Token(CSSToken::Comma),
+                number(b, NumericFlag::Integer),
+                ComponentValue::Token(CSSToken::Comma),
+                number(alpha, NumericFlag::Number),
+            ],
+        });
+    }
+
+    let (r, g, b) = (r as u8, g as u8, b as u8);
+    let hex = if [r, g, b].iter().all(|c| c >> 4 == c & 0xf) {
+        format!("{:x}{:x}{:x}", r & 0xf, g & 0xf, b & 0xf)
+    } else {
+        format!("{:02x}{:02x}{:02x}", r, g, b)
+    };
+
+    ComponentValue::Token(CSSToken::Hash(hex, HashFlag::Unrestricted))
+}
+
+const FUNCTIONS: [&str; 11] = [
+    "rgb",
+    "rgba",
+    "hsl",
+    "hsla",
+    "hwb",
+    "lab",
+    "lch",
+    "oklab",
+    "oklch",
+    "color",
+    "color-mix",
+];
+
+fn is_color(value: &ComponentValue) -> bool {
+    match value {
+        ComponentValue::Token(CSSToken::Hash(..)) => true,
+        ComponentValue::Function(function) => FUNCTIONS
+            .iter()
+            .any(|name| function.name.eq_ignore_ascii_case(name)),
+        _ => false,
+    }
+}
+
+/**
+ * Whether a value is a <color>: a hex color, a named or system one, currentcolor or a color function (whose arguments
+ * aren't checked, they could use var())
+ */
+pub fn is_valid(value: &ComponentValue) -> bool {
+    match value {
+        ComponentValue::Token(CSSToken::Hash(hex, _)) => self::hex(hex).is_some(),
+        ComponentValue::Token(CSSToken::Ident(name)) => {
+            name.eq_ignore_ascii_case("currentcolor")
+                || named(name).is_some()
+                || SYSTEM
+                    .iter()
+                    .any(|system| name.eq_ignore_ascii_case(system))
+        }
+        ComponentValue::Function(function) => {
+            is_color(value) || function.name.eq_ignore_ascii_case("light-dark")
+        }
+        ComponentValue::Token(_) | ComponentValue::Block(_) => false,
+    }
+}
+
+// The syntax a color uses that older browsers might not support
+fn features(value: &ComponentValue, used: &mut Vec<&'static Engines>) {
+    let function = match value {
+        ComponentValue::Token(CSSToken::Hash(hex, _)) if hex.len() == 4 || hex.len() == 8 => {
+            return used.push(&compat::HEX_ALPHA)
+        }
+        ComponentValue::Function(function) => function,
+        _ => return,
+    };
+
+    let arguments = arguments(&function.value);
+    let legacy = arguments
+        .iter()
+        .any(|argument| matches!(argument, Argument::Comma));
+
+    match function.name.to_ascii_lowercase().as_str() {
+        "rgb" | "rgba" | "hsl" | "hsla" if !legacy => used.push(&compat::SPACE_SEPARATED_COLORS),
+        "hwb" => used.push(&compat::HWB),
+        "lab" | "lch" | "oklab" | "oklch" => used.push(&compat::LAB),
+        "color" => used.push(&compat::COLOR_FUNCTION),
+        "color-mix" => used.push(&compat::COLOR_MIX),
+        _ => (),
+    }
+
+    if arguments
+        .first()
+        .is_some_and(|first| is_ident(first, "from"))
+    {
+        used.push(&compat::RELATIVE_COLORS);
+    }
+
+    for value in &function.value {
+        features(value, used);
+    }
+}
+
+// Replaces colors the targets don't support, returns whether anything changed
+fn lower(values: &mut [ComponentValue], targets: &[Target]) -> bool {
+    let mut changed = false;
+
+    for value in values.iter_mut() {
+        if is_color(value) {
+            let mut used: Vec<&Engines> = Vec::new();
+            features(value, &mut used);
+
+            if used.iter().any(|engines| compat::lacks(targets, engines)) {
+                if let Some(color) = parse(value) {
+                    *value = serialize(&color);
+                    changed = true;
+                }
+            }
+
+            continue;
+        }
+
+        match value {
+            ComponentValue::Function(function) => changed |= lower(&mut function.value, targets),
+            ComponentValue::Block(block) => changed |= lower(&mut block.value, targets),
+            ComponentValue::Token(_) => (),
+        }
+    }
+
+    changed
+}
+
+fn items(items: &mut Vec<BlockItem>, targets: &[Target], fallback: ColorFallback) {
+    let mut output: Vec<BlockItem> = Vec::new();
+
+    for item in items.drain(..) {
+        match item {
+            // Custom properties could be used anywhere, so they're left as written
+            BlockItem::Declaration(declaration) if declaration.name.starts_with("--") => {
+                output.push(BlockItem::Declaration(declaration))
+            }
+            BlockItem::Declaration(declaration) => {
+                let mut lowered = declaration.clone();
+
+                if lower(&mut lowered.value, targets) {
+                    output.push(BlockItem::Declaration(lowered));
+
+                    if fallback == ColorFallback::Both {
+                        output.push(BlockItem::Declaration(declaration));
+                    }
+                } else {
+                    output.push(BlockItem::Declaration(declaration));
+                }
+            }
+            BlockItem::Rule(mut rule) => {
+                self::rule(&mut rule, targets, fallback);
+                output.push(BlockItem::Rule(rule));
+            }
+        }
+    }
+
+    *items = output;
+}
+
+fn rule(rule: &mut Rule, targets: &[Target], fallback: ColorFallback) {
+    match rule {
+        Rule::Qualified(rule) => items(&mut rule.block, targets, fallback),
+        Rule::At(rule) => {
+            if let Some(block) = &mut rule.block {
+                items(block, targets, fallback);
+            }
+        }
+    }
+}
+
+/**
+ * Converts colors the targets don't support to ones they do
+ */
+pub fn stylesheet(stylesheet: &mut Stylesheet, targets: &[Target], fallback: ColorFallback) {
+    for rule in stylesheet.rules.iter_mut() {
+        self::rule(rule, targets, fallback);
+    }
+}
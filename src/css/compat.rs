@@ -0,0 +1,521 @@
+/**
+ * Browser compatibility data
+ *
+ * An offline table of when browsers started supporting CSS features, with and without a vendor prefix. Browsers are
+ * grouped by the engine they use, so Edge, Opera and Samsung Internet are looked up as the version of Chrome they're
+ * built on, and Safari on iOS as Safari.
+ *
+ * Versions are the first to support a feature, from MDN and caniuse. NEVER means not (yet) supported.
+ */
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub struct Version(pub u32, pub u32);
+
+const NEVER: Version = Version(u32::MAX, 0);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Engine {
+    Blink,
+    Gecko,
+    WebKit,
+}
+
+impl Engine {
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Engine::Gecko => "-moz-",
+            Engine::Blink | Engine::WebKit => "-webkit-",
+        }
+    }
+}
+
+// The first version supporting a feature with a prefix, and without one
+#[derive(Clone, Copy)]
+pub struct Support {
+    prefixed: Version,
+    unprefixed: Version,
+}
+
+// Support in Blink, Gecko and WebKit
+pub type Engines = [Support; 3];
+
+const fn support(prefixed: (u32, u32), unprefixed: (u32, u32)) -> Support {
+    Support {
+        prefixed: Version(prefixed.0, prefixed.1),
+        unprefixed: Version(unprefixed.0, unprefixed.1),
+    }
+}
+
+// Supported since a version, never with a prefix
+const fn since(major: u32, minor: u32) -> Support {
+    support((major, minor), (major, minor))
+}
+
+const NOT_SUPPORTED: Support = Support {
+    prefixed: NEVER,
+    unprefixed: NEVER,
+};
+
+const fn prefixed(major: u32, minor: u32) -> Support {
+    Support {
+        prefixed: Version(major, minor),
+        unprefixed: NEVER,
+    }
+}
+
+const ANIMATION: Engines = [
+    support((4, 0), (43, 0)),
+    support((5, 0), (16, 0)),
+    support((4, 0), (9, 0)),
+];
+const TRANSITION: Engines = [
+    support((1, 0), (26, 0)),
+    support((4, 0), (16, 0)),
+    support((3, 1), (6, 1)),
+];
+const TRANSFORM: Engines = [
+    support((4, 0), (36, 0)),
+    support((3, 5), (16, 0)),
+    support((3, 1), (9, 0)),
+];
+const TRANSFORM_3D: Engines = [
+    support((12, 0), (36, 0)),
+    support((10, 0), (16, 0)),
+    support((4, 0), (9, 0)),
+];
+const FLEXBOX: Engines = [
+    support((21, 0), (29, 0)),
+    since(22, 0),
+    support((6, 1), (9, 0)),
+];
+const MASK: Engines = [
+    support((1, 0), (120, 0)),
+    since(53, 0),
+    support((3, 1), (15, 4)),
+];
+const COLUMNS: Engines = [
+    support((1, 0), (50, 0)),
+    support((2, 0), (52, 0)),
+    support((3, 0), (9, 0)),
+];
+const TEXT_EMPHASIS: Engines = [
+    support((25, 0), (99, 0)),
+    since(46, 0),
+    support((6, 1), (7, 0)),
+];
+
+// Properties that have (had) a vendor prefixed version
+pub const PROPERTIES: [(&str, Engines); 56] = [
+    ("animation", ANIMATION),
+    ("animation-delay", ANIMATION),
+    ("animation-direction", ANIMATION),
+    ("animation-duration", ANIMATION),
+    ("animation-fill-mode", ANIMATION),
+    ("animation-iteration-count", ANIMATION),
+    ("animation-name", ANIMATION),
+    ("animation-play-state", ANIMATION),
+    ("animation-timing-function", ANIMATION),
+    ("transition", TRANSITION),
+    ("transition-delay", TRANSITION),
+    ("transition-duration", TRANSITION),
+    ("transition-property", TRANSITION),
+    ("transition-timing-function", TRANSITION),
+    ("transform", TRANSFORM),
+    ("transform-origin", TRANSFORM),
+    ("transform-style", TRANSFORM_3D),
+    ("perspective", TRANSFORM_3D),
+    ("perspective-origin", TRANSFORM_3D),
+    (
+        "backface-visibility",
+        [
+            support((12, 0), (36, 0)),
+            support((10, 0), (16, 0)),
+            support((4, 0), (15, 4)),
+        ],
+    ),
+    ("flex", FLEXBOX),
+    ("flex-basis", FLEXBOX),
+    ("flex-direction", FLEXBOX),
+    ("flex-flow", FLEXBOX),
+    ("flex-grow", FLEXBOX),
+    ("flex-shrink", FLEXBOX),
+    ("flex-wrap", FLEXBOX),
+    ("order", FLEXBOX),
+    ("align-content", FLEXBOX),
+    ("align-items", FLEXBOX),
+    ("align-self", FLEXBOX),
+    ("justify-content", FLEXBOX),
+    ("mask", MASK),
+    ("mask-clip", MASK),
+    ("mask-image", MASK),
+    ("mask-origin", MASK),
+    ("mask-position", MASK),
+    ("mask-repeat", MASK),
+    ("mask-size", MASK),
+    ("columns", COLUMNS),
+    ("column-count", COLUMNS),
+    ("column-fill", COLUMNS),
+    ("column-rule", COLUMNS),
+    ("column-span", COLUMNS),
+    ("column-width", COLUMNS),
+    ("text-emphasis", TEXT_EMPHASIS),
+    ("text-emphasis-color", TEXT_EMPHASIS),
+    ("text-emphasis-position", TEXT_EMPHASIS),
+    ("text-emphasis-style", TEXT_EMPHASIS),
+    (
+        "user-select",
+        [
+            support((1, 0), (54, 0)),
+            support((2, 0), (69, 0)),
+            prefixed(3, 1),
+        ],
+    ),
+    (
+        "appearance",
+        [
+            support((1, 0), (84, 0)),
+            support((1, 0), (80, 0)),
+            support((3, 1), (15, 4)),
+        ],
+    ),
+    (
+        "backdrop-filter",
+        [since(76, 0), since(103, 0), support((9, 0), (18, 0))],
+    ),
+    (
+        "hyphens",
+        [
+            support((55, 0), (88, 0)),
+            support((6, 0), (43, 0)),
+            support((5, 1), (17, 0)),
+        ],
+    ),
+    (
+        "box-decoration-break",
+        [support((22, 0), (130, 0)), since(32, 0), prefixed(7, 0)],
+    ),
+    (
+        "print-color-adjust",
+        [prefixed(17, 0), since(97, 0), support((6, 0), (15, 4))],
+    ),
+    (
+        "tab-size",
+        [since(21, 0), support((4, 0), (91, 0)), since(7, 0)],
+    ),
+];
+
+const INTRINSIC: [&str; 6] = [
+    "width",
+    "min-width",
+    "max-width",
+    "height",
+    "min-height",
+    "max-height",
+];
+
+// Keywords that have (had) a vendor prefixed version, and the properties they're used with
+pub const VALUES: [(&str, &[&str], Engines); 8] = [
+    (
+        "sticky",
+        &["position"],
+        [since(56, 0), since(32, 0), support((6, 1), (13, 0))],
+    ),
+    (
+        "fit-content",
+        &INTRINSIC,
+        [
+            support((22, 0), (46, 0)),
+            support((3, 0), (94, 0)),
+            support((6, 1), (11, 0)),
+        ],
+    ),
+    (
+        "max-content",
+        &INTRINSIC,
+        [
+            support((22, 0), (46, 0)),
+            support((3, 0), (66, 0)),
+            support((6, 1), (11, 0)),
+        ],
+    ),
+    (
+        "min-content",
+        &INTRINSIC,
+        [
+            support((22, 0), (46, 0)),
+            support((3, 0), (66, 0)),
+            support((6, 1), (11, 0)),
+        ],
+    ),
+    ("flex", &["display"], FLEXBOX),
+    ("inline-flex", &["display"], FLEXBOX),
+    (
+        "grab",
+        &["cursor"],
+        [
+            support((4, 0), (68, 0)),
+            support((2, 0), (27, 0)),
+            support((4, 0), (11, 0)),
+        ],
+    ),
+    (
+        "grabbing",
+        &["cursor"],
+        [
+            support((4, 0), (68, 0)),
+            support((2, 0), (27, 0)),
+            support((4, 0), (11, 0)),
+        ],
+    ),
+];
+
+pub const KEYFRAMES: Engines = ANIMATION;
+
+// Color syntax
+pub const HEX_ALPHA: Engines = [since(62, 0), since(49, 0), since(10, 0)];
+pub const SPACE_SEPARATED_COLORS: Engines = [since(65, 0), since(52, 0), since(12, 1)];
+pub const HWB: Engines = [since(101, 0), since(96, 0), since(15, 0)];
+pub const LAB: Engines = [since(111, 0), since(113, 0), since(15, 4)];
+pub const COLOR_FUNCTION: Engines = [since(111, 0), since(113, 0), since(15, 0)];
+pub const COLOR_MIX: Engines = [since(111, 0), since(113, 0), since(16, 2)];
+pub const RELATIVE_COLORS: Engines = [since(122, 0), since(128, 0), since(18, 0)];
+
+// Nested style rules, including ones starting with an identifier (div { ... })
+pub const NESTING: Engines = [since(120, 0), since(117, 0), since(17, 2)];
+
+// :is(), which flattened nested rules wrap parent selectors in
+pub const IS: Engines = [since(88, 0), since(78, 0), since(14, 0)];
+
+// @layer, and layer() in @import
+pub const LAYERS: Engines = [since(99, 0), since(97, 0), since(15, 4)];
+
+const LOGICAL: Engines = [since(87, 0), since(66, 0), since(14, 1)];
+
+// Properties that can be written another way for browsers without them
+pub const FALLBACKS: [(&str, Engines); 8] = [
+    ("inset", LOGICAL),
+    ("margin-block", LOGICAL),
+    ("margin-inline", LOGICAL),
+    ("padding-block", LOGICAL),
+    ("padding-inline", LOGICAL),
+    ("place-content", [since(59, 0), since(53, 0), since(9, 0)]),
+    ("place-items", [since(59, 0), since(45, 0), since(11, 0)]),
+    ("place-self", [since(59, 0), since(45, 0), since(11, 0)]),
+];
+
+// Font formats for @font-face src, from the smallest files to the largest. SVG fonts were dropped from Blink (38) and
+// embedded OpenType is only supported by Internet Explorer, which can't be targeted.
+pub const FONT_FORMATS: [(&str, Engines); 6] = [
+    ("woff2", [since(36, 0), since(39, 0), since(12, 0)]),
+    ("woff", [since(5, 0), since(3, 6), since(5, 1)]),
+    ("opentype", [since(4, 0), since(3, 5), since(3, 1)]),
+    ("truetype", [since(4, 0), since(3, 5), since(3, 1)]),
+    ("svg", [NOT_SUPPORTED, NOT_SUPPORTED, since(3, 2)]),
+    (
+        "embedded-opentype",
+        [NOT_SUPPORTED, NOT_SUPPORTED, NOT_SUPPORTED],
+    ),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Browser {
+    Chrome,
+    Edge,
+    Firefox,
+    Safari,
+    Ios,
+    Opera,
+    Samsung,
+}
+
+const BROWSERS: [(&str, Browser); 8] = [
+    ("chrome", Browser::Chrome),
+    ("edge", Browser::Edge),
+    ("firefox", Browser::Firefox),
+    ("safari", Browser::Safari),
+    ("ios", Browser::Ios),
+    ("ios_saf", Browser::Ios),
+    ("opera", Browser::Opera),
+    ("samsung", Browser::Samsung),
+];
+
+// The version of Chrome each version of Samsung Internet is based on
+const SAMSUNG: [(u32, u32); 22] = [
+    (4, 44),
+    (5, 51),
+    (6, 56),
+    (7, 59),
+    (8, 63),
+    (9, 67),
+    (10, 71),
+    (11, 75),
+    (12, 79),
+    (13, 83),
+    (14, 87),
+    (15, 90),
+    (16, 92),
+    (17, 96),
+    (18, 99),
+    (19, 102),
+    (20, 106),
+    (21, 110),
+    (22, 111),
+    (23, 115),
+    (24, 117),
+    (25, 121),
+];
+
+// A browser version to generate CSS for (chrome 100)
+#[derive(Clone, Copy)]
+pub struct Target {
+    browser: Browser,
+    version: Version,
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = BROWSERS
+            .iter()
+            .find(|(_, browser)| *browser == self.browser)
+            .map_or("", |(name, _)| *name);
+
+        match self.version {
+            Version(major, 0) => write!(f, "{} {}", name, major),
+            Version(major, minor) => write!(f, "{} {}.{}", name, major, minor),
+        }
+    }
+}
+
+impl Target {
+    /**
+     * Parses a target like "chrome 100" or "safari 15.4"
+     */
+    pub fn parse(text: &str) -> Result<Target, String> {
+        let mut parts = text.split_whitespace();
+
+        let (name, version) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(name), Some(version), None) => (name.to_ascii_lowercase(), version),
+            _ => {
+                return Err(format!(
+                    "\"{}\" should be a browser and a version, like \"chrome 100\"",
+                    text
+                ))
+            }
+        };
+
+        let browser = match BROWSERS.iter().find(|(browser, _)| *browser == name) {
+            Some((_, browser)) => *browser,
+            None => {
+                let names: Vec<&str> = BROWSERS.iter().map(|(name, _)| *name).collect();
+                return Err(format!(
+                    "Unknown browser \"{}\", expected one of {}",
+                    name,
+                    names.join(", ")
+                ));
+            }
+        };
+
+        let mut numbers = version.splitn(2, '.').map(|number| number.parse::<u32>());
+        let version = match (numbers.next(), numbers.next()) {
+            (Some(Ok(major)), None) => Version(major, 0),
+            (Some(Ok(major)), Some(Ok(minor))) => Version(major, minor),
+            _ => return Err(format!("Invalid version \"{}\" in \"{}\"", version, text)),
+        };
+
+        match browser {
+            Browser::Edge if version < Version(79, 0) => Err(String::from(
+                "Only Edge 79 and later (based on Chrome) can be targeted",
+            )),
+            Browser::Opera if version < Version(15, 0) => Err(String::from(
+                "Only Opera 15 and later (based on Chrome) can be targeted",
+            )),
+            Browser::Samsung if version < Version(4, 0) => Err(String::from(
+                "Only Samsung Internet 4 and later can be targeted",
+            )),
+            _ => Ok(Target { browser, version }),
+        }
+    }
+
+    // The engine and the version of it
+    fn engine(self) -> (Engine, Version) {
+        let Version(major, _) = self.version;
+
+        match self.browser {
+            Browser::Chrome | Browser::Edge => (Engine::Blink, self.version),
+            Browser::Opera => (Engine::Blink, Version(major + 14, 0)),
+            Browser::Samsung => {
+                let chrome = SAMSUNG
+                    .iter()
+                    .rev()
+                    .find(|(samsung, _)| *samsung <= major)
+                    .map_or(44, |(_, chrome)| *chrome);
+
+                (Engine::Blink, Version(chrome, 0))
+            }
+            Browser::Firefox => (Engine::Gecko, self.version),
+            Browser::Safari | Browser::Ios => (Engine::WebKit, self.version),
+        }
+    }
+}
+
+fn column(engine: Engine) -> usize {
+    match engine {
+        Engine::Blink => 0,
+        Engine::Gecko => 1,
+        Engine::WebKit => 2,
+    }
+}
+
+/**
+ * The prefixes some target needs for a feature
+ */
+pub fn prefixes(targets: &[Target], engines: &Engines) -> Vec<&'static str> {
+    let mut prefixes: Vec<&'static str> = Vec::new();
+
+    for target in targets {
+        let (engine, version) = target.engine();
+        let support = engines[column(engine)];
+
+        if support.prefixed <= version
+            && version < support.unprefixed
+            && !prefixes.contains(&engine.prefix())
+        {
+            prefixes.push(engine.prefix());
+        }
+    }
+
+    prefixes
+}
+
+/**
+ * Whether some target doesn't support a feature without a prefix
+ */
+pub fn lacks(targets: &[Target], engines: &Engines) -> bool {
+    targets.iter().any(|target| {
+        let (engine, version) = target.engine();
+        version < engines[column(engine)].unprefixed
+    })
+}
+
+/**
+ * Whether none of the targets support a feature without a prefix. Like `lacks`, support behind a prefix doesn't count,
+ * so it's meant for features that never had one (like font formats)
+ */
+pub fn unsupported(targets: &[Target], engines: &Engines) -> bool {
+    targets.iter().all(|target| {
+        let (engine, version) = target.engine();
+        version < engines[column(engine)].unprefixed
+    })
+}
+
+// Splits a vendor prefix off a name (-webkit-transform => (-webkit-, transform))
+pub fn unprefix(name: &str) -> (Option<&str>, &str) {
+    for prefix in ["-webkit-", "-moz-", "-ms-", "-o-"] {
+        if name.len() > prefix.len() && name[..prefix.len()].eq_ignore_ascii_case(prefix) {
+            return (Some(&name[..prefix.len()]), &name[prefix.len()..]);
+        }
+    }
+
+    (None, name)
+}
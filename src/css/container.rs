@@ -0,0 +1,759 @@
+/**
+ * Container queries
+ * https://www.w3.org/TR/css-contain-3/#container-rule
+ *
+ * @container preludes are read into a list of conditions, each with an optional container name and a query made of
+ * size features ((width > 400px)), style() and scroll-state() queries, not, and, and or. They're written back the
+ * shortest way:
+ *
+ *  - min- and max- features use the range syntax ((min-width: 400px) => (width>=400px))
+ *  - A lower and an upper bound on the same feature are joined ((width>=400px) and (width<800px) => (400px<=width<800px))
+ *  - Extra parentheses are dropped, nested and / or lists are flattened and keywords are lowercased
+ *  - Whitespace is only kept where it's needed: around and and or, and after not, which would run into the parentheses
+ *    next to them ((a)or (b) isn't read as a query)
+ *
+ * Preludes that can't be read are left as they are. problems() checks the queries against the container-name,
+ * container-type and container declarations of the stylesheet, to find names no container has, and size queries no
+ * container-type allows.
+ */
+use super::parse::{consume, tree::*};
+use super::serialize::needs_separator;
+use super::token::tokens::CSSToken;
+
+// § 6.1 Size features, whether they can be compared, and whether they need container-type: size
+const FEATURES: [(&str, bool, bool); 6] = [
+    ("aspect-ratio", true, true),
+    ("block-size", true, true),
+    ("height", true, true),
+    ("inline-size", true, false),
+    ("orientation", false, true),
+    ("width", true, false),
+];
+
+/**
+ * One end of a range
+ */
+#[derive(Clone)]
+pub struct Bound {
+    pub value: Vec<ComponentValue>,
+    pub inclusive: bool,
+}
+
+/**
+ * A size feature
+ */
+pub enum Feature {
+    // (orientation)
+    Boolean(String),
+
+    // (orientation: portrait) or (width: 400px)
+    Plain(String, Vec<ComponentValue>),
+
+    // (400px <= width < 800px), with the lower and upper bounds of the feature
+    Range {
+        name: String,
+        lower: Option<Bound>,
+        upper: Option<Bound>,
+    },
+}
+
+/**
+ * A container query
+ */
+pub enum Condition {
+    Not(Box<Condition>),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Size(Feature),
+
+    // style(), scroll-state() and anything else browsers take as unknown, kept as written
+    Other(ComponentValue),
+}
+
+/**
+ * A container condition: which container it applies to, and what it has to match
+ */
+pub struct Query {
+    pub name: Option<String>,
+    pub condition: Option<Condition>,
+}
+
+fn is_whitespace(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Whitespace))
+}
+
+fn is_ident(value: &ComponentValue, ident: &str) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Ident(name)) if name.eq_ignore_ascii_case(ident))
+}
+
+fn feature(name: &str) -> Option<(bool, bool)> {
+    FEATURES
+        .iter()
+        .find(|(feature, _, _)| name.eq_ignore_ascii_case(feature))
+        .map(|(_, range, size)| (*range, *size))
+}
+
+// A value without the whitespace it doesn't need (16 / 9 => 16/9)
+fn compact(values: &[ComponentValue]) -> Vec<ComponentValue> {
+    let mut values = values.to_vec();
+    consume::trim(&mut values);
+
+    let first = |value: &ComponentValue| match value {
+        ComponentValue::Token(token) => token.clone(),
+        ComponentValue::Function(function) => CSSToken::Function(function.name.clone()),
+        ComponentValue::Block(block) => block.token.clone(),
+    };
+    let last = |value: &ComponentValue| match value {
+        ComponentValue::Token(token) => token.clone(),
+        _ => CSSToken::RightParentheses,
+    };
+
+    (0..values.len())
+        .filter(|&index| {
+            !is_whitespace(&values[index])
+                || needs_separator(&last(&values[index - 1]), &first(&values[index + 1]))
+        })
+        .map(|index| values[index].clone())
+        .collect()
+}
+
+// A comparison in a range, and how many tokens it takes: (less, inclusive, equal)
+fn comparison(values: &[&ComponentValue]) -> Option<(Option<bool>, bool, usize)> {
+    let equals = matches!(
+        values.get(1),
+        Some(ComponentValue::Token(CSSToken::Delim('=')))
+    );
+
+    match values.first()? {
+        ComponentValue::Token(CSSToken::Delim('<')) => {
+            Some((Some(true), equals, 1 + equals as usize))
+        }
+        ComponentValue::Token(CSSToken::Delim('>')) => {
+            Some((Some(false), equals, 1 + equals as usize))
+        }
+        ComponentValue::Token(CSSToken::Delim('=')) => Some((None, true, 1)),
+        _ => None,
+    }
+}
+
+// § 6.1 A size feature, from what's inside its parentheses
+fn size_feature(values: &[ComponentValue]) -> Option<Feature> {
+    let parts: Vec<&ComponentValue> = values
+        .iter()
+        .filter(|value| !is_whitespace(value))
+        .collect();
+
+    match parts.as_slice() {
+        [ComponentValue::Token(CSSToken::Ident(name))] => {
+            feature(name)?;
+            return Some(Feature::Boolean(name.to_ascii_lowercase()));
+        }
+        [ComponentValue::Token(CSSToken::Ident(name)), ComponentValue::Token(CSSToken::Colon), ..] =>
+        {
+            let colon = values
+                .iter()
+                .position(|value| matches!(value, ComponentValue::Token(CSSToken::Colon)))?;
+            let value = compact(&values[colon + 1..]);
+            if value.is_empty() {
+                return None;
+            }
+
+            let name = name.to_ascii_lowercase();
+            let bound = |name: &str, lower: bool| {
+                let bound = Some(Bound {
+                    value: value.clone(),
+                    inclusive: true,
+                });
+                matches!(feature(name), Some((true, _))).then(|| Feature::Range {
+                    name: name.to_string(),
+                    lower: if lower { bound.clone() } else { None },
+                    upper: if lower { None } else { bound },
+                })
+            };
+
+            return match (name.strip_prefix("min-"), name.strip_prefix("max-")) {
+                (Some(name), _) => bound(name, true),
+                (_, Some(name)) => bound(name, false),
+                // Keywords like landscape aren't case-sensitive
+                _ => {
+                    feature(&name)?;
+                    let value = value
+                        .into_iter()
+                        .map(|value| match value {
+                            ComponentValue::Token(CSSToken::Ident(keyword)) => {
+                                ComponentValue::Token(CSSToken::Ident(keyword.to_ascii_lowercase()))
+                            }
+                            value => value,
+                        })
+                        .collect();
+
+                    Some(Feature::Plain(name, value))
+                }
+            };
+        }
+        _ => (),
+    }
+
+    // Values and comparisons, alternating
+    let mut sides: Vec<Vec<ComponentValue>> = vec![Vec::new()];
+    let mut comparisons: Vec<(Option<bool>, bool)> = Vec::new();
+    let mut index = 0;
+
+    while index < parts.len() {
+        match comparison(&parts[index..]) {
+            Some((less, inclusive, length)) => {
+                comparisons.push((less, inclusive));
+                sides.push(Vec::new());
+                index += length;
+            }
+            None => {
+                sides.last_mut()?.push(parts[index].clone());
+                index += 1;
+            }
+        }
+    }
+
+    if sides.iter().any(|side| side.is_empty()) {
+        return None;
+    }
+
+    let name = |side: &[ComponentValue]| match side {
+        [ComponentValue::Token(CSSToken::Ident(name))]
+            if matches!(feature(name), Some((true, _))) =>
+        {
+            Some(name.to_ascii_lowercase())
+        }
+        _ => None,
+    };
+    let bound = |side: &[ComponentValue], inclusive: bool| {
+        Some(Bound {
+            value: compact(side),
+            inclusive,
+        })
+    };
+
+    match (sides.as_slice(), comparisons.as_slice()) {
+        // width = 400px
+        ([left, right], [(None, _)]) => match (name(left), name(right)) {
+            (Some(name), None) => Some(Feature::Plain(name, compact(right))),
+            (None, Some(name)) => Some(Feature::Plain(name, compact(left))),
+            _ => None,
+        },
+        // width < 400px, or 400px < width
+        ([left, right], [(Some(less), inclusive)]) => {
+            let (name, value, lower) = match (name(left), name(right)) {
+                (Some(name), None) => (name, right, !less),
+                (None, Some(name)) => (name, left, *less),
+                _ => return None,
+            };
+
+            Some(Feature::Range {
+                name,
+                lower: if lower {
+                    bound(value, *inclusive)
+                } else {
+                    None
+                },
+                upper: if lower {
+                    None
+                } else {
+                    bound(value, *inclusive)
+                },
+            })
+        }
+        // 400px <= width < 800px, or 800px > width >= 400px
+        ([first, middle, last], [(Some(less), first_inclusive), (Some(also), last_inclusive)])
+            if less == also =>
+        {
+            let name = name(middle)?;
+            let (lower, upper) = if *less {
+                (bound(first, *first_inclusive), bound(last, *last_inclusive))
+            } else {
+                (bound(last, *last_inclusive), bound(first, *first_inclusive))
+            };
+
+            Some(Feature::Range { name, lower, upper })
+        }
+        _ => None,
+    }
+}
+
+// § 5.1 <query-in-parens>
+fn query_in_parens(value: &ComponentValue) -> Option<Condition> {
+    match value {
+        ComponentValue::Block(block) if matches!(block.token, CSSToken::LeftParentheses) => {
+            let inner: Vec<&ComponentValue> = block
+                .value
+                .iter()
+                .filter(|value| !is_whitespace(value))
+                .collect();
+
+            // A nested query, or a size feature
+            let nested = matches!(
+                inner.first(),
+                Some(ComponentValue::Block(_) | ComponentValue::Function(_))
+            ) || inner.first().is_some_and(|value| is_ident(value, "not"));
+
+            if nested {
+                if let Some(condition) = condition(&inner) {
+                    return Some(condition);
+                }
+            }
+
+            Some(match size_feature(&block.value) {
+                Some(feature) => Condition::Size(feature),
+                None => Condition::Other(value.clone()),
+            })
+        }
+        ComponentValue::Function(_) => Some(Condition::Other(value.clone())),
+        _ => None,
+    }
+}
+
+// § 5.1 <container-query>, from its component values without whitespace
+fn condition(values: &[&ComponentValue]) -> Option<Condition> {
+    if values.first().is_some_and(|value| is_ident(value, "not")) {
+        return match values {
+            [_, value] => Some(Condition::Not(Box::new(query_in_parens(value)?))),
+            _ => None,
+        };
+    }
+
+    let first = query_in_parens(values.first()?)?;
+    if values.len() == 1 {
+        return Some(first);
+    }
+
+    // and and or can't be mixed without parentheses
+    let or = is_ident(values.get(1)?, "or");
+    let mut conditions: Vec<Condition> = Vec::new();
+
+    for (index, value) in values.iter().enumerate() {
+        let condition = match index {
+            0 => continue,
+            index if index % 2 == 1 => {
+                if !is_ident(value, if or { "or" } else { "and" }) {
+                    return None;
+                }
+                continue;
+            }
+            _ => query_in_parens(value)?,
+        };
+
+        conditions.push(condition);
+    }
+
+    if values.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let mut flat: Vec<Condition> = Vec::new();
+    for condition in std::iter::once(first).chain(conditions) {
+        match condition {
+            Condition::And(nested) if !or => flat.extend(nested),
+            Condition::Or(nested) if or => flat.extend(nested),
+            condition => flat.push(condition),
+        }
+    }
+
+    Some(if or {
+        Condition::Or(flat)
+    } else {
+        Condition::And(bounds(flat))
+    })
+}
+
+// Joins a lower and an upper bound on the same feature into one range
+fn bounds(conditions: Vec<Condition>) -> Vec<Condition> {
+    let mut joined: Vec<Condition> = Vec::new();
+
+    for condition in conditions {
+        if let Condition::Size(Feature::Range { name, lower, upper }) = condition {
+            let open = joined.iter_mut().find_map(|other| match other {
+                Condition::Size(Feature::Range {
+                    name: other,
+                    lower: other_lower,
+                    upper: other_upper,
+                }) if *other == name
+                    && (lower.is_none() || other_lower.is_none())
+                    && (upper.is_none() || other_upper.is_none()) =>
+                {
+                    Some((other_lower, other_upper))
+                }
+                _ => None,
+            });
+
+            match open {
+                Some((other_lower, other_upper)) => {
+                    if lower.is_some() {
+                        *other_lower = lower;
+                    }
+                    if upper.is_some() {
+                        *other_upper = upper;
+                    }
+                }
+                None => joined.push(Condition::Size(Feature::Range { name, lower, upper })),
+            }
+            continue;
+        }
+
+        joined.push(condition);
+    }
+
+    joined
+}
+
+/**
+ * Reads an @container prelude, None when it isn't valid
+ */
+pub fn queries(prelude: &[ComponentValue]) -> Option<Vec<Query>> {
+    prelude
+        .split(|value| matches!(value, ComponentValue::Token(CSSToken::Comma)))
+        .map(|values| {
+            let values: Vec<&ComponentValue> = values
+                .iter()
+                .filter(|value| !is_whitespace(value))
+                .collect();
+
+            let (name, rest) = match values.split_first() {
+                Some((ComponentValue::Token(CSSToken::Ident(name)), rest))
+                    if !["none", "not", "and", "or"]
+                        .iter()
+                        .any(|keyword| name.eq_ignore_ascii_case(keyword)) =>
+                {
+                    (Some(name.clone()), rest)
+                }
+                _ => (None, values.as_slice()),
+            };
+
+            let condition = match rest {
+                [] if name.is_some() => None,
+                rest => Some(condition(rest)?),
+            };
+
+            Some(Query { name, condition })
+        })
+        .collect()
+}
+
+fn ident(name: &str) -> ComponentValue {
+    ComponentValue::Token(CSSToken::Ident(String::from(name)))
+}
+
+fn delim(ch: char) -> ComponentValue {
+    ComponentValue::Token(CSSToken::Delim(ch))
+}
+
+fn comparison_values(less: bool, inclusive: bool) -> Vec<ComponentValue> {
+    let mut values = vec![delim(if less { '<' } else { '>' })];
+    if inclusive {
+        values.push(delim('='));
+    }
+    values
+}
+
+impl Feature {
+    fn values(&self) -> Vec<ComponentValue> {
+        match self {
+            Feature::Boolean(name) => vec![ident(name)],
+            Feature::Plain(name, value) => {
+                let mut values = vec![ident(name), ComponentValue::Token(CSSToken::Colon)];
+                values.extend(value.iter().cloned());
+                values
+            }
+            Feature::Range { name, lower, upper } => {
+                let mut values: Vec<ComponentValue> = Vec::new();
+
+                match (lower, upper) {
+                    (Some(lower), Some(upper)) => {
+                        values.extend(lower.value.iter().cloned());
+                        values.extend(comparison_values(true, lower.inclusive));
+                        values.push(ident(name));
+                        values.extend(comparison_values(true, upper.inclusive));
+                        values.extend(upper.value.iter().cloned());
+                    }
+                    (Some(bound), None) | (None, Some(bound)) => {
+                        values.push(ident(name));
+                        values.extend(comparison_values(upper.is_some(), bound.inclusive));
+                        values.extend(bound.value.iter().cloned());
+                    }
+                    (None, None) => values.push(ident(name)),
+                }
+
+                values
+            }
+        }
+    }
+}
+
+impl Condition {
+    // The condition where a <query-in-parens> goes
+    fn in_parens(&self) -> ComponentValue {
+        match self {
+            Condition::Size(feature) => ComponentValue::Block(SimpleBlock {
+                token: CSSToken::LeftParentheses,
+                value: feature.values(),
+            }),
+            Condition::Other(value) => value.clone(),
+            condition => ComponentValue::Block(SimpleBlock {
+                token: CSSToken::LeftParentheses,
+                value: condition.values(),
+            }),
+        }
+    }
+
+    fn values(&self) -> Vec<ComponentValue> {
+        let list = |conditions: &[Condition], keyword: &str| {
+            let mut values: Vec<ComponentValue> = Vec::new();
+            for (index, condition) in conditions.iter().enumerate() {
+                if index > 0 {
+                    values.push(ComponentValue::Token(CSSToken::Whitespace));
+                    values.push(ident(keyword));
+                    values.push(ComponentValue::Token(CSSToken::Whitespace));
+                }
+                values.push(condition.in_parens());
+            }
+            values
+        };
+
+        match self {
+            Condition::Not(condition) => vec![
+                ident("not"),
+                ComponentValue::Token(CSSToken::Whitespace),
+                condition.in_parens(),
+            ],
+            Condition::And(conditions) => list(conditions, "and"),
+            Condition::Or(conditions) => list(conditions, "or"),
+            condition => vec![condition.in_parens()],
+        }
+    }
+
+    // Whether the condition queries the size of the container, and whether that's in both directions
+    fn size(&self) -> Option<bool> {
+        match self {
+            Condition::Not(condition) => condition.size(),
+            Condition::And(conditions) | Condition::Or(conditions) => conditions
+                .iter()
+                .filter_map(Condition::size)
+                .reduce(|a, b| a || b),
+            Condition::Size(
+                Feature::Boolean(name) | Feature::Plain(name, _) | Feature::Range { name, .. },
+            ) => feature(name).map(|(_, size)| size),
+            Condition::Other(_) => None,
+        }
+    }
+}
+
+/**
+ * Writes an @container prelude back
+ */
+pub fn prelude(queries: &[Query]) -> Vec<ComponentValue> {
+    let mut values: Vec<ComponentValue> = Vec::new();
+
+    for query in queries {
+        if !values.is_empty() {
+            values.push(ComponentValue::Token(CSSToken::Comma));
+        }
+
+        if let Some(name) = &query.name {
+            values.push(ComponentValue::Token(CSSToken::Ident(name.clone())));
+        }
+
+        if let Some(condition) = &query.condition {
+            if query.name.is_some() {
+                values.push(ComponentValue::Token(CSSToken::Whitespace));
+            }
+            values.extend(condition.values());
+        }
+    }
+
+    values
+}
+
+fn items(items: &mut [BlockItem]) {
+    for item in items.iter_mut() {
+        if let BlockItem::Rule(rule) = item {
+            self::rule(rule);
+        }
+    }
+}
+
+fn rule(rule: &mut Rule) {
+    match rule {
+        Rule::Qualified(rule) => items(&mut rule.block),
+        Rule::At(rule) => {
+            if rule.name.eq_ignore_ascii_case("container") {
+                if let Some(queries) = queries(&rule.prelude) {
+                    rule.prelude = prelude(&queries);
+                }
+            }
+
+            if let Some(block) = &mut rule.block {
+                items(block);
+            }
+        }
+    }
+}
+
+/**
+ * Shortens the @container queries of a stylesheet
+ */
+pub fn stylesheet(stylesheet: &mut Stylesheet) {
+    for rule in stylesheet.rules.iter_mut() {
+        self::rule(rule);
+    }
+}
+
+// The container names and types a style rule declares
+#[derive(Default)]
+struct Containers {
+    names: Vec<String>,
+    types: Vec<String>,
+}
+
+fn declared(block: &[BlockItem]) -> Containers {
+    let mut containers = Containers::default();
+
+    for item in block {
+        let declaration = match item {
+            BlockItem::Declaration(declaration) => declaration,
+            BlockItem::Rule(_) => continue,
+        };
+
+        let property = declaration.name.to_ascii_lowercase();
+
+        // container: name / type
+        let mut slash = false;
+        for value in &declaration.value {
+            let name = match value {
+                ComponentValue::Token(CSSToken::Delim('/')) => {
+                    slash = true;
+                    continue;
+                }
+                ComponentValue::Token(CSSToken::Ident(name)) => name,
+                _ => continue,
+            };
+
+            let naming = property == "container-name" || (property == "container" && !slash);
+            if naming && !name.eq_ignore_ascii_case("none") {
+                containers.names.push(name.clone());
+            } else if !naming && (property == "container-type" || property == "container") {
+                containers.types.push(name.to_ascii_lowercase());
+            }
+        }
+    }
+
+    containers
+}
+
+// The containers declared anywhere in a list of items
+fn collect(items: &[BlockItem], containers: &mut Vec<Containers>) {
+    for item in items {
+        match item {
+            BlockItem::Rule(Rule::Qualified(rule)) => {
+                containers.push(declared(&rule.block));
+                collect(&rule.block, containers);
+            }
+            BlockItem::Rule(Rule::At(AtRule {
+                block: Some(block), ..
+            })) => collect(block, containers),
+            _ => (),
+        }
+    }
+}
+
+// Whether container types allow a size query, in both directions or only the inline one
+fn allows(types: &[String], both: bool) -> bool {
+    types
+        .iter()
+        .any(|kind| kind == "size" || (kind == "inline-size" && !both))
+}
+
+fn check(items: &[BlockItem], containers: &[Containers], problems: &mut Vec<(i32, String)>) {
+    // Types declared without a name apply to any query without one, and to names declared elsewhere
+    let everywhere: Vec<String> = containers
+        .iter()
+        .flat_map(|containers| containers.types.iter().cloned())
+        .collect();
+
+    for item in items {
+        let rule = match item {
+            BlockItem::Rule(Rule::Qualified(rule)) => {
+                check(&rule.block, containers, problems);
+                continue;
+            }
+            BlockItem::Rule(Rule::At(rule)) => rule,
+            BlockItem::Declaration(_) => continue,
+        };
+
+        if rule.name.eq_ignore_ascii_case("container") {
+            for query in queries(&rule.prelude).unwrap_or_default() {
+                let size = query.condition.as_ref().and_then(Condition::size);
+
+                // The types declared along with the name, when that's known
+                let types: Vec<String> = match &query.name {
+                    Some(name) => {
+                        let named: Vec<&Containers> = containers
+                            .iter()
+                            .filter(|containers| containers.names.contains(name))
+                            .collect();
+
+                        if named.is_empty() {
+                            problems.push((
+                                rule.at,
+                                format!(
+                                    "@container query for \"{}\" that no container-name declares",
+                                    name
+                                ),
+                            ));
+                            continue;
+                        }
+
+                        if named.iter().all(|containers| !containers.types.is_empty()) {
+                            named
+                                .iter()
+                                .flat_map(|containers| containers.types.iter().cloned())
+                                .collect()
+                        } else {
+                            everywhere.clone()
+                        }
+                    }
+                    None => everywhere.clone(),
+                };
+
+                if let Some(both) = size.filter(|both| !allows(&types, *both)) {
+                    problems.push((
+                        rule.at,
+                        format!(
+                            "@container size query that no container-type: {} matches",
+                            if both { "size" } else { "size or inline-size" }
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if let Some(block) = &rule.block {
+            check(block, containers, problems);
+        }
+    }
+}
+
+/**
+ * The @container queries of a stylesheet that can't match a container it declares, along with where the rules are
+ */
+pub fn problems(stylesheet: &Stylesheet) -> Vec<(i32, String)> {
+    let items: Vec<BlockItem> = stylesheet
+        .rules
+        .iter()
+        .cloned()
+        .map(BlockItem::Rule)
+        .collect();
+
+    let mut containers: Vec<Containers> = Vec::new();
+    collect(&items, &mut containers);
+
+    let mut problems: Vec<(i32, String)> = Vec::new();
+    check(&items, &containers, &mut problems);
+    problems
+}
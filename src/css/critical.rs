@@ -0,0 +1,155 @@
+/**
+ * Critical CSS
+ *
+ * Splits a stylesheet in two for a page: the rules needed to render it at a viewport size, and everything else. A
+ * style rule is critical when one of its selectors matches an element in the document (see select.rs), and it's inside
+ * @media rules that apply to the viewport (see media.rs). Conditional rules and layers are split along with their
+ * contents, so both halves keep the same conditions.
+ *
+ * @keyframes and @font-face rules are critical when a critical rule refers to them. @import, @charset, @layer
+ * statements, @property and other rules that aren't tied to elements stay critical, @page is left for later and
+ * @namespace goes in both.
+ *
+ * The rest is meant to be loaded after the critical rules, so rules only keep their order within each half. A rule in
+ * the rest that used to come before a critical rule can override it where both apply, which only happens when the
+ * rest rule applies at a viewport size other than the one split for.
+ */
+use super::media::{self, Viewport};
+use super::parse::tree::*;
+use super::purge::{self, References};
+use super::select;
+use crate::html::dom::{Document, NodeId};
+
+// At-rules whose contents are split, keeping the same prelude in both halves
+const CONTAINERS: [&str; 6] = [
+    "media",
+    "supports",
+    "layer",
+    "container",
+    "scope",
+    "starting-style",
+];
+
+struct Context<'a> {
+    document: &'a Document,
+    elements: Vec<NodeId>,
+    viewport: &'a Viewport,
+    references: References,
+}
+
+impl Context<'_> {
+    fn matches(&self, prelude: &[ComponentValue]) -> bool {
+        // Browsers drop rules with invalid selectors, so they're never critical
+        let selectors = match select::list(prelude) {
+            Ok(selectors) => selectors,
+            Err(_) => return false,
+        };
+
+        self.elements.iter().any(|element| {
+            selectors
+                .iter()
+                .any(|selector| selector.matches(self.document, *element))
+        })
+    }
+}
+
+fn split(rules: &[Rule], context: &Context, critical: &mut Vec<Rule>, rest: &mut Vec<Rule>) {
+    for rule in rules {
+        let at = match rule {
+            Rule::Qualified(style) => {
+                if context.matches(&style.prelude) {
+                    critical.push(rule.clone());
+                } else {
+                    rest.push(rule.clone());
+                }
+                continue;
+            }
+            Rule::At(at) => at,
+        };
+
+        let name = at.name.to_ascii_lowercase();
+
+        if let Some(referenced) = context.references.refers(at) {
+            if referenced {
+                critical.push(rule.clone());
+            } else {
+                rest.push(rule.clone());
+            }
+            continue;
+        }
+
+        match (name.as_str(), &at.block) {
+            ("media", Some(_)) if !media::matches(&at.prelude, context.viewport) => {
+                rest.push(rule.clone())
+            }
+            (name, Some(block)) if CONTAINERS.contains(&name) => {
+                let rules: Vec<Rule> = block
+                    .iter()
+                    .filter_map(|item| match item {
+                        BlockItem::Rule(rule) => Some(rule.clone()),
+                        BlockItem::Declaration(_) => None,
+                    })
+                    .collect();
+
+                let mut inner_critical: Vec<Rule> = Vec::new();
+                let mut inner_rest: Vec<Rule> = Vec::new();
+                split(&rules, context, &mut inner_critical, &mut inner_rest);
+
+                // Layers stay critical even when they're empty, as the first one to appear decides their order
+                if !inner_critical.is_empty() || name == "layer" {
+                    critical.push(at.with_block(inner_critical));
+                }
+                if !inner_rest.is_empty() {
+                    rest.push(at.with_block(inner_rest));
+                }
+            }
+            ("page", _) => rest.push(rule.clone()),
+            ("namespace", _) => {
+                critical.push(rule.clone());
+                rest.push(rule.clone());
+            }
+            _ => critical.push(rule.clone()),
+        }
+    }
+}
+
+/**
+ * Splits a stylesheet into the rules needed to render a document at a viewport size, and the rest
+ */
+pub fn stylesheet(
+    stylesheet: &Stylesheet,
+    document: &Document,
+    viewport: &Viewport,
+) -> (Stylesheet, Stylesheet) {
+    let mut context = Context {
+        document,
+        elements: (0..document.nodes.len())
+            .filter(|id| document.element(*id).is_some())
+            .collect(),
+        viewport,
+        references: References::default(),
+    };
+
+    // Find the style rules first, then the @keyframes and @font-face rules they refer to
+    let mut critical: Vec<Rule> = Vec::new();
+    let mut rest: Vec<Rule> = Vec::new();
+    split(&stylesheet.rules, &context, &mut critical, &mut rest);
+
+    let items: Vec<BlockItem> = critical.into_iter().map(BlockItem::Rule).collect();
+    purge::references(&items, &mut context.references);
+
+    let mut critical: Vec<Rule> = Vec::new();
+    let mut rest: Vec<Rule> = Vec::new();
+    split(&stylesheet.rules, &context, &mut critical, &mut rest);
+
+    (
+        Stylesheet {
+            rules: critical,
+            comments: stylesheet.comments.clone(),
+        },
+        Stylesheet {
+            rules: rest,
+            comments: stylesheet.comments.clone(),
+        },
+    )
+}
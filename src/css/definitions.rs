@@ -0,0 +1,543 @@
+/**
+ * Property grammars
+ *
+ * The value definitions of properties, and of the types they use that aren't in types.rs, as the specs write them
+ * (css-box, css-backgrounds, css-fonts, css-align, ...). Grammars that are more permissive than the spec are marked,
+ * they're simplified so they never reject a value browsers accept. Properties without a grammar here aren't checked.
+ */
+// Types used by the property grammars, <line-style> and friends
+pub const TYPES: [(&str, &str); 51] = [
+    ("absolute-size", "xx-small | x-small | small | medium | large | x-large | xx-large | xxx-large"),
+    ("attachment", "scroll | fixed | local"),
+    ("baseline-position", "[ first | last ]? && baseline"),
+    // Any function a basic shape can be, with its arguments unchecked
+    (
+        "basic-shape",
+        "inset( <declaration-value> ) | circle( <declaration-value>? ) | ellipse( <declaration-value>? ) | \
+         polygon( <declaration-value> ) | path( <declaration-value> ) | rect( <declaration-value> ) | \
+         xywh( <declaration-value> ) | shape( <declaration-value> )",
+    ),
+    ("bg-clip", "<visual-box> | border-area | text"),
+    ("bg-image", "none | <image>"),
+    (
+        "bg-layer",
+        "<bg-image> || <bg-position> [ / <bg-size> ]? || <repeat-style> || <attachment> || <visual-box> || <bg-clip>",
+    ),
+    (
+        "bg-position",
+        "[ left | center | right | top | bottom | <length-percentage> ] | \
+         [ left | center | right | <length-percentage> ] [ top | center | bottom | <length-percentage> ] | \
+         [ center | [ left | right ] <length-percentage>? ] && [ center | [ top | bottom ] <length-percentage>? ]",
+    ),
+    ("bg-size", "[ <length-percentage [0,∞]> | auto ]{1,2} | cover | contain"),
+    (
+        "blend-mode",
+        "normal | multiply | screen | overlay | darken | lighten | color-dodge | color-burn | hard-light | \
+         soft-light | difference | exclusion | hue | saturation | color | luminosity",
+    ),
+    ("content-distribution", "space-between | space-around | space-evenly | stretch"),
+    ("content-position", "center | start | end | flex-start | flex-end"),
+    ("counter-style", "<custom-ident> | symbols( <declaration-value> )"),
+    (
+        "cursor-keyword",
+        "auto | default | none | context-menu | help | pointer | progress | wait | cell | crosshair | text | \
+         vertical-text | alias | copy | move | no-drop | not-allowed | grab | grabbing | e-resize | n-resize | \
+         ne-resize | nw-resize | s-resize | se-resize | sw-resize | w-resize | ew-resize | ns-resize | nesw-resize | \
+         nwse-resize | col-resize | row-resize | all-scroll | zoom-in | zoom-out",
+    ),
+    ("display-box", "contents | none"),
+    ("display-inside", "flow | flow-root | table | flex | grid | ruby | math"),
+    (
+        "display-internal",
+        "table-row-group | table-header-group | table-footer-group | table-row | table-cell | table-column-group | \
+         table-column | table-caption | ruby-base | ruby-text | ruby-base-container | ruby-text-container",
+    ),
+    ("display-legacy", "inline-block | inline-table | inline-flex | inline-grid"),
+    ("display-listitem", "<display-outside>? && [ flow | flow-root ]? && list-item"),
+    ("display-outside", "block | inline | run-in"),
+    (
+        "easing-function",
+        "linear | ease | ease-in | ease-out | ease-in-out | step-start | step-end | linear( <declaration-value> ) | \
+         cubic-bezier( <number [0,1]> , <number> , <number [0,1]> , <number> ) | \
+         steps( <integer [1,∞]> [ , <step-position> ]? )",
+    ),
+    // Any filter function, with its arguments unchecked
+    (
+        "filter-function",
+        "blur( <declaration-value>? ) | brightness( <declaration-value>? ) | contrast( <declaration-value>? ) | \
+         drop-shadow( <declaration-value> ) | grayscale( <declaration-value>? ) | \
+         hue-rotate( <declaration-value>? ) | invert( <declaration-value>? ) | opacity( <declaration-value>? ) | \
+         saturate( <declaration-value>? ) | sepia( <declaration-value>? )",
+    ),
+    ("font-weight-absolute", "normal | bold | <number [1,1000]>"),
+    (
+        "font-width",
+        "normal | <percentage [0,∞]> | ultra-condensed | extra-condensed | condensed | semi-condensed | \
+         semi-expanded | expanded | extra-expanded | ultra-expanded",
+    ),
+    ("geometry-box", "<visual-box> | margin-box | fill-box | stroke-box | view-box"),
+    (
+        "grid-line",
+        "auto | <custom-ident> | [ [ <integer [-∞,-1]> | <integer [1,∞]> ] && <custom-ident>? ] | \
+         [ span && [ <integer [1,∞]> || <custom-ident> ] ]",
+    ),
+    ("inset-value", "auto | <length-percentage> | anchor( <declaration-value> )"),
+    ("keyframes-name", "<custom-ident> | <string>"),
+    ("line-style", "none | hidden | dotted | dashed | solid | double | groove | ridge | inset | outset"),
+    ("line-width", "<length [0,∞]> | thin | medium | thick"),
+    ("margin-value", "<length-percentage> | auto | anchor-size( <declaration-value>? )"),
+    ("max-size", "none | <size-keyword>"),
+    ("overflow-position", "unsafe | safe"),
+    ("overflow-value", "visible | hidden | clip | scroll | auto | overlay"),
+    (
+        "position",
+        "[ left | center | right | top | bottom | <length-percentage> ] | \
+         [ left | center | right ] && [ top | center | bottom ] | \
+         [ left | center | right | <length-percentage> ] [ top | center | bottom | <length-percentage> ] | \
+         [ [ left | right ] <length-percentage> ] && [ [ top | bottom ] <length-percentage> ]",
+    ),
+    ("ratio", "<number [0,∞]> [ / <number [0,∞]> ]?"),
+    ("relative-size", "larger | smaller"),
+    ("repeat-style", "repeat-x | repeat-y | [ repeat | space | round | no-repeat ]{1,2}"),
+    ("self-position", "center | start | end | self-start | self-end | flex-start | flex-end"),
+    ("shadow", "<color>? && [ <length>{2} <length [0,∞]>? <length>? ] && inset?"),
+    (
+        "single-animation",
+        "[ auto | <time [0s,∞]> ] || <easing-function> || <time> || <single-animation-iteration-count> || \
+         <single-animation-direction> || <single-animation-fill-mode> || <single-animation-play-state> || \
+         [ none | <keyframes-name> ]",
+    ),
+    ("single-animation-direction", "normal | reverse | alternate | alternate-reverse"),
+    ("single-animation-fill-mode", "none | forwards | backwards | both"),
+    ("single-animation-iteration-count", "infinite | <number [0,∞]>"),
+    ("single-animation-play-state", "running | paused"),
+    (
+        "single-transition",
+        "[ none | all | <custom-ident> ] || <time [0s,∞]> || <easing-function> || <time> || \
+         [ normal | allow-discrete ]",
+    ),
+    (
+        "size",
+        "auto | <size-keyword> | anchor-size( <declaration-value>? )",
+    ),
+    (
+        "size-keyword",
+        "<length-percentage [0,∞]> | min-content | max-content | fit-content | \
+         fit-content( <length-percentage [0,∞]> ) | stretch | calc-size( <declaration-value> )",
+    ),
+    ("step-position", "jump-start | jump-end | jump-none | jump-both | start | end"),
+    ("system-family-name", "caption | icon | menu | message-box | small-caption | status-bar"),
+    ("visual-box", "content-box | padding-box | border-box"),
+];
+
+// The grammars of properties, by name
+pub const PROPERTIES: [(&str, &str); 295] = [
+    ("accent-color", "auto | <color>"),
+    (
+        "align-content",
+        "normal | <baseline-position> | <content-distribution> | <overflow-position>? <content-position>",
+    ),
+    (
+        "align-items",
+        "normal | stretch | <baseline-position> | <overflow-position>? <self-position> | anchor-center",
+    ),
+    (
+        "align-self",
+        "auto | normal | stretch | <baseline-position> | <overflow-position>? <self-position> | anchor-center",
+    ),
+    ("animation", "<single-animation>#"),
+    ("animation-composition", "[ replace | add | accumulate ]#"),
+    ("animation-delay", "<time>#"),
+    ("animation-direction", "<single-animation-direction>#"),
+    ("animation-duration", "[ auto | <time [0s,∞]> ]#"),
+    ("animation-fill-mode", "<single-animation-fill-mode>#"),
+    ("animation-iteration-count", "<single-animation-iteration-count>#"),
+    ("animation-name", "[ none | <keyframes-name> ]#"),
+    ("animation-play-state", "<single-animation-play-state>#"),
+    ("animation-timing-function", "<easing-function>#"),
+    ("aspect-ratio", "auto || <ratio>"),
+    ("backdrop-filter", "none | [ <filter-function> | <url> ]+"),
+    ("backface-visibility", "visible | hidden"),
+    (
+        "background",
+        "<bg-layer>#? , [ <'background-color'> || <bg-image> || <bg-position> [ / <bg-size> ]? || <repeat-style> || \
+         <attachment> || <visual-box> || <bg-clip> ]",
+    ),
+    ("background-attachment", "<attachment>#"),
+    ("background-blend-mode", "<blend-mode>#"),
+    ("background-clip", "<bg-clip>#"),
+    ("background-color", "<color>"),
+    ("background-image", "<bg-image>#"),
+    ("background-origin", "<visual-box>#"),
+    ("background-position", "<bg-position>#"),
+    (
+        "background-position-x",
+        "[ center | [ [ left | right | x-start | x-end ]? <length-percentage>? ]! ]#",
+    ),
+    (
+        "background-position-y",
+        "[ center | [ [ top | bottom | y-start | y-end ]? <length-percentage>? ]! ]#",
+    ),
+    ("background-repeat", "<repeat-style>#"),
+    ("background-size", "<bg-size>#"),
+    ("block-size", "<size>"),
+    ("border", "<line-width> || <line-style> || <color>"),
+    ("border-block", "<line-width> || <line-style> || <color>"),
+    ("border-block-color", "<color>{1,2}"),
+    ("border-block-end", "<line-width> || <line-style> || <color>"),
+    ("border-block-end-color", "<color>"),
+    ("border-block-end-style", "<line-style>"),
+    ("border-block-end-width", "<line-width>"),
+    ("border-block-start", "<line-width> || <line-style> || <color>"),
+    ("border-block-start-color", "<color>"),
+    ("border-block-start-style", "<line-style>"),
+    ("border-block-start-width", "<line-width>"),
+    ("border-block-style", "<line-style>{1,2}"),
+    ("border-block-width", "<line-width>{1,2}"),
+    ("border-bottom", "<line-width> || <line-style> || <color>"),
+    ("border-bottom-color", "<color>"),
+    ("border-bottom-left-radius", "<length-percentage [0,∞]>{1,2}"),
+    ("border-bottom-right-radius", "<length-percentage [0,∞]>{1,2}"),
+    ("border-bottom-style", "<line-style>"),
+    ("border-bottom-width", "<line-width>"),
+    ("border-collapse", "separate | collapse"),
+    ("border-color", "<color>{1,4}"),
+    ("border-end-end-radius", "<length-percentage [0,∞]>{1,2}"),
+    ("border-end-start-radius", "<length-percentage [0,∞]>{1,2}"),
+    (
+        "border-image",
+        "<'border-image-source'> || <'border-image-slice'> [ / <'border-image-width'> | \
+         / <'border-image-width'>? / <'border-image-outset'> ]? || <'border-image-repeat'>",
+    ),
+    ("border-image-outset", "[ <length [0,∞]> | <number [0,∞]> ]{1,4}"),
+    ("border-image-repeat", "[ stretch | repeat | round | space ]{1,2}"),
+    ("border-image-slice", "[ <number [0,∞]> | <percentage [0,∞]> ]{1,4} && fill?"),
+    ("border-image-source", "none | <image>"),
+    ("border-image-width", "[ <length-percentage [0,∞]> | <number [0,∞]> | auto ]{1,4}"),
+    ("border-inline", "<line-width> || <line-style> || <color>"),
+    ("border-inline-color", "<color>{1,2}"),
+    ("border-inline-end", "<line-width> || <line-style> || <color>"),
+    ("border-inline-end-color", "<color>"),
+    ("border-inline-end-style", "<line-style>"),
+    ("border-inline-end-width", "<line-width>"),
+    ("border-inline-start", "<line-width> || <line-style> || <color>"),
+    ("border-inline-start-color", "<color>"),
+    ("border-inline-start-style", "<line-style>"),
+    ("border-inline-start-width", "<line-width>"),
+    ("border-inline-style", "<line-style>{1,2}"),
+    ("border-inline-width", "<line-width>{1,2}"),
+    ("border-left", "<line-width> || <line-style> || <color>"),
+    ("border-left-color", "<color>"),
+    ("border-left-style", "<line-style>"),
+    ("border-left-width", "<line-width>"),
+    (
+        "border-radius",
+        "<length-percentage [0,∞]>{1,4} [ / <length-percentage [0,∞]>{1,4} ]?",
+    ),
+    ("border-right", "<line-width> || <line-style> || <color>"),
+    ("border-right-color", "<color>"),
+    ("border-right-style", "<line-style>"),
+    ("border-right-width", "<line-width>"),
+    ("border-spacing", "<length>{1,2}"),
+    ("border-start-end-radius", "<length-percentage [0,∞]>{1,2}"),
+    ("border-start-start-radius", "<length-percentage [0,∞]>{1,2}"),
+    ("border-style", "<line-style>{1,4}"),
+    ("border-top", "<line-width> || <line-style> || <color>"),
+    ("border-top-color", "<color>"),
+    ("border-top-left-radius", "<length-percentage [0,∞]>{1,2}"),
+    ("border-top-right-radius", "<length-percentage [0,∞]>{1,2}"),
+    ("border-top-style", "<line-style>"),
+    ("border-top-width", "<line-width>"),
+    ("border-width", "<line-width>{1,4}"),
+    ("bottom", "<inset-value>"),
+    ("box-decoration-break", "slice | clone"),
+    ("box-shadow", "none | <shadow>#"),
+    ("box-sizing", "content-box | border-box"),
+    (
+        "break-after",
+        "auto | avoid | always | all | avoid-page | page | left | right | recto | verso | avoid-column | column | \
+         avoid-region | region",
+    ),
+    (
+        "break-before",
+        "auto | avoid | always | all | avoid-page | page | left | right | recto | verso | avoid-column | column | \
+         avoid-region | region",
+    ),
+    ("break-inside", "auto | avoid | avoid-page | avoid-column | avoid-region"),
+    ("caption-side", "top | bottom"),
+    ("caret-color", "auto | <color>"),
+    ("clear", "none | left | right | both | inline-start | inline-end"),
+    ("clip-path", "none | <url> | <basic-shape> || <geometry-box>"),
+    ("color", "<color>"),
+    ("color-scheme", "normal | [ light | dark | <custom-ident> ]+ && only?"),
+    ("column-count", "auto | <integer [1,∞]>"),
+    ("column-gap", "normal | <length-percentage [0,∞]>"),
+    ("column-rule", "<line-width> || <line-style> || <color>"),
+    ("column-rule-color", "<color>"),
+    ("column-rule-style", "<line-style>"),
+    ("column-rule-width", "<line-width>"),
+    ("column-span", "none | all"),
+    ("column-width", "auto | <length [0,∞]>"),
+    ("columns", "[ auto | <length [0,∞]> ] || [ auto | <integer [1,∞]> ]"),
+    (
+        "contain",
+        "none | strict | content | [ [ size | inline-size ] || layout || style || paint ]",
+    ),
+    ("container", "<'container-name'> [ / <'container-type'> ]?"),
+    ("container-name", "none | <custom-ident>+"),
+    ("container-type", "normal | [ [ size | inline-size ] || scroll-state ]"),
+    ("content-visibility", "visible | auto | hidden"),
+    ("counter-increment", "none | [ <custom-ident> <integer>? ]+"),
+    (
+        "counter-reset",
+        "none | [ [ <custom-ident> | reversed( <custom-ident> ) ] <integer>? ]+",
+    ),
+    ("counter-set", "none | [ <custom-ident> <integer>? ]+"),
+    ("cursor", "[ [ <url> | <image> ] [ <number> <number> ]? , ]* <cursor-keyword>"),
+    ("direction", "ltr | rtl"),
+    (
+        "display",
+        "[ <display-outside> || <display-inside> ] | <display-listitem> | <display-internal> | <display-box> | \
+         <display-legacy>",
+    ),
+    ("empty-cells", "show | hide"),
+    ("field-sizing", "fixed | content"),
+    ("filter", "none | [ <filter-function> | <url> ]+"),
+    ("flex", "none | [ <'flex-grow'> <'flex-shrink'>? || <'flex-basis'> ]"),
+    ("flex-basis", "content | <size>"),
+    ("flex-direction", "row | row-reverse | column | column-reverse"),
+    ("flex-flow", "<'flex-direction'> || <'flex-wrap'>"),
+    ("flex-grow", "<number [0,∞]>"),
+    ("flex-shrink", "<number [0,∞]>"),
+    ("flex-wrap", "nowrap | wrap | wrap-reverse"),
+    ("float", "left | right | none | inline-start | inline-end"),
+    (
+        "font",
+        "[ [ <'font-style'> || small-caps || <'font-weight'> || <font-width> ]? <'font-size'> \
+         [ / <'line-height'> ]? <'font-family'> ] | <system-family-name>",
+    ),
+    ("font-family", "[ <string> | <custom-ident>+ ]#"),
+    ("font-feature-settings", "normal | [ <string> [ <integer [0,∞]> | on | off ]? ]#"),
+    ("font-kerning", "auto | normal | none"),
+    ("font-optical-sizing", "auto | none"),
+    (
+        "font-size",
+        "<absolute-size> | <relative-size> | <length-percentage [0,∞]> | math",
+    ),
+    ("font-stretch", "<font-width>"),
+    ("font-style", "normal | italic | oblique <angle>?"),
+    ("font-synthesis", "none | [ weight || style || small-caps || position ]"),
+    ("font-variation-settings", "normal | [ <string> <number> ]#"),
+    ("font-weight", "<font-weight-absolute> | bolder | lighter"),
+    ("forced-color-adjust", "auto | none | preserve-parent-color"),
+    ("gap", "<'row-gap'> <'column-gap'>?"),
+    ("grid-area", "<grid-line> [ / <grid-line> ]{0,3}"),
+    ("grid-auto-flow", "[ row | column ] || dense"),
+    ("grid-column", "<grid-line> [ / <grid-line> ]?"),
+    ("grid-column-end", "<grid-line>"),
+    ("grid-column-gap", "<length-percentage [0,∞]>"),
+    ("grid-column-start", "<grid-line>"),
+    ("grid-gap", "<length-percentage [0,∞]>{1,2}"),
+    ("grid-row", "<grid-line> [ / <grid-line> ]?"),
+    ("grid-row-end", "<grid-line>"),
+    ("grid-row-gap", "<length-percentage [0,∞]>"),
+    ("grid-row-start", "<grid-line>"),
+    ("grid-template-areas", "none | <string>+"),
+    ("height", "<size>"),
+    ("hyphens", "none | manual | auto"),
+    ("image-rendering", "auto | smooth | high-quality | pixelated | crisp-edges"),
+    ("inline-size", "<size>"),
+    ("inset", "<inset-value>{1,4}"),
+    ("inset-block", "<inset-value>{1,2}"),
+    ("inset-block-end", "<inset-value>"),
+    ("inset-block-start", "<inset-value>"),
+    ("inset-inline", "<inset-value>{1,2}"),
+    ("inset-inline-end", "<inset-value>"),
+    ("inset-inline-start", "<inset-value>"),
+    ("interpolate-size", "numeric-only | allow-keywords"),
+    ("isolation", "auto | isolate"),
+    (
+        "justify-content",
+        "normal | <content-distribution> | <overflow-position>? [ <content-position> | left | right ]",
+    ),
+    (
+        "justify-items",
+        "normal | stretch | <baseline-position> | <overflow-position>? [ <self-position> | left | right ] | \
+         legacy && [ left | right | center ]? | anchor-center",
+    ),
+    (
+        "justify-self",
+        "auto | normal | stretch | <baseline-position> | <overflow-position>? [ <self-position> | left | right ] | \
+         anchor-center",
+    ),
+    ("left", "<inset-value>"),
+    ("letter-spacing", "normal | <length-percentage>"),
+    ("line-height", "normal | <number [0,∞]> | <length-percentage [0,∞]>"),
+    ("list-style", "<'list-style-position'> || <'list-style-image'> || <'list-style-type'>"),
+    ("list-style-image", "none | <image>"),
+    ("list-style-position", "inside | outside"),
+    ("list-style-type", "<counter-style> | <string> | none"),
+    ("margin", "<margin-value>{1,4}"),
+    ("margin-block", "<margin-value>{1,2}"),
+    ("margin-block-end", "<margin-value>"),
+    ("margin-block-start", "<margin-value>"),
+    ("margin-bottom", "<margin-value>"),
+    ("margin-inline", "<margin-value>{1,2}"),
+    ("margin-inline-end", "<margin-value>"),
+    ("margin-inline-start", "<margin-value>"),
+    ("margin-left", "<margin-value>"),
+    ("margin-right", "<margin-value>"),
+    ("margin-top", "<margin-value>"),
+    ("max-block-size", "<max-size>"),
+    ("max-height", "<max-size>"),
+    ("max-inline-size", "<max-size>"),
+    ("max-width", "<max-size>"),
+    ("min-block-size", "<size>"),
+    ("min-height", "<size>"),
+    ("min-inline-size", "<size>"),
+    ("min-width", "<size>"),
+    ("mix-blend-mode", "<blend-mode> | plus-darker | plus-lighter"),
+    ("object-fit", "fill | contain | cover | none | scale-down"),
+    ("object-position", "<position>"),
+    ("opacity", "<number> | <percentage>"),
+    ("order", "<integer>"),
+    ("orphans", "<integer [1,∞]>"),
+    ("outline", "<'outline-width'> || <'outline-style'> || <'outline-color'>"),
+    ("outline-color", "auto | <color> | invert"),
+    ("outline-offset", "<length>"),
+    ("outline-style", "auto | <line-style>"),
+    ("outline-width", "<line-width>"),
+    ("overflow", "<overflow-value>{1,2}"),
+    ("overflow-block", "<overflow-value>"),
+    ("overflow-inline", "<overflow-value>"),
+    ("overflow-wrap", "normal | break-word | anywhere"),
+    ("overflow-x", "<overflow-value>"),
+    ("overflow-y", "<overflow-value>"),
+    ("overscroll-behavior", "[ contain | none | auto ]{1,2}"),
+    ("overscroll-behavior-x", "contain | none | auto"),
+    ("overscroll-behavior-y", "contain | none | auto"),
+    ("padding", "<length-percentage [0,∞]>{1,4}"),
+    ("padding-block", "<length-percentage [0,∞]>{1,2}"),
+    ("padding-block-end", "<length-percentage [0,∞]>"),
+    ("padding-block-start", "<length-percentage [0,∞]>"),
+    ("padding-bottom", "<length-percentage [0,∞]>"),
+    ("padding-inline", "<length-percentage [0,∞]>{1,2}"),
+    ("padding-inline-end", "<length-percentage [0,∞]>"),
+    ("padding-inline-start", "<length-percentage [0,∞]>"),
+    ("padding-left", "<length-percentage [0,∞]>"),
+    ("padding-right", "<length-percentage [0,∞]>"),
+    ("padding-top", "<length-percentage [0,∞]>"),
+    ("perspective", "none | <length [0,∞]>"),
+    ("perspective-origin", "<position>"),
+    ("place-content", "<'align-content'> <'justify-content'>?"),
+    ("place-items", "<'align-items'> <'justify-items'>?"),
+    ("place-self", "<'align-self'> <'justify-self'>?"),
+    (
+        "pointer-events",
+        "auto | none | visiblepainted | visiblefill | visiblestroke | visible | painted | fill | stroke | all | \
+         bounding-box",
+    ),
+    ("position", "static | relative | absolute | sticky | fixed"),
+    ("resize", "none | both | horizontal | vertical | block | inline"),
+    ("right", "<inset-value>"),
+    ("rotate", "none | <angle> | <zero> | [ x | y | z | <number>{3} ] && [ <angle> | <zero> ]"),
+    ("row-gap", "normal | <length-percentage [0,∞]>"),
+    ("scale", "none | [ <number> | <percentage> ]{1,3}"),
+    ("scroll-behavior", "auto | smooth"),
+    ("scroll-margin", "<length>{1,4}"),
+    ("scroll-padding", "[ auto | <length-percentage [0,∞]> ]{1,4}"),
+    ("scroll-snap-align", "[ none | start | end | center ]{1,2}"),
+    ("scroll-snap-stop", "normal | always"),
+    (
+        "scroll-snap-type",
+        "none | [ x | y | block | inline | both ] [ mandatory | proximity ]?",
+    ),
+    ("tab-size", "<number [0,∞]> | <length [0,∞]>"),
+    ("table-layout", "auto | fixed"),
+    (
+        "text-align",
+        "start | end | left | right | center | justify | match-parent | justify-all",
+    ),
+    (
+        "text-align-last",
+        "auto | start | end | left | right | center | justify | match-parent",
+    ),
+    (
+        "text-decoration",
+        "<'text-decoration-line'> || <'text-decoration-thickness'> || <'text-decoration-style'> || \
+         <'text-decoration-color'>",
+    ),
+    ("text-decoration-color", "<color>"),
+    (
+        "text-decoration-line",
+        "none | [ underline || overline || line-through || blink ]",
+    ),
+    ("text-decoration-style", "solid | double | dotted | dashed | wavy"),
+    ("text-decoration-thickness", "auto | from-font | <length-percentage>"),
+    ("text-indent", "<length-percentage> && hanging? && each-line?"),
+    ("text-overflow", "[ clip | ellipsis | <string> ]{1,2}"),
+    (
+        "text-rendering",
+        "auto | optimizespeed | optimizelegibility | geometricprecision",
+    ),
+    ("text-shadow", "none | [ <color>? && <length>{2,3} ]#"),
+    ("text-size-adjust", "none | auto | <percentage [0,∞]>"),
+    (
+        "text-transform",
+        "none | math-auto | [ [ capitalize | uppercase | lowercase ] || full-width || full-size-kana ]",
+    ),
+    ("text-underline-offset", "auto | <length-percentage>"),
+    ("text-underline-position", "auto | from-font | [ under || [ left | right ] ]"),
+    (
+        "text-wrap",
+        "[ wrap | nowrap ] || [ auto | balance | stable | pretty ]",
+    ),
+    ("top", "<inset-value>"),
+    (
+        "touch-action",
+        "auto | none | [ [ pan-x | pan-left | pan-right ] || [ pan-y | pan-up | pan-down ] || pinch-zoom ] | \
+         manipulation",
+    ),
+    ("transform", "none | <transform-function>+"),
+    (
+        "transform-origin",
+        "[ left | center | right | top | bottom | <length-percentage> ] | \
+         [ left | center | right | <length-percentage> ] [ top | center | bottom | <length-percentage> ] <length>? | \
+         [ [ center | left | right ] && [ center | top | bottom ] ] <length>?",
+    ),
+    ("transform-style", "flat | preserve-3d"),
+    ("transition", "<single-transition>#"),
+    ("transition-behavior", "[ normal | allow-discrete ]#"),
+    ("transition-delay", "<time>#"),
+    ("transition-duration", "<time [0s,∞]>#"),
+    ("transition-property", "none | [ all | <custom-ident> ]#"),
+    ("transition-timing-function", "<easing-function>#"),
+    ("translate", "none | <length-percentage> [ <length-percentage> <length>? ]?"),
+    (
+        "unicode-bidi",
+        "normal | embed | isolate | bidi-override | isolate-override | plaintext",
+    ),
+    ("user-select", "auto | text | none | contain | all"),
+    (
+        "vertical-align",
+        "baseline | sub | super | text-top | text-bottom | middle | top | bottom | <length-percentage>",
+    ),
+    ("visibility", "visible | hidden | collapse"),
+    (
+        "white-space",
+        "normal | pre | pre-wrap | pre-line | \
+         [ collapse | discard | preserve | preserve-breaks | preserve-spaces | break-spaces ] || [ wrap | nowrap ]",
+    ),
+    ("widows", "<integer [1,∞]>"),
+    ("width", "<size>"),
+    ("will-change", "auto | [ scroll-position | contents | <custom-ident> ]#"),
+    ("word-break", "normal | break-all | keep-all | break-word | auto-phrase"),
+    ("word-spacing", "normal | <length-percentage>"),
+    ("word-wrap", "normal | break-word | anywhere"),
+    (
+        "writing-mode",
+        "horizontal-tb | vertical-rl | vertical-lr | sideways-rl | sideways-lr | lr | lr-tb | rl | rl-tb | tb | tb-rl",
+    ),
+    ("z-index", "auto | <integer>"),
+    ("zoom", "normal | reset | <number [0,∞]> | <percentage [0,∞]>"),
+];
@@ -0,0 +1,187 @@
+/**
+ * Fallbacks for properties some targets don't support, written with older properties that mean the same thing
+ *
+ *  - inset => top, right, bottom and left
+ *  - margin-block and padding-block => the top and bottom sides (for horizontal writing modes)
+ *  - margin-inline and padding-inline => the left and right sides, when both get the same value
+ *  - place-content, place-items and place-self => their align- and justify- properties
+ *
+ * Values using var(), env() or attr() aren't touched, as they could hold any number of values.
+ */
+use super::compat::{self, Target};
+use super::parse::tree::*;
+use super::serialize::{values, Context};
+use super::token::tokens::CSSToken;
+
+type Values = Vec<ComponentValue>;
+
+// Splits a value on whitespace, None if it can't be split safely
+fn split(value: &[ComponentValue]) -> Option<Vec<Values>> {
+    let mut parts: Vec<Values> = Vec::new();
+    let mut part: Values = Vec::new();
+
+    for component in value {
+        match component {
+            ComponentValue::Token(CSSToken::Whitespace) => {
+                if !part.is_empty() {
+                    parts.push(std::mem::take(&mut part));
+                }
+            }
+            ComponentValue::Token(CSSToken::Comma) => return None,
+            ComponentValue::Function(function)
+                if ["var", "env", "attr"]
+                    .iter()
+                    .any(|name| function.name.eq_ignore_ascii_case(name)) =>
+            {
+                return None
+            }
+            component => part.push(component.clone()),
+        }
+    }
+
+    if !part.is_empty() {
+        parts.push(part);
+    }
+
+    Some(parts)
+}
+
+fn is_keyword(value: &[ComponentValue], keywords: &[&str]) -> bool {
+    match value {
+        [ComponentValue::Token(CSSToken::Ident(name))] => keywords
+            .iter()
+            .any(|keyword| name.eq_ignore_ascii_case(keyword)),
+        _ => false,
+    }
+}
+
+// The longhands for a declaration, None if it can't be written another way
+fn longhands(name: &str, value: &[ComponentValue]) -> Option<Vec<(String, Values)>> {
+    let parts = split(value)?;
+    let part = |index: usize| parts[index].clone();
+
+    let sides = |prefix: &str, sides: [&str; 4], values: [usize; 4]| {
+        sides
+            .iter()
+            .zip(values)
+            .map(|(side, index)| (format!("{}{}", prefix, side), part(index)))
+            .collect::<Vec<(String, Values)>>()
+    };
+    let physical = ["top", "right", "bottom", "left"];
+
+    match name {
+        // The same as margin and padding, but for positions
+        "inset" => match parts.len() {
+            1 => Some(sides("", physical, [0, 0, 0, 0])),
+            2 => Some(sides("", physical, [0, 1, 0, 1])),
+            3 => Some(sides("", physical, [0, 1, 2, 1])),
+            4 => Some(sides("", physical, [0, 1, 2, 3])),
+            _ => None,
+        },
+
+        "margin-block" | "padding-block" => {
+            let prefix = &name[..name.len() - 5];
+            let end = match parts.len() {
+                1 => 0,
+                2 => 1,
+                _ => return None,
+            };
+
+            Some(vec![
+                (format!("{}top", prefix), part(0)),
+                (format!("{}bottom", prefix), part(end)),
+            ])
+        }
+
+        // Which side is the start depends on the direction
+        "margin-inline" | "padding-inline" => {
+            let prefix = &name[..name.len() - 6];
+            let symmetric = parts.len() == 1
+                || (parts.len() == 2
+                    && values(&parts[0], Context::Value) == values(&parts[1], Context::Value));
+
+            if !symmetric {
+                return None;
+            }
+
+            Some(vec![
+                (format!("{}left", prefix), part(0)),
+                (format!("{}right", prefix), part(0)),
+            ])
+        }
+
+        "place-content" | "place-items" | "place-self" => {
+            let property = &name[6..];
+            let complex = ["first", "last", "safe", "unsafe", "legacy", "baseline"];
+
+            if parts.is_empty()
+                || parts.len() > 2
+                || parts.iter().any(|part| is_keyword(part, &complex))
+            {
+                return None;
+            }
+
+            Some(vec![
+                (format!("align-{}", property), part(0)),
+                (format!("justify-{}", property), part(parts.len() - 1)),
+            ])
+        }
+
+        _ => None,
+    }
+}
+
+fn items(items: &mut Vec<BlockItem>, targets: &[Target]) {
+    let mut output: Vec<BlockItem> = Vec::new();
+
+    for item in items.drain(..) {
+        match item {
+            BlockItem::Declaration(declaration) => {
+                let name = declaration.name.to_ascii_lowercase();
+                let lacking = compat::FALLBACKS.iter().any(|(property, engines)| {
+                    *property == name && compat::lacks(targets, engines)
+                });
+
+                match longhands(&name, &declaration.value) {
+                    Some(longhands) if lacking => {
+                        output.extend(longhands.into_iter().map(|(name, value)| {
+                            BlockItem::Declaration(Declaration {
+                                name,
+                                value,
+                                important: declaration.important,
+                                at: declaration.at,
+                            })
+                        }))
+                    }
+                    _ => output.push(BlockItem::Declaration(declaration)),
+                }
+            }
+            BlockItem::Rule(mut rule) => {
+                self::rule(&mut rule, targets);
+                output.push(BlockItem::Rule(rule));
+            }
+        }
+    }
+
+    *items = output;
+}
+
+fn rule(rule: &mut Rule, targets: &[Target]) {
+    match rule {
+        Rule::Qualified(rule) => items(&mut rule.block, targets),
+        Rule::At(rule) => {
+            if let Some(block) = &mut rule.block {
+                items(block, targets);
+            }
+        }
+    }
+}
+
+/**
+ * Replaces properties the targets lack with ones they have
+ */
+pub fn stylesheet(stylesheet: &mut Stylesheet, targets: &[Target]) {
+    for rule in stylesheet.rules.iter_mut() {
+        self::rule(rule, targets);
+    }
+}
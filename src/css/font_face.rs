@@ -0,0 +1,754 @@
+/**
+ * @font-face rules
+ * https://www.w3.org/TR/css-fonts-4/#font-face-rule
+ *
+ * Descriptors are validated the way browsers read them, and what they'd ignore is dropped: unknown descriptors,
+ * invalid values, src entries that aren't url() or local() with an optional format() and tech(), and whole faces
+ * without a font-family or src. problems() lists what was wrong, for the invalid-font-face lint rule.
+ *
+ * What's left is shortened:
+ *
+ *  - font-weight: bold 400 => 400 700 (keywords become numbers, ranges go up, 400 400 => 400)
+ *  - unicode-range: U+100-17F, U+0-FF => U+0-17F (sorted, with overlapping and adjacent ranges joined)
+ *  - a face that's the same as an earlier one for the same family, apart from unicode-range, replaces it with both
+ *    ranges
+ *  - with targets, src is ordered by format from the smallest files to the largest, leaving out formats none of the
+ *    targets support (embedded-opentype, and svg without Safari). local() entries stay first, and src isn't
+ *    reordered when an entry has no format() or uses tech().
+ */
+use super::compat::{self, Target};
+use super::layer;
+use super::parse::{consume, tree::*};
+use super::serialize::{values, Context};
+use super::token::tokens::{CSSToken, NumericFlag};
+
+const DESCRIPTORS: [&str; 16] = [
+    "ascent-override",
+    "descent-override",
+    "font-display",
+    "font-family",
+    "font-feature-settings",
+    "font-language-override",
+    "font-named-instance",
+    "font-stretch",
+    "font-style",
+    "font-variation-settings",
+    "font-weight",
+    "font-width",
+    "line-gap-override",
+    "size-adjust",
+    "src",
+    "unicode-range",
+];
+
+// Names a font-family can't be, as they mean something else in font-family properties
+const RESERVED: [&str; 19] = [
+    "cursive",
+    "default",
+    "emoji",
+    "fangsong",
+    "fantasy",
+    "inherit",
+    "initial",
+    "math",
+    "monospace",
+    "revert",
+    "revert-layer",
+    "sans-serif",
+    "serif",
+    "system-ui",
+    "ui-monospace",
+    "ui-rounded",
+    "ui-sans-serif",
+    "ui-serif",
+    "unset",
+];
+
+// § 4.3.3 The keywords format() takes, strings can be anything
+const FORMATS: [&str; 7] = [
+    "collection",
+    "embedded-opentype",
+    "opentype",
+    "svg",
+    "truetype",
+    "woff",
+    "woff2",
+];
+
+// § 4.3.4 The font technologies tech() takes
+const TECHNOLOGIES: [&str; 11] = [
+    "color-cbdt",
+    "color-colrv0",
+    "color-colrv1",
+    "color-sbix",
+    "color-svg",
+    "features-aat",
+    "features-graphite",
+    "features-opentype",
+    "incremental",
+    "palettes",
+    "variations",
+];
+
+const STRETCHES: [&str; 9] = [
+    "ultra-condensed",
+    "extra-condensed",
+    "condensed",
+    "semi-condensed",
+    "normal",
+    "semi-expanded",
+    "expanded",
+    "extra-expanded",
+    "ultra-expanded",
+];
+
+// One entry of src
+struct Source {
+    values: Vec<ComponentValue>,
+    local: bool,
+
+    // What format() says, in lowercase
+    format: Option<String>,
+    tech: bool,
+}
+
+fn is_whitespace(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Whitespace))
+}
+
+fn is_face(rule: &Rule) -> bool {
+    matches!(rule, Rule::At(rule) if rule.name.eq_ignore_ascii_case("font-face"))
+}
+
+// The components of a value, without whitespace
+fn parts(value: &[ComponentValue]) -> Vec<&ComponentValue> {
+    value.iter().filter(|value| !is_whitespace(value)).collect()
+}
+
+// Splits a value on commas, trimming each part
+fn split(value: &[ComponentValue]) -> Vec<Vec<ComponentValue>> {
+    let mut parts: Vec<Vec<ComponentValue>> = vec![Vec::new()];
+
+    for component in value {
+        match component {
+            ComponentValue::Token(CSSToken::Comma) => parts.push(Vec::new()),
+            component => parts.last_mut().unwrap().push(component.clone()),
+        }
+    }
+
+    for part in parts.iter_mut() {
+        consume::trim(part);
+    }
+
+    parts
+}
+
+fn is_keyword(value: &ComponentValue, keywords: &[&str]) -> bool {
+    match value {
+        ComponentValue::Token(CSSToken::Ident(name)) => keywords
+            .iter()
+            .any(|keyword| name.eq_ignore_ascii_case(keyword)),
+        _ => false,
+    }
+}
+
+// A <family-name>: a string, or identifiers that aren't a generic family or a CSS-wide keyword on their own
+fn is_family_name(value: &[ComponentValue]) -> bool {
+    match parts(value).as_slice() {
+        [ComponentValue::Token(CSSToken::String(_))] => true,
+        [name] => {
+            matches!(name, ComponentValue::Token(CSSToken::Ident(_)))
+                && !is_keyword(name, &RESERVED)
+        }
+        names => {
+            !names.is_empty()
+                && names
+                    .iter()
+                    .all(|name| matches!(name, ComponentValue::Token(CSSToken::Ident(_))))
+        }
+    }
+}
+
+fn is_url(value: &ComponentValue) -> bool {
+    match value {
+        ComponentValue::Token(CSSToken::URL(_)) => true,
+        ComponentValue::Function(function) => function.name.eq_ignore_ascii_case("url"),
+        _ => false,
+    }
+}
+
+fn function<'a>(value: &'a ComponentValue, name: &str) -> Option<&'a Function> {
+    match value {
+        ComponentValue::Function(function) if function.name.eq_ignore_ascii_case(name) => {
+            Some(function)
+        }
+        _ => None,
+    }
+}
+
+// § 4.3.3 format(woff2) or format("woff2"), and the older format("woff", "truetype")
+fn format(function: &Function) -> Option<String> {
+    let formats = split(&function.value);
+    let strings: Option<Vec<&String>> = formats
+        .iter()
+        .map(|format| match format.as_slice() {
+            [ComponentValue::Token(CSSToken::String(format))] => Some(format),
+            _ => None,
+        })
+        .collect();
+
+    match (strings, formats.as_slice()) {
+        (Some(strings), _) => Some(strings[0].to_ascii_lowercase()),
+        (None, [format]) => match format.as_slice() {
+            [ComponentValue::Token(CSSToken::Ident(format))]
+                if FORMATS.contains(&format.to_ascii_lowercase().as_str()) =>
+            {
+                Some(format.to_ascii_lowercase())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// § 4.3.4 tech(variations, color-colrv1)
+fn is_tech(function: &Function) -> bool {
+    split(&function.value)
+        .iter()
+        .all(|technology| match technology.as_slice() {
+            [technology] => is_keyword(technology, &TECHNOLOGIES),
+            _ => false,
+        })
+}
+
+// § 4.3 An entry of src: url() [format()]? [tech()]?, or local()
+fn source(values: Vec<ComponentValue>) -> Option<Source> {
+    let parts = parts(&values);
+
+    let (local, format, tech) = match parts.as_slice() {
+        [local] => match function(local, "local") {
+            Some(local) if is_family_name(&local.value) => (true, None, false),
+            _ if is_url(local) => (false, None, false),
+            _ => return None,
+        },
+        [url, rest @ ..] if is_url(url) && rest.len() <= 2 => {
+            let mut rest = rest.iter().peekable();
+
+            let format = match rest.peek().and_then(|value| function(value, "format")) {
+                Some(function) => {
+                    rest.next();
+                    Some(self::format(function)?)
+                }
+                None => None,
+            };
+
+            let tech = match rest.next() {
+                Some(value) => match function(value, "tech") {
+                    Some(function) if is_tech(function) => true,
+                    _ => return None,
+                },
+                None => false,
+            };
+
+            if rest.next().is_some() {
+                return None;
+            }
+
+            (false, format, tech)
+        }
+        _ => return None,
+    };
+
+    Some(Source {
+        values,
+        local,
+        format,
+        tech,
+    })
+}
+
+fn is_number(value: &ComponentValue, min: f64, max: f64) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Number(number, _)) if (min..=max).contains(number))
+}
+
+fn is_percentage(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Percentage(percentage)) if *percentage >= 0.0)
+}
+
+fn is_angle(value: &ComponentValue) -> bool {
+    match value {
+        ComponentValue::Token(CSSToken::Dimension(_, _, unit)) => ["deg", "grad", "rad", "turn"]
+            .iter()
+            .any(|angle| unit.eq_ignore_ascii_case(angle)),
+        _ => false,
+    }
+}
+
+// Whether a descriptor other than src has a valid value
+fn is_valid(name: &str, value: &[ComponentValue]) -> bool {
+    let parts = parts(value);
+
+    // Most descriptors take auto on its own, or one or two values for a range
+    let range = |valid: &dyn Fn(&ComponentValue) -> bool| match parts.as_slice() {
+        [auto] if is_keyword(auto, &["auto"]) => true,
+        [value] => valid(value),
+        [start, end] => valid(start) && valid(end),
+        _ => false,
+    };
+
+    match name {
+        "font-family" => is_family_name(value),
+        "font-display" => match parts.as_slice() {
+            [display] => is_keyword(display, &["auto", "block", "swap", "fallback", "optional"]),
+            _ => false,
+        },
+        "font-weight" => {
+            range(&|value| is_keyword(value, &["normal", "bold"]) || is_number(value, 1.0, 1000.0))
+        }
+        "font-stretch" | "font-width" => {
+            range(&|value| is_keyword(value, &STRETCHES) || is_percentage(value))
+        }
+        "font-style" => match parts.as_slice() {
+            [style] => is_keyword(style, &["auto", "normal", "italic", "oblique"]),
+            [oblique, angles @ ..] if angles.len() <= 2 => {
+                is_keyword(oblique, &["oblique"]) && angles.iter().all(|angle| is_angle(angle))
+            }
+            _ => false,
+        },
+        "unicode-range" => split(value).iter().all(|range| {
+            matches!(
+                range.as_slice(),
+                [ComponentValue::Token(CSSToken::UnicodeRange(..))]
+            )
+        }),
+        "ascent-override" | "descent-override" | "line-gap-override" => match parts.as_slice() {
+            [value] => is_keyword(value, &["normal"]) || is_percentage(value),
+            _ => false,
+        },
+        "size-adjust" => matches!(parts.as_slice(), [value] if is_percentage(value)),
+
+        // Feature and variation settings, instances and languages are passed on to the font
+        _ => !parts.is_empty(),
+    }
+}
+
+// The descriptors of a face that browsers would use, with what's wrong with the rest. None if the face is invalid.
+fn descriptors(rule: &AtRule) -> (Option<Vec<Declaration>>, Vec<String>) {
+    let mut problems: Vec<String> = Vec::new();
+    let mut descriptors: Vec<Declaration> = Vec::new();
+
+    if !rule.prelude.is_empty() {
+        problems.push(format!(
+            "@font-face {} has a prelude, so it's ignored",
+            values(&rule.prelude, Context::Prelude)
+        ));
+        return (None, problems);
+    }
+
+    for item in rule.block.iter().flatten() {
+        let declaration = match item {
+            BlockItem::Declaration(declaration) => declaration,
+            BlockItem::Rule(_) => {
+                problems.push(String::from("@font-face can't have rules inside it"));
+                continue;
+            }
+        };
+        let name = declaration.name.to_ascii_lowercase();
+
+        if !DESCRIPTORS.contains(&name.as_str()) {
+            problems.push(format!(
+                "Unknown @font-face descriptor {}",
+                declaration.name
+            ));
+            continue;
+        }
+        if declaration.important {
+            problems.push(format!(
+                "!important isn't allowed in @font-face: {}",
+                declaration
+            ));
+            continue;
+        }
+
+        let mut declaration = Declaration {
+            name,
+            value: declaration.value.clone(),
+            important: false,
+            at: declaration.at,
+        };
+
+        if declaration.name == "src" {
+            let mut sources: Vec<ComponentValue> = Vec::new();
+
+            for entry in split(&declaration.value) {
+                let written = values(&entry, Context::Value);
+
+                match source(entry) {
+                    Some(source) => {
+                        if !sources.is_empty() {
+                            sources.push(ComponentValue::Token(CSSToken::Comma));
+                        }
+                        sources.extend(source.values);
+                    }
+                    None => problems.push(format!("Invalid src entry in @font-face: {}", written)),
+                }
+            }
+
+            if sources.is_empty() {
+                continue;
+            }
+            declaration.value = sources;
+        } else if !is_valid(&declaration.name, &declaration.value) {
+            problems.push(format!("Invalid @font-face descriptor {}", declaration));
+            continue;
+        }
+
+        // The last one wins
+        descriptors.retain(|other| other.name != declaration.name);
+        descriptors.push(declaration);
+    }
+
+    for required in ["font-family", "src"] {
+        if !descriptors
+            .iter()
+            .any(|descriptor| descriptor.name == required)
+        {
+            problems.push(format!(
+                "@font-face without a valid {} is ignored",
+                required
+            ));
+            return (None, problems);
+        }
+    }
+
+    (Some(descriptors), problems)
+}
+
+// bold 400 => 400 700
+fn weight(value: &mut Vec<ComponentValue>) {
+    let weights: Vec<f64> = parts(value)
+        .iter()
+        .filter_map(|weight| match weight {
+            ComponentValue::Token(CSSToken::Number(number, _)) => Some(*number),
+            weight if is_keyword(weight, &["normal"]) => Some(400.0),
+            weight if is_keyword(weight, &["bold"]) => Some(700.0),
+            _ => None,
+        })
+        .collect();
+
+    let mut weights = match weights.as_slice() {
+        [weight] => vec![*weight],
+        [start, end] if start == end => vec![*start],
+        [start, end] => vec![start.min(*end), start.max(*end)],
+        _ => return,
+    };
+
+    *value = weights
+        .drain(..)
+        .enumerate()
+        .flat_map(|(index, weight)| {
+            let flag = if weight.fract() == 0.0 {
+                NumericFlag::Integer
+            } else {
+                NumericFlag::Number
+            };
+            let number = ComponentValue::Token(CSSToken::Number(weight, flag));
+
+            match index {
+                0 => vec![number],
+                _ => vec![ComponentValue::Token(CSSToken::Whitespace), number],
+            }
+        })
+        .collect();
+}
+
+fn ranges(value: &[ComponentValue]) -> Vec<(u32, u32)> {
+    value
+        .iter()
+        .filter_map(|range| match range {
+            ComponentValue::Token(CSSToken::UnicodeRange(start, end)) => Some((*start, *end)),
+            _ => None,
+        })
+        .collect()
+}
+
+// Sorts ranges, joining the ones that overlap or touch
+fn coalesce(mut ranges: Vec<(u32, u32)>) -> Vec<ComponentValue> {
+    ranges.sort();
+
+    let mut joined: Vec<(u32, u32)> = Vec::new();
+    for (start, end) in ranges {
+        match joined.last_mut() {
+            Some((_, last)) if start <= last.saturating_add(1) => *last = (*last).max(end),
+            _ => joined.push((start, end)),
+        }
+    }
+
+    let mut value: Vec<ComponentValue> = Vec::new();
+    for (start, end) in joined {
+        if !value.is_empty() {
+            value.push(ComponentValue::Token(CSSToken::Comma));
+        }
+        value.push(ComponentValue::Token(CSSToken::UnicodeRange(start, end)));
+    }
+
+    value
+}
+
+// Orders src from the smallest format to the largest, leaving out the ones no target supports
+fn order(value: &mut Vec<ComponentValue>, targets: &[Target]) {
+    let sources: Vec<Source> = split(value).into_iter().filter_map(source).collect();
+
+    let rank = |source: &Source| {
+        source.format.as_ref().and_then(|format| {
+            compat::FONT_FORMATS
+                .iter()
+                .position(|(known, _)| known == format)
+        })
+    };
+
+    // Without a known format (or with tech()), an entry may have been put where it is on purpose
+    if sources
+        .iter()
+        .any(|source| !source.local && (source.tech || rank(source).is_none()))
+    {
+        return;
+    }
+
+    let mut urls: Vec<(usize, &Source)> = sources
+        .iter()
+        .filter(|source| !source.local)
+        .map(|source| (rank(source).unwrap_or(0), source))
+        .collect();
+    urls.sort_by_key(|(rank, _)| *rank);
+
+    // When none of the formats are supported they're all kept, something has to be tried
+    let supported = |rank: usize| !compat::unsupported(targets, &compat::FONT_FORMATS[rank].1);
+    if urls.iter().any(|(rank, _)| supported(*rank)) {
+        urls.retain(|(rank, _)| supported(*rank));
+    }
+
+    // local() entries keep their place, the urls fill the places urls were in
+    let mut urls = urls.into_iter().map(|(_, source)| source);
+    let mut ordered: Vec<ComponentValue> = Vec::new();
+
+    for source in &sources {
+        let source = match source.local {
+            true => source,
+            false => match urls.next() {
+                Some(url) => url,
+                None => continue,
+            },
+        };
+
+        if !ordered.is_empty() {
+            ordered.push(ComponentValue::Token(CSSToken::Comma));
+        }
+        ordered.extend(source.values.iter().cloned());
+    }
+
+    *value = ordered;
+}
+
+fn family(rule: &Rule) -> Option<String> {
+    let block = match rule {
+        Rule::At(rule) => rule.block.as_ref()?,
+        Rule::Qualified(_) => return None,
+    };
+
+    block.iter().find_map(|item| match item {
+        BlockItem::Declaration(declaration) if declaration.name == "font-family" => {
+            Some(values(&declaration.value, Context::Value).to_ascii_lowercase())
+        }
+        _ => None,
+    })
+}
+
+// The descriptors of a face apart from unicode-range, in a form that can be compared
+fn signature(rule: &Rule) -> Vec<String> {
+    let mut signature: Vec<String> = match rule {
+        Rule::At(AtRule {
+            block: Some(block), ..
+        }) => block
+            .iter()
+            .filter_map(|item| match item {
+                BlockItem::Declaration(declaration) if declaration.name != "unicode-range" => {
+                    Some(declaration.to_string())
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    signature.sort();
+    signature
+}
+
+fn unicode_range(rule: &Rule) -> Option<Vec<(u32, u32)>> {
+    match rule {
+        Rule::At(AtRule {
+            block: Some(block), ..
+        }) => block.iter().find_map(|item| match item {
+            BlockItem::Declaration(declaration) if declaration.name == "unicode-range" => {
+                Some(ranges(&declaration.value))
+            }
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+// Replaces a face with a later one that's the same apart from unicode-range, when no other face for the family is
+// between them (it would take precedence over the earlier one, but not the later one). Faces aren't moved past a layer
+// either, as the order of layers matters more than the order of the rules.
+fn merge(rules: &mut Vec<Rule>) {
+    let mut index = 0;
+
+    while index < rules.len() {
+        let family = match is_face(&rules[index])
+            .then(|| family(&rules[index]))
+            .flatten()
+        {
+            Some(family) => family,
+            None => {
+                index += 1;
+                continue;
+            }
+        };
+
+        let next = (index + 1..rules.len())
+            .take_while(|other| !layer::contains(&rules[*other]))
+            .find(|other| {
+                is_face(&rules[*other]) && self::family(&rules[*other]) == Some(family.clone())
+            });
+
+        let other = match next {
+            Some(other) if signature(&rules[index]) == signature(&rules[other]) => other,
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+
+        // Without unicode-range a face covers everything
+        let ranges = match (unicode_range(&rules[index]), unicode_range(&rules[other])) {
+            (Some(mut first), Some(second)) => {
+                first.extend(second);
+                Some(first)
+            }
+            _ => None,
+        };
+
+        if let Rule::At(AtRule {
+            block: Some(block), ..
+        }) = &mut rules[other]
+        {
+            block.retain(|item| {
+                !matches!(item, BlockItem::Declaration(declaration) if declaration.name == "unicode-range")
+            });
+
+            if let Some(ranges) = ranges {
+                block.push(BlockItem::Declaration(Declaration {
+                    name: String::from("unicode-range"),
+                    value: coalesce(ranges),
+                    important: false,
+                    at: 0,
+                }));
+            }
+        }
+
+        rules.remove(index);
+    }
+}
+
+fn face(rule: &mut AtRule, targets: &[Target]) -> bool {
+    let mut descriptors = match descriptors(rule).0 {
+        Some(descriptors) => descriptors,
+        None => return false,
+    };
+
+    for descriptor in descriptors.iter_mut() {
+        match descriptor.name.as_str() {
+            "font-weight" => weight(&mut descriptor.value),
+            "unicode-range" => descriptor.value = coalesce(ranges(&descriptor.value)),
+            "src" if !targets.is_empty() => order(&mut descriptor.value, targets),
+            _ => (),
+        }
+    }
+
+    rule.block = Some(
+        descriptors
+            .into_iter()
+            .map(BlockItem::Declaration)
+            .collect(),
+    );
+    true
+}
+
+fn rules(rules: &mut Vec<Rule>, targets: &[Target]) {
+    rules.retain_mut(|rule| match rule {
+        Rule::At(rule) if rule.name.eq_ignore_ascii_case("font-face") => face(rule, targets),
+        Rule::At(AtRule {
+            block: Some(block), ..
+        }) => {
+            // Faces can be in conditional rules, which only hold rules
+            if block.iter().all(|item| matches!(item, BlockItem::Rule(_))) {
+                let mut nested: Vec<Rule> = std::mem::take(block)
+                    .into_iter()
+                    .filter_map(|item| match item {
+                        BlockItem::Rule(rule) => Some(rule),
+                        BlockItem::Declaration(_) => None,
+                    })
+                    .collect();
+
+                self::rules(&mut nested, targets);
+                *block = nested.into_iter().map(BlockItem::Rule).collect();
+            }
+            true
+        }
+        _ => true,
+    });
+
+    merge(rules);
+}
+
+/**
+ * Drops what browsers would ignore in @font-face rules and shortens the rest
+ */
+pub fn stylesheet(stylesheet: &mut Stylesheet, targets: &[Target]) {
+    rules(&mut stylesheet.rules, targets);
+}
+
+fn collect(rule: &Rule, problems: &mut Vec<(i32, String)>) {
+    let block = match rule {
+        Rule::At(rule) if rule.name.eq_ignore_ascii_case("font-face") => {
+            let (_, found) = descriptors(rule);
+            problems.extend(found.into_iter().map(|problem| (rule.at, problem)));
+            return;
+        }
+        Rule::At(rule) => match &rule.block {
+            Some(block) => block,
+            None => return,
+        },
+        Rule::Qualified(rule) => &rule.block,
+    };
+
+    for item in block {
+        if let BlockItem::Rule(rule) = item {
+            collect(rule, problems);
+        }
+    }
+}
+
+/**
+ * What's wrong with the @font-face rules of a stylesheet, along with where the rules are
+ */
+pub fn problems(stylesheet: &Stylesheet) -> Vec<(i32, String)> {
+    let mut problems: Vec<(i32, String)> = Vec::new();
+    for rule in &stylesheet.rules {
+        collect(rule, &mut problems);
+    }
+
+    problems
+}
@@ -0,0 +1,788 @@
+/**
+ * Property value grammars
+ * https://www.w3.org/TR/css-values-4/#value-defs
+ *
+ * The grammars in definitions.rs are written in the value definition syntax of the specs ([ <length> | auto ]{1,4},
+ * <color> || <line-style>, <shadow>#) and compiled into nodes once. A declaration is checked by matching its
+ * component values against the grammar of its property, trying every way the value could be split up, which gives
+ * either what each component value is (typed parse) or the first one that can't be matched.
+ *
+ * Juxtaposition binds tighter than &&, which binds tighter than ||, which binds tighter than |. Whitespace between
+ * component values isn't significant, and a comma is left out along with everything before or after it (§ 2.6).
+ * Values using var() and friends, or a vendor prefix, aren't checked.
+ */
+use super::compat;
+use super::definitions::{PROPERTIES, TYPES};
+use super::parse::tree::*;
+use super::serialize::{values, Context};
+use super::token::tokens::CSSToken;
+use super::types;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+enum Node {
+    // auto, matched without case
+    Keyword(String),
+
+    // , or /
+    Literal(char),
+
+    // <length [0,∞]>, a type from types.rs or definitions.rs with an optional range
+    Type(String, Option<(f64, f64)>),
+
+    // <'margin-top'>, the grammar of another property
+    Property(String),
+
+    // fit-content( <length-percentage> ), the arguments matched as a whole
+    Function(String, Box<Node>),
+
+    // a b c
+    Sequence(Vec<Node>),
+
+    // a && b, all of them in any order
+    All(Vec<Node>),
+
+    // a || b, one or more of them in any order
+    Any(Vec<Node>),
+
+    // a | b, exactly one of them
+    One(Vec<Node>),
+
+    // a?, a*, a+, a{1,4} and a# (comma separated), the maximum is None for no limit
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        comma: bool,
+    },
+
+    // [ a? b? ]!, at least one component value
+    Required(Box<Node>),
+}
+
+// § 2.1 The parts of the value definition syntax
+#[derive(Clone, PartialEq)]
+enum Part {
+    Open,
+    Close,
+    Function(String),
+    Closing,
+    Keyword(String),
+    Literal(char),
+    Type(String),
+    Bar,
+    Or,
+    And,
+    Multiplier(char),
+    Range(usize, Option<usize>),
+}
+
+fn number(text: &str) -> Option<f64> {
+    match text.trim() {
+        "∞" | "+∞" => Some(f64::INFINITY),
+        "-∞" => Some(f64::NEG_INFINITY),
+        // Bounds can have a unit ([0s,∞]), which is the canonical one
+        text => text
+            .trim_end_matches(|ch: char| ch.is_ascii_alphabetic())
+            .parse()
+            .ok(),
+    }
+}
+
+fn parts(text: &str) -> Option<Vec<Part>> {
+    let mut parts: Vec<Part> = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        let part = match ch {
+            ' ' => continue,
+            '[' => Part::Open,
+            ']' => Part::Close,
+            ')' => Part::Closing,
+            ',' | '/' => Part::Literal(ch),
+            '?' | '*' | '+' | '#' | '!' => Part::Multiplier(ch),
+            '|' if chars.peek() == Some(&'|') => {
+                chars.next();
+                Part::Or
+            }
+            '|' => Part::Bar,
+            '&' if chars.next() == Some('&') => Part::And,
+            '<' => {
+                let mut name = String::new();
+                for ch in chars.by_ref() {
+                    if ch == '>' {
+                        break;
+                    }
+                    name.push(ch);
+                }
+                Part::Type(name)
+            }
+            '{' => {
+                let mut range = String::new();
+                for ch in chars.by_ref() {
+                    if ch == '}' {
+                        break;
+                    }
+                    range.push(ch);
+                }
+
+                match range.split_once(',') {
+                    Some((min, "")) => Part::Range(min.trim().parse().ok()?, None),
+                    Some((min, max)) => {
+                        Part::Range(min.trim().parse().ok()?, Some(max.trim().parse().ok()?))
+                    }
+                    None => {
+                        let count = range.trim().parse().ok()?;
+                        Part::Range(count, Some(count))
+                    }
+                }
+            }
+            ch if ch.is_ascii_alphabetic() || ch == '-' => {
+                let mut name = String::from(ch);
+                while let Some(&ch) = chars.peek() {
+                    if !ch.is_ascii_alphanumeric() && ch != '-' {
+                        break;
+                    }
+                    name.push(ch);
+                    chars.next();
+                }
+
+                if chars.peek() == Some(&'(') {
+                    chars.next();
+                    Part::Function(name)
+                } else {
+                    Part::Keyword(name)
+                }
+            }
+            _ => return None,
+        };
+
+        parts.push(part);
+    }
+
+    Some(parts)
+}
+
+struct Parser {
+    parts: Vec<Part>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Part> {
+        self.parts.get(self.index)
+    }
+
+    fn eat(&mut self, part: &Part) -> bool {
+        let found = self.peek() == Some(part);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    // Nodes joined by a combinator, each made of nodes joined by the next one
+    fn combined(
+        &mut self,
+        combinator: &Part,
+        next: fn(&mut Parser) -> Option<Node>,
+        node: fn(Vec<Node>) -> Node,
+    ) -> Option<Node> {
+        let mut nodes = vec![next(self)?];
+        while self.eat(combinator) {
+            nodes.push(next(self)?);
+        }
+
+        Some(match nodes.len() {
+            1 => nodes.remove(0),
+            _ => node(nodes),
+        })
+    }
+
+    fn one(&mut self) -> Option<Node> {
+        self.combined(&Part::Bar, Parser::any, Node::One)
+    }
+
+    fn any(&mut self) -> Option<Node> {
+        self.combined(&Part::Or, Parser::all, Node::Any)
+    }
+
+    fn all(&mut self) -> Option<Node> {
+        self.combined(&Part::And, Parser::sequence, Node::All)
+    }
+
+    fn sequence(&mut self) -> Option<Node> {
+        let mut nodes: Vec<Node> = Vec::new();
+        while !matches!(
+            self.peek(),
+            None | Some(Part::Close | Part::Closing | Part::Bar | Part::Or | Part::And)
+        ) {
+            nodes.push(self.multiplied()?);
+        }
+
+        match nodes.len() {
+            0 => None,
+            1 => Some(nodes.remove(0)),
+            _ => Some(Node::Sequence(nodes)),
+        }
+    }
+
+    fn multiplied(&mut self) -> Option<Node> {
+        let mut node = self.term()?;
+
+        loop {
+            let (min, max, comma) = match self.peek() {
+                Some(Part::Multiplier('?')) => (0, Some(1), false),
+                Some(Part::Multiplier('*')) => (0, None, false),
+                Some(Part::Multiplier('+')) => (1, None, false),
+                Some(Part::Multiplier('#')) => match self.parts.get(self.index + 1) {
+                    // #{1,4}
+                    Some(Part::Range(min, max)) => {
+                        self.index += 1;
+                        (*min, *max, true)
+                    }
+                    _ => (1, None, true),
+                },
+                Some(Part::Multiplier('!')) => {
+                    self.index += 1;
+                    node = Node::Required(Box::new(node));
+                    continue;
+                }
+                Some(Part::Range(min, max)) => (*min, *max, false),
+                _ => return Some(node),
+            };
+
+            self.index += 1;
+            node = Node::Repeat {
+                node: Box::new(node),
+                min,
+                max,
+                comma,
+            };
+        }
+    }
+
+    fn term(&mut self) -> Option<Node> {
+        let part = self.peek()?.clone();
+        self.index += 1;
+
+        match part {
+            Part::Open => {
+                let node = self.one()?;
+                self.eat(&Part::Close).then_some(node)
+            }
+            Part::Function(name) => {
+                let node = self.one()?;
+                self.eat(&Part::Closing)
+                    .then(|| Node::Function(name, Box::new(node)))
+            }
+            Part::Keyword(name) => Some(Node::Keyword(name)),
+            Part::Literal(ch) => Some(Node::Literal(ch)),
+            Part::Type(name) => {
+                if let Some(property) = name.strip_prefix('\'') {
+                    return Some(Node::Property(property.strip_suffix('\'')?.to_string()));
+                }
+
+                match name.split_once(' ') {
+                    Some((name, range)) => {
+                        let (min, max) = range
+                            .strip_prefix('[')?
+                            .strip_suffix(']')?
+                            .split_once(',')?;
+                        Some(Node::Type(
+                            name.to_string(),
+                            Some((number(min)?, number(max)?)),
+                        ))
+                    }
+                    None => Some(Node::Type(name, None)),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+fn compile(name: &str, text: &str) -> Node {
+    let parts = parts(text);
+    let mut parser = Parser {
+        parts: parts.unwrap_or_default(),
+        index: 0,
+    };
+
+    match parser.one() {
+        Some(node) if parser.index == parser.parts.len() => node,
+        _ => panic!("The grammar of {} can't be parsed: {}", name, text),
+    }
+}
+
+struct Grammars {
+    properties: HashMap<&'static str, Node>,
+    types: HashMap<&'static str, Node>,
+}
+
+fn grammars() -> &'static Grammars {
+    static GRAMMARS: OnceLock<Grammars> = OnceLock::new();
+
+    GRAMMARS.get_or_init(|| Grammars {
+        properties: PROPERTIES
+            .iter()
+            .map(|(name, text)| (*name, compile(name, text)))
+            .collect(),
+        types: TYPES
+            .iter()
+            .map(|(name, text)| (*name, compile(name, text)))
+            .collect(),
+    })
+}
+
+/**
+ * A component value of a declaration, with what it was matched as
+ */
+#[derive(Clone)]
+pub struct Matched {
+    // The types and properties it was matched through, outermost first (font-weight, font-weight-absolute)
+    pub path: Vec<String>,
+
+    // What it matched: <length>, a keyword (lowercase), a literal or a function (steps())
+    pub term: String,
+
+    pub value: ComponentValue,
+}
+
+// Where matching a node can end, with what was matched on the way, the preferred way first
+type Ends = Vec<(usize, Vec<Matched>)>;
+
+fn push(ends: &mut Ends, end: usize, matched: Vec<Matched>) {
+    if !ends.iter().any(|(other, _)| *other == end) {
+        ends.push((end, matched));
+    }
+}
+
+fn joined(matched: &[Matched], more: Vec<Matched>) -> Vec<Matched> {
+    let mut matched = matched.to_vec();
+    matched.extend(more);
+    matched
+}
+
+fn in_range(value: &ComponentValue, range: Option<(f64, f64)>) -> bool {
+    let (min, max) = match range {
+        Some(range) => range,
+        None => return true,
+    };
+
+    match value {
+        ComponentValue::Token(
+            CSSToken::Number(number, _)
+            | CSSToken::Percentage(number)
+            | CSSToken::Dimension(number, _, _),
+        ) => *number >= min && *number <= max,
+        // Math functions are clamped to the range
+        _ => true,
+    }
+}
+
+// Component values without the whitespace between them
+fn significant(values: &[ComponentValue]) -> Vec<ComponentValue> {
+    values
+        .iter()
+        .filter(|value| !matches!(value, ComponentValue::Token(CSSToken::Whitespace)))
+        .cloned()
+        .collect()
+}
+
+struct Matcher<'a> {
+    grammars: &'static Grammars,
+    values: &'a [ComponentValue],
+
+    // How far a term was matched, for saying what's unexpected
+    furthest: Cell<usize>,
+}
+
+impl Matcher<'_> {
+    fn matched(&self, start: usize, path: &[String], term: String) -> Ends {
+        self.furthest.set(self.furthest.get().max(start + 1));
+
+        vec![(
+            start + 1,
+            vec![Matched {
+                path: path.to_vec(),
+                term,
+                value: self.values[start].clone(),
+            }],
+        )]
+    }
+
+    fn ends(&self, node: &Node, start: usize, path: &[String]) -> Ends {
+        let value = self.values.get(start);
+
+        match node {
+            Node::Keyword(keyword) => match value {
+                Some(ComponentValue::Token(CSSToken::Ident(ident)))
+                    if ident.eq_ignore_ascii_case(keyword) =>
+                {
+                    self.matched(start, path, keyword.clone())
+                }
+                _ => Vec::new(),
+            },
+            // § 2.6 A comma is left out when everything before or after it in the list is, or after another comma
+            Node::Literal(',')
+                if start == 0
+                    || start == self.values.len()
+                    || matches!(
+                        self.values[start - 1],
+                        ComponentValue::Token(CSSToken::Comma)
+                    ) =>
+            {
+                vec![(start, Vec::new())]
+            }
+            Node::Literal(literal) => match value {
+                Some(ComponentValue::Token(CSSToken::Comma))
+                    if *literal == ',' && start + 1 < self.values.len() =>
+                {
+                    self.matched(start, path, String::from(","))
+                }
+                Some(ComponentValue::Token(CSSToken::Delim(delim))) if delim == literal => {
+                    self.matched(start, path, literal.to_string())
+                }
+                _ => Vec::new(),
+            },
+            Node::Type(name, range) => self.typed(name, *range, start, path),
+            Node::Property(name) => match self.grammars.properties.get(name.as_str()) {
+                Some(grammar) => {
+                    self.ends(grammar, start, &[path, std::slice::from_ref(name)].concat())
+                }
+                None => Vec::new(),
+            },
+            Node::Function(name, arguments) => match value {
+                Some(ComponentValue::Function(function))
+                    if function.name.eq_ignore_ascii_case(name) =>
+                {
+                    let values = significant(&function.value);
+                    let matcher = Matcher {
+                        grammars: self.grammars,
+                        values: &values,
+                        furthest: Cell::new(0),
+                    };
+
+                    if matcher
+                        .ends(arguments, 0, path)
+                        .iter()
+                        .any(|(end, _)| *end == values.len())
+                    {
+                        self.matched(start, path, format!("{}()", name))
+                    } else {
+                        Vec::new()
+                    }
+                }
+                _ => Vec::new(),
+            },
+            Node::Sequence(nodes) => {
+                let mut ends = Vec::new();
+                self.sequence(nodes, start, Vec::new(), path, &mut ends);
+                ends
+            }
+            Node::All(nodes) | Node::Any(nodes) => {
+                let mut ends = Vec::new();
+                let mut seen = HashSet::new();
+                let all = matches!(node, Node::All(_));
+                self.combination(nodes, all, 0, start, Vec::new(), path, &mut seen, &mut ends);
+                ends
+            }
+            Node::One(nodes) => {
+                let mut ends = Vec::new();
+                for node in nodes {
+                    for (end, matched) in self.ends(node, start, path) {
+                        push(&mut ends, end, matched);
+                    }
+                }
+                ends
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                comma,
+            } => {
+                let mut ends = Vec::new();
+                let repeat = Repeat {
+                    node,
+                    min: *min,
+                    max: *max,
+                    comma: *comma,
+                };
+                self.repeat(&repeat, 0, start, Vec::new(), path, &mut ends);
+                ends
+            }
+            Node::Required(node) => self
+                .ends(node, start, path)
+                .into_iter()
+                .filter(|(end, _)| *end > start)
+                .collect(),
+        }
+    }
+
+    fn typed(&self, name: &str, range: Option<(f64, f64)>, start: usize, path: &[String]) -> Ends {
+        if let Some(grammar) = self.grammars.types.get(name) {
+            return self.ends(grammar, start, &[path, &[name.to_string()]].concat());
+        }
+
+        let value = match self.values.get(start) {
+            Some(value) => value,
+            None => return Vec::new(),
+        };
+        let term = format!("<{}>", name);
+
+        match name {
+            // Anything at all, the longest first
+            "declaration-value" => {
+                self.furthest.set(self.values.len());
+                (start + 1..=self.values.len())
+                    .rev()
+                    .map(|end| {
+                        let matched = self.values[start..end]
+                            .iter()
+                            .map(|value| Matched {
+                                path: path.to_vec(),
+                                term: term.clone(),
+                                value: value.clone(),
+                            })
+                            .collect();
+                        (end, matched)
+                    })
+                    .collect()
+            }
+            // A unitless 0, which some angles can be
+            "zero" => match value {
+                ComponentValue::Token(CSSToken::Number(number, _)) if *number == 0.0 => {
+                    self.matched(start, path, term)
+                }
+                _ => Vec::new(),
+            },
+            name if types::matches(name, value) == Some(true) && in_range(value, range) => {
+                self.matched(start, path, term)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn sequence(
+        &self,
+        nodes: &[Node],
+        position: usize,
+        matched: Vec<Matched>,
+        path: &[String],
+        ends: &mut Ends,
+    ) {
+        let (node, rest) = match nodes.split_first() {
+            Some(split) => split,
+            None => return push(ends, position, matched),
+        };
+
+        for (end, more) in self.ends(node, position, path) {
+            self.sequence(rest, end, joined(&matched, more), path, ends);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn combination(
+        &self,
+        nodes: &[Node],
+        all: bool,
+        used: u64,
+        position: usize,
+        matched: Vec<Matched>,
+        path: &[String],
+        seen: &mut HashSet<(u64, usize)>,
+        ends: &mut Ends,
+    ) {
+        // Where a combination ends only depends on what's used and where it is
+        if !seen.insert((used, position)) {
+            return;
+        }
+
+        let complete = if all {
+            used.count_ones() as usize == nodes.len()
+        } else {
+            used != 0
+        };
+
+        for (index, node) in nodes.iter().enumerate() {
+            if used & (1 << index) != 0 {
+                continue;
+            }
+
+            for (end, more) in self.ends(node, position, path) {
+                // Each of || has to match something, but && takes optional ones
+                if end == position && !all {
+                    continue;
+                }
+
+                self.combination(
+                    nodes,
+                    all,
+                    used | (1 << index),
+                    end,
+                    joined(&matched, more),
+                    path,
+                    seen,
+                    ends,
+                );
+            }
+        }
+
+        if complete {
+            push(ends, position, matched);
+        }
+    }
+
+    fn repeat(
+        &self,
+        repeat: &Repeat,
+        count: usize,
+        position: usize,
+        matched: Vec<Matched>,
+        path: &[String],
+        ends: &mut Ends,
+    ) {
+        if repeat.max.is_none_or(|max| count < max) {
+            let (start, separator) = if repeat.comma && count > 0 {
+                match self.values.get(position) {
+                    Some(ComponentValue::Token(CSSToken::Comma)) => (
+                        Some(position + 1),
+                        self.matched(position, path, String::from(",")).remove(0).1,
+                    ),
+                    _ => (None, Vec::new()),
+                }
+            } else {
+                (Some(position), Vec::new())
+            };
+
+            if let Some(start) = start {
+                for (end, more) in self.ends(repeat.node, start, path) {
+                    // Matching nothing again and again wouldn't end
+                    if end == position && count >= repeat.min {
+                        continue;
+                    }
+
+                    let more = joined(&joined(&matched, separator.clone()), more);
+                    self.repeat(repeat, count + 1, end, more, path, ends);
+                }
+            }
+        }
+
+        // More repetitions are preferred
+        if count >= repeat.min {
+            push(ends, position, matched);
+        }
+    }
+}
+
+struct Repeat<'a> {
+    node: &'a Node,
+    min: usize,
+    max: Option<usize>,
+    comma: bool,
+}
+
+// Whether a value uses a vendor prefixed keyword or function, which aren't in the grammars
+fn is_prefixed(values: &[ComponentValue]) -> bool {
+    values.iter().any(|value| match value {
+        ComponentValue::Token(CSSToken::Ident(name)) => compat::unprefix(name).0.is_some(),
+        ComponentValue::Function(function) => {
+            compat::unprefix(&function.name).0.is_some() || is_prefixed(&function.value)
+        }
+        ComponentValue::Block(block) => is_prefixed(&block.value),
+        ComponentValue::Token(_) => false,
+    })
+}
+
+/**
+ * Matches the value of a declaration against the grammar of its property, giving what each component value is, or
+ * why browsers would ignore the declaration. None when it can't be checked: for custom and unknown properties, and
+ * values with var() or a vendor prefix.
+ */
+pub fn check(declaration: &Declaration) -> Option<Result<Vec<Matched>, String>> {
+    let name = declaration.name.to_ascii_lowercase();
+    let grammar = grammars().properties.get(name.as_str())?;
+
+    if types::is_substituted(&declaration.value) || is_prefixed(&declaration.value) {
+        return None;
+    }
+
+    let values = significant(&declaration.value);
+
+    if let [ComponentValue::Token(CSSToken::Ident(keyword))] = values.as_slice() {
+        if types::is_css_wide(&values) {
+            return Some(Ok(vec![Matched {
+                path: Vec::new(),
+                term: keyword.to_ascii_lowercase(),
+                value: values[0].clone(),
+            }]));
+        }
+    }
+
+    let matcher = Matcher {
+        grammars: grammars(),
+        values: &values,
+        furthest: Cell::new(0),
+    };
+
+    if let Some((_, matched)) = matcher
+        .ends(grammar, 0, &[])
+        .into_iter()
+        .find(|(end, _)| *end == values.len())
+    {
+        return Some(Ok(matched));
+    }
+
+    let reason = match values.get(matcher.furthest.get()) {
+        Some(unexpected) => format!(
+            "{} is unexpected",
+            self::values(std::slice::from_ref(unexpected), Context::Value)
+        ),
+        None if values.is_empty() => String::from("it's empty"),
+        None => String::from("it ends too early"),
+    };
+
+    Some(Err(format!(
+        "{} isn't valid, so browsers ignore it ({})",
+        declaration, reason
+    )))
+}
+
+// Declarations are only checked in style rules, at-rules like @font-face have descriptors with their own grammars
+fn collect(items: &[BlockItem], style: bool, problems: &mut Vec<(i32, String)>) {
+    for item in items {
+        match item {
+            BlockItem::Declaration(declaration) if style => {
+                if let Some(Err(problem)) = check(declaration) {
+                    problems.push((declaration.at, problem));
+                }
+            }
+            BlockItem::Declaration(_) => (),
+            BlockItem::Rule(Rule::Qualified(rule)) => collect(&rule.block, true, problems),
+            BlockItem::Rule(Rule::At(rule)) => {
+                if let Some(block) = &rule.block {
+                    collect(block, style, problems);
+                }
+            }
+        }
+    }
+}
+
+/**
+ * The declarations of a stylesheet whose values don't match the grammar of their property, along with where they are
+ */
+pub fn problems(stylesheet: &Stylesheet) -> Vec<(i32, String)> {
+    let items: Vec<BlockItem> = stylesheet
+        .rules
+        .iter()
+        .cloned()
+        .map(BlockItem::Rule)
+        .collect();
+
+    let mut problems: Vec<(i32, String)> = Vec::new();
+    collect(&items, false, &mut problems);
+    problems
+}
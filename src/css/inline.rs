@@ -0,0 +1,169 @@
+/**
+ * Inlining CSS into style attributes
+ *
+ * Email clients ignore most stylesheets, so every declaration that applies to an element is written into its style
+ * attribute instead. The declarations of each element are put in cascade order (see cascade.rs), weakest first, so the
+ * attribute means what the stylesheets did. Declarations overridden by a later one for the same property are left out,
+ * and the style the element already had comes last, as it wins over the stylesheets.
+ *
+ * Not everything can be inlined, and what can't is kept to go back in a <style> element:
+ *
+ *  - Rules in @media, @supports and other conditional rules, as they don't always apply
+ *  - Selectors with pseudo-elements (::before) or pseudo-classes that depend on the user (:hover), keeping the static
+ *    selectors of the same rule inlined
+ *  - @font-face, @keyframes, @import and other at-rules
+ *
+ * Layers are kept around the rules in them on both sides, so their order still counts. Rules with invalid selectors are
+ * dropped, as browsers would, and elements in <head> aren't styled.
+ *
+ * Kept rules lose to the inlined declarations unless they're !important, which is how the @media rules of responsive
+ * emails are usually written anyway.
+ */
+use super::cascade::{Cascade, Origin};
+use super::media::Viewport;
+use super::nesting;
+use super::parse::tree::*;
+use super::select;
+use super::token::tokens::CSSToken;
+use crate::html::dom::{Document, NodeId};
+use std::collections::HashSet;
+
+// Splits a style rule's selector list into the selectors that can be inlined and the ones that can't
+fn split_rule(rule: &QualifiedRule, inlined: &mut Vec<Rule>, kept: &mut Vec<Rule>) {
+    let mut static_prelude: Vec<ComponentValue> = Vec::new();
+    let mut dynamic_prelude: Vec<ComponentValue> = Vec::new();
+
+    for selector in rule
+        .prelude
+        .split(|value| matches!(value, ComponentValue::Token(CSSToken::Comma)))
+    {
+        // One invalid selector makes the whole rule invalid
+        let prelude = match select::parse(selector) {
+            Ok(parsed) if parsed.is_static() => &mut static_prelude,
+            Ok(_) => &mut dynamic_prelude,
+            Err(_) => return,
+        };
+
+        if !prelude.is_empty() {
+            prelude.push(ComponentValue::Token(CSSToken::Comma));
+        }
+        prelude.extend(selector.iter().cloned());
+    }
+
+    for (prelude, rules) in [(static_prelude, inlined), (dynamic_prelude, kept)] {
+        if !prelude.is_empty() {
+            rules.push(Rule::Qualified(QualifiedRule {
+                prelude,
+                block: rule.block.clone(),
+            }));
+        }
+    }
+}
+
+fn split(rules: &[Rule], inlined: &mut Vec<Rule>, kept: &mut Vec<Rule>) {
+    for rule in rules {
+        let at = match rule {
+            Rule::Qualified(style) => {
+                split_rule(style, inlined, kept);
+                continue;
+            }
+            Rule::At(at) => at,
+        };
+
+        match (at.name.to_ascii_lowercase().as_str(), &at.block) {
+            ("layer", Some(block)) => {
+                let rules: Vec<Rule> = block
+                    .iter()
+                    .filter_map(|item| match item {
+                        BlockItem::Rule(rule) => Some(rule.clone()),
+                        BlockItem::Declaration(_) => None,
+                    })
+                    .collect();
+
+                let mut inner_inlined: Vec<Rule> = Vec::new();
+                let mut inner_kept: Vec<Rule> = Vec::new();
+                split(&rules, &mut inner_inlined, &mut inner_kept);
+
+                if !inner_inlined.is_empty() {
+                    inlined.push(at.with_block(inner_inlined));
+                }
+                if !inner_kept.is_empty() {
+                    kept.push(at.with_block(inner_kept));
+                }
+            }
+            // The order of layers matters on both sides
+            ("layer", None) => {
+                inlined.push(rule.clone());
+                kept.push(rule.clone());
+            }
+            // HTML documents have their own encoding
+            ("charset", _) => (),
+            _ => kept.push(rule.clone()),
+        }
+    }
+}
+
+/**
+ * Writes the declarations of a stylesheet into the style attributes of the elements they apply to, and returns the
+ * rules that couldn't be inlined
+ */
+pub fn document(document: &mut Document, stylesheet: &Stylesheet) -> Stylesheet {
+    let mut flat = Stylesheet {
+        rules: stylesheet.rules.clone(),
+        comments: Vec::new(),
+    };
+    // Kept rules end up in email clients, which don't know :is()
+    nesting::stylesheet(&mut flat, false);
+
+    let mut inlined: Vec<Rule> = Vec::new();
+    let mut kept: Vec<Rule> = Vec::new();
+    split(&flat.rules, &mut inlined, &mut kept);
+
+    // Nothing left to inline depends on the viewport
+    let mut cascade = Cascade::new(Viewport::DEFAULT);
+    cascade.add(
+        &Stylesheet {
+            rules: inlined,
+            comments: Vec::new(),
+        },
+        Origin::Author,
+    );
+
+    let elements: Vec<NodeId> = (0..document.nodes.len())
+        .filter(|id| {
+            document.element(*id).is_some() && !document.within(*id, |name| name == "head")
+        })
+        .collect();
+
+    for id in elements {
+        let mut declarations = cascade.declarations(document, id);
+
+        // Only the last declaration of a property counts
+        let mut seen: HashSet<String> = HashSet::new();
+        declarations.reverse();
+        declarations.retain(|declaration| {
+            let name = &declaration.name;
+            seen.insert(if name.starts_with("--") {
+                name.clone()
+            } else {
+                name.to_ascii_lowercase()
+            })
+        });
+        declarations.reverse();
+
+        if declarations.is_empty() {
+            continue;
+        }
+
+        let style: Vec<String> = declarations
+            .iter()
+            .map(|declaration| declaration.to_string())
+            .collect();
+        document.set_attribute(id, "style", &style.join(";"));
+    }
+
+    Stylesheet {
+        rules: kept,
+        comments: stylesheet.comments.clone(),
+    }
+}
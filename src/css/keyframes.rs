@@ -0,0 +1,337 @@
+/**
+ * @keyframes rules
+ * https://www.w3.org/TR/css-animations-1/#keyframes
+ *
+ * Keyframe selectors are written the shortest way (from => 0%, 100% => to), keyframes with the same declarations are
+ * merged into one rule (0%,to{opacity:0}), and a @keyframes rule followed by another one with the same name in the
+ * same block is dropped, as the last one wins. Empty keyframes and !important declarations, which browsers ignore in
+ * keyframes, are dropped too.
+ *
+ * Keyframes are only merged when none of their selectors appear in another keyframe, as declarations for the same
+ * time cascade across keyframes.
+ *
+ * unused() removes @keyframes rules no animation or animation-name in the stylesheet refers to, and rename() gives
+ * animations new names, in both the @keyframes rules and the declarations using them.
+ */
+use super::compat;
+use super::parse::{consume, tree::*};
+use super::purge::{self, References};
+use super::serialize::{values, Context};
+use super::token::tokens::CSSToken;
+
+// Names a @keyframes rule can't have without quotes
+const RESERVED: [&str; 7] = [
+    "none",
+    "initial",
+    "inherit",
+    "unset",
+    "default",
+    "revert",
+    "revert-layer",
+];
+
+// The name a @keyframes rule defines, quoted or not
+fn name(rule: &AtRule) -> Option<&str> {
+    match rule.prelude.as_slice() {
+        [ComponentValue::Token(CSSToken::Ident(name) | CSSToken::String(name))] => Some(name),
+        _ => None,
+    }
+}
+
+/**
+ * Whether an animation name can be written without quotes
+ */
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    let start = match chars.next() {
+        Some('-') => chars.next(),
+        first => first,
+    };
+
+    start.is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_' || !ch.is_ascii())
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || !ch.is_ascii())
+        && !RESERVED
+            .iter()
+            .any(|reserved| name.eq_ignore_ascii_case(reserved))
+}
+
+// The selectors of a keyframe, written the shortest way and without duplicates
+fn selectors(prelude: &[ComponentValue]) -> Vec<Vec<ComponentValue>> {
+    let mut selectors: Vec<Vec<ComponentValue>> = Vec::new();
+
+    for selector in prelude.split(|value| matches!(value, ComponentValue::Token(CSSToken::Comma))) {
+        let mut selector = selector.to_vec();
+        consume::trim(&mut selector);
+
+        let selector = match selector.as_slice() {
+            [ComponentValue::Token(CSSToken::Ident(from))] if from.eq_ignore_ascii_case("from") => {
+                vec![ComponentValue::Token(CSSToken::Percentage(0.0))]
+            }
+            [ComponentValue::Token(CSSToken::Percentage(to))] if *to == 100.0 => {
+                vec![ComponentValue::Token(CSSToken::Ident(String::from("to")))]
+            }
+            [ComponentValue::Token(CSSToken::Ident(to))] if to.eq_ignore_ascii_case("to") => {
+                vec![ComponentValue::Token(CSSToken::Ident(String::from("to")))]
+            }
+            _ => selector,
+        };
+
+        let written = values(&selector, Context::Selector);
+        if !selectors
+            .iter()
+            .any(|other| values(other, Context::Selector) == written)
+        {
+            selectors.push(selector);
+        }
+    }
+
+    selectors
+}
+
+fn join(selectors: Vec<Vec<ComponentValue>>) -> Vec<ComponentValue> {
+    let mut prelude: Vec<ComponentValue> = Vec::new();
+
+    for selector in selectors {
+        if !prelude.is_empty() {
+            prelude.push(ComponentValue::Token(CSSToken::Comma));
+        }
+        prelude.extend(selector);
+    }
+
+    prelude
+}
+
+fn block(rule: &QualifiedRule) -> String {
+    rule.block
+        .iter()
+        .map(|item| match item {
+            BlockItem::Declaration(declaration) => declaration.to_string(),
+            BlockItem::Rule(_) => String::new(),
+        })
+        .collect::<Vec<String>>()
+        .join(";")
+}
+
+// Merges the keyframes with the same declarations, when their selectors aren't used anywhere else
+fn merge(keyframes: &mut Vec<QualifiedRule>) {
+    let keys: Vec<Vec<String>> = keyframes
+        .iter()
+        .map(|keyframe| {
+            selectors(&keyframe.prelude)
+                .iter()
+                .map(|selector| values(selector, Context::Selector))
+                .collect()
+        })
+        .collect();
+
+    let unique = |index: usize| {
+        keys[index].iter().all(|key| {
+            keys.iter()
+                .enumerate()
+                .all(|(other, keys)| other == index || !keys.contains(key))
+        })
+    };
+
+    let mut merged: Vec<QualifiedRule> = Vec::new();
+    let mut blocks: Vec<Option<String>> = Vec::new();
+
+    for (index, keyframe) in std::mem::take(keyframes).into_iter().enumerate() {
+        let written = unique(index).then(|| block(&keyframe));
+
+        match blocks
+            .iter()
+            .position(|other| written.is_some() && *other == written)
+        {
+            Some(other) => {
+                let mut prelude = std::mem::take(&mut merged[other].prelude);
+                prelude.push(ComponentValue::Token(CSSToken::Comma));
+                prelude.extend(keyframe.prelude);
+                merged[other].prelude = prelude;
+            }
+            None => {
+                merged.push(keyframe);
+                blocks.push(written);
+            }
+        }
+    }
+
+    *keyframes = merged;
+}
+
+fn keyframes(rule: &mut AtRule) {
+    if let Some(name) = name(rule).filter(|name| is_identifier(name)) {
+        rule.prelude = vec![ComponentValue::Token(CSSToken::Ident(name.to_string()))];
+    }
+
+    let block = match &mut rule.block {
+        Some(block) => block,
+        None => return,
+    };
+
+    let mut keyframes: Vec<QualifiedRule> = std::mem::take(block)
+        .into_iter()
+        .filter_map(|item| match item {
+            BlockItem::Rule(Rule::Qualified(mut keyframe)) => {
+                keyframe.block.retain(|item| {
+                    matches!(item, BlockItem::Declaration(declaration) if !declaration.important)
+                });
+                keyframe.prelude = join(selectors(&keyframe.prelude));
+
+                Some(keyframe).filter(|keyframe| !keyframe.block.is_empty())
+            }
+            _ => None,
+        })
+        .collect();
+
+    merge(&mut keyframes);
+
+    *block = keyframes
+        .into_iter()
+        .map(|keyframe| BlockItem::Rule(Rule::Qualified(keyframe)))
+        .collect();
+}
+
+fn rules(rules: &mut Vec<Rule>) {
+    for rule in rules.iter_mut() {
+        let rule = match rule {
+            Rule::At(rule) => rule,
+            Rule::Qualified(_) => continue,
+        };
+
+        if purge::is_keyframes(rule) {
+            keyframes(rule);
+            continue;
+        }
+
+        // Keyframes can be in conditional rules, which only hold rules
+        if let Some(block) = &mut rule.block {
+            if block.iter().all(|item| matches!(item, BlockItem::Rule(_))) {
+                let mut nested: Vec<Rule> = std::mem::take(block)
+                    .into_iter()
+                    .filter_map(|item| match item {
+                        BlockItem::Rule(rule) => Some(rule),
+                        BlockItem::Declaration(_) => None,
+                    })
+                    .collect();
+
+                self::rules(&mut nested);
+                *block = nested.into_iter().map(BlockItem::Rule).collect();
+            }
+        }
+    }
+
+    // The last @keyframes rule with a name wins, prefixed ones are separate
+    let defined: Vec<Option<(String, String)>> = rules
+        .iter()
+        .map(|rule| match rule {
+            Rule::At(rule) if purge::is_keyframes(rule) => {
+                name(rule).map(|name| (rule.name.to_ascii_lowercase(), name.to_string()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut index = 0;
+    rules.retain(|_| {
+        index += 1;
+        match &defined[index - 1] {
+            Some(name) => !defined[index..].contains(&Some(name.clone())),
+            None => true,
+        }
+    });
+}
+
+/**
+ * Shortens the @keyframes rules of a stylesheet
+ */
+pub fn stylesheet(stylesheet: &mut Stylesheet) {
+    rules(&mut stylesheet.rules);
+}
+
+fn remove(items: &mut Vec<BlockItem>, references: &References) {
+    items.retain_mut(|item| match item {
+        BlockItem::Rule(Rule::At(rule)) if purge::is_keyframes(rule) => {
+            references.refers(rule) != Some(false)
+        }
+        BlockItem::Rule(Rule::At(AtRule {
+            block: Some(block), ..
+        })) => {
+            remove(block, references);
+            true
+        }
+        _ => true,
+    });
+}
+
+/**
+ * Removes the @keyframes rules nothing in the stylesheet refers to (custom properties count, they could end up in an
+ * animation). When an animation reads a custom property the stylesheet doesn't define, they're all kept.
+ */
+pub fn unused(stylesheet: &mut Stylesheet) {
+    let mut items: Vec<BlockItem> = std::mem::take(&mut stylesheet.rules)
+        .into_iter()
+        .map(BlockItem::Rule)
+        .collect();
+
+    let mut references = References::default();
+    purge::references(&items, &mut references);
+    remove(&mut items, &references);
+
+    stylesheet.rules = items
+        .into_iter()
+        .filter_map(|item| match item {
+            BlockItem::Rule(rule) => Some(rule),
+            BlockItem::Declaration(_) => None,
+        })
+        .collect();
+}
+
+// Renames the animations in an animation or animation-name value
+fn names(value: &mut [ComponentValue], names: &[(String, String)]) {
+    for component in value.iter_mut() {
+        if let ComponentValue::Token(CSSToken::Ident(name) | CSSToken::String(name)) = component {
+            if let Some((_, renamed)) = names.iter().find(|(original, _)| original == name) {
+                *component = ComponentValue::Token(CSSToken::Ident(renamed.clone()));
+            }
+        }
+    }
+}
+
+fn rename_rules(rules: &mut [Rule], renames: &[(String, String)]) {
+    for rule in rules.iter_mut() {
+        let block = match rule {
+            Rule::Qualified(rule) => &mut rule.block,
+            Rule::At(rule) => {
+                if purge::is_keyframes(rule) {
+                    names(&mut rule.prelude, renames);
+                    continue;
+                }
+
+                match &mut rule.block {
+                    Some(block) => block,
+                    None => continue,
+                }
+            }
+        };
+
+        for item in block.iter_mut() {
+            match item {
+                BlockItem::Declaration(declaration) => {
+                    let name = declaration.name.to_ascii_lowercase();
+
+                    if matches!(compat::unprefix(&name).1, "animation" | "animation-name") {
+                        names(&mut declaration.value, renames);
+                    }
+                }
+                BlockItem::Rule(rule) => rename_rules(std::slice::from_mut(rule), renames),
+            }
+        }
+    }
+}
+
+/**
+ * Gives animations new names, (original, new) pairs
+ */
+pub fn rename(stylesheet: &mut Stylesheet, renames: &[(String, String)]) {
+    rename_rules(&mut stylesheet.rules, renames);
+}
@@ -0,0 +1,506 @@
+/**
+ * Cascade layers
+ * https://www.w3.org/TR/css-cascade-5/#layering
+ *
+ * @layer statements (@layer a, b.c;) declare layers, @layer blocks (@layer a { ... }, or @layer { ... } for an
+ * anonymous layer) hold their rules, and @import url(x) layer(a) puts a whole stylesheet in one. Layers are ordered by
+ * when their name is first seen, among the sublayers of the same layer. Rules directly in a layer come after its
+ * sublayers, and rules outside of any layer after every layer (§ 6.4.3).
+ *
+ * flatten() is for browsers without cascade layers. @layer rules are unwrapped and selectors get :not(#\#), which
+ * matches any element with the specificity of an ID, repeated so that every layer beats the layers before it whatever
+ * the specificity of their selectors. !important declarations, where earlier layers win, are moved to a rule of their
+ * own counting the other way. revert-layer can't be written without layers, and @keyframes or @font-face rules with
+ * the same name in different layers are decided by their order instead.
+ */
+use super::parse::{consume, tree::*};
+use super::token::tokens::{CSSToken, HashFlag};
+use super::url;
+use std::collections::HashMap;
+
+// At-rules that can hold @layer rules, and style rules in a layer
+const GROUPS: [&str; 6] = [
+    "container",
+    "layer",
+    "media",
+    "scope",
+    "starting-style",
+    "supports",
+];
+
+/**
+ * What an @layer rule declares
+ */
+pub enum Layer {
+    // @layer a, b.c;
+    Statement(Vec<Vec<String>>),
+
+    // @layer a.b { ... }, without a name for an anonymous layer
+    Block(Option<Vec<String>>),
+}
+
+// The rules in a block
+fn rules(block: Vec<BlockItem>) -> Vec<Rule> {
+    block
+        .into_iter()
+        .filter_map(|item| match item {
+            BlockItem::Rule(rule) => Some(rule),
+            BlockItem::Declaration(_) => None,
+        })
+        .collect()
+}
+
+fn is_whitespace(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Whitespace))
+}
+
+// A layer name, idents separated by dots without any whitespace (a.b.c)
+fn name(values: &[ComponentValue]) -> Option<Vec<String>> {
+    let mut values = values.to_vec();
+    consume::trim(&mut values);
+
+    let mut parts: Vec<String> = Vec::new();
+    for (index, value) in values.iter().enumerate() {
+        match (index % 2, value) {
+            (0, ComponentValue::Token(CSSToken::Ident(part))) => parts.push(part.clone()),
+            (1, ComponentValue::Token(CSSToken::Delim('.'))) => (),
+            _ => return None,
+        }
+    }
+
+    (values.len() % 2 == 1).then_some(parts)
+}
+
+/**
+ * The layer names in a list (a, b.c), each split on its dots. None when one of them isn't a valid name.
+ */
+pub fn names(values: &[ComponentValue]) -> Option<Vec<Vec<String>>> {
+    values
+        .split(|value| matches!(value, ComponentValue::Token(CSSToken::Comma)))
+        .map(name)
+        .collect()
+}
+
+/**
+ * What an @layer rule declares, None for other rules and for @layer rules browsers would ignore
+ */
+pub fn layer(rule: &AtRule) -> Option<Layer> {
+    if !rule.name.eq_ignore_ascii_case("layer") {
+        return None;
+    }
+
+    let anonymous = rule.prelude.iter().all(is_whitespace);
+
+    match &rule.block {
+        None if anonymous => None,
+        None => names(&rule.prelude).map(Layer::Statement),
+        Some(_) if anonymous => Some(Layer::Block(None)),
+        Some(_) => match names(&rule.prelude)?.as_slice() {
+            [name] => Some(Layer::Block(Some(name.clone()))),
+            _ => None,
+        },
+    }
+}
+
+/**
+ * The layer an @import rule puts the stylesheet in: Some(None) for an anonymous one (layer), Some(Some(name)) for
+ * layer(name)
+ */
+pub fn import(rule: &AtRule) -> Option<Option<Vec<String>>> {
+    if !rule.name.eq_ignore_ascii_case("import") {
+        return None;
+    }
+
+    let mut values = rule.prelude.iter().filter(|value| !is_whitespace(value));
+    url::value(values.next()?)?;
+
+    match values.next()? {
+        ComponentValue::Token(CSSToken::Ident(layer)) if layer.eq_ignore_ascii_case("layer") => {
+            Some(None)
+        }
+        ComponentValue::Function(function) if function.name.eq_ignore_ascii_case("layer") => {
+            name(&function.value).map(Some)
+        }
+        _ => None,
+    }
+}
+
+/**
+ * Whether a rule is, or holds, an @layer block
+ */
+pub fn contains(rule: &Rule) -> bool {
+    match rule {
+        Rule::At(rule) if matches!(layer(rule), Some(Layer::Block(_))) => true,
+        Rule::At(AtRule {
+            block: Some(block), ..
+        }) => block
+            .iter()
+            .any(|item| matches!(item, BlockItem::Rule(rule) if contains(rule))),
+        _ => false,
+    }
+}
+
+/**
+ * § 6.4.3 The layers seen so far, in the order they were declared
+ */
+#[derive(Default)]
+pub struct Layers {
+    // Layer names by the path of their parent layer
+    sublayers: HashMap<Vec<String>, Vec<String>>,
+    anonymous: u32,
+}
+
+impl Layers {
+    /**
+     * The position of a layer among its ancestors' sublayers, declaring it if it's new. Positions sort the way layers
+     * apply, with declarations directly in a layer after its sublayers, and the ones outside of layers ([]) last.
+     */
+    pub fn declare(&mut self, path: &[String]) -> Vec<u32> {
+        let mut key: Vec<u32> = Vec::new();
+
+        for index in 0..path.len() {
+            let siblings = self.sublayers.entry(path[..index].to_vec()).or_default();
+            let position = match siblings.iter().position(|name| *name == path[index]) {
+                Some(position) => position,
+                None => {
+                    siblings.push(path[index].clone());
+                    siblings.len() - 1
+                }
+            };
+            key.push(position as u32);
+        }
+
+        key.push(u32::MAX);
+        key
+    }
+
+    /**
+     * Declares the layer a block puts its rules in, inside another layer, and returns its path. Every anonymous layer
+     * is a new one.
+     */
+    pub fn block(&mut self, parent: &[String], name: Option<Vec<String>>) -> Vec<String> {
+        let mut path = parent.to_vec();
+        match name {
+            Some(name) => path.extend(name),
+            None => {
+                self.anonymous += 1;
+                path.push(format!("\0{}", self.anonymous));
+            }
+        }
+
+        self.declare(&path);
+        path
+    }
+
+    // The named layers under a layer, from the weakest to the strongest
+    fn named(&self, path: &[String], order: &mut Vec<Vec<String>>) {
+        for name in self.sublayers.get(path).into_iter().flatten() {
+            if name.starts_with('\0') {
+                continue;
+            }
+
+            let mut sublayer = path.to_vec();
+            sublayer.push(name.clone());
+
+            self.named(&sublayer, order);
+            order.push(sublayer);
+        }
+    }
+
+    /**
+     * The named layers, from the weakest to the strongest (a.b before a)
+     */
+    pub fn order(&self) -> Vec<Vec<String>> {
+        let mut order: Vec<Vec<String>> = Vec::new();
+        self.named(&[], &mut order);
+        order
+    }
+}
+
+// Declares the layers in a list of rules, in the order a browser sees them, and calls style for each style rule with
+// the position of its layer
+fn walk(
+    rules: &[Rule],
+    path: &[String],
+    layers: &mut Layers,
+    style: &mut impl FnMut(&QualifiedRule, Vec<u32>),
+) {
+    for rule in rules {
+        let rule = match rule {
+            Rule::Qualified(rule) => {
+                style(rule, layers.declare(path));
+                continue;
+            }
+            Rule::At(rule) => rule,
+        };
+
+        let block = self::rules(rule.block.clone().unwrap_or_default());
+
+        match layer(rule) {
+            Some(Layer::Statement(names)) => {
+                for name in names {
+                    layers.block(path, Some(name));
+                }
+            }
+            Some(Layer::Block(name)) => {
+                let path = layers.block(path, name);
+                walk(&block, &path, layers, style);
+            }
+            None if rule.name.eq_ignore_ascii_case("layer") => (),
+            None => {
+                if let Some(name) = import(rule) {
+                    layers.block(path, name);
+                } else if GROUPS
+                    .iter()
+                    .any(|group| rule.name.eq_ignore_ascii_case(group))
+                {
+                    walk(&block, path, layers, style);
+                }
+            }
+        }
+    }
+}
+
+/**
+ * The named layers of a stylesheet, from the weakest to the strongest
+ */
+pub fn order(rules: &[Rule]) -> Vec<Vec<String>> {
+    let mut layers = Layers::default();
+    walk(rules, &[], &mut layers, &mut |_, _| ());
+    layers.order()
+}
+
+/**
+ * An @layer statement declaring layers in order, leaving out the ones a sublayer before them declares (a.b, a)
+ */
+pub fn statement(names: &[Vec<String>]) -> Rule {
+    let mut prelude: Vec<ComponentValue> = Vec::new();
+
+    for (index, name) in names.iter().enumerate() {
+        if names[..index].iter().any(|other| other.starts_with(name)) {
+            continue;
+        }
+
+        if !prelude.is_empty() {
+            prelude.push(ComponentValue::Token(CSSToken::Comma));
+        }
+
+        for (index, part) in name.iter().enumerate() {
+            if index > 0 {
+                prelude.push(ComponentValue::Token(CSSToken::Delim('.')));
+            }
+            prelude.push(ComponentValue::Token(CSSToken::Ident(part.clone())));
+        }
+    }
+
+    Rule::At(AtRule {
+        name: String::from("layer"),
+        prelude,
+        block: None,
+        at: 0,
+    })
+}
+
+// The most IDs a selector list can have, counting every ID in it (:is(#a, #b) only has one, but more is fine) and
+// the ones nested rules add
+fn ids(rule: &QualifiedRule) -> u32 {
+    fn count(values: &[ComponentValue]) -> u32 {
+        values
+            .iter()
+            .map(|value| match value {
+                ComponentValue::Token(CSSToken::Hash(..)) => 1,
+                ComponentValue::Function(function) => count(&function.value),
+                ComponentValue::Block(block) => count(&block.value),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    fn nested(items: &[BlockItem]) -> u32 {
+        items
+            .iter()
+            .map(|item| match item {
+                BlockItem::Rule(Rule::Qualified(rule)) => ids(rule),
+                BlockItem::Rule(Rule::At(AtRule {
+                    block: Some(block), ..
+                })) => nested(block),
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    let own = rule
+        .prelude
+        .split(|value| matches!(value, ComponentValue::Token(CSSToken::Comma)))
+        .map(count)
+        .max()
+        .unwrap_or(0);
+
+    own + nested(&rule.block)
+}
+
+fn is_combinator(value: &ComponentValue) -> bool {
+    matches!(
+        value,
+        ComponentValue::Token(CSSToken::Whitespace | CSSToken::Delim('>' | '+' | '~'))
+    )
+}
+
+// Where a pseudo-element starts in a compound selector (::before, or :before and the other legacy ones)
+fn pseudo_element(compound: &[ComponentValue]) -> Option<usize> {
+    compound.windows(2).position(|pair| match pair {
+        [ComponentValue::Token(CSSToken::Colon), ComponentValue::Token(CSSToken::Colon)] => true,
+        [ComponentValue::Token(CSSToken::Colon), ComponentValue::Token(CSSToken::Ident(name))] => {
+            ["after", "before", "first-letter", "first-line"]
+                .iter()
+                .any(|legacy| name.eq_ignore_ascii_case(legacy))
+        }
+        _ => false,
+    })
+}
+
+// Adds :not(#\#) to each selector in a list, in the last compound selector before any pseudo-element
+fn raise(prelude: &[ComponentValue], ids: usize) -> Vec<ComponentValue> {
+    if ids == 0 {
+        return prelude.to_vec();
+    }
+
+    let not = [
+        ComponentValue::Token(CSSToken::Colon),
+        ComponentValue::Function(Function {
+            name: String::from("not"),
+            value: vec![ComponentValue::Token(CSSToken::Hash(
+                String::from("#"),
+                HashFlag::Id,
+            ))],
+        }),
+    ];
+
+    let mut raised: Vec<ComponentValue> = Vec::new();
+
+    for selector in prelude.split(|value| matches!(value, ComponentValue::Token(CSSToken::Comma))) {
+        let mut selector = selector.to_vec();
+        consume::trim(&mut selector);
+
+        let compound = selector
+            .iter()
+            .rposition(is_combinator)
+            .map_or(0, |combinator| combinator + 1);
+        let at = pseudo_element(&selector[compound..]).map_or(selector.len(), |at| compound + at);
+
+        if !raised.is_empty() {
+            raised.push(ComponentValue::Token(CSSToken::Comma));
+        }
+        raised.extend(selector[..at].iter().cloned());
+        for _ in 0..ids {
+            raised.extend(not.iter().cloned());
+        }
+        raised.extend(selector[at..].iter().cloned());
+    }
+
+    raised
+}
+
+// Unwraps the @layer rules in a list, raising the specificity of each style rule by the IDs its layer needs
+fn unwrap(
+    rules: Vec<Rule>,
+    keys: &mut impl Iterator<Item = Vec<u32>>,
+    ids: &impl Fn(&[u32], bool) -> usize,
+) -> Vec<Rule> {
+    let mut unwrapped: Vec<Rule> = Vec::new();
+
+    for rule in rules {
+        let mut rule = match rule {
+            Rule::Qualified(rule) => {
+                let key = keys.next().unwrap_or_default();
+                let (normal, important) = (ids(&key, false), ids(&key, true));
+
+                if normal == important {
+                    unwrapped.push(Rule::Qualified(QualifiedRule {
+                        prelude: raise(&rule.prelude, normal),
+                        block: rule.block,
+                    }));
+                    continue;
+                }
+
+                // !important declarations count the other way, so they need a rule of their own
+                let (important_items, items): (Vec<BlockItem>, Vec<BlockItem>) =
+                    rule.block.into_iter().partition(|item| {
+                        matches!(item, BlockItem::Declaration(declaration) if declaration.important)
+                    });
+
+                for (block, ids) in [(items, normal), (important_items, important)] {
+                    if !block.is_empty() {
+                        unwrapped.push(Rule::Qualified(QualifiedRule {
+                            prelude: raise(&rule.prelude, ids),
+                            block,
+                        }));
+                    }
+                }
+                continue;
+            }
+            Rule::At(rule) => rule,
+        };
+
+        // Statements only set the order, and browsers ignore invalid @layer rules
+        if rule.name.eq_ignore_ascii_case("layer") {
+            if let (Some(Layer::Block(_)), Some(block)) = (layer(&rule), rule.block) {
+                unwrapped.extend(unwrap(self::rules(block), keys, ids));
+            }
+            continue;
+        }
+
+        let grouped = GROUPS
+            .iter()
+            .any(|group| rule.name.eq_ignore_ascii_case(group));
+
+        if let Some(block) = rule.block.as_mut().filter(|_| grouped) {
+            *block = unwrap(self::rules(std::mem::take(block)), keys, ids)
+                .into_iter()
+                .map(BlockItem::Rule)
+                .collect();
+        }
+        unwrapped.push(Rule::At(rule));
+    }
+
+    unwrapped
+}
+
+/**
+ * Replaces the @layer rules of a stylesheet with style rules that win in the same order, for browsers without
+ * cascade layers
+ */
+pub fn flatten(stylesheet: &mut Stylesheet) {
+    let mut layers = Layers::default();
+    let mut keys: Vec<Vec<u32>> = Vec::new();
+    let mut most = 0;
+
+    walk(&stylesheet.rules, &[], &mut layers, &mut |rule, key| {
+        most = most.max(ids(rule));
+        keys.push(key);
+    });
+
+    let mut ranks = keys.clone();
+    ranks.sort();
+    ranks.dedup();
+
+    // Each layer needs one ID more than any selector in the layer before it can have
+    let step = most as usize + 1;
+    let ids = |key: &[u32], important: bool| {
+        let rank = ranks
+            .binary_search_by(|other| other.as_slice().cmp(key))
+            .unwrap_or(0);
+        if important {
+            (ranks.len() - 1 - rank) * step
+        } else {
+            rank * step
+        }
+    };
+
+    stylesheet.rules = unwrap(
+        std::mem::take(&mut stylesheet.rules),
+        &mut keys.into_iter(),
+        &ids,
+    );
+}
@@ -0,0 +1,243 @@
+/**
+ * Class and ID mangling
+ *
+ * Renames the classes and IDs in selectors to the shortest names available, with the most used names getting the
+ * shortest ones. What each original name became is written as JSON next to the output (main.min.css.json), for
+ * templates to look the new names up in:
+ *
+ *  {
+ *    "classes": { "button": "a", "button-primary": "b" },
+ *    "ids": { "main": "a" }
+ *  }
+ *
+ * Classes and IDs are named separately, as they can't be confused in HTML. Reserved names (which can use * and ?
+ * wildcards) are left alone and never given to anything else. The seed shuffles the letters new names are made from,
+ * the same stylesheet and seed always give the same names. Only selectors are rewritten, so attribute selectors
+ * ([class~=button]) and <style> elements in HTML files aren't.
+ */
+use super::parse::tree::*;
+use super::token::tokens::{CSSToken, HashFlag};
+use crate::inputs;
+use std::fmt;
+
+// Names start with a letter, lowercase so they still match in quirks mode
+const FIRST: &str = "abcdefghijklmnopqrstuvwxyz";
+const REST: &str = "abcdefghijklmnopqrstuvwxyz0123456789";
+
+// How often each name is used, in the order they're found
+#[derive(Default)]
+struct Counts {
+    classes: Vec<(String, usize)>,
+    ids: Vec<(String, usize)>,
+}
+
+fn count(names: &mut Vec<(String, usize)>, name: &str) {
+    match names.iter_mut().find(|(other, _)| other == name) {
+        Some((_, count)) => *count += 1,
+        None => names.push((name.to_string(), 1)),
+    }
+}
+
+/**
+ * The original and mangled names
+ */
+pub struct Names {
+    classes: Vec<(String, String)>,
+    ids: Vec<(String, String)>,
+}
+
+fn find<'a>(names: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    names
+        .iter()
+        .find(|(original, _)| original == name)
+        .map(|(_, mangled)| mangled.as_str())
+}
+
+// Calls visit with every class (true) and ID (false) in a selector, which can change them
+fn selector(values: &mut [ComponentValue], visit: &mut dyn FnMut(&mut String, bool)) {
+    let mut class = false;
+
+    for value in values.iter_mut() {
+        match value {
+            ComponentValue::Token(CSSToken::Ident(name)) if class => visit(name, true),
+            ComponentValue::Token(CSSToken::Hash(name, HashFlag::Id)) => visit(name, false),
+            ComponentValue::Function(function) => selector(&mut function.value, visit),
+            ComponentValue::Block(block) => selector(&mut block.value, visit),
+            _ => (),
+        }
+
+        class = matches!(value, ComponentValue::Token(CSSToken::Delim('.')));
+    }
+}
+
+fn rules(rules: &mut [Rule], visit: &mut dyn FnMut(&mut String, bool)) {
+    for rule in rules.iter_mut() {
+        let block = match rule {
+            Rule::Qualified(rule) => {
+                selector(&mut rule.prelude, visit);
+                &mut rule.block
+            }
+            Rule::At(rule) => {
+                // @scope (.card) to (.content) is the only at-rule with selectors in its prelude
+                if rule.name.eq_ignore_ascii_case("scope") {
+                    selector(&mut rule.prelude, visit);
+                }
+
+                match &mut rule.block {
+                    Some(block) => block,
+                    None => continue,
+                }
+            }
+        };
+
+        for item in block.iter_mut() {
+            if let BlockItem::Rule(rule) = item {
+                self::rules(std::slice::from_mut(rule), visit);
+            }
+        }
+    }
+}
+
+// A small pseudo random generator (SplitMix64), so the same seed always shuffles the same way
+fn shuffle(alphabet: &str, seed: u64) -> Vec<char> {
+    let mut chars: Vec<char> = alphabet.chars().collect();
+    if seed == 0 {
+        return chars;
+    }
+
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    };
+
+    for index in (1..chars.len()).rev() {
+        let other = (next() % (index as u64 + 1)) as usize;
+        chars.swap(index, other);
+    }
+
+    chars
+}
+
+// The nth shortest name (a, b, ... z, aa, ab, ...)
+fn generate(mut index: usize, first: &[char], rest: &[char]) -> String {
+    let mut name = String::new();
+    name.push(first[index % first.len()]);
+    index /= first.len();
+
+    while index > 0 {
+        index -= 1;
+        name.push(rest[index % rest.len()]);
+        index /= rest.len();
+    }
+
+    name
+}
+
+fn is_reserved(name: &str, reserved: &[String]) -> bool {
+    let name: Vec<char> = name.chars().collect();
+
+    reserved.iter().any(|pattern| {
+        let pattern: Vec<char> = pattern.chars().collect();
+        inputs::matches(&pattern, &name)
+    })
+}
+
+// Gives the most used names the shortest new names, skipping reserved ones
+fn assign(mut counts: Vec<(String, usize)>, reserved: &[String], seed: u64) -> Vec<(String, String)> {
+    let first = shuffle(FIRST, seed);
+    let rest = shuffle(REST, seed.rotate_left(32));
+
+    counts.retain(|(name, _)| !is_reserved(name, reserved));
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+
+    let mut index = 0;
+    counts
+        .into_iter()
+        .map(|(name, _)| loop {
+            let mangled = generate(index, &first, &rest);
+            index += 1;
+
+            if !is_reserved(&mangled, reserved) {
+                return (name, mangled);
+            }
+        })
+        .collect()
+}
+
+/**
+ * Mangles the classes and IDs in a stylesheet, returning the names they were given
+ */
+pub fn stylesheet(stylesheet: &mut Stylesheet, reserved: &[String], seed: u64) -> Names {
+    let mut counts = Counts::default();
+    rules(&mut stylesheet.rules, &mut |name, class| {
+        if class {
+            count(&mut counts.classes, name);
+        } else {
+            count(&mut counts.ids, name);
+        }
+    });
+
+    let names = Names {
+        classes: assign(counts.classes, reserved, seed),
+        ids: assign(counts.ids, reserved, seed),
+    };
+
+    rules(&mut stylesheet.rules, &mut |name, class| {
+        let names = if class { &names.classes } else { &names.ids };
+
+        if let Some(mangled) = find(names, name) {
+            *name = mangled.to_string();
+        }
+    });
+
+    names
+}
+
+// A JSON string
+pub fn string(string: &str) -> String {
+    let mut quoted = String::from("\"");
+
+    for ch in string.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            ch if (ch as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+impl fmt::Display for Names {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{{")?;
+
+        for (index, (key, names)) in [("classes", &self.classes), ("ids", &self.ids)]
+            .iter()
+            .enumerate()
+        {
+            write!(f, "  {}: {{", string(key))?;
+
+            for (index, (original, mangled)) in names.iter().enumerate() {
+                let comma = if index + 1 < names.len() { "," } else { "" };
+                write!(f, "\n    {}: {}{}", string(original), string(mangled), comma)?;
+            }
+
+            let comma = if index == 0 { "," } else { "" };
+            if names.is_empty() {
+                writeln!(f, "}}{}", comma)?;
+            } else {
+                writeln!(f, "\n  }}{}", comma)?;
+            }
+        }
+
+        writeln!(f, "}}")
+    }
+}
@@ -0,0 +1,385 @@
+/**
+ * Media query evaluation
+ * https://www.w3.org/TR/mediaqueries-5/
+ *
+ * Decides whether a media query list applies to a screen of a given size. Both the min-/max- prefixes and the range
+ * syntax (400px <= width < 800px) are understood. Everything that isn't the size of the viewport takes the value a
+ * typical desktop browser would have: a fine pointer that can hover, 1dppx, light colors and no reduced motion. Lengths
+ * in em and rem are 16px.
+ *
+ * Features that aren't known are taken to match, so a rule is only ruled out when it's certain not to apply.
+ */
+use super::parse::tree::*;
+use super::token::tokens::CSSToken;
+
+/**
+ * The size of the screen, in CSS pixels
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Viewport {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Viewport {
+    // A common laptop screen, used when no size is given
+    pub const DEFAULT: Viewport = Viewport {
+        width: 1300.0,
+        height: 900.0,
+    };
+
+    /**
+     * Reads a size like 1300x900
+     */
+    pub fn parse(size: &str) -> Option<Viewport> {
+        let (width, height) = size.split_once('x')?;
+        let width: f64 = width.trim().parse().ok()?;
+        let height: f64 = height.trim().parse().ok()?;
+
+        (width > 0.0 && height > 0.0).then_some(Viewport { width, height })
+    }
+}
+
+// The value of a feature for the viewport
+enum Value {
+    Number(f64),
+    Keyword(&'static str),
+}
+
+// What a feature's values are measured in
+#[derive(Clone, Copy)]
+enum Kind {
+    Length,
+    Ratio,
+    Resolution,
+    Number,
+    Keyword,
+}
+
+fn feature(name: &str, viewport: &Viewport) -> Option<(Kind, Value)> {
+    let number = |kind: Kind, value: f64| Some((kind, Value::Number(value)));
+    let keyword = |value: &'static str| Some((Kind::Keyword, Value::Keyword(value)));
+
+    match name {
+        "width" | "device-width" => number(Kind::Length, viewport.width),
+        "height" | "device-height" => number(Kind::Length, viewport.height),
+        "aspect-ratio" | "device-aspect-ratio" => {
+            number(Kind::Ratio, viewport.width / viewport.height)
+        }
+        "orientation" if viewport.height >= viewport.width => keyword("portrait"),
+        "orientation" => keyword("landscape"),
+        "resolution" => number(Kind::Resolution, 1.0),
+        "color" => number(Kind::Number, 8.0),
+        "color-index" | "monochrome" | "grid" => number(Kind::Number, 0.0),
+        "hover" | "any-hover" => keyword("hover"),
+        "pointer" | "any-pointer" => keyword("fine"),
+        "prefers-color-scheme" => keyword("light"),
+        "prefers-reduced-motion"
+        | "prefers-reduced-transparency"
+        | "prefers-reduced-data"
+        | "prefers-contrast" => keyword("no-preference"),
+        "forced-colors" | "inverted-colors" => keyword("none"),
+        "scan" => keyword("progressive"),
+        "update" => keyword("fast"),
+        "overflow-block" | "overflow-inline" => keyword("scroll"),
+        "display-mode" => keyword("browser"),
+        "scripting" => keyword("enabled"),
+        "color-gamut" => keyword("srgb"),
+        "dynamic-range" | "video-dynamic-range" => keyword("standard"),
+        _ => None,
+    }
+}
+
+// A length in pixels
+fn length(value: f64, unit: &str, viewport: &Viewport) -> Option<f64> {
+    Some(match unit.to_ascii_lowercase().as_str() {
+        "px" => value,
+        "em" | "rem" => value * 16.0,
+        "ex" | "ch" | "rex" | "rch" => value * 8.0,
+        "vw" => value * viewport.width / 100.0,
+        "vh" => value * viewport.height / 100.0,
+        "vmin" => value * viewport.width.min(viewport.height) / 100.0,
+        "vmax" => value * viewport.width.max(viewport.height) / 100.0,
+        "in" => value * 96.0,
+        "cm" => value * 96.0 / 2.54,
+        "mm" => value * 96.0 / 25.4,
+        "q" => value * 96.0 / 101.6,
+        "pt" => value * 96.0 / 72.0,
+        "pc" => value * 16.0,
+        _ => return None,
+    })
+}
+
+// A resolution in dppx
+fn resolution(value: f64, unit: &str) -> Option<f64> {
+    Some(match unit.to_ascii_lowercase().as_str() {
+        "dppx" | "x" => value,
+        "dpi" => value / 96.0,
+        "dpcm" => value * 2.54 / 96.0,
+        _ => return None,
+    })
+}
+
+// Reads a value of a kind, which is None when it's not understood
+fn value(tokens: &[&CSSToken], kind: Kind, viewport: &Viewport) -> Option<f64> {
+    match (kind, tokens) {
+        (Kind::Length, [CSSToken::Dimension(value, _, unit)]) => length(*value, unit, viewport),
+        (Kind::Resolution, [CSSToken::Dimension(value, _, unit)]) => resolution(*value, unit),
+        (Kind::Ratio, [CSSToken::Number(a, _), CSSToken::Delim('/'), CSSToken::Number(b, _)]) => {
+            Some(a / b)
+        }
+        (_, [CSSToken::Number(value, _)]) => Some(*value),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn compare(self, a: f64, b: f64) -> bool {
+        match self {
+            Comparison::Less => a < b,
+            Comparison::LessOrEqual => a <= b,
+            Comparison::Equal => a == b,
+            Comparison::GreaterOrEqual => a >= b,
+            Comparison::Greater => a > b,
+        }
+    }
+
+    // The same comparison with the sides swapped (1 < width is width > 1)
+    fn flip(self) -> Comparison {
+        match self {
+            Comparison::Less => Comparison::Greater,
+            Comparison::LessOrEqual => Comparison::GreaterOrEqual,
+            Comparison::Equal => Comparison::Equal,
+            Comparison::GreaterOrEqual => Comparison::LessOrEqual,
+            Comparison::Greater => Comparison::Less,
+        }
+    }
+}
+
+// Splits a range like 400px <= width < 800px into its values and comparisons
+fn comparisons<'a>(
+    tokens: &'a [&'a CSSToken],
+) -> Option<(Vec<&'a [&'a CSSToken]>, Vec<Comparison>)> {
+    let mut parts: Vec<&[&CSSToken]> = Vec::new();
+    let mut comparisons: Vec<Comparison> = Vec::new();
+    let mut start = 0;
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let equals = matches!(tokens.get(index + 1), Some(CSSToken::Delim('=')));
+        let comparison = match tokens[index] {
+            CSSToken::Delim('<') if equals => Comparison::LessOrEqual,
+            CSSToken::Delim('<') => Comparison::Less,
+            CSSToken::Delim('>') if equals => Comparison::GreaterOrEqual,
+            CSSToken::Delim('>') => Comparison::Greater,
+            CSSToken::Delim('=') => Comparison::Equal,
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+
+        parts.push(&tokens[start..index]);
+        comparisons.push(comparison);
+        index += if equals { 2 } else { 1 };
+        start = index;
+    }
+    parts.push(&tokens[start..]);
+
+    (comparisons.len() == parts.len() - 1).then_some((parts, comparisons))
+}
+
+// A feature inside parentheses: (color), (min-width: 600px) or (width >= 600px)
+fn media_feature(values: &[ComponentValue], viewport: &Viewport) -> bool {
+    let tokens: Option<Vec<&CSSToken>> = values
+        .iter()
+        .filter(|value| !matches!(value, ComponentValue::Token(CSSToken::Whitespace)))
+        .map(|value| match value {
+            ComponentValue::Token(token) => Some(token),
+            _ => None,
+        })
+        .collect();
+
+    // Values with calc() and other functions aren't worked out
+    let tokens = match tokens {
+        Some(tokens) => tokens,
+        None => return true,
+    };
+
+    match tokens.as_slice() {
+        // Boolean context, which is false for 0, none and no-preference
+        [CSSToken::Ident(name)] => match feature(&name.to_ascii_lowercase(), viewport) {
+            Some((_, Value::Number(value))) => value != 0.0,
+            Some((_, Value::Keyword(keyword))) => keyword != "none" && keyword != "no-preference",
+            None => true,
+        },
+        [CSSToken::Ident(name), CSSToken::Colon, rest @ ..] => {
+            let name = name.to_ascii_lowercase();
+            let (prefix, name) = match (name.strip_prefix("min-"), name.strip_prefix("max-")) {
+                (Some(name), _) => (Some(Comparison::GreaterOrEqual), name),
+                (_, Some(name)) => (Some(Comparison::LessOrEqual), name),
+                _ => (None, name.as_str()),
+            };
+
+            match (feature(name, viewport), rest) {
+                (Some((_, Value::Keyword(keyword))), [CSSToken::Ident(expected)])
+                    if prefix.is_none() =>
+                {
+                    expected.eq_ignore_ascii_case(keyword)
+                }
+                (Some((kind, Value::Number(actual))), rest) => match value(rest, kind, viewport) {
+                    Some(expected) => prefix
+                        .unwrap_or(Comparison::Equal)
+                        .compare(actual, expected),
+                    None => true,
+                },
+                (Some(_), _) => false,
+                (None, _) => true,
+            }
+        }
+        tokens => {
+            let (parts, comparisons) = match comparisons(tokens) {
+                Some(range) => range,
+                None => return true,
+            };
+
+            // The feature is the only part that's an identifier
+            let position = match parts
+                .iter()
+                .position(|part| matches!(part, [CSSToken::Ident(_)]))
+            {
+                Some(position) => position,
+                None => return true,
+            };
+            let (kind, actual) = match parts[position] {
+                [CSSToken::Ident(name)] => match feature(&name.to_ascii_lowercase(), viewport) {
+                    Some((kind, Value::Number(actual))) => (kind, actual),
+                    _ => return true,
+                },
+                _ => return true,
+            };
+
+            comparisons.iter().enumerate().all(|(index, comparison)| {
+                // Each comparison is between the feature and the value on its other side
+                let (other, comparison) = if index < position {
+                    (parts[index], comparison.flip())
+                } else {
+                    (parts[index + 1], *comparison)
+                };
+
+                value(other, kind, viewport)
+                    .is_none_or(|expected| comparison.compare(actual, expected))
+            })
+        }
+    }
+}
+
+fn is_ident(value: Option<&&ComponentValue>, ident: &str) -> bool {
+    matches!(value, Some(ComponentValue::Token(CSSToken::Ident(name))) if name.eq_ignore_ascii_case(ident))
+}
+
+// A condition made of features, not, and, or and parentheses
+fn condition(values: &[&ComponentValue], viewport: &Viewport) -> bool {
+    if is_ident(values.first(), "not") {
+        return !condition(&values[1..], viewport);
+    }
+
+    let mut result: Option<bool> = None;
+    let mut or = false;
+
+    for (index, value) in values.iter().enumerate() {
+        if index % 2 == 1 {
+            or = is_ident(Some(value), "or");
+            continue;
+        }
+
+        let matched = match value {
+            ComponentValue::Block(block) if matches!(block.token, CSSToken::LeftParentheses) => {
+                let inner: Vec<&ComponentValue> = block
+                    .value
+                    .iter()
+                    .filter(|value| !matches!(value, ComponentValue::Token(CSSToken::Whitespace)))
+                    .collect();
+
+                // A nested condition, or a feature
+                if matches!(inner.first(), Some(ComponentValue::Block(_)))
+                    || is_ident(inner.first(), "not")
+                {
+                    condition(&inner, viewport)
+                } else {
+                    media_feature(&block.value, viewport)
+                }
+            }
+            _ => true,
+        };
+
+        result = Some(match result {
+            Some(result) if or => result || matched,
+            Some(result) => result && matched,
+            None => matched,
+        });
+    }
+
+    result.unwrap_or(true)
+}
+
+// A single media query: [not | only] type [and condition], or a condition
+fn query(values: &[ComponentValue], viewport: &Viewport) -> bool {
+    let values: Vec<&ComponentValue> = values
+        .iter()
+        .filter(|value| !matches!(value, ComponentValue::Token(CSSToken::Whitespace)))
+        .collect();
+
+    let (not, start) = match values.first() {
+        Some(ComponentValue::Token(CSSToken::Ident(name))) if name.eq_ignore_ascii_case("not") => {
+            (true, 1)
+        }
+        Some(ComponentValue::Token(CSSToken::Ident(name))) if name.eq_ignore_ascii_case("only") => {
+            (false, 1)
+        }
+        _ => (false, 0),
+    };
+
+    let matched = match values.get(start) {
+        Some(ComponentValue::Token(CSSToken::Ident(media))) => {
+            let media = matches!(media.to_ascii_lowercase().as_str(), "all" | "screen");
+
+            if is_ident(values.get(start + 1), "and") {
+                media && condition(&values[start + 2..], viewport)
+            } else {
+                media
+            }
+        }
+        _ => return condition(&values, viewport),
+    };
+
+    matched != not
+}
+
+/**
+ * Whether a media query list (the prelude of @media) applies to the viewport
+ */
+pub fn matches(prelude: &[ComponentValue], viewport: &Viewport) -> bool {
+    let queries: Vec<&[ComponentValue]> = prelude
+        .split(|value| matches!(value, ComponentValue::Token(CSSToken::Comma)))
+        .collect();
+
+    // An empty list is all
+    if prelude
+        .iter()
+        .all(|value| matches!(value, ComponentValue::Token(CSSToken::Whitespace)))
+    {
+        return true;
+    }
+
+    queries.iter().any(|values| query(values, viewport))
+}
@@ -1,17 +1,100 @@
+pub mod canonical;
+pub mod color;
+pub mod compat;
+pub mod container;
+pub mod cascade;
+pub mod critical;
+pub mod definitions;
+pub mod fallback;
+pub mod font_face;
+pub mod grammar;
+pub mod inline;
+pub mod keyframes;
+pub mod layer;
+pub mod mangle;
+pub mod media;
+pub mod modules;
+pub mod nesting;
+pub mod parse;
+pub mod prefix;
+pub mod property;
+pub mod purge;
+pub mod select;
+pub mod serialize;
 pub mod token;
+pub mod types;
+pub mod url;
+use crate::config::{Comments, Config};
 use std::string::String;
 
-pub fn minimize(contents: String) -> Result<String, token::error::ParseError> {
-    let tokens = match token::tokenize(contents) {
-        Ok(tokens) => tokens,
-        Err(e) => return Err(e),
+/**
+ * Optimizes a parsed (or bundled) stylesheet
+ */
+pub fn optimize(stylesheet: &mut parse::tree::Stylesheet, config: &Config) {
+    if config.comments == Comments::None {
+        stylesheet.comments.clear();
+    }
+
+    if config.enabled("css-container") {
+        container::stylesheet(stylesheet);
+    }
+
+    if config.enabled("css-font-face") {
+        font_face::stylesheet(stylesheet, &config.targets);
+    }
+
+    if config.enabled("css-keyframes") {
+        keyframes::stylesheet(stylesheet);
+    }
+
+    if config.unused_keyframes {
+        keyframes::unused(stylesheet);
+    }
+
+    if !config.animations.is_empty() {
+        keyframes::rename(stylesheet, &config.animations);
+    }
+
+    let rewrites = canonical::Rewrites {
+        background_none: config.enabled("css-background-none"),
+        font_weight: config.enabled("css-font-weight"),
+        initial: config.enabled("css-initial"),
+        keyword_case: config.enabled("css-keyword-case"),
+        // Without targets there's no telling whether #0000 is understood
+        transparent: config.enabled("css-transparent")
+            && !config.targets.is_empty()
+            && !compat::lacks(&config.targets, &compat::HEX_ALPHA),
+        zero_units: config.enabled("css-zero-units"),
     };
+    canonical::stylesheet(stylesheet, rewrites);
+
+    // Without targets, prefixes are left as they are
+    if !config.targets.is_empty() {
+        if config.enabled("css-nesting") && compat::lacks(&config.targets, &compat::NESTING) {
+            nesting::stylesheet(stylesheet, !compat::lacks(&config.targets, &compat::IS));
+        }
+
+        if config.enabled("css-layers") && compat::lacks(&config.targets, &compat::LAYERS) {
+            layer::flatten(stylesheet);
+        }
+
+        if config.enabled("css-colors") {
+            color::stylesheet(stylesheet, &config.targets, config.color_fallback);
+        }
+
+        if config.enabled("css-fallbacks") {
+            fallback::stylesheet(stylesheet, &config.targets);
+        }
+
+        if config.enabled("css-prefixes") {
+            prefix::stylesheet(stylesheet, &config.targets);
+        }
+    }
+}
 
-    let string = tokens
-        .iter()
-        .map(|token| token.to_string())
-        .collect::<Vec<String>>()
-        .join("");
+pub fn minimize(contents: String, config: &Config) -> Result<String, token::error::ParseError> {
+    let mut stylesheet = parse::stylesheet(contents)?;
+    optimize(&mut stylesheet, config);
 
-    Ok(string)
+    Ok(stylesheet.to_string())
 }
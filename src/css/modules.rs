@@ -0,0 +1,415 @@
+/**
+ * CSS Modules
+ * https://github.com/css-modules/css-modules
+ *
+ * Makes the classes and IDs of a stylesheet local to it, by renaming them to the original name followed by a hash of
+ * the file (.button in components/button.css becomes .button_k3x9q). Names wrapped in :global() are left alone, and
+ * :local() marks the names that are renamed, which is the default. Both can also be written without parentheses
+ * (:global .title), switching every name after them until the next selector in the list.
+ *
+ * A rule for a single class can compose other classes, which adds their names to what the class exports:
+ *
+ *  .button { composes: reset rounded; }               classes of the same stylesheet
+ *  .button { composes: base from "./base.css"; }      classes of another module, relative to this one
+ *  .button { composes: btn from global; }             global classes, used as they are
+ *
+ * What each local name became is exported as JSON, for scripts and templates to look up, with composed names after
+ * the name itself:
+ *
+ *  {
+ *    "button": "button_k3x9q reset_k3x9q base_7fz2a",
+ *    "reset": "reset_k3x9q"
+ *  }
+ *
+ * Keyframes and other names outside selectors stay global.
+ */
+use super::mangle;
+use super::parse::{consume, tree::*};
+use super::serialize::{values, Context};
+use super::token::tokens::{CSSToken, HashFlag};
+use std::fmt;
+
+/**
+ * The names a module exports, each local name with the class names it stands for
+ */
+#[derive(Default)]
+pub struct Exports {
+    names: Vec<(String, Vec<String>)>,
+}
+
+impl Exports {
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.names
+            .iter()
+            .find(|(local, _)| local == name)
+            .map(|(_, names)| names.as_slice())
+    }
+}
+
+impl fmt::Display for Exports {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{")?;
+
+        for (index, (local, names)) in self.names.iter().enumerate() {
+            let comma = if index + 1 < self.names.len() {
+                ","
+            } else {
+                ""
+            };
+            write!(
+                f,
+                "\n  {}: {}{}",
+                mangle::string(local),
+                mangle::string(&names.join(" ")),
+                comma
+            )?;
+        }
+
+        if self.names.is_empty() {
+            writeln!(f, "}}")
+        } else {
+            writeln!(f, "\n}}")
+        }
+    }
+}
+
+// Where composed classes come from
+#[derive(Clone)]
+enum From {
+    Local,
+    Global,
+    File(String),
+}
+
+struct Module<'a> {
+    suffix: String,
+    // Local names in the order they're found
+    locals: Vec<String>,
+    // Classes and what they compose, in order
+    composed: Vec<(String, Vec<(String, From)>)>,
+    resolve: &'a mut dyn FnMut(&str) -> Result<Exports, String>,
+}
+
+// A short hash of the file (FNV-1a), the same for every build
+fn hash(scope: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in scope.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    (0..5)
+        .map(|_| {
+            let digit = DIGITS[(hash % 36) as usize] as char;
+            hash /= 36;
+            digit
+        })
+        .collect()
+}
+
+fn is_empty(rule: &Rule) -> bool {
+    matches!(rule, Rule::Qualified(rule) if rule.block.is_empty())
+}
+
+fn is_scope(name: &str) -> bool {
+    name.eq_ignore_ascii_case("local") || name.eq_ignore_ascii_case("global")
+}
+
+fn is_whitespace(value: Option<&ComponentValue>) -> bool {
+    matches!(value, Some(ComponentValue::Token(CSSToken::Whitespace)))
+}
+
+impl Module<'_> {
+    fn rename(&mut self, name: &str) -> String {
+        if !self.locals.iter().any(|local| local == name) {
+            self.locals.push(name.to_string());
+        }
+
+        format!("{}_{}", name, self.suffix)
+    }
+
+    // Renames the local classes and IDs of a selector list, unwrapping :local() and :global()
+    fn selector(&mut self, prelude: Vec<ComponentValue>, local: bool) -> Vec<ComponentValue> {
+        let mut output: Vec<ComponentValue> = Vec::new();
+        let mut mode = local;
+        let mut values = prelude.into_iter().peekable();
+
+        while let Some(value) = values.next() {
+            match value {
+                ComponentValue::Token(CSSToken::Comma) => {
+                    mode = local;
+                    output.push(value);
+                }
+                ComponentValue::Token(CSSToken::Colon) => match values.peek() {
+                    Some(ComponentValue::Token(CSSToken::Ident(name))) if is_scope(name) => {
+                        mode = name.eq_ignore_ascii_case("local");
+                        values.next();
+
+                        // :global .title leaves a space where :global was
+                        if is_whitespace(values.peek())
+                            && (output.is_empty()
+                                || is_whitespace(output.last())
+                                || matches!(
+                                    output.last(),
+                                    Some(ComponentValue::Token(CSSToken::Comma))
+                                ))
+                        {
+                            values.next();
+                        }
+                    }
+                    Some(ComponentValue::Function(function)) if is_scope(&function.name) => {
+                        let local = function.name.eq_ignore_ascii_case("local");
+                        let mut inner = function.value.clone();
+                        consume::trim(&mut inner);
+                        values.next();
+
+                        output.extend(self.selector(inner, local));
+                    }
+                    _ => output.push(value),
+                },
+                ComponentValue::Token(CSSToken::Delim('.')) => {
+                    output.push(value);
+
+                    if mode {
+                        if let Some(ComponentValue::Token(CSSToken::Ident(name))) = values.peek() {
+                            let renamed = self.rename(&name.clone());
+                            values.next();
+                            output.push(ComponentValue::Token(CSSToken::Ident(renamed)));
+                        }
+                    }
+                }
+                ComponentValue::Token(CSSToken::Hash(name, HashFlag::Id)) if mode => output.push(
+                    ComponentValue::Token(CSSToken::Hash(self.rename(&name), HashFlag::Id)),
+                ),
+                ComponentValue::Function(function) => {
+                    output.push(ComponentValue::Function(Function {
+                        value: self.selector(function.value, mode),
+                        name: function.name,
+                    }))
+                }
+                value => output.push(value),
+            }
+        }
+
+        output
+    }
+
+    // The class a rule is for, when its selector is a single local class
+    fn class(prelude: &[ComponentValue]) -> Option<String> {
+        let mut prelude = prelude.to_vec();
+        consume::trim(&mut prelude);
+
+        match prelude.as_slice() {
+            [ComponentValue::Token(CSSToken::Delim('.')), ComponentValue::Token(CSSToken::Ident(name))] => {
+                Some(name.clone())
+            }
+            [ComponentValue::Token(CSSToken::Colon), ComponentValue::Function(function)]
+                if function.name.eq_ignore_ascii_case("local") =>
+            {
+                Self::class(&function.value)
+            }
+            _ => None,
+        }
+    }
+
+    // Takes the composes declarations out of a rule, remembering what they compose
+    fn composes(&mut self, rule: &mut QualifiedRule) -> Result<(), String> {
+        let mut composed: Vec<(String, From)> = Vec::new();
+        let mut found = false;
+
+        for item in &rule.block {
+            let declaration = match item {
+                BlockItem::Declaration(declaration)
+                    if declaration.name.eq_ignore_ascii_case("composes") =>
+                {
+                    declaration
+                }
+                _ => continue,
+            };
+            found = true;
+
+            let parts: Vec<&ComponentValue> = declaration
+                .value
+                .iter()
+                .filter(|value| !is_whitespace(Some(value)))
+                .collect();
+            let mut names: Vec<String> = Vec::new();
+            let mut from = From::Local;
+            let mut parts = parts.into_iter();
+
+            while let Some(part) = parts.next() {
+                match part {
+                    ComponentValue::Token(CSSToken::Ident(keyword))
+                        if keyword.eq_ignore_ascii_case("from") =>
+                    {
+                        from = match parts.next() {
+                            Some(ComponentValue::Token(CSSToken::String(file))) => {
+                                From::File(file.clone())
+                            }
+                            Some(ComponentValue::Token(CSSToken::Ident(global)))
+                                if global.eq_ignore_ascii_case("global") =>
+                            {
+                                From::Global
+                            }
+                            _ => {
+                                return Err(format!(
+                                    "composes: {} should end with from \"file.css\" or from global",
+                                    values(&declaration.value, Context::Value)
+                                ))
+                            }
+                        };
+
+                        if parts.next().is_some() {
+                            return Err(format!(
+                                "Nothing can follow from in composes: {}",
+                                values(&declaration.value, Context::Value)
+                            ));
+                        }
+                    }
+                    ComponentValue::Token(CSSToken::Ident(name)) => names.push(name.clone()),
+                    _ => {
+                        return Err(format!(
+                            "composes: {} should only list class names",
+                            values(&declaration.value, Context::Value)
+                        ))
+                    }
+                }
+            }
+
+            composed.extend(names.into_iter().map(|name| (name, from.clone())));
+        }
+
+        if !found {
+            return Ok(());
+        }
+
+        let class = Self::class(&rule.prelude).ok_or_else(|| {
+            format!(
+                "composes can only be used in rules for a single class, not {}",
+                values(&rule.prelude, Context::Selector)
+            )
+        })?;
+
+        rule.block.retain(|item| {
+            !matches!(item, BlockItem::Declaration(declaration)
+                if declaration.name.eq_ignore_ascii_case("composes"))
+        });
+
+        match self.composed.iter_mut().find(|(name, _)| *name == class) {
+            Some((_, list)) => list.extend(composed),
+            None => self.composed.push((class, composed)),
+        }
+
+        Ok(())
+    }
+
+    fn rule(&mut self, rule: &mut Rule) -> Result<(), String> {
+        let block = match rule {
+            Rule::Qualified(rule) => {
+                self.composes(rule)?;
+                rule.prelude = self.selector(std::mem::take(&mut rule.prelude), true);
+                &mut rule.block
+            }
+            Rule::At(rule) => {
+                // Keyframe selectors (from, 50%) aren't classes
+                if rule.name.to_ascii_lowercase().ends_with("keyframes") {
+                    return Ok(());
+                }
+
+                if rule.name.eq_ignore_ascii_case("scope") {
+                    rule.prelude = self.selector(std::mem::take(&mut rule.prelude), true);
+                }
+
+                match &mut rule.block {
+                    Some(block) => block,
+                    None => return Ok(()),
+                }
+            }
+        };
+
+        for item in block.iter_mut() {
+            if let BlockItem::Rule(rule) = item {
+                self.rule(rule)?;
+            }
+        }
+
+        // A rule with nothing but composes isn't needed anymore
+        block.retain(|item| !matches!(item, BlockItem::Rule(rule) if is_empty(rule)));
+        Ok(())
+    }
+
+    // The names a local name stands for, following composes
+    fn export(&mut self, name: &str, chain: &mut Vec<String>) -> Result<Vec<String>, String> {
+        if chain.iter().any(|other| other == name) {
+            chain.push(name.to_string());
+            return Err(format!("composes cycle detected: {}", chain.join(" -> ")));
+        }
+        if !self.locals.iter().any(|local| local == name) {
+            return Err(format!("composes: there's no class {} here", name));
+        }
+
+        let mut names = vec![format!("{}_{}", name, self.suffix)];
+        let composed: Vec<(String, From)> = self
+            .composed
+            .iter()
+            .find(|(class, _)| class == name)
+            .map_or(Vec::new(), |(_, composed)| composed.clone());
+
+        chain.push(name.to_string());
+        for (other, from) in composed {
+            let others = match from {
+                From::Local => self.export(&other, chain)?,
+                From::Global => vec![other],
+                From::File(file) => {
+                    let exports = (self.resolve)(&file)?;
+                    match exports.get(&other) {
+                        Some(names) => names.to_vec(),
+                        None => {
+                            return Err(format!("composes: there's no class {} in {}", other, file))
+                        }
+                    }
+                }
+            };
+
+            for other in others {
+                if !names.contains(&other) {
+                    names.push(other);
+                }
+            }
+        }
+        chain.pop();
+
+        Ok(names)
+    }
+}
+
+/**
+ * Makes the classes and IDs of a stylesheet local to it, returning the names it exports. The scope (usually the path
+ * of the file) decides the hash, and resolve reads the exports of the modules composes refers to.
+ */
+pub fn stylesheet(
+    stylesheet: &mut Stylesheet,
+    scope: &str,
+    resolve: &mut dyn FnMut(&str) -> Result<Exports, String>,
+) -> Result<Exports, String> {
+    let mut module = Module {
+        suffix: hash(scope),
+        locals: Vec::new(),
+        composed: Vec::new(),
+        resolve,
+    };
+
+    for rule in stylesheet.rules.iter_mut() {
+        module.rule(rule)?;
+    }
+    stylesheet.rules.retain(|rule| !is_empty(rule));
+
+    let mut exports = Exports::default();
+    for local in module.locals.clone() {
+        let names = module.export(&local, &mut Vec::new())?;
+        exports.names.push((local, names));
+    }
+
+    Ok(exports)
+}
@@ -0,0 +1,374 @@
+/**
+ * CSS Nesting
+ * https://www.w3.org/TR/css-nesting-1/
+ *
+ * Flattens nested style rules into top level rules, for browsers without nesting support.
+ *
+ *  - & is replaced by the parent selector. Where writing the parent in place would change what's matched or the
+ *    specificity (a parent list, or a parent with combinators in the middle of a selector) it's wrapped in :is(). For
+ *    targets without :is(), the nested selector is repeated for each parent selector instead, with the parent written
+ *    in place, which is what nesting compiled to before :is() (.p, .q { & + & {} } => .p+.p, .p+.q, .q+.p, .q+.q)
+ *  - Nested selectors without & are relative to the parent (.b => & .b, > .b => & > .b)
+ *  - Nested conditional rules (@media, @supports, @container, @layer and @starting-style) are hoisted out of
+ *    the style rule, with the declarations and rules inside them applying to the parent selector
+ *  - Declarations after nested rules stay after them, so the cascade order doesn't change
+ */
+use super::parse::tree::*;
+use super::select;
+use super::token::tokens::CSSToken;
+
+type Selector = Vec<ComponentValue>;
+
+// At-rules whose contents apply to the parent style rule
+const CONDITIONAL: [&str; 5] = ["media", "supports", "container", "layer", "starting-style"];
+
+fn is_conditional(rule: &AtRule) -> bool {
+    rule.block.is_some()
+        && CONDITIONAL
+            .iter()
+            .any(|name| rule.name.eq_ignore_ascii_case(name))
+}
+
+fn is_combinator(value: &ComponentValue) -> bool {
+    matches!(
+        value,
+        ComponentValue::Token(CSSToken::Whitespace | CSSToken::Delim('>' | '+' | '~'))
+    )
+}
+
+fn is_nesting(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Delim('&')))
+}
+
+// The selectors of a list, without the whitespace around them
+fn selectors(list: &[ComponentValue]) -> Vec<Selector> {
+    select::split(list)
+        .into_iter()
+        .map(|selector| {
+            let mut selector = selector.to_vec();
+            super::parse::consume::trim(&mut selector);
+            selector
+        })
+        .collect()
+}
+
+fn join(selectors: &[Selector]) -> Vec<ComponentValue> {
+    let mut list: Vec<ComponentValue> = Vec::new();
+
+    for (index, selector) in selectors.iter().enumerate() {
+        if index > 0 {
+            list.push(ComponentValue::Token(CSSToken::Comma));
+        }
+        list.extend(selector.iter().cloned());
+    }
+
+    list
+}
+
+fn contains_nesting(values: &[ComponentValue]) -> bool {
+    values.iter().any(|value| match value {
+        ComponentValue::Function(function) => contains_nesting(&function.value),
+        ComponentValue::Block(block) => contains_nesting(&block.value),
+        value => is_nesting(value),
+    })
+}
+
+// The parent selectors, and how & can be replaced by them
+struct Parent {
+    selectors: Vec<Selector>,
+
+    // A single selector without combinators
+    compound: bool,
+
+    // Whether the targets support :is(), otherwise a selector is repeated for each parent selector & can stand for
+    is: bool,
+}
+
+fn is_type(value: &ComponentValue) -> bool {
+    matches!(
+        value,
+        ComponentValue::Token(CSSToken::Ident(_) | CSSToken::Delim('*'))
+    )
+}
+
+fn count_nesting(values: &[ComponentValue]) -> usize {
+    values
+        .iter()
+        .map(|value| match value {
+            ComponentValue::Function(function) => count_nesting(&function.value),
+            ComponentValue::Block(block) => count_nesting(&block.value),
+            value => usize::from(is_nesting(value)),
+        })
+        .sum()
+}
+
+// Writes a parent selector where & is, with the compound selectors around & merged. The parent's combinators go before
+// the compound selector & is in, and a type selector (div or *) goes to its start (.foo& => div.foo). Returns false
+// when two type selectors would meet, which can't be written in place.
+fn in_place(
+    replaced: &mut Selector,
+    compound_at: usize,
+    parent: &Selector,
+    next: Option<&ComponentValue>,
+) -> bool {
+    let split = parent
+        .iter()
+        .rposition(is_combinator)
+        .map_or(0, |index| index + 1);
+    let (prefix, last) = parent.split_at(split);
+
+    let current = &replaced[compound_at..];
+    let types = [current.first(), last.first(), next]
+        .iter()
+        .filter(|value| value.is_some_and(is_type))
+        .count();
+    if types > 1 {
+        return false;
+    }
+
+    let mut compound = replaced.split_off(compound_at);
+    let mut last = last.to_vec();
+
+    replaced.extend(prefix.iter().cloned());
+    if last.first().is_some_and(is_type) {
+        replaced.push(last.remove(0));
+    }
+    if let Some(next) = next {
+        replaced.push(next.clone());
+    }
+    if compound.first().is_some_and(is_type) {
+        replaced.push(compound.remove(0));
+    }
+    replaced.extend(compound);
+    replaced.extend(last);
+
+    true
+}
+
+impl Parent {
+    fn new(selectors: Vec<Selector>, is: bool) -> Parent {
+        let compound = selectors.len() == 1 && !selectors[0].iter().any(is_combinator);
+
+        Parent {
+            selectors,
+            compound,
+            is,
+        }
+    }
+
+    // :is(parent)
+    fn is(&self) -> [ComponentValue; 2] {
+        [
+            ComponentValue::Token(CSSToken::Colon),
+            ComponentValue::Function(Function {
+                name: String::from("is"),
+                value: join(&self.selectors),
+            }),
+        ]
+    }
+
+    // Replaces each & in a selector, first is whether the values start a (top level) selector. Without :is(), each &
+    // is replaced by the next of the chosen parent selectors.
+    fn replace<'a>(
+        &self,
+        values: &[ComponentValue],
+        first: bool,
+        chosen: &mut impl Iterator<Item = &'a Selector>,
+    ) -> Selector {
+        let mut replaced: Selector = Vec::new();
+
+        // Where the compound selector being written starts
+        let mut compound_at = 0;
+        let mut index = 0;
+
+        while index < values.len() {
+            let value = &values[index];
+            index += 1;
+
+            match value {
+                ComponentValue::Function(function) => {
+                    replaced.push(ComponentValue::Function(Function {
+                        name: function.name.clone(),
+                        value: self.replace(&function.value, false, chosen),
+                    }));
+                    continue;
+                }
+                ComponentValue::Block(block) => {
+                    replaced.push(ComponentValue::Block(SimpleBlock {
+                        token: block.token.clone(),
+                        value: self.replace(&block.value, false, chosen),
+                    }));
+                    continue;
+                }
+                value if !is_nesting(value) => {
+                    replaced.push(value.clone());
+                    if is_combinator(value)
+                        || matches!(value, ComponentValue::Token(CSSToken::Comma))
+                    {
+                        compound_at = replaced.len();
+                    }
+                    continue;
+                }
+                _ => (),
+            }
+
+            // A type selector right after & has to come first (&div => div.a)
+            let next = values.get(index).filter(|next| is_type(next));
+
+            let parent = if self.is {
+                let start = index == 1;
+                let single = self.selectors.len() == 1 && (self.compound || (first && start));
+                self.selectors.first().filter(|_| single)
+            } else {
+                chosen.next()
+            };
+
+            if parent.is_some_and(|parent| in_place(&mut replaced, compound_at, parent, next)) {
+                if next.is_some() {
+                    index += 1;
+                }
+                continue;
+            }
+
+            if let Some(next) = next {
+                replaced.push(next.clone());
+                index += 1;
+            }
+            replaced.extend(self.is());
+        }
+
+        replaced
+    }
+
+    // Resolves a nested selector list against the parent
+    fn resolve(&self, list: &[ComponentValue]) -> Vec<ComponentValue> {
+        let selectors: Vec<Selector> = selectors(list)
+            .into_iter()
+            .flat_map(|selector| {
+                let selector = if contains_nesting(&selector) {
+                    selector
+                } else {
+                    // Relative selectors start with an implied & and a descendant combinator
+                    let mut relative = vec![ComponentValue::Token(CSSToken::Delim('&'))];
+                    if !selector.first().is_some_and(is_combinator) {
+                        relative.push(ComponentValue::Token(CSSToken::Whitespace));
+                    }
+                    relative.extend(selector);
+                    relative
+                };
+
+                if self.is {
+                    return vec![self.replace(&selector, true, &mut std::iter::empty())];
+                }
+
+                // Every combination of parent selectors for the &s in the selector (& + & => .p+.p,.p+.q,.q+.p,.q+.q)
+                let mut combinations: Vec<Vec<&Selector>> = vec![Vec::new()];
+                for _ in 0..count_nesting(&selector) {
+                    combinations = combinations
+                        .into_iter()
+                        .flat_map(|combination| {
+                            self.selectors.iter().map(move |parent| {
+                                let mut combination = combination.clone();
+                                combination.push(parent);
+                                combination
+                            })
+                        })
+                        .collect();
+                }
+
+                combinations
+                    .into_iter()
+                    .map(|combination| self.replace(&selector, true, &mut combination.into_iter()))
+                    .collect()
+            })
+            .collect();
+
+        join(&selectors)
+    }
+}
+
+// Flattens a style rule and everything nested in it into a list of rules
+fn style_rule(
+    selector: Vec<ComponentValue>,
+    items: Vec<BlockItem>,
+    is: bool,
+    output: &mut Vec<Rule>,
+) {
+    let parent = Parent::new(selectors(&selector), is);
+    let mut declarations: Vec<BlockItem> = Vec::new();
+
+    let flush = |declarations: &mut Vec<BlockItem>, output: &mut Vec<Rule>| {
+        if !declarations.is_empty() {
+            output.push(Rule::Qualified(QualifiedRule {
+                prelude: selector.clone(),
+                block: std::mem::take(declarations),
+            }));
+        }
+    };
+
+    for item in items {
+        match item {
+            BlockItem::Rule(Rule::Qualified(rule)) => {
+                flush(&mut declarations, output);
+                style_rule(parent.resolve(&rule.prelude), rule.block, is, output);
+            }
+            BlockItem::Rule(Rule::At(mut rule)) if is_conditional(&rule) => {
+                flush(&mut declarations, output);
+
+                let mut rules: Vec<Rule> = Vec::new();
+                style_rule(
+                    selector.clone(),
+                    rule.block.take().unwrap_or_default(),
+                    is,
+                    &mut rules,
+                );
+
+                if !rules.is_empty() {
+                    rule.block = Some(rules.into_iter().map(BlockItem::Rule).collect());
+                    output.push(Rule::At(rule));
+                }
+            }
+            item => declarations.push(item),
+        }
+    }
+
+    flush(&mut declarations, output);
+}
+
+fn rules(rules: Vec<Rule>, is: bool) -> Vec<Rule> {
+    let mut output: Vec<Rule> = Vec::new();
+
+    for rule in rules {
+        match rule {
+            Rule::Qualified(rule) => style_rule(rule.prelude, rule.block, is, &mut output),
+            Rule::At(mut rule) if is_conditional(&rule) => {
+                let block = rule.block.take().unwrap_or_default();
+                let inner = block
+                    .into_iter()
+                    .filter_map(|item| match item {
+                        BlockItem::Rule(rule) => Some(rule),
+                        BlockItem::Declaration(_) => None,
+                    })
+                    .collect();
+
+                rule.block = Some(
+                    self::rules(inner, is)
+                        .into_iter()
+                        .map(BlockItem::Rule)
+                        .collect(),
+                );
+                output.push(Rule::At(rule));
+            }
+            rule => output.push(rule),
+        }
+    }
+
+    output
+}
+
+/**
+ * Flattens nested style rules, using :is() for parent selectors only when the targets support it
+ */
+pub fn stylesheet(stylesheet: &mut Stylesheet, is: bool) {
+    stylesheet.rules = rules(std::mem::take(&mut stylesheet.rules), is);
+}
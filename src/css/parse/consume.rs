@@ -0,0 +1,331 @@
+/**
+ * § 5.4 Parser Algorithms
+ *
+ * Block contents are consumed the way CSS Nesting expects: anything that looks like a declaration is tried as one
+ * first, and if that fails it is parsed again as a nested rule.
+ */
+use super::tree::{
+    AtRule, BlockItem, ComponentValue, Declaration, Function, QualifiedRule, Rule, SimpleBlock,
+};
+use super::Tokens;
+use crate::css::token::tokens::CSSToken;
+
+// Removes leading and trailing whitespace
+pub fn trim(values: &mut Vec<ComponentValue>) {
+    let whitespace =
+        |value: &ComponentValue| matches!(value, ComponentValue::Token(CSSToken::Whitespace));
+
+    while values.last().is_some_and(whitespace) {
+        values.pop();
+    }
+
+    let leading = values.iter().take_while(|value| whitespace(value)).count();
+    values.drain(..leading);
+}
+
+// § 5.4.1 Consume a list of rules (at the top level of a stylesheet)
+pub fn rule_list(tokens: &mut Tokens) -> Vec<Rule> {
+    let mut rules: Vec<Rule> = Vec::new();
+
+    loop {
+        match tokens.peek() {
+            CSSToken::EOF => return rules,
+
+            // <!-- and --> are only allowed at the top level for legacy reasons, so they're dropped
+            CSSToken::Whitespace | CSSToken::CDO | CSSToken::CDC => {
+                tokens.next();
+            }
+
+            CSSToken::AtKeyword(_) => rules.push(Rule::At(at_rule(tokens, false))),
+
+            _ => {
+                if let Some(rule) = qualified_rule(tokens, false) {
+                    rules.push(Rule::Qualified(rule));
+                }
+            }
+        }
+    }
+}
+
+// § 5.4.2 Consume an at-rule, inside a block a } ends it
+pub fn at_rule(tokens: &mut Tokens, nested: bool) -> AtRule {
+    let at = tokens.position();
+    let name = match tokens.next() {
+        CSSToken::AtKeyword(name) => name,
+        _ => String::new(),
+    };
+
+    let mut prelude: Vec<ComponentValue> = Vec::new();
+    let block = loop {
+        match tokens.peek() {
+            CSSToken::Semicolon => {
+                tokens.next();
+                break None;
+            }
+            CSSToken::EOF => break None,
+            CSSToken::RightBrace if nested => break None,
+            CSSToken::LeftBrace => {
+                tokens.next();
+                break Some(block_contents(tokens));
+            }
+            _ => prelude.push(component_value(tokens)),
+        }
+    };
+
+    trim(&mut prelude);
+
+    AtRule {
+        name,
+        prelude,
+        block,
+        at,
+    }
+}
+
+// § 5.4.3 Consume a qualified rule, nothing is returned if the rule has no block
+pub fn qualified_rule(tokens: &mut Tokens, nested: bool) -> Option<QualifiedRule> {
+    let mut prelude: Vec<ComponentValue> = Vec::new();
+
+    loop {
+        match tokens.peek() {
+            CSSToken::EOF => return None,
+            CSSToken::RightBrace if nested => return None,
+
+            // Inside a block, this was an invalid declaration rather than a rule
+            CSSToken::Semicolon if nested => {
+                tokens.next();
+                return None;
+            }
+
+            CSSToken::LeftBrace => {
+                tokens.next();
+                trim(&mut prelude);
+
+                return Some(QualifiedRule {
+                    prelude,
+                    block: block_contents(tokens),
+                });
+            }
+            _ => prelude.push(component_value(tokens)),
+        }
+    }
+}
+
+// § 5.4.4 Consume a block's contents, up to and including the closing }
+pub fn block_contents(tokens: &mut Tokens) -> Vec<BlockItem> {
+    let mut items: Vec<BlockItem> = Vec::new();
+
+    loop {
+        match tokens.peek() {
+            CSSToken::Whitespace | CSSToken::Semicolon => {
+                tokens.next();
+            }
+            CSSToken::EOF => return items,
+            CSSToken::RightBrace => {
+                tokens.next();
+                return items;
+            }
+            CSSToken::AtKeyword(_) => {
+                items.push(BlockItem::Rule(Rule::At(at_rule(tokens, true))));
+            }
+            token => {
+                let mark = tokens.mark();
+
+                if matches!(token, CSSToken::Ident(_)) {
+                    if let Some(declaration) = declaration(tokens) {
+                        items.push(BlockItem::Declaration(declaration));
+                        continue;
+                    }
+
+                    tokens.reset(mark);
+                }
+
+                if let Some(rule) = qualified_rule(tokens, true) {
+                    items.push(BlockItem::Rule(Rule::Qualified(rule)));
+                }
+            }
+        }
+    }
+}
+
+// § 5.4.5 Consume a declaration, the ; or } after it is left alone
+pub fn declaration(tokens: &mut Tokens) -> Option<Declaration> {
+    let at = tokens.position();
+    let name = match tokens.next() {
+        CSSToken::Ident(name) => name,
+        _ => return None,
+    };
+
+    while let CSSToken::Whitespace = tokens.peek() {
+        tokens.next();
+    }
+
+    if !matches!(tokens.next(), CSSToken::Colon) {
+        return None;
+    }
+
+    let start = tokens.position();
+    let mut value: Vec<ComponentValue> = Vec::new();
+    while !matches!(
+        tokens.peek(),
+        CSSToken::Semicolon | CSSToken::RightBrace | CSSToken::EOF
+    ) {
+        value.push(component_value(tokens));
+    }
+
+    trim(&mut value);
+
+    if name.eq_ignore_ascii_case("unicode-range") {
+        if let Some(ranges) = unicode_ranges(&tokens.text(start, tokens.position())) {
+            value = ranges;
+        }
+    }
+
+    // The last two non-whitespace tokens are ! and important
+    let mut important = false;
+    if let Some(ComponentValue::Token(CSSToken::Ident(last))) = value.last() {
+        if last.eq_ignore_ascii_case("important") {
+            let mut rest = value[..value.len() - 1].to_vec();
+            trim(&mut rest);
+
+            if let Some(ComponentValue::Token(CSSToken::Delim('!'))) = rest.last() {
+                rest.pop();
+                trim(&mut rest);
+
+                value = rest;
+                important = true;
+            }
+        }
+    }
+
+    // A {} block means this was really a nested rule (a:hover { ... }), custom properties may contain anything
+    let block = value.iter().any(|value| match value {
+        ComponentValue::Block(block) => matches!(block.token, CSSToken::LeftBrace),
+        _ => false,
+    });
+
+    if block && !name.starts_with("--") {
+        return None;
+    }
+
+    Some(Declaration {
+        name,
+        value,
+        important,
+        at,
+    })
+}
+
+// § 7.1 A <urange> (U+26, U+0-7F, U+4??), which has to be read from how it was written: U+00E9 is tokenized as an
+// identifier and the number 0e9
+fn unicode_range(text: &str) -> Option<(u32, u32)> {
+    let rest = text
+        .strip_prefix("U+")
+        .or_else(|| text.strip_prefix("u+"))?;
+
+    let digits = rest.chars().take_while(|ch| ch.is_ascii_hexdigit()).count();
+    let (first, rest) = rest.split_at(digits);
+    let wildcards = rest.chars().take_while(|ch| *ch == '?').count();
+
+    let (start, end) = if wildcards > 0 {
+        if digits + wildcards > 6 || wildcards != rest.len() {
+            return None;
+        }
+
+        let start = format!("{}{}", first, "0".repeat(wildcards));
+        let end = format!("{}{}", first, "F".repeat(wildcards));
+        (start, end)
+    } else {
+        let end = match rest.strip_prefix('-') {
+            Some(end) => end,
+            None if rest.is_empty() => first,
+            None => return None,
+        };
+
+        let valid = |digits: &str| {
+            !digits.is_empty()
+                && digits.len() <= 6
+                && digits.chars().all(|ch| ch.is_ascii_hexdigit())
+        };
+        if !valid(first) || !valid(end) {
+            return None;
+        }
+
+        (first.to_string(), end.to_string())
+    };
+
+    let start = u32::from_str_radix(&start, 16).ok()?;
+    let end = u32::from_str_radix(&end, 16).ok()?;
+
+    // Ranges past the last code point, or going backwards, are invalid
+    if end > 0x10FFFF || start > end {
+        return None;
+    }
+
+    Some((start, end))
+}
+
+// The value of unicode-range as ranges, None if it isn't a list of them
+fn unicode_ranges(text: &str) -> Option<Vec<ComponentValue>> {
+    let mut ranges: Vec<ComponentValue> = Vec::new();
+
+    for range in text.split(',') {
+        if !ranges.is_empty() {
+            ranges.push(ComponentValue::Token(CSSToken::Comma));
+        }
+
+        let (start, end) = unicode_range(range.trim())?;
+        ranges.push(ComponentValue::Token(CSSToken::UnicodeRange(start, end)));
+    }
+
+    Some(ranges)
+}
+
+// § 5.4.6 Consume a component value
+pub fn component_value(tokens: &mut Tokens) -> ComponentValue {
+    match tokens.next() {
+        token @ (CSSToken::LeftBrace | CSSToken::LeftBracket | CSSToken::LeftParentheses) => {
+            ComponentValue::Block(simple_block(tokens, token))
+        }
+        CSSToken::Function(name) => ComponentValue::Function(function(tokens, name)),
+        token => ComponentValue::Token(token),
+    }
+}
+
+// § 5.4.7 Consume a simple block
+fn simple_block(tokens: &mut Tokens, token: CSSToken) -> SimpleBlock {
+    let mut value: Vec<ComponentValue> = Vec::new();
+
+    loop {
+        match (&token, tokens.peek()) {
+            (_, CSSToken::EOF) => break,
+            (CSSToken::LeftBrace, CSSToken::RightBrace)
+            | (CSSToken::LeftBracket, CSSToken::RightBracket)
+            | (CSSToken::LeftParentheses, CSSToken::RightParentheses) => {
+                tokens.next();
+                break;
+            }
+            _ => value.push(component_value(tokens)),
+        }
+    }
+
+    SimpleBlock { token, value }
+}
+
+// § 5.4.8 Consume a function
+fn function(tokens: &mut Tokens, name: String) -> Function {
+    let mut value: Vec<ComponentValue> = Vec::new();
+
+    loop {
+        match tokens.peek() {
+            CSSToken::EOF => break,
+            CSSToken::RightParentheses => {
+                tokens.next();
+                break;
+            }
+            _ => value.push(component_value(tokens)),
+        }
+    }
+
+    Function { name, value }
+}
@@ -0,0 +1,129 @@
+/**
+ * CSS Parser
+ * https://www.w3.org/TR/css-syntax-3/#parsing
+ *
+ * Turns the tokens into a tree of rules and declarations. Like browsers, the parser recovers from invalid CSS by
+ * dropping whatever it can't make sense of, so only tokenizer errors are reported.
+ */
+pub mod consume;
+pub mod tree;
+
+use super::token::error::ParseError;
+use super::token::tokens::CSSToken;
+use super::token::{preprocess, tokenize};
+
+// § 5.3 The token stream the parser consumes, comments are dropped here as the parser never sees them
+pub struct Tokens {
+    tokens: Vec<(CSSToken, i32)>,
+    index: usize,
+    eof: (CSSToken, i32),
+
+    // What the tokens were made from, for the few values that have to be read from how they were written
+    source: Vec<char>,
+}
+
+impl Tokens {
+    pub fn new(contents: &str, tokens: Vec<(CSSToken, i32)>) -> Tokens {
+        let end = tokens.last().map_or(0, |(_, at)| *at);
+
+        Tokens {
+            source: preprocess(contents.to_string()).chars().collect(),
+            tokens: tokens
+                .into_iter()
+                .filter(|(token, _)| !matches!(token, CSSToken::Comment(_)))
+                .collect(),
+            index: 0,
+            eof: (CSSToken::EOF, end),
+        }
+    }
+
+    fn current(&self) -> &(CSSToken, i32) {
+        self.tokens.get(self.index).unwrap_or(&self.eof)
+    }
+
+    pub fn peek(&self) -> &CSSToken {
+        &self.current().0
+    }
+
+    // The position of the next token
+    pub fn position(&self) -> i32 {
+        self.current().1
+    }
+
+    // The source between two positions
+    pub fn text(&self, start: i32, end: i32) -> String {
+        let end = (end.max(0) as usize).min(self.source.len());
+        let start = (start.max(0) as usize).min(end);
+
+        self.source[start..end].iter().collect()
+    }
+
+    // Not an Iterator, as it never runs out: EOF is returned at the end
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> CSSToken {
+        let token = self.current().0.clone();
+        self.index += 1;
+
+        token
+    }
+
+    // Used to rewind after looking ahead
+    pub fn mark(&self) -> usize {
+        self.index
+    }
+
+    pub fn reset(&mut self, mark: usize) {
+        self.index = mark;
+    }
+}
+
+/**
+ * § 5.3.3 Parse a stylesheet
+ */
+pub fn stylesheet(contents: String) -> Result<tree::Stylesheet, ParseError> {
+    let tokens = tokenize(contents.clone())?;
+
+    let comments = tokens
+        .iter()
+        .filter_map(|(token, _)| match token {
+            CSSToken::Comment(text) if text.starts_with('!') => Some(text.clone()),
+            _ => None,
+        })
+        .collect();
+
+    let mut tokens = Tokens::new(&contents, tokens);
+
+    Ok(tree::Stylesheet {
+        rules: consume::rule_list(&mut tokens),
+        comments,
+    })
+}
+
+/**
+ * § 5.3.10 Parse a list of component values, like a selector or a media query list on its own
+ */
+pub fn component_values(contents: String) -> Result<Vec<tree::ComponentValue>, ParseError> {
+    let mut tokens = Tokens::new(&contents, tokenize(contents.clone())?);
+    let mut values: Vec<tree::ComponentValue> = Vec::new();
+
+    while !matches!(tokens.peek(), CSSToken::EOF) {
+        values.push(consume::component_value(&mut tokens));
+    }
+
+    Ok(values)
+}
+
+/**
+ * § 5.3.8 Parse a list of declarations, like the contents of a style attribute
+ */
+pub fn declaration_list(contents: String) -> Result<Vec<tree::Declaration>, ParseError> {
+    let mut tokens = Tokens::new(&contents, tokenize(contents.clone())?);
+
+    Ok(consume::block_contents(&mut tokens)
+        .into_iter()
+        .filter_map(|item| match item {
+            tree::BlockItem::Declaration(declaration) => Some(declaration),
+            tree::BlockItem::Rule(_) => None,
+        })
+        .collect())
+}
@@ -0,0 +1,84 @@
+/**
+ * The stylesheet tree
+ * https://www.w3.org/TR/css-syntax-3/#parsing
+ *
+ * At-rules and declarations remember the position they started at, so errors about them can point back into the
+ * source. Ones added while optimizing are at 0.
+ */
+use crate::css::token::tokens::CSSToken;
+
+pub struct Stylesheet {
+    pub rules: Vec<Rule>,
+
+    // Comments starting with ! (/*! license */), which are kept when asked for
+    pub comments: Vec<String>,
+}
+
+#[derive(Clone)]
+pub enum Rule {
+    Qualified(QualifiedRule),
+    At(AtRule),
+}
+
+// A style rule (a { color: red }), or a keyframe (50% { opacity: 0 })
+#[derive(Clone)]
+pub struct QualifiedRule {
+    pub prelude: Vec<ComponentValue>,
+    pub block: Vec<BlockItem>,
+}
+
+// @media screen { ... } or @import "a.css";
+#[derive(Clone)]
+pub struct AtRule {
+    pub name: String,
+    pub prelude: Vec<ComponentValue>,
+    pub block: Option<Vec<BlockItem>>,
+    pub at: i32,
+}
+
+impl AtRule {
+    // Copies an at-rule with other contents
+    pub(crate) fn with_block(&self, block: Vec<Rule>) -> Rule {
+        Rule::At(AtRule {
+            name: self.name.clone(),
+            prelude: self.prelude.clone(),
+            block: Some(block.into_iter().map(BlockItem::Rule).collect()),
+            at: self.at,
+        })
+    }
+}
+
+// The contents of a {} block are declarations mixed with (nested) rules
+#[derive(Clone)]
+pub enum BlockItem {
+    Declaration(Declaration),
+    Rule(Rule),
+}
+
+#[derive(Clone)]
+pub struct Declaration {
+    pub name: String,
+    pub value: Vec<ComponentValue>,
+    pub important: bool,
+    pub at: i32,
+}
+
+#[derive(Clone)]
+pub enum ComponentValue {
+    Token(CSSToken),
+    Function(Function),
+    Block(SimpleBlock),
+}
+
+#[derive(Clone)]
+pub struct Function {
+    pub name: String,
+    pub value: Vec<ComponentValue>,
+}
+
+// A (), [] or {} block, token is the opening token
+#[derive(Clone)]
+pub struct SimpleBlock {
+    pub token: CSSToken,
+    pub value: Vec<ComponentValue>,
+}
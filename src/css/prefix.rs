@@ -0,0 +1,287 @@
+/**
+ * Vendor prefixes
+ *
+ * With browser targets, prefixed versions of properties, keywords (position: -webkit-sticky) and @keyframes are added
+ * where a target needs them, and removed where none do. A prefixed declaration without a standard one next to it is
+ * turned into the standard one instead of being removed. Prefixes missing from the compatibility table (like
+ * -webkit-tap-highlight-color) are left alone.
+ *
+ * Inside @-webkit-keyframes and @-moz-keyframes only that vendor's prefixes are added.
+ */
+use super::compat::{self, Engines, Target, KEYFRAMES};
+use super::parse::tree::*;
+use super::serialize::{values, Context};
+use super::token::tokens::CSSToken;
+
+struct Prefixer<'a> {
+    targets: &'a [Target],
+}
+
+// The value of a declaration, when it's a single keyword
+fn keyword(declaration: &Declaration) -> Option<String> {
+    match declaration.value.as_slice() {
+        [ComponentValue::Token(CSSToken::Ident(keyword))] => Some(keyword.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+fn property(name: &str) -> Option<&'static Engines> {
+    compat::PROPERTIES
+        .iter()
+        .find(|(property, _)| *property == name)
+        .map(|(_, engines)| engines)
+}
+
+fn value(name: &str, keyword: &str) -> Option<&'static Engines> {
+    compat::VALUES
+        .iter()
+        .find(|(value, properties, _)| *value == keyword && properties.contains(&name))
+        .map(|(_, _, engines)| engines)
+}
+
+// The declarations (name and keyword) and keyframes (name and prelude) in a block, before anything is changed
+struct Existing {
+    declarations: Vec<(String, Option<String>)>,
+    keyframes: Vec<(String, String)>,
+}
+
+impl Existing {
+    fn new(items: &[BlockItem]) -> Existing {
+        let mut existing = Existing {
+            declarations: Vec::new(),
+            keyframes: Vec::new(),
+        };
+
+        for item in items {
+            match item {
+                BlockItem::Declaration(declaration) => existing
+                    .declarations
+                    .push((declaration.name.to_ascii_lowercase(), keyword(declaration))),
+                BlockItem::Rule(Rule::At(rule)) => existing.keyframes.push((
+                    rule.name.to_ascii_lowercase(),
+                    values(&rule.prelude, Context::Prelude),
+                )),
+                BlockItem::Rule(Rule::Qualified(_)) => (),
+            }
+        }
+
+        existing
+    }
+
+    fn has(&self, name: &str, keyword: Option<&str>) -> bool {
+        self.declarations.iter().any(|(other, value)| {
+            other == name && (keyword.is_none() || value.as_deref() == keyword)
+        })
+    }
+
+    fn has_keyframes(&self, name: &str, prelude: &str) -> bool {
+        self.keyframes
+            .iter()
+            .any(|(other, other_prelude)| other == name && other_prelude == prelude)
+    }
+}
+
+impl Prefixer<'_> {
+    // The prefixes the targets need for a feature, limited to one vendor inside prefixed @keyframes
+    fn needed(&self, engines: &Engines, only: Option<&str>) -> Vec<&'static str> {
+        compat::prefixes(self.targets, engines)
+            .into_iter()
+            .filter(|prefix| only.is_none_or(|only| only.eq_ignore_ascii_case(prefix)))
+            .collect()
+    }
+
+    fn wanted(&self, prefix: &str, engines: &Engines, only: Option<&str>) -> bool {
+        self.needed(engines, only)
+            .iter()
+            .any(|needed| needed.eq_ignore_ascii_case(prefix))
+    }
+
+    // Removes (or unprefixes) a declaration no target needs the prefix of, returns false if it's removed
+    fn unprefix(
+        &self,
+        declaration: &mut Declaration,
+        existing: &Existing,
+        only: Option<&str>,
+    ) -> bool {
+        let name = declaration.name.to_ascii_lowercase();
+
+        if let (Some(prefix), unprefixed) = compat::unprefix(&name) {
+            if let Some(engines) = property(unprefixed) {
+                if self.wanted(prefix, engines, only) {
+                    return true;
+                }
+
+                if existing.has(unprefixed, None) {
+                    return false;
+                }
+
+                declaration.name = unprefixed.to_string();
+            }
+        }
+
+        let name = declaration.name.to_ascii_lowercase();
+        let keyword = match keyword(declaration) {
+            Some(keyword) => keyword,
+            None => return true,
+        };
+
+        if let (Some(prefix), unprefixed) = compat::unprefix(&keyword) {
+            if let Some(engines) = value(&name, unprefixed) {
+                if self.wanted(prefix, engines, only) {
+                    return true;
+                }
+
+                if existing.has(&name, Some(unprefixed)) {
+                    return false;
+                }
+
+                declaration.value = vec![ComponentValue::Token(CSSToken::Ident(
+                    unprefixed.to_string(),
+                ))];
+            }
+        }
+
+        true
+    }
+
+    // The prefixed versions a declaration needs, that aren't there already
+    fn prefixed(
+        &self,
+        declaration: &Declaration,
+        existing: &Existing,
+        only: Option<&str>,
+    ) -> Vec<Declaration> {
+        let name = declaration.name.to_ascii_lowercase();
+        let mut prefixed: Vec<Declaration> = Vec::new();
+
+        if let Some(engines) = property(&name) {
+            for prefix in self.needed(engines, only) {
+                let name = format!("{}{}", prefix, name);
+
+                if !existing.has(&name, None) {
+                    prefixed.push(Declaration {
+                        name,
+                        ..declaration.clone()
+                    });
+                }
+            }
+        }
+
+        if let Some(keyword) = keyword(declaration) {
+            if let Some(engines) = value(&name, &keyword) {
+                for prefix in self.needed(engines, only) {
+                    let keyword = format!("{}{}", prefix, keyword);
+
+                    if !existing.has(&name, Some(&keyword)) {
+                        prefixed.push(Declaration {
+                            value: vec![ComponentValue::Token(CSSToken::Ident(keyword))],
+                            ..declaration.clone()
+                        });
+                    }
+                }
+            }
+        }
+
+        prefixed
+    }
+
+    fn rule(
+        &self,
+        rule: Rule,
+        existing: &Existing,
+        only: Option<&str>,
+        output: &mut Vec<BlockItem>,
+    ) {
+        let mut rule = match rule {
+            Rule::Qualified(mut rule) => {
+                rule.block = self.items(rule.block, only);
+                output.push(BlockItem::Rule(Rule::Qualified(rule)));
+                return;
+            }
+            Rule::At(rule) => rule,
+        };
+
+        let name = rule.name.to_ascii_lowercase();
+
+        if let (prefix, "keyframes") = compat::unprefix(&name) {
+            let prelude = values(&rule.prelude, Context::Prelude);
+
+            if let Some(prefix) = prefix {
+                if self.wanted(prefix, &KEYFRAMES, only) {
+                    rule.block = rule.block.map(|block| self.items(block, Some(prefix)));
+                    output.push(BlockItem::Rule(Rule::At(rule)));
+                    return;
+                }
+
+                if existing.has_keyframes("keyframes", &prelude) {
+                    return;
+                }
+
+                rule.name = String::from("keyframes");
+            }
+
+            for prefix in self.needed(&KEYFRAMES, only) {
+                let name = format!("{}keyframes", prefix);
+
+                if !existing.has_keyframes(&name, &prelude) {
+                    output.push(BlockItem::Rule(Rule::At(AtRule {
+                        name,
+                        block: rule
+                            .block
+                            .clone()
+                            .map(|block| self.items(block, Some(prefix))),
+                        ..rule.clone()
+                    })));
+                }
+            }
+        }
+
+        rule.block = rule.block.map(|block| self.items(block, only));
+        output.push(BlockItem::Rule(Rule::At(rule)));
+    }
+
+    fn items(&self, items: Vec<BlockItem>, only: Option<&str>) -> Vec<BlockItem> {
+        let existing = Existing::new(&items);
+        let mut output: Vec<BlockItem> = Vec::new();
+
+        for item in items {
+            let mut declaration = match item {
+                BlockItem::Declaration(declaration) => declaration,
+                BlockItem::Rule(rule) => {
+                    self.rule(rule, &existing, only, &mut output);
+                    continue;
+                }
+            };
+
+            if !self.unprefix(&mut declaration, &existing, only) {
+                continue;
+            }
+
+            for prefixed in self.prefixed(&declaration, &existing, only) {
+                output.push(BlockItem::Declaration(prefixed));
+            }
+
+            output.push(BlockItem::Declaration(declaration));
+        }
+
+        output
+    }
+}
+
+/**
+ * Adds and removes vendor prefixes for the targets
+ */
+pub fn stylesheet(stylesheet: &mut Stylesheet, targets: &[Target]) {
+    let prefixer = Prefixer { targets };
+
+    let rules = std::mem::take(&mut stylesheet.rules);
+    let items = prefixer.items(rules.into_iter().map(BlockItem::Rule).collect(), None);
+
+    stylesheet.rules = items
+        .into_iter()
+        .filter_map(|item| match item {
+            BlockItem::Rule(rule) => Some(rule),
+            BlockItem::Declaration(_) => None,
+        })
+        .collect();
+}
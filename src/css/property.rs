@@ -0,0 +1,373 @@
+/**
+ * @property rules
+ * https://www.w3.org/TR/css-properties-values-api-1/#at-property-rule
+ *
+ * The syntax descriptor of a @property rule is compiled into the types its custom property takes (<length> | auto,
+ * <color>#, ...), and every declaration of that custom property is checked against it. A declaration that doesn't
+ * match is invalid at computed-value time, so the property falls back to its initial value.
+ *
+ * Registrations browsers ignore are reported too: one without a syntax or inherits descriptor, or without an
+ * initial-value when the syntax isn't "*", and one whose initial-value doesn't match the syntax or depends on the
+ * element (1em, 10vw). The last valid registration of a custom property wins.
+ */
+use super::parse::{self, consume, tree::*};
+use super::serialize::{values, Context};
+use super::token::tokens::CSSToken;
+use super::types;
+
+// § 5.1 The types a syntax can use
+const TYPES: [&str; 15] = [
+    "angle",
+    "color",
+    "custom-ident",
+    "image",
+    "integer",
+    "length",
+    "length-percentage",
+    "number",
+    "percentage",
+    "resolution",
+    "string",
+    "time",
+    "transform-function",
+    "transform-list",
+    "url",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Multiplier {
+    One,
+
+    // <length>+, space separated
+    Space,
+
+    // <length>#, comma separated
+    Comma,
+}
+
+#[derive(Clone)]
+enum Kind {
+    Type(String),
+    Ident(String),
+}
+
+#[derive(Clone)]
+pub struct Component {
+    kind: Kind,
+    multiplier: Multiplier,
+}
+
+#[derive(Clone)]
+pub enum Syntax {
+    // *, any value
+    Universal,
+
+    // <length> | auto, the value has to match one of them
+    Components(Vec<Component>),
+}
+
+// § 5.4.2 A component of a syntax, like <length>+ or auto
+fn component(values: &[ComponentValue]) -> Option<Component> {
+    let (kind, multiplier) = match values {
+        [ComponentValue::Token(CSSToken::Delim('<')), ComponentValue::Token(CSSToken::Ident(name)), ComponentValue::Token(CSSToken::Delim('>')), multiplier @ ..] =>
+        {
+            let name = name.to_ascii_lowercase();
+            if !TYPES.contains(&name.as_str()) {
+                return None;
+            }
+            (Kind::Type(name), multiplier)
+        }
+        [ComponentValue::Token(CSSToken::Ident(name)), multiplier @ ..] => {
+            let ident = ComponentValue::Token(CSSToken::Ident(name.clone()));
+            if types::matches("custom-ident", &ident) != Some(true) {
+                return None;
+            }
+            (Kind::Ident(name.clone()), multiplier)
+        }
+        _ => return None,
+    };
+
+    let multiplier = match multiplier {
+        [] => Multiplier::One,
+        [ComponentValue::Token(CSSToken::Delim('+'))] => Multiplier::Space,
+        [ComponentValue::Token(CSSToken::Delim('#'))] => Multiplier::Comma,
+        _ => return None,
+    };
+
+    match kind {
+        // <transform-list> already is a list of <transform-function>
+        Kind::Type(name) if name == "transform-list" => {
+            (multiplier == Multiplier::One).then(|| Component {
+                kind: Kind::Type(String::from("transform-function")),
+                multiplier: Multiplier::Space,
+            })
+        }
+        kind => Some(Component { kind, multiplier }),
+    }
+}
+
+/**
+ * § 5.4 Parses the value of a syntax descriptor, None when browsers wouldn't accept it
+ */
+pub fn syntax(text: &str) -> Option<Syntax> {
+    let mut values = parse::component_values(text.to_string()).ok()?;
+    consume::trim(&mut values);
+
+    if let [ComponentValue::Token(CSSToken::Delim('*'))] = values.as_slice() {
+        return Some(Syntax::Universal);
+    }
+
+    values
+        .split(|value| matches!(value, ComponentValue::Token(CSSToken::Delim('|'))))
+        .map(|component| {
+            let mut component = component.to_vec();
+            consume::trim(&mut component);
+            self::component(&component)
+        })
+        .collect::<Option<Vec<Component>>>()
+        .map(Syntax::Components)
+}
+
+impl Component {
+    fn matches(&self, value: &ComponentValue) -> bool {
+        match &self.kind {
+            Kind::Type(name) => types::matches(name, value) == Some(true),
+            // Idents in a syntax are matched as written
+            Kind::Ident(ident) => {
+                matches!(value, ComponentValue::Token(CSSToken::Ident(name)) if name == ident)
+            }
+        }
+    }
+
+    fn matches_all(&self, values: &[ComponentValue]) -> bool {
+        let items: Vec<Vec<ComponentValue>> = match self.multiplier {
+            Multiplier::One => vec![values.to_vec()],
+            Multiplier::Space => values
+                .split(|value| matches!(value, ComponentValue::Token(CSSToken::Whitespace)))
+                .filter(|item| !item.is_empty())
+                .map(|item| item.to_vec())
+                .collect(),
+            Multiplier::Comma => values
+                .split(|value| matches!(value, ComponentValue::Token(CSSToken::Comma)))
+                .map(|item| {
+                    let mut item = item.to_vec();
+                    consume::trim(&mut item);
+                    item
+                })
+                .collect(),
+        };
+
+        !items.is_empty()
+            && items
+                .iter()
+                .all(|item| matches!(item.as_slice(), [value] if self.matches(value)))
+    }
+}
+
+impl Syntax {
+    /**
+     * Whether a value matches the syntax, without var() or CSS-wide keywords (which always do in declarations)
+     */
+    pub fn matches(&self, values: &[ComponentValue]) -> bool {
+        match self {
+            Syntax::Universal => true,
+            Syntax::Components(components) => components
+                .iter()
+                .any(|component| component.matches_all(values)),
+        }
+    }
+}
+
+// A custom property registration
+struct Registration {
+    name: String,
+    text: String,
+    syntax: Syntax,
+}
+
+// The custom property a @property rule registers, or why browsers ignore it
+fn registration(rule: &AtRule) -> Result<Registration, String> {
+    let name = match rule.prelude.as_slice() {
+        [ComponentValue::Token(CSSToken::Ident(name))]
+            if name.starts_with("--") && name != "--" =>
+        {
+            name.clone()
+        }
+        prelude => {
+            return Err(format!(
+                "@property {} should name one custom property",
+                values(prelude, Context::Prelude)
+            ))
+        }
+    };
+
+    // The last one of each descriptor wins
+    let descriptor = |descriptor: &str| {
+        rule.block
+            .iter()
+            .flatten()
+            .rev()
+            .find_map(|item| match item {
+                BlockItem::Declaration(declaration)
+                    if declaration.name.eq_ignore_ascii_case(descriptor) =>
+                {
+                    Some(declaration)
+                }
+                _ => None,
+            })
+    };
+
+    let (text, syntax) = match descriptor("syntax").map(|syntax| syntax.value.as_slice()) {
+        Some([ComponentValue::Token(CSSToken::String(text))]) => match self::syntax(text) {
+            Some(syntax) => (text.clone(), syntax),
+            None => {
+                return Err(format!(
+                    "@property {} has an invalid syntax \"{}\"",
+                    name, text
+                ))
+            }
+        },
+        _ => return Err(format!("@property {} has no syntax descriptor", name)),
+    };
+
+    match descriptor("inherits").map(|inherits| inherits.value.as_slice()) {
+        Some([ComponentValue::Token(CSSToken::Ident(inherits))])
+            if inherits.eq_ignore_ascii_case("true") || inherits.eq_ignore_ascii_case("false") => {}
+        _ => {
+            return Err(format!(
+                "@property {} has no inherits descriptor, true or false",
+                name
+            ))
+        }
+    }
+
+    match (descriptor("initial-value"), &syntax) {
+        (None, Syntax::Universal) => (),
+        (None, Syntax::Components(_)) => {
+            return Err(format!(
+                "@property {} has no initial-value, only the \"*\" syntax can leave it out",
+                name
+            ))
+        }
+        (Some(initial), _) => {
+            let written = values(&initial.value, Context::Raw);
+
+            if !syntax.matches(&initial.value) {
+                return Err(format!(
+                    "@property {} has an initial-value {} that doesn't match its syntax \"{}\"",
+                    name, written, text
+                ));
+            }
+
+            // § 2.7 Anything but the universal syntax needs a computationally independent initial value
+            if matches!(syntax, Syntax::Components(_)) && types::is_relative(&initial.value) {
+                return Err(format!(
+                    "@property {} has an initial-value {} that depends on the element, it can't use em, vw or var()",
+                    name, written
+                ));
+            }
+        }
+    }
+
+    Ok(Registration { name, text, syntax })
+}
+
+fn collect(
+    items: &[BlockItem],
+    registrations: &mut Vec<Registration>,
+    problems: &mut Vec<(i32, String)>,
+) {
+    for item in items {
+        match item {
+            BlockItem::Rule(Rule::At(rule)) if rule.name.eq_ignore_ascii_case("property") => {
+                match registration(rule) {
+                    Ok(registration) => registrations.push(registration),
+                    Err(problem) => {
+                        problems.push((rule.at, format!("{}, so it's ignored", problem)))
+                    }
+                }
+            }
+            BlockItem::Rule(Rule::At(AtRule {
+                block: Some(block), ..
+            })) => collect(block, registrations, problems),
+            _ => (),
+        }
+    }
+}
+
+fn registrations(stylesheet: &Stylesheet) -> (Vec<Registration>, Vec<(i32, String)>) {
+    let items: Vec<BlockItem> = stylesheet
+        .rules
+        .iter()
+        .cloned()
+        .map(BlockItem::Rule)
+        .collect();
+
+    let mut registrations: Vec<Registration> = Vec::new();
+    let mut problems: Vec<(i32, String)> = Vec::new();
+    collect(&items, &mut registrations, &mut problems);
+
+    (registrations, problems)
+}
+
+/**
+ * The @property rules of a stylesheet browsers ignore, why, and where they are
+ */
+pub fn problems(stylesheet: &Stylesheet) -> Vec<(i32, String)> {
+    registrations(stylesheet).1
+}
+
+fn check(items: &[BlockItem], registrations: &[Registration], problems: &mut Vec<(i32, String)>) {
+    for item in items {
+        match item {
+            BlockItem::Declaration(declaration) => {
+                let registration = match registrations
+                    .iter()
+                    .rev()
+                    .find(|registration| registration.name == declaration.name)
+                {
+                    Some(registration) => registration,
+                    None => continue,
+                };
+
+                if !types::is_css_wide(&declaration.value)
+                    && !types::is_substituted(&declaration.value)
+                    && !registration.syntax.matches(&declaration.value)
+                {
+                    problems.push((
+                        declaration.at,
+                        format!(
+                            "{} doesn't match the syntax \"{}\" @property gives it, so it's invalid at computed-value time",
+                            declaration, registration.text
+                        ),
+                    ));
+                }
+            }
+            BlockItem::Rule(Rule::Qualified(rule)) => check(&rule.block, registrations, problems),
+            BlockItem::Rule(Rule::At(rule)) => {
+                if let Some(block) = &rule.block {
+                    check(block, registrations, problems);
+                }
+            }
+        }
+    }
+}
+
+/**
+ * The declarations of custom properties that don't match the syntax their @property rule gives them, along with where
+ * they are
+ */
+pub fn mismatches(stylesheet: &Stylesheet) -> Vec<(i32, String)> {
+    let (registrations, _) = registrations(stylesheet);
+
+    let items: Vec<BlockItem> = stylesheet
+        .rules
+        .iter()
+        .cloned()
+        .map(BlockItem::Rule)
+        .collect();
+
+    let mut problems: Vec<(i32, String)> = Vec::new();
+    check(&items, &registrations, &mut problems);
+    problems
+}
@@ -0,0 +1,361 @@
+/**
+ * Unused CSS removal
+ *
+ * Removes the selectors that can't match anything in the content (see purge.rs), and the style rules left without
+ * any. A selector is kept unless a class, ID, tag or attribute it needs isn't used anywhere:
+ *
+ *  - :is(), :where() and :has() need one of their selectors, :not() and other pseudo-classes don't need anything
+ *  - & in nested selectors needs nothing, the parent rule was already kept
+ *
+ * Afterwards @keyframes and @font-face rules nothing refers to anymore are removed, along with @media, @supports and
+ * @container rules left empty. @layer rules stay even when they're empty, as they set the order of the layers.
+ */
+use super::compat;
+use super::parse::tree::*;
+use super::select::split;
+use super::token::tokens::CSSToken;
+use crate::purge::Used;
+
+// Conditional rules that do nothing once they're empty
+const CONDITIONAL: [&str; 5] = ["media", "supports", "container", "scope", "starting-style"];
+
+pub fn is_keyframes(rule: &AtRule) -> bool {
+    compat::unprefix(&rule.name.to_ascii_lowercase()).1 == "keyframes"
+}
+
+fn is_delim(value: Option<&ComponentValue>, delim: char) -> bool {
+    matches!(value, Some(ComponentValue::Token(CSSToken::Delim(ch))) if *ch == delim)
+}
+
+// Whether a selector could match something in the content
+fn matchable(selector: &[ComponentValue], used: &Used) -> bool {
+    for (index, value) in selector.iter().enumerate() {
+        let previous = index.checked_sub(1).and_then(|index| selector.get(index));
+        let next = selector.get(index + 1);
+
+        let found = match value {
+            ComponentValue::Token(CSSToken::Ident(name)) if is_delim(previous, '.') => {
+                used.class(name)
+            }
+            ComponentValue::Token(CSSToken::Hash(name, _)) => used.id(name),
+
+            // A type selector, unless it's the name of a pseudo-class or part of a namespace (svg|rect)
+            ComponentValue::Token(CSSToken::Ident(name)) => {
+                matches!(previous, Some(ComponentValue::Token(CSSToken::Colon)))
+                    || is_delim(previous, '|')
+                    || is_delim(next, '|')
+                    || used.tag(name)
+            }
+
+            // [attribute] and [attribute=value], the value isn't checked
+            ComponentValue::Block(block) => {
+                let mut values = block
+                    .value
+                    .iter()
+                    .filter(|value| !matches!(value, ComponentValue::Token(CSSToken::Whitespace)));
+
+                match (values.next(), values.next()) {
+                    (Some(ComponentValue::Token(CSSToken::Ident(name))), next) => {
+                        is_delim(next, '|') || used.attribute(name)
+                    }
+                    _ => true,
+                }
+            }
+
+            ComponentValue::Function(function) => match function.name.to_ascii_lowercase().as_str()
+            {
+                "is" | "where" | "has" | "matches" | "-webkit-any" | "-moz-any" => {
+                    split(&function.value)
+                        .iter()
+                        .any(|selector| matchable(selector, used))
+                }
+                _ => true,
+            },
+
+            _ => true,
+        };
+
+        if !found {
+            return false;
+        }
+    }
+
+    true
+}
+
+// Removes the selectors that can't match, returns false if none are left
+fn selectors(prelude: &mut Vec<ComponentValue>, used: &Used) -> bool {
+    let selectors = split(prelude);
+    let kept: Vec<&[ComponentValue]> = selectors
+        .iter()
+        .copied()
+        .filter(|selector| matchable(selector, used))
+        .collect();
+
+    if kept.len() == selectors.len() {
+        return true;
+    }
+
+    let mut list: Vec<ComponentValue> = Vec::new();
+    for (index, selector) in kept.iter().enumerate() {
+        if index > 0 {
+            list.push(ComponentValue::Token(CSSToken::Comma));
+        }
+        list.extend(selector.iter().cloned());
+    }
+    super::parse::consume::trim(&mut list);
+
+    let any = !kept.is_empty();
+    *prelude = list;
+    any
+}
+
+fn items(items: &mut Vec<BlockItem>, used: &Used) {
+    items.retain_mut(|item| match item {
+        BlockItem::Rule(rule) => self::rule(rule, used),
+        BlockItem::Declaration(_) => true,
+    });
+}
+
+// Purges a rule, returns false if it should be removed
+fn rule(rule: &mut Rule, used: &Used) -> bool {
+    match rule {
+        Rule::Qualified(rule) => {
+            if !selectors(&mut rule.prelude, used) {
+                return false;
+            }
+
+            items(&mut rule.block, used);
+            true
+        }
+        Rule::At(rule) if is_keyframes(rule) => true,
+        Rule::At(rule) => {
+            if let Some(block) = &mut rule.block {
+                items(block, used);
+            }
+
+            true
+        }
+    }
+}
+
+// The names @keyframes and @font-face rules are referred to by
+#[derive(Default)]
+pub struct References {
+    animations: Vec<String>,
+    fonts: Vec<String>,
+
+    // Custom properties read with var() in animations
+    variables: Vec<String>,
+
+    // Custom properties defined in the stylesheet, with the ones their values read
+    properties: Vec<(String, Vec<String>)>,
+}
+
+impl References {
+    // Whether a @keyframes or @font-face rule is referred to, None for any other rule
+    pub fn refers(&self, rule: &AtRule) -> Option<bool> {
+        let name = defined(rule)?;
+
+        Some(if is_keyframes(rule) {
+            // An animation name coming from outside the stylesheet could be any of them
+            self.animations.contains(&name)
+                || self
+                    .variables
+                    .iter()
+                    .any(|variable| self.unresolved(variable, &mut Vec::new()))
+        } else {
+            self.fonts
+                .iter()
+                .any(|font| font.eq_ignore_ascii_case(&name))
+        })
+    }
+
+    // Whether a custom property isn't defined in the stylesheet, or reads one that isn't
+    fn unresolved(&self, property: &str, seen: &mut Vec<String>) -> bool {
+        if seen.iter().any(|seen| seen == property) {
+            return false;
+        }
+        seen.push(property.to_string());
+
+        let mut definitions = self
+            .properties
+            .iter()
+            .filter(|(name, _)| name == property)
+            .peekable();
+
+        definitions.peek().is_none()
+            || definitions.any(|(_, reads)| reads.iter().any(|read| self.unresolved(read, seen)))
+    }
+}
+
+// The custom properties a value reads with var()
+fn variables(value: &[ComponentValue], variables: &mut Vec<String>) {
+    for component in value {
+        if let ComponentValue::Function(function) = component {
+            if function.name.eq_ignore_ascii_case("var") {
+                let name = function
+                    .value
+                    .iter()
+                    .find(|value| !matches!(value, ComponentValue::Token(CSSToken::Whitespace)));
+
+                if let Some(ComponentValue::Token(CSSToken::Ident(name))) = name {
+                    variables.push(name.clone());
+                }
+            }
+
+            // Fallbacks can read custom properties too
+            self::variables(&function.value, variables);
+        }
+    }
+}
+
+// A run of identifiers is a name too (font-family: My Font)
+fn flush(run: &mut Vec<String>, names: &mut Vec<String>) {
+    if run.len() > 1 {
+        names.push(run.join(" "));
+    }
+    run.clear();
+}
+
+// Each name in a value: strings, identifiers and runs of identifiers
+fn names(value: &[ComponentValue], names: &mut Vec<String>) {
+    let mut run: Vec<String> = Vec::new();
+
+    for component in value {
+        match component {
+            ComponentValue::Token(CSSToken::Ident(ident)) => {
+                names.push(ident.clone());
+                run.push(ident.clone());
+            }
+            ComponentValue::Token(CSSToken::Whitespace) => (),
+            ComponentValue::Token(CSSToken::String(string)) => {
+                flush(&mut run, names);
+                names.push(string.clone());
+            }
+            ComponentValue::Function(function) => {
+                flush(&mut run, names);
+                self::names(&function.value, names);
+            }
+            _ => flush(&mut run, names),
+        }
+    }
+
+    flush(&mut run, names);
+}
+
+pub fn references(items: &[BlockItem], references: &mut References) {
+    for item in items {
+        match item {
+            BlockItem::Declaration(declaration) => {
+                let name = declaration.name.to_ascii_lowercase();
+
+                match compat::unprefix(&name).1 {
+                    "animation" | "animation-name" => {
+                        names(&declaration.value, &mut references.animations);
+                        variables(&declaration.value, &mut references.variables);
+                    }
+                    "font" | "font-family" => names(&declaration.value, &mut references.fonts),
+                    // Custom properties could end up anywhere
+                    _ if name.starts_with("--") => {
+                        names(&declaration.value, &mut references.animations);
+                        names(&declaration.value, &mut references.fonts);
+
+                        let mut reads: Vec<String> = Vec::new();
+                        variables(&declaration.value, &mut reads);
+                        references
+                            .properties
+                            .push((declaration.name.clone(), reads));
+                    }
+                    _ => (),
+                }
+            }
+            BlockItem::Rule(Rule::Qualified(rule)) => self::references(&rule.block, references),
+            BlockItem::Rule(Rule::At(rule)) => {
+                let defines = is_keyframes(rule) || rule.name.eq_ignore_ascii_case("font-face");
+
+                if let Some(block) = rule.block.as_ref().filter(|_| !defines) {
+                    self::references(block, references);
+                }
+            }
+        }
+    }
+}
+
+// The name a @keyframes or @font-face rule defines
+fn defined(rule: &AtRule) -> Option<String> {
+    let mut defined: Vec<String> = Vec::new();
+
+    if is_keyframes(rule) {
+        names(&rule.prelude, &mut defined);
+    } else if rule.name.eq_ignore_ascii_case("font-face") {
+        let family = rule.block.as_ref()?.iter().find_map(|item| match item {
+            BlockItem::Declaration(declaration)
+                if declaration.name.eq_ignore_ascii_case("font-family") =>
+            {
+                Some(declaration)
+            }
+            _ => None,
+        })?;
+
+        names(&family.value, &mut defined);
+    }
+
+    // The whole name is last
+    defined.pop()
+}
+
+// Removes unreferenced @keyframes and @font-face rules, and the conditional rules left empty
+fn orphans(items: &mut Vec<BlockItem>, references: &References) {
+    items.retain_mut(|item| {
+        let rule = match item {
+            BlockItem::Rule(Rule::At(rule)) => rule,
+            BlockItem::Rule(Rule::Qualified(rule)) => {
+                orphans(&mut rule.block, references);
+                return true;
+            }
+            BlockItem::Declaration(_) => return true,
+        };
+
+        if let Some(referenced) = references.refers(rule) {
+            return referenced;
+        }
+
+        let keyframes = is_keyframes(rule);
+        match &mut rule.block {
+            Some(block) if !keyframes => {
+                orphans(block, references);
+
+                !block.is_empty()
+                    || !CONDITIONAL
+                        .iter()
+                        .any(|name| rule.name.eq_ignore_ascii_case(name))
+            }
+            _ => true,
+        }
+    });
+}
+
+/**
+ * Removes the rules that can't match anything the content uses
+ */
+pub fn stylesheet(stylesheet: &mut Stylesheet, used: &Used) {
+    let mut rules: Vec<BlockItem> = std::mem::take(&mut stylesheet.rules)
+        .into_iter()
+        .map(BlockItem::Rule)
+        .collect();
+
+    items(&mut rules, used);
+
+    let mut found = References::default();
+    references(&rules, &mut found);
+    orphans(&mut rules, &found);
+
+    stylesheet.rules = rules
+        .into_iter()
+        .filter_map(|item| match item {
+            BlockItem::Rule(rule) => Some(rule),
+            BlockItem::Declaration(_) => None,
+        })
+        .collect();
+}
@@ -0,0 +1,1001 @@
+/**
+ * Selector matching
+ * https://www.w3.org/TR/selectors-4/
+ *
+ * Parses selectors and matches them against the elements of an HTML document (html::dom), which can be parsed or
+ * built by hand with Document::append. Everything in Selectors Level 4 that a static document can answer is supported:
+ *
+ *  - All four combinators, and relative selectors in :has() (:has(> img), :has(+ .error))
+ *  - Attribute selectors with every operator (= ~= |= ^= $= *=) and the i and s flags
+ *  - :is(), :where(), :not() and :has(), with the specificity rules of § 17
+ *  - Structural pseudo-classes: :root, :empty, :first-child, :nth-child(An+B of S), :nth-last-of-type() and the rest
+ *  - :lang(), :link, :checked, :disabled, :enabled, :required, :optional and :defined, from the attributes
+ *
+ * The document doesn't change, so pseudo-classes that depend on the user (:hover, :focus, :visited) never match, and
+ * neither do pseudo-classes that aren't known. Pseudo-elements (::before) are matched on the element they belong to.
+ *
+ * As in HTML documents, type selectors and attribute names ignore case, and so do the values of the attributes HTML
+ * lists as case-insensitive (like type and lang) unless the s flag is given. Namespace prefixes (svg|rect) are
+ * accepted but not checked.
+ */
+use super::parse::{self, tree::*};
+use super::token::tokens::{CSSToken, HashFlag};
+use crate::html::dom::{Document, NodeData, NodeId};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Combinator {
+    // a b
+    Descendant,
+    // a > b
+    Child,
+    // a + b
+    NextSibling,
+    // a ~ b
+    SubsequentSibling,
+}
+
+// § 6.1 Attribute presence and value selectors, § 6.2 Substring matching attribute selectors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    // [a=b]
+    Equals,
+    // [a~=b]
+    Includes,
+    // [a|=b]
+    DashMatch,
+    // [a^=b]
+    Prefix,
+    // [a$=b]
+    Suffix,
+    // [a*=b]
+    Substring,
+}
+
+// § 6.3 Case-sensitivity, set by the i and s flags
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+    // What HTML says for the attribute
+    Default,
+    Sensitive,
+    Insensitive,
+}
+
+#[derive(Debug, Clone)]
+pub struct Attribute {
+    // Lowercase
+    pub name: String,
+
+    // The operator and value, None for [name]
+    pub value: Option<(Operator, String)>,
+    pub case: Case,
+}
+
+/**
+ * § 14.1 An+B, optionally only counting the siblings that match a selector list (:nth-child(2n of .item))
+ */
+#[derive(Debug, Clone)]
+pub struct Nth {
+    pub a: i64,
+    pub b: i64,
+    pub of: Option<Vec<Selector>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Pseudo {
+    Root,
+    Empty,
+    NthChild(Nth),
+    NthLastChild(Nth),
+    NthOfType(Nth),
+    NthLastOfType(Nth),
+    OnlyChild,
+    OnlyOfType,
+    Link,
+    Checked,
+    Disabled,
+    Enabled,
+    Required,
+    Optional,
+    Defined,
+    Lang(String),
+    Is(Vec<Selector>),
+    Where(Vec<Selector>),
+    Not(Vec<Selector>),
+
+    // Relative selectors, each starting with the combinator from the :has() element
+    Has(Vec<Selector>),
+
+    // Pseudo-classes that depend on the user or aren't known, which never match
+    State,
+}
+
+#[derive(Debug, Clone)]
+pub enum Simple {
+    Universal,
+    Type(String),
+    Id(String),
+    Class(String),
+    Attribute(Attribute),
+    Pseudo(Pseudo),
+}
+
+/**
+ * A complex selector (main > .content p::first-line)
+ */
+#[derive(Debug, Clone)]
+pub struct Selector {
+    // Compound selectors from left to right, each with the combinator before it. The first one's is only used by
+    // relative selectors, where it's the combinator from the :has() element.
+    compounds: Vec<(Combinator, Vec<Simple>)>,
+    pseudo_element: Option<String>,
+}
+
+/**
+ * A selector that can't be parsed, which makes browsers drop the whole rule
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Invalid;
+
+/**
+ * § 17 Specificity: the number of ID selectors, of class, attribute and pseudo-class selectors, and of type and
+ * pseudo-element selectors
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Specificity(pub u32, pub u32, pub u32);
+
+impl std::ops::Add for Specificity {
+    type Output = Specificity;
+
+    fn add(self, other: Specificity) -> Specificity {
+        Specificity(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+// Pseudo-elements that can be written with a single colon (:before)
+const LEGACY_PSEUDO_ELEMENTS: [&str; 4] = ["before", "after", "first-line", "first-letter"];
+
+// § 4.16.2 Case-sensitivity of selectors (HTML), the attributes whose values are matched ignoring ASCII case
+const CASE_INSENSITIVE: [&str; 45] = [
+    "accept",
+    "accept-charset",
+    "align",
+    "alink",
+    "axis",
+    "bgcolor",
+    "charset",
+    "checked",
+    "clear",
+    "codetype",
+    "color",
+    "compact",
+    "declare",
+    "defer",
+    "dir",
+    "direction",
+    "disabled",
+    "enctype",
+    "face",
+    "frame",
+    "hreflang",
+    "http-equiv",
+    "lang",
+    "language",
+    "link",
+    "media",
+    "method",
+    "multiple",
+    "nohref",
+    "noresize",
+    "noshade",
+    "nowrap",
+    "readonly",
+    "rel",
+    "rev",
+    "rules",
+    "scope",
+    "scrolling",
+    "selected",
+    "shape",
+    "target",
+    "text",
+    "type",
+    "valign",
+    "valuetype",
+];
+
+/**
+ * Splits a selector list on its commas
+ */
+pub fn split(list: &[ComponentValue]) -> Vec<&[ComponentValue]> {
+    list.split(|value| matches!(value, ComponentValue::Token(CSSToken::Comma)))
+        .collect()
+}
+
+fn is_whitespace(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Whitespace))
+}
+
+struct Parser<'a> {
+    values: &'a [ComponentValue],
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a ComponentValue> {
+        self.values.get(self.index)
+    }
+
+    fn token(&self, offset: usize) -> Option<&'a CSSToken> {
+        match self.values.get(self.index + offset) {
+            Some(ComponentValue::Token(token)) => Some(token),
+            _ => None,
+        }
+    }
+
+    fn whitespace(&mut self) -> bool {
+        let start = self.index;
+        while self.peek().is_some_and(is_whitespace) {
+            self.index += 1;
+        }
+
+        self.index > start
+    }
+
+    fn combinator(&mut self) -> Option<Combinator> {
+        let combinator = match self.token(0) {
+            Some(CSSToken::Delim('>')) => Combinator::Child,
+            Some(CSSToken::Delim('+')) => Combinator::NextSibling,
+            Some(CSSToken::Delim('~')) => Combinator::SubsequentSibling,
+            _ => return None,
+        };
+        self.index += 1;
+
+        Some(combinator)
+    }
+
+    // Skips a namespace prefix (ns| or *| or |) in front of a type selector
+    fn namespace(&mut self) {
+        match (self.token(0), self.token(1)) {
+            (Some(CSSToken::Delim('|')), _) => self.index += 1,
+            (Some(CSSToken::Ident(_) | CSSToken::Delim('*')), Some(CSSToken::Delim('|'))) => {
+                self.index += 2
+            }
+            _ => (),
+        }
+    }
+
+    // A complex selector, or a relative one that can start with a combinator
+    fn selector(&mut self, relative: bool) -> Result<Selector, Invalid> {
+        let mut selector = Selector {
+            compounds: Vec::new(),
+            pseudo_element: None,
+        };
+
+        self.whitespace();
+        let mut combinator = match self.combinator() {
+            Some(combinator) if relative => combinator,
+            Some(_) => return Err(Invalid),
+            None => Combinator::Descendant,
+        };
+        self.whitespace();
+
+        loop {
+            let compound = self.compound(&mut selector.pseudo_element)?;
+            if compound.is_empty() && selector.pseudo_element.is_none() {
+                return Err(Invalid);
+            }
+            selector.compounds.push((combinator, compound));
+
+            let whitespace = self.whitespace();
+            if self.peek().is_none() {
+                break;
+            }
+
+            combinator = match self.combinator() {
+                Some(combinator) => combinator,
+                None if whitespace => Combinator::Descendant,
+                None => return Err(Invalid),
+            };
+
+            // Nothing can follow a pseudo-element
+            if selector.pseudo_element.is_some() {
+                return Err(Invalid);
+            }
+            self.whitespace();
+        }
+
+        Ok(selector)
+    }
+
+    fn compound(&mut self, pseudo_element: &mut Option<String>) -> Result<Vec<Simple>, Invalid> {
+        let mut compound: Vec<Simple> = Vec::new();
+
+        self.namespace();
+        match self.token(0) {
+            Some(CSSToken::Ident(name)) => {
+                compound.push(Simple::Type(name.to_ascii_lowercase()));
+                self.index += 1;
+            }
+            Some(CSSToken::Delim('*')) => {
+                compound.push(Simple::Universal);
+                self.index += 1;
+            }
+            _ => (),
+        }
+
+        while let Some(value) = self.peek() {
+            let simple = match value {
+                ComponentValue::Token(CSSToken::Delim('.')) => match self.token(1) {
+                    Some(CSSToken::Ident(name)) => {
+                        self.index += 1;
+                        Simple::Class(name.clone())
+                    }
+                    _ => return Err(Invalid),
+                },
+                ComponentValue::Token(CSSToken::Hash(name, HashFlag::Id)) => {
+                    Simple::Id(name.clone())
+                }
+
+                // & outside of a style rule is the root element
+                ComponentValue::Token(CSSToken::Delim('&')) => Simple::Pseudo(Pseudo::Root),
+                ComponentValue::Block(block) if matches!(block.token, CSSToken::LeftBracket) => {
+                    Simple::Attribute(attribute(&block.value)?)
+                }
+                ComponentValue::Token(CSSToken::Colon) => {
+                    self.index += 1;
+                    let element = matches!(self.token(0), Some(CSSToken::Colon));
+                    if element {
+                        self.index += 1;
+                    }
+
+                    match (self.peek(), element) {
+                        (Some(ComponentValue::Token(CSSToken::Ident(name))), false)
+                            if !LEGACY_PSEUDO_ELEMENTS
+                                .contains(&name.to_ascii_lowercase().as_str()) =>
+                        {
+                            Simple::Pseudo(pseudo_class(name))
+                        }
+                        (Some(ComponentValue::Function(function)), false) => {
+                            Simple::Pseudo(functional(function)?)
+                        }
+                        (Some(ComponentValue::Token(CSSToken::Ident(name))), _) => {
+                            *pseudo_element = Some(name.to_ascii_lowercase());
+                            self.index += 1;
+                            self.states();
+                            break;
+                        }
+                        (Some(ComponentValue::Function(function)), true) => {
+                            *pseudo_element = Some(function.name.to_ascii_lowercase());
+                            self.index += 1;
+                            self.states();
+                            break;
+                        }
+                        _ => return Err(Invalid),
+                    }
+                }
+                _ => break,
+            };
+
+            compound.push(simple);
+            self.index += 1;
+        }
+
+        Ok(compound)
+    }
+
+    // Skips the pseudo-classes that apply to a pseudo-element (::-webkit-scrollbar:horizontal)
+    fn states(&mut self) {
+        while matches!(self.token(0), Some(CSSToken::Colon))
+            && matches!(
+                self.values.get(self.index + 1),
+                Some(ComponentValue::Token(CSSToken::Ident(_)) | ComponentValue::Function(_))
+            )
+        {
+            self.index += 2;
+        }
+    }
+}
+
+fn attribute(values: &[ComponentValue]) -> Result<Attribute, Invalid> {
+    let values: Vec<&CSSToken> = values
+        .iter()
+        .filter(|value| !is_whitespace(value))
+        .map(|value| match value {
+            ComponentValue::Token(token) => Ok(token),
+            _ => Err(Invalid),
+        })
+        .collect::<Result<_, _>>()?;
+
+    // Skip the namespace prefix, unless the | is part of |=
+    let start = match values.as_slice() {
+        [CSSToken::Delim('|'), CSSToken::Ident(_), ..] => 1,
+        [CSSToken::Ident(_) | CSSToken::Delim('*'), CSSToken::Delim('|'), CSSToken::Ident(_), ..] => {
+            2
+        }
+        _ => 0,
+    };
+
+    let (name, rest) = match &values[start..] {
+        [CSSToken::Ident(name), rest @ ..] => (name.to_ascii_lowercase(), rest),
+        _ => return Err(Invalid),
+    };
+
+    let (operator, rest) = match rest {
+        [] => {
+            return Ok(Attribute {
+                name,
+                value: None,
+                case: Case::Default,
+            })
+        }
+        [CSSToken::Delim('='), rest @ ..] => (Operator::Equals, rest),
+        [CSSToken::Delim(delim), CSSToken::Delim('='), rest @ ..] => match delim {
+            '~' => (Operator::Includes, rest),
+            '|' => (Operator::DashMatch, rest),
+            '^' => (Operator::Prefix, rest),
+            '$' => (Operator::Suffix, rest),
+            '*' => (Operator::Substring, rest),
+            _ => return Err(Invalid),
+        },
+        _ => return Err(Invalid),
+    };
+
+    let (value, flag) = match rest {
+        [CSSToken::Ident(value) | CSSToken::String(value)] => (value, None),
+        [CSSToken::Ident(value) | CSSToken::String(value), CSSToken::Ident(flag)] => {
+            (value, Some(flag.to_ascii_lowercase()))
+        }
+        _ => return Err(Invalid),
+    };
+
+    let case = match flag.as_deref() {
+        None => Case::Default,
+        Some("i") => Case::Insensitive,
+        Some("s") => Case::Sensitive,
+        Some(_) => return Err(Invalid),
+    };
+
+    Ok(Attribute {
+        name,
+        value: Some((operator, value.clone())),
+        case,
+    })
+}
+
+// Reads the An+B in :nth-*(), and an optional "of S"
+fn nth(values: &[ComponentValue], of: bool) -> Result<Nth, Invalid> {
+    let position = values.iter().position(
+        |value| matches!(value, ComponentValue::Token(CSSToken::Ident(name)) if name.eq_ignore_ascii_case("of")),
+    );
+
+    let (formula, selectors) = match position {
+        Some(position) if of => (&values[..position], Some(list(&values[position + 1..])?)),
+        Some(_) => return Err(Invalid),
+        None => (values, None),
+    };
+
+    // Write the tokens back out (2n+1 is a dimension and a number, -n-1 is one identifier)
+    let mut text = String::new();
+    for value in formula.iter().filter(|value| !is_whitespace(value)) {
+        match value {
+            ComponentValue::Token(CSSToken::Ident(ident)) => text.push_str(ident),
+            ComponentValue::Token(CSSToken::Delim(delim @ ('+' | '-'))) => text.push(*delim),
+            ComponentValue::Token(CSSToken::Number(number, _)) if number.fract() == 0.0 => {
+                if *number >= 0.0 && !text.is_empty() && !text.ends_with(['+', '-']) {
+                    text.push('+');
+                }
+                text.push_str(&number.to_string());
+            }
+            ComponentValue::Token(CSSToken::Dimension(number, _, unit))
+                if number.fract() == 0.0 =>
+            {
+                text.push_str(&format!("{}{}", number, unit));
+            }
+            _ => return Err(Invalid),
+        }
+    }
+
+    let text = text.to_ascii_lowercase();
+    let integer = |text: &str| {
+        text.strip_prefix('+')
+            .unwrap_or(text)
+            .parse::<i64>()
+            .map_err(|_| Invalid)
+    };
+
+    let (a, b) = match text.as_str() {
+        "odd" => (2, 1),
+        "even" => (2, 0),
+        text => match text.split_once('n') {
+            Some((a, b)) => {
+                let a = match a {
+                    "" | "+" => 1,
+                    "-" => -1,
+                    a => integer(a)?,
+                };
+                let b = match b {
+                    "" => 0,
+                    b if b.starts_with(['+', '-']) => integer(b)?,
+                    _ => return Err(Invalid),
+                };
+
+                (a, b)
+            }
+            None => (0, integer(text)?),
+        },
+    };
+
+    Ok(Nth {
+        a,
+        b,
+        of: selectors,
+    })
+}
+
+fn pseudo_class(name: &str) -> Pseudo {
+    let name = name.to_ascii_lowercase();
+    let nth = |b: i64| Nth { a: 0, b, of: None };
+
+    match name.as_str() {
+        "root" | "scope" => Pseudo::Root,
+        "empty" => Pseudo::Empty,
+        "first-child" => Pseudo::NthChild(nth(1)),
+        "last-child" => Pseudo::NthLastChild(nth(1)),
+        "only-child" => Pseudo::OnlyChild,
+        "first-of-type" => Pseudo::NthOfType(nth(1)),
+        "last-of-type" => Pseudo::NthLastOfType(nth(1)),
+        "only-of-type" => Pseudo::OnlyOfType,
+        "link" | "any-link" => Pseudo::Link,
+        "checked" => Pseudo::Checked,
+        "disabled" => Pseudo::Disabled,
+        "enabled" => Pseudo::Enabled,
+        "required" => Pseudo::Required,
+        "optional" => Pseudo::Optional,
+        "defined" => Pseudo::Defined,
+        _ => Pseudo::State,
+    }
+}
+
+fn functional(function: &Function) -> Result<Pseudo, Invalid> {
+    let name = function.name.to_ascii_lowercase();
+    let value = &function.value;
+
+    Ok(match name.as_str() {
+        // :is() and :where() are forgiving, invalid selectors in them are left out
+        "is" | "matches" | "-webkit-any" | "-moz-any" => Pseudo::Is(forgiving(value)),
+        "where" => Pseudo::Where(forgiving(value)),
+        "not" => Pseudo::Not(list(value)?),
+        "has" => Pseudo::Has(
+            split(value)
+                .into_iter()
+                .map(|selector| {
+                    Parser {
+                        values: selector,
+                        index: 0,
+                    }
+                    .selector(true)
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        "nth-child" => Pseudo::NthChild(nth(value, true)?),
+        "nth-last-child" => Pseudo::NthLastChild(nth(value, true)?),
+        "nth-of-type" => Pseudo::NthOfType(nth(value, false)?),
+        "nth-last-of-type" => Pseudo::NthLastOfType(nth(value, false)?),
+        "lang" => match value.iter().find(|value| !is_whitespace(value)) {
+            Some(ComponentValue::Token(CSSToken::Ident(lang) | CSSToken::String(lang))) => {
+                Pseudo::Lang(lang.to_ascii_lowercase())
+            }
+            _ => return Err(Invalid),
+        },
+        _ => Pseudo::State,
+    })
+}
+
+fn forgiving(values: &[ComponentValue]) -> Vec<Selector> {
+    split(values)
+        .into_iter()
+        .filter_map(|selector| parse(selector).ok())
+        .collect()
+}
+
+/**
+ * Parses a single complex selector
+ */
+pub fn parse(values: &[ComponentValue]) -> Result<Selector, Invalid> {
+    let mut parser = Parser { values, index: 0 };
+    parser.selector(false)
+}
+
+/**
+ * Parses a selector list, which is invalid as a whole if any of its selectors is
+ */
+pub fn list(values: &[ComponentValue]) -> Result<Vec<Selector>, Invalid> {
+    split(values).into_iter().map(parse).collect()
+}
+
+/**
+ * Parses a selector list written as text, like "nav > a:not(.active)"
+ */
+pub fn selectors(text: &str) -> Result<Vec<Selector>, String> {
+    let values = parse::component_values(text.to_string()).map_err(|e| e.to_string())?;
+
+    list(&values).map_err(|_| format!("Invalid selector \"{}\"", text))
+}
+
+// The element siblings before (closest first) and after an element
+fn siblings(document: &Document, id: NodeId) -> (Vec<NodeId>, Vec<NodeId>) {
+    let elements = match document.node(id).parent {
+        Some(parent) => document.element_children(parent),
+        None => return (Vec::new(), Vec::new()),
+    };
+    let position = elements.iter().position(|child| *child == id).unwrap_or(0);
+
+    let mut before = elements[..position].to_vec();
+    before.reverse();
+
+    (before, elements[position + 1..].to_vec())
+}
+
+fn descendants(document: &Document, id: NodeId, found: &mut Vec<NodeId>) {
+    for child in document.element_children(id) {
+        found.push(child);
+        descendants(document, child, found);
+    }
+}
+
+fn compare(operator: Operator, actual: &str, expected: &str) -> bool {
+    match operator {
+        Operator::Equals => actual == expected,
+        Operator::Includes => {
+            !expected.is_empty() && actual.split_ascii_whitespace().any(|word| word == expected)
+        }
+        Operator::DashMatch => {
+            actual == expected
+                || actual
+                    .strip_prefix(expected)
+                    .is_some_and(|rest| rest.starts_with('-'))
+        }
+        // An empty string matches nothing
+        Operator::Prefix => !expected.is_empty() && actual.starts_with(expected),
+        Operator::Suffix => !expected.is_empty() && actual.ends_with(expected),
+        Operator::Substring => !expected.is_empty() && actual.contains(expected),
+    }
+}
+
+fn is_form_control(name: &str) -> bool {
+    matches!(
+        name,
+        "button" | "input" | "select" | "textarea" | "optgroup" | "option" | "fieldset"
+    )
+}
+
+impl Attribute {
+    fn matches(&self, document: &Document, id: NodeId) -> bool {
+        let actual = match document.attribute(id, &self.name) {
+            Some(actual) => actual,
+            None => return false,
+        };
+
+        let (operator, expected) = match &self.value {
+            Some(value) => value,
+            None => return true,
+        };
+
+        let insensitive = match self.case {
+            Case::Default => CASE_INSENSITIVE.contains(&self.name.as_str()),
+            Case::Sensitive => false,
+            Case::Insensitive => true,
+        };
+
+        if insensitive {
+            compare(
+                *operator,
+                &actual.to_ascii_lowercase(),
+                &expected.to_ascii_lowercase(),
+            )
+        } else {
+            compare(*operator, actual, expected)
+        }
+    }
+}
+
+impl Nth {
+    // Whether a 1-based position is An+B for some n >= 0
+    fn position(&self, position: i64) -> bool {
+        let offset = position - self.b;
+
+        if self.a == 0 {
+            offset == 0
+        } else {
+            offset % self.a == 0 && offset / self.a >= 0
+        }
+    }
+
+    // Counts the element and the siblings on one side that are of the same type, or match the selector list
+    fn matches(&self, document: &Document, id: NodeId, siblings: &[NodeId], of_type: bool) -> bool {
+        let counts = |sibling: NodeId| {
+            if of_type {
+                document.name(sibling) == document.name(id)
+            } else {
+                self.of.as_ref().is_none_or(|selectors| {
+                    selectors
+                        .iter()
+                        .any(|selector| selector.matches(document, sibling))
+                })
+            }
+        };
+
+        if !counts(id) {
+            return false;
+        }
+
+        let position = siblings.iter().filter(|sibling| counts(**sibling)).count() as i64 + 1;
+        self.position(position)
+    }
+}
+
+fn pseudo(pseudo: &Pseudo, document: &Document, id: NodeId) -> bool {
+    let name = document.name(id).unwrap_or("");
+    let has = |attribute: &str| document.attribute(id, attribute).is_some();
+
+    match pseudo {
+        Pseudo::Root => document.parent_element(id).is_none(),
+        Pseudo::Empty => {
+            document
+                .node(id)
+                .children
+                .iter()
+                .all(|child| match &document.node(*child).data {
+                    NodeData::Comment(_) => true,
+                    NodeData::Text(text) => text.is_empty(),
+                    _ => false,
+                })
+        }
+        Pseudo::NthChild(nth) => nth.matches(document, id, &siblings(document, id).0, false),
+        Pseudo::NthLastChild(nth) => nth.matches(document, id, &siblings(document, id).1, false),
+        Pseudo::NthOfType(nth) => nth.matches(document, id, &siblings(document, id).0, true),
+        Pseudo::NthLastOfType(nth) => nth.matches(document, id, &siblings(document, id).1, true),
+        Pseudo::OnlyChild => {
+            let (before, after) = siblings(document, id);
+            before.is_empty() && after.is_empty()
+        }
+        Pseudo::OnlyOfType => {
+            let (before, after) = siblings(document, id);
+            before
+                .iter()
+                .chain(&after)
+                .all(|sibling| document.name(*sibling) != Some(name))
+        }
+        Pseudo::Link => matches!(name, "a" | "area") && has("href"),
+        Pseudo::Checked => {
+            (name == "input" && has("checked")) || (name == "option" && has("selected"))
+        }
+        Pseudo::Disabled => is_form_control(name) && has("disabled"),
+        Pseudo::Enabled => is_form_control(name) && !has("disabled"),
+        Pseudo::Required => matches!(name, "input" | "select" | "textarea") && has("required"),
+        Pseudo::Optional => matches!(name, "input" | "select" | "textarea") && !has("required"),
+
+        // Custom elements (with a - in their name) are never defined without scripts
+        Pseudo::Defined => !name.contains('-'),
+        Pseudo::Lang(lang) => {
+            let mut current = Some(id);
+            while let Some(id) = current {
+                if let Some(value) = document.attribute(id, "lang") {
+                    return compare(Operator::DashMatch, &value.to_ascii_lowercase(), lang);
+                }
+                current = document.parent_element(id);
+            }
+            false
+        }
+        Pseudo::Is(selectors) | Pseudo::Where(selectors) => selectors
+            .iter()
+            .any(|selector| selector.pseudo_element.is_none() && selector.matches(document, id)),
+        Pseudo::Not(selectors) => !selectors
+            .iter()
+            .any(|selector| selector.matches(document, id)),
+        Pseudo::Has(selectors) => selectors.iter().any(|selector| {
+            // The elements a relative selector could end on
+            let mut candidates: Vec<NodeId> = Vec::new();
+            match selector.compounds[0].0 {
+                Combinator::Descendant | Combinator::Child => {
+                    descendants(document, id, &mut candidates)
+                }
+                Combinator::NextSibling | Combinator::SubsequentSibling => {
+                    for sibling in siblings(document, id).1 {
+                        candidates.push(sibling);
+                        descendants(document, sibling, &mut candidates);
+                    }
+                }
+            }
+
+            candidates.into_iter().any(|candidate| {
+                selector.matches_at(selector.compounds.len() - 1, document, candidate, Some(id))
+            })
+        }),
+        Pseudo::State => false,
+    }
+}
+
+fn simple(simple: &Simple, document: &Document, id: NodeId) -> bool {
+    match simple {
+        Simple::Universal => true,
+        Simple::Type(name) => document
+            .name(id)
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(name)),
+        Simple::Id(name) => document.attribute(id, "id") == Some(name.as_str()),
+        Simple::Class(name) => document
+            .attribute(id, "class")
+            .is_some_and(|classes| classes.split_ascii_whitespace().any(|class| class == name)),
+        Simple::Attribute(attribute) => attribute.matches(document, id),
+        Simple::Pseudo(class) => pseudo(class, document, id),
+    }
+}
+
+// Whether one element is related to another by a combinator (from > to, from + to and so on)
+fn related(document: &Document, combinator: Combinator, from: NodeId, to: NodeId) -> bool {
+    match combinator {
+        Combinator::Descendant => {
+            let mut current = document.parent_element(to);
+            while let Some(ancestor) = current {
+                if ancestor == from {
+                    return true;
+                }
+                current = document.parent_element(ancestor);
+            }
+            false
+        }
+        Combinator::Child => document.parent_element(to) == Some(from),
+        Combinator::NextSibling => siblings(document, to).0.first() == Some(&from),
+        Combinator::SubsequentSibling => siblings(document, to).0.contains(&from),
+    }
+}
+
+// The specificity of the most specific selector in a list
+fn most(selectors: &[Selector]) -> Specificity {
+    selectors
+        .iter()
+        .map(Selector::specificity)
+        .max()
+        .unwrap_or_default()
+}
+
+impl Simple {
+    fn specificity(&self) -> Specificity {
+        match self {
+            Simple::Universal => Specificity(0, 0, 0),
+            Simple::Type(_) => Specificity(0, 0, 1),
+            Simple::Id(_) => Specificity(1, 0, 0),
+            Simple::Class(_) | Simple::Attribute(_) => Specificity(0, 1, 0),
+            Simple::Pseudo(pseudo) => match pseudo {
+                Pseudo::Is(selectors) | Pseudo::Not(selectors) | Pseudo::Has(selectors) => {
+                    most(selectors)
+                }
+                Pseudo::Where(_) => Specificity(0, 0, 0),
+                Pseudo::NthChild(Nth {
+                    of: Some(selectors),
+                    ..
+                })
+                | Pseudo::NthLastChild(Nth {
+                    of: Some(selectors),
+                    ..
+                }) => Specificity(0, 1, 0) + most(selectors),
+                _ => Specificity(0, 1, 0),
+            },
+        }
+    }
+
+    fn is_static(&self) -> bool {
+        match self {
+            Simple::Pseudo(Pseudo::State) => false,
+            Simple::Pseudo(
+                Pseudo::Is(selectors)
+                | Pseudo::Where(selectors)
+                | Pseudo::Not(selectors)
+                | Pseudo::Has(selectors),
+            ) => selectors.iter().all(Selector::is_static),
+            Simple::Pseudo(
+                Pseudo::NthChild(Nth {
+                    of: Some(selectors),
+                    ..
+                })
+                | Pseudo::NthLastChild(Nth {
+                    of: Some(selectors),
+                    ..
+                }),
+            ) => selectors.iter().all(Selector::is_static),
+            _ => true,
+        }
+    }
+}
+
+impl Selector {
+    // Matches the compounds up to and including index, with the last one on the element. Relative selectors are
+    // anchored to the :has() element.
+    fn matches_at(
+        &self,
+        index: usize,
+        document: &Document,
+        id: NodeId,
+        anchor: Option<NodeId>,
+    ) -> bool {
+        let (combinator, compound) = &self.compounds[index];
+
+        if !compound.iter().all(|part| simple(part, document, id)) {
+            return false;
+        }
+
+        if index == 0 {
+            return anchor.is_none_or(|anchor| related(document, *combinator, anchor, id));
+        }
+
+        let previous = |candidate: NodeId| self.matches_at(index - 1, document, candidate, anchor);
+
+        match combinator {
+            Combinator::Descendant => {
+                let mut current = document.parent_element(id);
+                while let Some(ancestor) = current {
+                    if previous(ancestor) {
+                        return true;
+                    }
+                    current = document.parent_element(ancestor);
+                }
+                false
+            }
+            Combinator::Child => document.parent_element(id).is_some_and(previous),
+            Combinator::NextSibling => siblings(document, id)
+                .0
+                .first()
+                .is_some_and(|sibling| previous(*sibling)),
+            Combinator::SubsequentSibling => siblings(document, id).0.into_iter().any(previous),
+        }
+    }
+
+    /**
+     * Whether the selector matches an element, or one of its pseudo-elements
+     */
+    pub fn matches(&self, document: &Document, id: NodeId) -> bool {
+        document.element(id).is_some()
+            && self.matches_at(self.compounds.len() - 1, document, id, None)
+    }
+
+    pub fn specificity(&self) -> Specificity {
+        let elements = Specificity(0, 0, self.pseudo_element.iter().count() as u32);
+
+        self.compounds
+            .iter()
+            .flat_map(|(_, compound)| compound)
+            .map(Simple::specificity)
+            .fold(elements, |total, specificity| total + specificity)
+    }
+
+    // The pseudo-element the selector ends with (before for p::before)
+    pub fn pseudo_element(&self) -> Option<&str> {
+        self.pseudo_element.as_deref()
+    }
+
+    /**
+     * Whether the document alone decides what the selector matches: it doesn't end with a pseudo-element, and has no
+     * pseudo-classes that depend on the user (or aren't known)
+     */
+    pub fn is_static(&self) -> bool {
+        self.pseudo_element.is_none()
+            && self
+                .compounds
+                .iter()
+                .flat_map(|(_, compound)| compound)
+                .all(Simple::is_static)
+    }
+}
+
+/**
+ * The elements matching any of the selectors, in document order
+ */
+pub fn query(document: &Document, selectors: &[Selector]) -> Vec<NodeId> {
+    (0..document.nodes.len())
+        .filter(|id| {
+            selectors
+                .iter()
+                .any(|selector| selector.matches(document, *id))
+        })
+        .collect()
+}
@@ -0,0 +1,316 @@
+/**
+ * Minimal serialization of the stylesheet tree
+ * https://www.w3.org/TR/css-syntax-3/#serialization
+ *
+ * Whitespace is only written where it means something: between tokens that would otherwise run together, as a
+ * descendant combinator in selectors, around + and - in math functions, and inside custom property values.
+ * Everything else (around commas, colons, parentheses, braces and semicolons) is dropped.
+ */
+use super::parse::tree::{
+    AtRule, BlockItem, ComponentValue, Declaration, QualifiedRule, Rule, Stylesheet,
+};
+use super::token::tokens::CSSToken;
+use std::fmt::{Display, Formatter, Result};
+
+// Functions where whitespace around + and - is required
+const MATH_FUNCTIONS: [&str; 22] = [
+    "calc",
+    "min",
+    "max",
+    "clamp",
+    "round",
+    "mod",
+    "rem",
+    "sin",
+    "cos",
+    "tan",
+    "asin",
+    "acos",
+    "atan",
+    "atan2",
+    "pow",
+    "sqrt",
+    "hypot",
+    "log",
+    "exp",
+    "abs",
+    "sign",
+    "calc-size",
+];
+
+// What a list of component values is, which decides where whitespace is significant
+#[derive(Clone, Copy, PartialEq)]
+pub enum Context {
+    Selector,
+    Prelude,
+    Value,
+
+    // Custom property values are kept as written
+    Raw,
+}
+
+pub fn is_math(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    let unprefixed = name
+        .trim_start_matches("-webkit-")
+        .trim_start_matches("-moz-");
+
+    MATH_FUNCTIONS.contains(&unprefixed)
+}
+
+fn is_numeric(token: &CSSToken) -> bool {
+    matches!(
+        token,
+        CSSToken::Number(..) | CSSToken::Percentage(_) | CSSToken::Dimension(..)
+    )
+}
+
+// § 9 Two tokens that would be tokenized differently if written next to each other
+pub fn needs_separator(previous: &CSSToken, next: &CSSToken) -> bool {
+    let identifier = matches!(
+        next,
+        CSSToken::Ident(_)
+            | CSSToken::Function(_)
+            | CSSToken::URL(_)
+            | CSSToken::BadURL
+            | CSSToken::Delim('-')
+            | CSSToken::CDC
+            | CSSToken::UnicodeRange(..)
+    ) || is_numeric(next);
+
+    match previous {
+        CSSToken::Ident(_) => identifier || matches!(next, CSSToken::LeftParentheses),
+        CSSToken::AtKeyword(_)
+        | CSSToken::Hash(..)
+        | CSSToken::Dimension(..)
+        | CSSToken::Delim('#')
+        | CSSToken::Delim('-')
+        | CSSToken::Delim('@') => identifier,
+        CSSToken::Number(..) => identifier || matches!(next, CSSToken::Delim('%' | '.')),
+        CSSToken::UnicodeRange(..) => identifier || matches!(next, CSSToken::Delim('?')),
+        CSSToken::Delim('.' | '+') => is_numeric(next),
+        CSSToken::Delim('/') => matches!(next, CSSToken::Delim('*')),
+        _ => false,
+    }
+}
+
+// Decides whether whitespace written between two tokens needs to be kept
+fn keep_whitespace(
+    context: Context,
+    previous: &CSSToken,
+    next: &CSSToken,
+    math: bool,
+    brackets: usize,
+) -> bool {
+    if needs_separator(previous, next) || context == Context::Raw {
+        return true;
+    }
+
+    let open = matches!(
+        previous,
+        CSSToken::Comma
+            | CSSToken::Colon
+            | CSSToken::Function(_)
+            | CSSToken::LeftParentheses
+            | CSSToken::LeftBracket
+    );
+    let close = matches!(
+        next,
+        CSSToken::Comma | CSSToken::RightParentheses | CSSToken::RightBracket
+    );
+
+    if open || close {
+        return false;
+    }
+
+    let sign = |token: &CSSToken| matches!(token, CSSToken::Delim('+' | '-'));
+    let combinator = |token: &CSSToken| matches!(token, CSSToken::Delim('>' | '+' | '~'));
+
+    match context {
+        // Whitespace is the descendant combinator, except inside attribute selectors
+        Context::Selector => brackets == 0 && !combinator(previous) && !combinator(next),
+
+        // @charset is matched byte for byte, so it keeps its space
+        Context::Prelude => match previous {
+            CSSToken::AtKeyword(name) => name == "charset",
+            _ => true,
+        },
+        Context::Value => math && (sign(previous) || sign(next)),
+        Context::Raw => true,
+    }
+}
+
+// Writes component values out as a flat list of tokens
+fn flatten(values: &[ComponentValue], tokens: &mut Vec<CSSToken>) {
+    for value in values {
+        match value {
+            ComponentValue::Token(token) => tokens.push(token.clone()),
+            ComponentValue::Function(function) => {
+                tokens.push(CSSToken::Function(function.name.clone()));
+                flatten(&function.value, tokens);
+                tokens.push(CSSToken::RightParentheses);
+            }
+            ComponentValue::Block(block) => {
+                tokens.push(block.token.clone());
+                flatten(&block.value, tokens);
+                tokens.push(match block.token {
+                    CSSToken::LeftBrace => CSSToken::RightBrace,
+                    CSSToken::LeftBracket => CSSToken::RightBracket,
+                    _ => CSSToken::RightParentheses,
+                });
+            }
+        }
+    }
+}
+
+// Writes tokens with as little whitespace as possible
+fn write_tokens(tokens: &[CSSToken], context: Context) -> String {
+    let mut output = String::new();
+    let mut previous: Option<&CSSToken> = None;
+    let mut whitespace = false;
+
+    // Whether each open function or block is (inside) a math function
+    let mut math: Vec<bool> = Vec::new();
+    let mut brackets = 0;
+
+    for token in tokens {
+        if let CSSToken::Whitespace = token {
+            whitespace = previous.is_some();
+            continue;
+        }
+
+        if let Some(previous) = previous {
+            let inside_math = math.last().copied().unwrap_or(false);
+
+            if whitespace && keep_whitespace(context, previous, token, inside_math, brackets) {
+                output.push(' ');
+            } else if needs_separator(previous, token) {
+                output.push_str("/**/");
+            }
+        }
+
+        match token {
+            CSSToken::Function(name) => {
+                let inside_math = math.last().copied().unwrap_or(false);
+                math.push(inside_math || is_math(name));
+            }
+            CSSToken::LeftParentheses | CSSToken::LeftBrace | CSSToken::LeftBracket => {
+                if let CSSToken::LeftBracket = token {
+                    brackets += 1;
+                }
+
+                math.push(math.last().copied().unwrap_or(false));
+            }
+            CSSToken::RightParentheses | CSSToken::RightBrace | CSSToken::RightBracket => {
+                if let CSSToken::RightBracket = token {
+                    brackets -= 1;
+                }
+
+                math.pop();
+            }
+            _ => (),
+        }
+
+        output.push_str(&token.to_string());
+        previous = Some(token);
+        whitespace = false;
+    }
+
+    output
+}
+
+/**
+ * Serializes component values
+ */
+pub fn values(values: &[ComponentValue], context: Context) -> String {
+    let mut tokens: Vec<CSSToken> = Vec::new();
+    flatten(values, &mut tokens);
+
+    write_tokens(&tokens, context)
+}
+
+fn block(f: &mut Formatter, items: &[BlockItem]) -> Result {
+    write!(f, "{{")?;
+
+    for (index, item) in items.iter().enumerate() {
+        match item {
+            BlockItem::Declaration(declaration) => {
+                write!(f, "{}", declaration)?;
+
+                if index + 1 < items.len() {
+                    write!(f, ";")?;
+                }
+            }
+            BlockItem::Rule(rule) => write!(f, "{}", rule)?,
+        }
+    }
+
+    write!(f, "}}")
+}
+
+impl Display for Stylesheet {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        for comment in &self.comments {
+            write!(f, "{}", CSSToken::Comment(comment.clone()))?;
+        }
+
+        for rule in &self.rules {
+            write!(f, "{}", rule)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            Rule::Qualified(rule) => write!(f, "{}", rule),
+            Rule::At(rule) => write!(f, "{}", rule),
+        }
+    }
+}
+
+impl Display for QualifiedRule {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "{}", values(&self.prelude, Context::Selector))?;
+        block(f, &self.block)
+    }
+}
+
+impl Display for AtRule {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let mut tokens = vec![CSSToken::AtKeyword(self.name.clone()), CSSToken::Whitespace];
+        flatten(&self.prelude, &mut tokens);
+
+        write!(f, "{}", write_tokens(&tokens, Context::Prelude))?;
+
+        match &self.block {
+            Some(items) => block(f, items),
+            None => write!(f, ";"),
+        }
+    }
+}
+
+impl Display for Declaration {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let context = if self.name.starts_with("--") {
+            Context::Raw
+        } else {
+            Context::Value
+        };
+
+        write!(
+            f,
+            "{}:{}",
+            CSSToken::Ident(self.name.clone()),
+            values(&self.value, context)
+        )?;
+
+        if self.important {
+            write!(f, "!important")?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,6 @@
-/**
+/*!
  * Tests for certain classes of code points or certain algorithms to check if a stream of code points meet a criteria
  */
-
 use std::cmp::Ordering;
 use std::iter::Peekable;
 use std::str::Chars;
@@ -37,7 +36,7 @@ pub fn is_name_code_point(point: char) -> bool {
 pub const MAX_CODE_POINT: u32 = 0x10FFFF;
 
 pub fn is_surrogate(num: u32) -> bool {
-    0xD800 <= num && num <= 0xDFFF
+    (0xD800..=0xDFFF).contains(&num)
 }
 
 pub fn is_nonprintable(ch: &char) -> bool {
@@ -50,7 +49,7 @@ pub fn is_nonprintable(ch: &char) -> bool {
     code == 0x000B ||
 
     // code point between U+000E SHIFT OUT and U+001F INFORMATION SEPARATOR ONE
-    code >= 0x000E && code <= 0x001F ||
+    (0x000E..=0x001F).contains(&code) ||
 
     // or U+007F DELETE
     code == 0x007F
@@ -64,7 +63,7 @@ pub fn is_whitespace(ch: &char) -> bool {
     ch.partial_cmp(&'\n') == Some(Ordering::Equal) || 
     
     // U+0009 CHARACTER TABULATION
-    ch.partial_cmp(&' ') == Some(Ordering::Equal) ||
+    ch.partial_cmp(&'\t') == Some(Ordering::Equal) ||
 
     // U+0020 SPACE
     ch.partial_cmp(&' ') == Some(Ordering::Equal)
@@ -123,15 +122,16 @@ pub fn is_identifier(points: &mut Peekable<Chars>) -> bool {
             // Now we have to clone the points to avoid consuming anything from the main stream
             let mut points = points.clone();
 
-            // ch here
-           if let Some(ch) = points.next() {
+            // Skip over the -
+            points.next();
 
-                if is_name_start_code_point(ch) || ch == '-' {
-                    true
-                } else if is_valid_escape(&mut points) {
+            // Look at the second code point
+            if let Some(ch) = points.peek() {
+
+                if is_name_start_code_point(*ch) || equal(ch, &'-') {
                     true
                 } else {
-                    false
+                    is_valid_escape(&mut points)
                 }
 
            } else {
@@ -154,7 +154,7 @@ pub fn is_identifier(points: &mut Peekable<Chars>) -> bool {
     }
 }
 
-// 4.3.10. Check if three code points would start a number
+// 4.3.10. Check if three code points would start a number (Look ahead, consumes nothing)
 pub fn is_number(points: &mut Peekable<Chars>) -> bool {
 
     // Lets look at the first digit to determine if we need to clone
@@ -162,7 +162,7 @@ pub fn is_number(points: &mut Peekable<Chars>) -> bool {
 
         // U+002B PLUS SIGN (+)
         // U+002D HYPHEN-MINUS (-)
-        if equal(ch, &'+') || equal(ch, &'+') { 
+        if equal(ch, &'+') || equal(ch, &'-') {
 
             // Clone points and advance to check the second
             let mut points = points.clone();
@@ -196,6 +196,10 @@ pub fn is_number(points: &mut Peekable<Chars>) -> bool {
         // U+002E FULL STOP (.)
         } else if equal(ch, &'.') {
 
+            // Clone points and advance to check the second
+            let mut points = points.clone();
+            points.next();
+
             // If it starts with a . the second char needs to be a digit (like .4)
             if let Some(second) = points.next() {
                 second.is_ascii_digit()
@@ -214,4 +218,3 @@ pub fn is_number(points: &mut Peekable<Chars>) -> bool {
     }
 
 }
-
@@ -11,7 +11,6 @@ use super::check::{
 use super::error::ParseError;
 use super::tokens::{CSSToken, NumericFlag};
 use std::char;
-use std::i32;
 use std::num::ParseFloatError;
 
 // 4.3.11. Consume a name
@@ -29,6 +28,10 @@ pub fn name(points: &mut Peekable<Chars>, position: &mut i32) -> Result<String,
                     let ch = points.next().unwrap();
                     name.push(ch);
                 } else if is_valid_escape(points) {
+                    // Consume the \ before the escaped code point
+                    *position += 1;
+                    points.next();
+
                     let result = escape(points, position);
 
                     match result {
@@ -86,6 +89,14 @@ pub fn escape(points: &mut Peekable<Chars>, position: &mut i32) -> Result<char,
             }
         }
 
+        // If the next input code point is whitespace, consume it as well, it only ends the escape
+        if let Some(ch) = points.peek() {
+            if is_whitespace(ch) {
+                *position += 1;
+                points.next();
+            }
+        }
+
         // Now that we've collected the hex, turn it into a number
         let ch = match u32::from_str_radix(hex.as_str(), 16) {
             Ok(number) => {
@@ -100,11 +111,7 @@ pub fn escape(points: &mut Peekable<Chars>, position: &mut i32) -> Result<char,
                        char::from_u32_unchecked() as the spec defines checks in place.
                        However, it's probably better to just return � if parsing fails
                     */
-                    if result.is_none() {
-                        '�'
-                    } else {
-                        result.unwrap()
-                    }
+                    result.unwrap_or('�')
                 }
             }
 
@@ -146,79 +153,10 @@ fn digits(points: &mut Peekable<Chars>, position: &mut i32) -> String {
 }
 
 // 4.3.13. Convert a string to a number
+// Parsed as a whole, which rounds to the closest f64. Adding up the parts like the spec does isn't exact in floating
+// point (.3 became 0.30000000000000004) and counted the exponent digits as fraction digits (1.1e1 became 10.1)
 pub fn to_number(string: String) -> Result<f64, ParseFloatError> {
-    let mut iter = string.chars().peekable();
-
-    // Sign
-    let mut s = 1.0;
-    if let Some(sign) = iter.peek() {
-        if equal(sign, &'-') {
-            // Advance the pointer and set sign
-            s = -1.0;
-            iter.next();
-        } else if equal(sign, &'+') {
-            iter.next();
-        }
-    };
-
-    // Digits
-    let num = digits(&mut iter, &mut 0);
-    let i: f64 = match num.parse::<f64>() {
-        Ok(parsed) => parsed,
-        Err(e) => return Err(e),
-    };
-
-    // Decimal point
-    if let Some(sign) = iter.peek() {
-        if equal(sign, &'.') {
-            // Advance the pointer through the decimal points
-            iter.next();
-        }
-    };
-
-    // Fraction digits
-    let mut d = 0;
-    let num = digits(&mut iter, &mut d);
-    let f: f64 = if num.len() < 1 {
-        0.0
-    } else {
-        match num.parse::<f64>() {
-            Ok(parsed) => parsed,
-            Err(e) => return Err(e),
-        }
-    };
-
-    // Exponential Indicator
-    if let Some(sign) = iter.peek() {
-        if equal(sign, &'e') || equal(sign, &'E') {
-            // Advance the pointer through the decimal points
-            iter.next();
-        }
-    };
-
-    // Expoential Sign
-    let mut t = 1.0;
-    if let Some(sign) = iter.peek() {
-        if equal(sign, &'-') {
-            // Advance the pointer and set sign
-            t = -1.0;
-            iter.next();
-        }
-    };
-
-    // Exponent digits
-    let num = digits(&mut iter, &mut d);
-    let e: f64 = if num.len() < 1 {
-        0.0
-    } else {
-        match num.parse::<f64>() {
-            Ok(parsed) => parsed,
-            Err(e) => return Err(e),
-        }
-    };
-
-    // Create the number from the segments
-    Ok(s * (i + f * 10f64.powf(-d as f64)) * 10f64.powf(t * e))
+    string.parse::<f64>()
 }
 
 // 4.3.12. Consume a number
@@ -284,6 +222,7 @@ pub fn number(
 
                             repr.push(e);
                             repr.push(pm);
+                            flag = NumericFlag::Number;
 
                             // Consume digits
                             // Consume digits if any and push them onto repr
@@ -296,8 +235,10 @@ pub fn number(
                 // Otherwise, if the second is the ASCII digit, then consume digits to add to repr
                 } else if second.is_ascii_digit() {
                     // Consume the e
+                    *position += 1;
                     let e = points.next().unwrap();
                     repr.push(e);
+                    flag = NumericFlag::Number;
 
                     let string = digits(points, position);
                     repr.push_str(string.as_str());
@@ -331,14 +272,14 @@ pub fn numeric_token(
     position: &mut i32,
 ) -> Result<CSSToken, ParseError> {
     match number(points, position) {
-        Err(e) => return Err(e),
+        Err(e) => Err(e),
         Ok((number, flag)) => {
             // If next 3 code points are an identifier, then this is a dimension
             if is_identifier(points) {
                 let result = name(points, position);
 
                 match result {
-                    Ok(dim) => Ok(CSSToken::Dimension(number, NumericFlag::Number, dim)),
+                    Ok(dim) => Ok(CSSToken::Dimension(number, flag, dim)),
                     Err(e) => Err(e),
                 }
             // If the next char is a % then it's a percentage
@@ -371,21 +312,19 @@ pub fn whitespace(points: &mut Peekable<Chars>, position: &mut i32) {
 // 4.3.14. Consume the remnants of a bad url
 pub fn bad_url_remnant(points: &mut Peekable<Chars>, position: &mut i32) {
     loop {
-        *position += 1;
-        let next = points.next();
-
-        loop {
-            match next {
-                None => break,
-                Some(ch) => {
-                    if ch == ')' {
-                        break;
-                    } else if is_valid_escape(points) {
-                        let _ = escape(points, position);
-                    }
-                }
-            };
+        // A valid escape is consumed whole, so an escaped ) doesn't end the url
+        if is_valid_escape(points) {
+            *position += 1;
+            points.next();
+            let _ = escape(points, position);
+            continue;
         }
+
+        *position += 1;
+        match points.next() {
+            None | Some(')') => return,
+            Some(_) => (),
+        };
     }
 }
 
@@ -415,10 +354,20 @@ pub fn url_token(points: &mut Peekable<Chars>, position: &mut i32) -> Result<CSS
                 if ch == ')' {
                     return Ok(CSSToken::URL(string));
 
-                // If it's whitespace, consume as much whitespace as possible
+                // If it's whitespace, consume as much whitespace as possible, the url has to end after it
                 } else if is_whitespace(&ch) {
                     whitespace(points, position);
 
+                    if points.peek().is_some() && !next_char_equals(points, &')') {
+                        bad_url_remnant(points, position);
+
+                        return Err(ParseError {
+                            error_text: "Bad URL detected",
+                            at: *position,
+                            token: Some(CSSToken::BadURL),
+                        });
+                    }
+
                 // These characters indicate a bad URL
                 } else if ch == '"' || ch == '\'' || ch == '(' || is_nonprintable(&ch) {
                     // Consume the remnants of the bad url and return a token
@@ -432,7 +381,7 @@ pub fn url_token(points: &mut Peekable<Chars>, position: &mut i32) -> Result<CSS
 
                 // Check and account for valid escapes
                 } else if ch == '\\' {
-                    if is_valid_escape(points) {
+                    if !next_char_equals(points, &'\n') && points.peek().is_some() {
                         match escape(points, position) {
                             Ok(ch) => string.push(ch),
                             Err(e) => return Err(e),
@@ -461,13 +410,10 @@ pub fn ident_like_token(
     points: &mut Peekable<Chars>,
     position: &mut i32,
 ) -> Result<CSSToken, ParseError> {
-    let string = match name(points, position) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
+    let string = name(points, position)?;
 
     // If string’s value is an ASCII case-insensitive match for "url", and the next input code point is U+0028 LEFT PARENTHESIS ((), consume it.
-    if string.to_ascii_lowercase() == String::from("url") && next_char_equals(points, &'(') {
+    if string.eq_ignore_ascii_case("url") && next_char_equals(points, &'(') {
         *position += 1;
         points.next();
 
@@ -508,18 +454,19 @@ pub fn ident_like_token(
         if peek.is_some() && (equal(peek.unwrap(), &'"') || equal(peek.unwrap(), &'\'')) {
             return Ok(CSSToken::Function(string));
         } else if peek.is_some() && is_whitespace(peek.unwrap()) {
-            let mut points = points.clone();
-            let next = points.next();
+            let mut lookahead = points.clone();
+            lookahead.next();
+            let next = lookahead.next();
 
             // Then we gotta check if the next character after that is ' or "
-            match next {
-                Some(c) => {
-                    if c == '\'' || c == '"' {
-                        return Ok(CSSToken::Function(string));
-                    }
+            if let Some(c) = next {
+                if c == '\'' || c == '"' {
+                    return Ok(CSSToken::Function(string));
                 }
-                None => {}
             };
+
+            return url_token(points, position);
+
         // Otherwise, consume a url token, and return it.
         } else {
             return url_token(points, position);
@@ -7,7 +7,7 @@ pub mod consume;
 
 use tokens::CSSToken;
 
-/**
+/*
  * CSS Tokenizer
  * https://www.w3.org/TR/css-syntax-3/#tokenizing-and-parsing
  */
@@ -28,22 +28,45 @@ pub fn preprocess(contents: String) -> String {
         .replace("\x00", "\u{FFFD}")
 }
 
+/**
+ * The line and column (both counted from 1) of a position in the contents, as the tokenizer counts positions
+ */
+pub fn location(contents: &str, at: i32) -> (usize, usize) {
+    let contents = preprocess(contents.to_string());
+    let mut line = 1;
+    let mut column = 1;
+
+    for ch in contents.chars().take(at.max(0) as usize) {
+        if ch == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+
+    (line, column)
+}
+
 /**
  * § Tokenization
  *
  * 1. Comments
  * 2. Whitespace
+ *
+ * Each token is paired with the position it starts at
  */
-pub fn tokenize(contents: String) -> Result<Vec<tokens::CSSToken>, error::ParseError> {
+pub fn tokenize(contents: String) -> Result<Vec<(tokens::CSSToken, i32)>, error::ParseError> {
     // Prepare the contents
     let contents = preprocess(contents);
 
-    let mut tokens: Vec<tokens::CSSToken> = Vec::new();
+    let mut tokens: Vec<(tokens::CSSToken, i32)> = Vec::new();
     let mut iter = contents.chars().peekable();
 
     let mut position = 0;
 
     loop {
+        let start = position;
         let result = parsers::parse(&mut iter, &mut position);
 
         // Go through the possible options
@@ -56,14 +79,14 @@ pub fn tokenize(contents: String) -> Result<Vec<tokens::CSSToken>, error::ParseE
             Ok(result) => {
                 match result {
                     CSSToken::EOF => break,
-                    _ => tokens.push(result),
+                    _ => tokens.push((result, start)),
                 };
             }
         }
     }
 
     // Add the EOF token from the end
-    tokens.push(CSSToken::EOF);
+    tokens.push((CSSToken::EOF, position));
 
     Ok(tokens)
 }
@@ -8,7 +8,6 @@
  */
 use super::super::check::{is_identifier, next_char_equals};
 use super::super::consume;
-use super::super::error::ParseError;
 use super::super::tokens::CSSToken;
 use super::ParseResult;
 use std::iter::Peekable;
@@ -25,17 +24,7 @@ pub fn parse(points: &mut Peekable<Chars>, position: &mut i32) -> ParseResult {
                 Err(e) => Err(e),
             }
         } else {
-            *position += 1;
-            let next = points.next();
-
-            match next {
-                Some(c) => Ok(Some(CSSToken::Delim(c))),
-                None => Err(ParseError {
-                    at: *position,
-                    token: None,
-                    error_text: "Unexpected End of File (EoF) around @",
-                }),
-            }
+            Ok(Some(CSSToken::Delim('@')))
         }
     } else {
         Ok(None)
@@ -14,9 +14,7 @@ use std::str::Chars;
 
 pub fn parse(points: &mut Peekable<Chars>, position: &mut i32) -> ParseResult {
     if next_char_equals(points, &'\\') {
-        *position += 1;
-        points.next();
-
+        // The escape is consumed as part of the identifier
         if is_valid_escape(points) {
             match consume::ident_like_token(points, position) {
                 Ok(token) => Ok(Some(token)),
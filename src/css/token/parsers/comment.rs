@@ -22,8 +22,14 @@ pub fn parse(points: &mut Peekable<Chars>, position: &mut i32) -> ParseResult {
     let comment_starts = lookahead(points, &COMMENT_START);
 
     if comment_starts {
+        // Consume the opening characters, so /*/ doesn't also count as the end
+        points.next();
+        points.next();
+        *position += 2;
+
         // Lookahead to the end of the comment
         let mut empty = false;
+        let mut text = String::new();
 
         while !empty {
             let end = lookahead(points, &COMMENT_END);
@@ -34,16 +40,19 @@ pub fn parse(points: &mut Peekable<Chars>, position: &mut i32) -> ParseResult {
 
                 *position += 2;
 
-                return Ok(Some(CSSToken::Comment));
+                return Ok(Some(CSSToken::Comment(text)));
             }
 
             *position += 1;
-            empty = points.next().is_none();
+            match points.next() {
+                Some(ch) => text.push(ch),
+                None => empty = true,
+            }
         }
 
         // If we've reach the end of the iterator return a parse error
         Err(ParseError {
-            token: Some(CSSToken::Comment),
+            token: Some(CSSToken::Comment(text)),
             error_text: "Unexpected End Of File (EOF)",
             at: *position,
         })
@@ -3,12 +3,12 @@
  *
  * Possible outcomes:
  *  - Ok(None) => No match
- *  - Ok(Some(CSSToken::Delim)) =>
+ *  - Ok(Some(CSSToken::Hash)) => #id or #FFFFFF
+ *  - Ok(Some(CSSToken::Delim)) => A lone #
  *
  */
 use super::super::check::{is_identifier, is_name_code_point, is_valid_escape};
 use super::super::consume;
-use super::super::error::ParseError;
 use super::super::tokens::{CSSToken, HashFlag};
 use super::ParseResult;
 use std::char;
@@ -56,21 +56,11 @@ pub fn parse(points: &mut Peekable<Chars>, position: &mut i32) -> ParseResult {
                         )));
                     }
                 };
-
-            // Otherwise the # is being used a delimiter
-            } else {
-                *position += 1;
-                let ch = points.next().unwrap();
-
-                return Ok(Some(CSSToken::Delim(ch)));
             }
-        } else {
-            return Err(ParseError {
-                token: None,
-                at: *position,
-                error_text: "Unexpected EOF when creating hash",
-            });
         }
+
+        // Otherwise the # is being used a delimiter
+        return Ok(Some(CSSToken::Delim(NUMBER_START)));
     }
 
     // If it doesn't match then this parser doesn't match
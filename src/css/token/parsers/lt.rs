@@ -3,7 +3,6 @@
  *
  */
 use super::super::check::next_char_equals;
-use super::super::error::ParseError;
 use super::super::tokens::CSSToken;
 use super::lookahead;
 use super::ParseResult;
@@ -27,17 +26,7 @@ pub fn parse(points: &mut Peekable<Chars>, position: &mut i32) -> ParseResult {
 
             Ok(Some(CSSToken::CDO))
         } else {
-            *position += 1;
-            let next = points.next();
-
-            match next {
-                Some(c) => Ok(Some(CSSToken::Delim(c))),
-                None => Err(ParseError {
-                    at: *position,
-                    token: None,
-                    error_text: "Unexpected End of File (EoF) around <",
-                }),
-            }
+            Ok(Some(CSSToken::Delim('<')))
         }
     } else {
         Ok(None)
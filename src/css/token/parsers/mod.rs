@@ -42,7 +42,7 @@ pub fn lookahead(iter: &mut Peekable<Chars>, chars: &[char]) -> bool {
         match next {
             None => return false,
             Some(ch) => {
-                if ch.partial_cmp(&char) != Some(Ordering::Equal) {
+                if ch.partial_cmp(char) != Some(Ordering::Equal) {
                     return false;
                 }
             }
@@ -58,184 +58,104 @@ pub fn lookahead(iter: &mut Peekable<Chars>, chars: &[char]) -> bool {
 pub fn parse(iter: &mut Peekable<Chars>, position: &mut i32) -> Result<CSSToken, ParseError> {
     // Match Parsers
     // Comment
-    match comment::parse(iter, position) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = comment::parse(iter, position)? {
+        return Ok(token);
+    }
 
     // Whitespace
-    match whitespace::parse(iter, position) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = whitespace::parse(iter, position)? {
+        return Ok(token);
+    }
 
     // String Token (Double Quotes)
-    match string::parse(iter, position, '"') {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = string::parse(iter, position, '"')? {
+        return Ok(token);
+    }
 
     // NUMBER SIGN (#)
-    match hash::parse(iter, position) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = hash::parse(iter, position)? {
+        return Ok(token);
+    }
 
     // String Token (Single Quotes)
-    match string::parse(iter, position, '\'') {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = string::parse(iter, position, '\'')? {
+        return Ok(token);
+    }
 
     // Left Paren
-    match single_char::parse(iter, position, '(', CSSToken::LeftParentheses) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = single_char::parse(iter, position, '(', CSSToken::LeftParentheses)? {
+        return Ok(token);
+    }
 
     // Right Paren
-    match single_char::parse(iter, position, ')', CSSToken::RightParentheses) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = single_char::parse(iter, position, ')', CSSToken::RightParentheses)? {
+        return Ok(token);
+    }
 
     // Plus symbol
-    match plus::parse(iter, position) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = plus::parse(iter, position)? {
+        return Ok(token);
+    }
 
     // Comma Token
-    match single_char::parse(iter, position, ',', CSSToken::Comma) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = single_char::parse(iter, position, ',', CSSToken::Comma)? {
+        return Ok(token);
+    }
 
     // Minus symbol
-    match minus::parse(iter, position) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = minus::parse(iter, position)? {
+        return Ok(token);
+    }
 
     // Full Stop
-    match period::parse(iter, position) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = period::parse(iter, position)? {
+        return Ok(token);
+    }
 
     // Colon
-    match single_char::parse(iter, position, ':', CSSToken::Colon) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = single_char::parse(iter, position, ':', CSSToken::Colon)? {
+        return Ok(token);
+    }
 
     // Semicolon
-    match single_char::parse(iter, position, ';', CSSToken::Semicolon) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = single_char::parse(iter, position, ';', CSSToken::Semicolon)? {
+        return Ok(token);
+    }
 
     // Less than <
-    match lt::parse(iter, position) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = lt::parse(iter, position)? {
+        return Ok(token);
+    }
 
     // Commerical At @
-    match at::parse(iter, position) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = at::parse(iter, position)? {
+        return Ok(token);
+    }
 
     // Left Square Bracket
-    match single_char::parse(iter, position, '[', CSSToken::LeftBracket) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = single_char::parse(iter, position, '[', CSSToken::LeftBracket)? {
+        return Ok(token);
+    }
 
     // Backlash \
-    match backslash::parse(iter, position) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = backslash::parse(iter, position)? {
+        return Ok(token);
+    }
 
     // Right Square Bracket ]
-    match single_char::parse(iter, position, ']', CSSToken::RightBracket) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = single_char::parse(iter, position, ']', CSSToken::RightBracket)? {
+        return Ok(token);
+    }
 
     // Left Curly Brace
-    match single_char::parse(iter, position, '{', CSSToken::LeftBrace) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = single_char::parse(iter, position, '{', CSSToken::LeftBrace)? {
+        return Ok(token);
+    }
 
     // Right Curly Brace
-    match single_char::parse(iter, position, '}', CSSToken::RightBrace) {
-        Err(e) => return Err(e),
-        Ok(result) => match result {
-            Some(token) => return Ok(token),
-            None => (),
-        },
-    };
+    if let Some(token) = single_char::parse(iter, position, '}', CSSToken::RightBrace)? {
+        return Ok(token);
+    }
 
     // EOF
     if iter.peek().is_none() {
@@ -251,6 +171,7 @@ pub fn parse(iter: &mut Peekable<Chars>, position: &mut i32) -> Result<CSSToken,
     } else if is_name_start_code_point(*ch) {
         ident_like_token(iter, position)
     } else {
+        *position += 1;
         let ch = iter.next().unwrap();
         Ok(CSSToken::Delim(ch))
     }
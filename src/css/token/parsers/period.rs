@@ -23,6 +23,9 @@ pub fn parse(points: &mut Peekable<Chars>, position: &mut i32) -> ParseResult {
 
         // Otherwise return a delimiter
         } else {
+            *position += 1;
+            points.next();
+
             Ok(Some(CSSToken::Delim('.')))
         }
     } else {
@@ -73,9 +73,7 @@ pub fn parse(
                     let next = points.peek();
 
                     // Do nothing on EOF
-                    if next.is_some() {
-                        let ch = next.unwrap();
-
+                    if let Some(ch) = next {
                         // If the next character is a newline, consume it (escaping newlines in strings)
                         if ch.partial_cmp(&'\n') == Some(Ordering::Equal) {
                             *position += 1;
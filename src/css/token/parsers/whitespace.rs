@@ -17,7 +17,7 @@ pub fn parse(points: &mut Peekable<Chars>, position: &mut i32) -> ParseResult {
     let next = points.peek();
 
     match next {
-        Some(ch) if is_whitespace(&ch) => {
+        Some(ch) if is_whitespace(ch) => {
             consume::whitespace(points, position);
             Ok(Some(CSSToken::Whitespace))
         }
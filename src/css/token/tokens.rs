@@ -1,9 +1,10 @@
 use std::fmt::Display;
 
 // Possible CSS tokens
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone)]
 pub enum CSSToken {
-    Comment,
+    Comment(String),
     Ident(String),
     Function(String),
     AtKeyword(String),
@@ -16,6 +17,9 @@ pub enum CSSToken {
     Number(f64, NumericFlag),
     Percentage(f64),
     Dimension(f64, NumericFlag, String),
+
+    // A range of code points (U+0-7F), only made for unicode-range (see parse/consume.rs)
+    UnicodeRange(u32, u32),
     Whitespace,
     CDO,
     CDC,
@@ -44,30 +48,162 @@ pub enum NumericFlag {
     Number,
 }
 
+// Numbers are written without a leading zero (.5, -.5)
 fn format_number(number: &f64, flag: &NumericFlag) -> String {
-    match flag {
-        NumericFlag::Integer => format!("{}", *number as usize),
+    let number = match flag {
+        NumericFlag::Integer => format!("{}", *number as i64),
         NumericFlag::Number => format!("{}", number),
+    };
+
+    if let Some(fraction) = number.strip_prefix("0.") {
+        format!(".{}", fraction)
+    } else if let Some(fraction) = number.strip_prefix("-0.") {
+        format!("-.{}", fraction)
+    } else {
+        number
+    }
+}
+
+// The shortest way to write a range: U+26, U+4?? or U+0-7F
+fn format_unicode_range(start: u32, end: u32) -> String {
+    if start == end {
+        return format!("U+{:X}", start);
+    }
+
+    let range = format!("U+{:X}-{:X}", start, end);
+
+    // The most trailing hex digits that go from 0 to F
+    let wildcards = (1..=6)
+        .rev()
+        .find(|digits| {
+            let mask = (1u32 << (digits * 4)) - 1;
+            start & mask == 0 && end == start | mask
+        })
+        .unwrap_or(0);
+
+    if wildcards == 0 {
+        return range;
+    }
+
+    let prefix = match start >> (wildcards * 4) {
+        0 => String::new(),
+        prefix => format!("{:X}", prefix),
+    };
+    let wildcard = format!("U+{}{}", prefix, "?".repeat(wildcards as usize));
+
+    // At most 6 digits and question marks
+    if wildcard.len() <= 8 && wildcard.len() < range.len() {
+        wildcard
+    } else {
+        range
+    }
+}
+
+// Escapes a code point as hex, the trailing space is only needed if the next code point could continue the escape
+// (at the end, whatever token comes next might)
+fn format_hex_escape(serialized: &mut String, ch: char, next: Option<&char>) {
+    serialized.push_str(format!("\\{:x}", ch as u32).as_str());
+
+    if next.is_none_or(|next| next.is_ascii_hexdigit() || next.is_whitespace()) {
+        serialized.push(' ');
+    }
+}
+
+// CSSOM § 2.1 Serialize an identifier (or a name, when it doesn't have to start like an identifier)
+fn format_name(name: &str, identifier: bool) -> String {
+    let mut serialized = String::new();
+    let mut points = name.chars().peekable();
+    let mut index = 0;
+
+    while let Some(ch) = points.next() {
+        let digit_start = identifier
+            && ch.is_ascii_digit()
+            && (index == 0 || (index == 1 && name.starts_with('-')));
+
+        if ch == '\0' {
+            serialized.push('\u{FFFD}');
+        } else if ('\u{1}'..='\u{1F}').contains(&ch) || ch == '\u{7F}' || digit_start {
+            format_hex_escape(&mut serialized, ch, points.peek());
+        } else if identifier && index == 0 && ch == '-' && points.peek().is_none() {
+            serialized.push_str("\\-");
+        } else if ch as u32 >= 0x80 || ch == '-' || ch == '_' || ch.is_ascii_alphanumeric() {
+            serialized.push(ch);
+        } else {
+            serialized.push('\\');
+            serialized.push(ch);
+        }
+
+        index += 1;
     }
+
+    serialized
+}
+
+// CSSOM § 2.1 Serialize a string, using whichever quote needs fewer escapes
+fn format_string(string: &str) -> String {
+    let quote = if string.contains('"') && !string.contains('\'') {
+        '\''
+    } else {
+        '"'
+    };
+
+    let mut serialized = String::new();
+    serialized.push(quote);
+
+    let mut points = string.chars().peekable();
+    while let Some(ch) = points.next() {
+        if ch == quote || ch == '\\' {
+            serialized.push('\\');
+            serialized.push(ch);
+        } else if ('\u{1}'..='\u{1F}').contains(&ch) || ch == '\u{7F}' {
+            format_hex_escape(&mut serialized, ch, points.peek());
+        } else {
+            serialized.push(ch);
+        }
+    }
+
+    serialized.push(quote);
+    serialized
 }
 
 impl Display for CSSToken {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            CSSToken::Comment => write!(f, ""),
-            CSSToken::Ident(name) => write!(f, "{}", name),
-            CSSToken::Function(name) => write!(f, "{}(", name),
-            CSSToken::AtKeyword(keyword) => write!(f, "@{}", keyword),
-            CSSToken::Hash(ident, _flag) => write!(f, "#{}", ident),
-            CSSToken::String(name) => write!(f, "{}", name),
+            CSSToken::Comment(text) => write!(f, "/*{}*/", text),
+            CSSToken::Ident(name) => write!(f, "{}", format_name(name, true)),
+            CSSToken::Function(name) => write!(f, "{}(", format_name(name, true)),
+            CSSToken::AtKeyword(keyword) => write!(f, "@{}", format_name(keyword, true)),
+            CSSToken::Hash(ident, _flag) => write!(f, "#{}", format_name(ident, false)),
+            CSSToken::String(name) => write!(f, "{}", format_string(name)),
             CSSToken::BadString => write!(f, ""),
+            // § 4.3.6 Quotes, parentheses, whitespace, backslashes and control characters make a bad url, so urls
+            // with them are written as strings
+            CSSToken::URL(url)
+                if url.chars().any(|ch| {
+                    matches!(ch, '"' | '\'' | '(' | ')' | '\\')
+                        || ch.is_whitespace()
+                        || ch.is_control()
+                }) =>
+            {
+                write!(f, "url({})", format_string(url))
+            }
             CSSToken::URL(url) => write!(f, "url({})", url),
             CSSToken::BadURL => write!(f, ""),
             CSSToken::Delim(ch) => write!(f, "{}", ch),
             CSSToken::Number(value, flag) => write!(f, "{}", format_number(value, flag)),
-            CSSToken::Percentage(value) => write!(f, "{}%", value),
+            CSSToken::Percentage(value) => {
+                write!(f, "{}%", format_number(value, &NumericFlag::Number))
+            }
             CSSToken::Dimension(amount, flag, unit) => {
-                write!(f, "{}{}", format_number(amount, flag), unit)
+                write!(
+                    f,
+                    "{}{}",
+                    format_number(amount, flag),
+                    format_name(unit, true)
+                )
+            }
+            CSSToken::UnicodeRange(start, end) => {
+                write!(f, "{}", format_unicode_range(*start, *end))
             }
             CSSToken::Whitespace => write!(f, " "),
             CSSToken::CDO => write!(f, ""),
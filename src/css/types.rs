@@ -0,0 +1,186 @@
+/**
+ * Data types
+ * https://www.w3.org/TR/css-values-4/
+ *
+ * Checks whether a component value is a <length>, a <color> or another of the types values are made of. Math
+ * functions (calc(), min(), ...) are taken to be any numeric type, and var(), env() and attr() can't be checked until
+ * they're substituted in the browser.
+ */
+use super::color;
+use super::parse::tree::*;
+use super::serialize::is_math;
+use super::token::tokens::{CSSToken, NumericFlag};
+
+// § 5.1.1 Keywords every property takes
+pub const CSS_WIDE_KEYWORDS: [&str; 5] = ["initial", "inherit", "unset", "revert", "revert-layer"];
+
+// § 6 Length units relative to the font or the viewport
+const RELATIVE_LENGTHS: [&str; 42] = [
+    "em", "rem", "ex", "rex", "cap", "rcap", "ch", "rch", "ic", "ric", "lh", "rlh", "vw", "vh",
+    "vi", "vb", "vmin", "vmax", "svw", "svh", "svi", "svb", "svmin", "svmax", "lvw", "lvh", "lvi",
+    "lvb", "lvmin", "lvmax", "dvw", "dvh", "dvi", "dvb", "dvmin", "dvmax", "cqw", "cqh", "cqi",
+    "cqb", "cqmin", "cqmax",
+];
+
+// § 6.2 Absolute length units
+const ABSOLUTE_LENGTHS: [&str; 7] = ["px", "cm", "mm", "q", "in", "pt", "pc"];
+
+const ANGLES: [&str; 4] = ["deg", "grad", "rad", "turn"];
+const TIMES: [&str; 2] = ["s", "ms"];
+const FREQUENCIES: [&str; 2] = ["hz", "khz"];
+const RESOLUTIONS: [&str; 4] = ["dpi", "dpcm", "dppx", "x"];
+
+// Functions whose value is only known in the browser
+const SUBSTITUTIONS: [&str; 3] = ["var", "env", "attr"];
+
+// css-images-4 § 2 Functions making an <image>
+const IMAGES: [&str; 11] = [
+    "linear-gradient",
+    "radial-gradient",
+    "conic-gradient",
+    "repeating-linear-gradient",
+    "repeating-radial-gradient",
+    "repeating-conic-gradient",
+    "image",
+    "image-set",
+    "cross-fade",
+    "element",
+    "paint",
+];
+
+// css-transforms-2 § 13 Transform functions
+const TRANSFORMS: [&str; 21] = [
+    "matrix",
+    "matrix3d",
+    "perspective",
+    "rotate",
+    "rotate3d",
+    "rotatex",
+    "rotatey",
+    "rotatez",
+    "scale",
+    "scale3d",
+    "scalex",
+    "scaley",
+    "scalez",
+    "skew",
+    "skewx",
+    "skewy",
+    "translate",
+    "translate3d",
+    "translatex",
+    "translatey",
+    "translatez",
+];
+
+fn is_unit(unit: &str, units: &[&str]) -> bool {
+    units.iter().any(|known| unit.eq_ignore_ascii_case(known))
+}
+
+fn is_function(value: &ComponentValue, names: &[&str]) -> bool {
+    matches!(value, ComponentValue::Function(function) if names.iter().any(|name| function.name.eq_ignore_ascii_case(name)))
+}
+
+/**
+ * Whether a value uses var(), env() or attr() anywhere, so what it is can't be known before it's substituted
+ */
+pub fn is_substituted(values: &[ComponentValue]) -> bool {
+    values.iter().any(|value| match value {
+        ComponentValue::Function(function) => {
+            is_function(value, &SUBSTITUTIONS) || is_substituted(&function.value)
+        }
+        ComponentValue::Block(block) => is_substituted(&block.value),
+        ComponentValue::Token(_) => false,
+    })
+}
+
+/**
+ * Whether a value is one of the keywords every property takes
+ */
+pub fn is_css_wide(values: &[ComponentValue]) -> bool {
+    match values {
+        [ComponentValue::Token(CSSToken::Ident(keyword))] => CSS_WIDE_KEYWORDS
+            .iter()
+            .any(|wide| keyword.eq_ignore_ascii_case(wide)),
+        _ => false,
+    }
+}
+
+/**
+ * Whether a value depends on the element it's used on: font and viewport relative lengths, var() and other
+ * substitutions (properties-values-api § 2.7, computationally independent values can't)
+ */
+pub fn is_relative(values: &[ComponentValue]) -> bool {
+    values.iter().any(|value| match value {
+        ComponentValue::Token(CSSToken::Dimension(_, _, unit)) => is_unit(unit, &RELATIVE_LENGTHS),
+        ComponentValue::Function(function) => {
+            is_function(value, &SUBSTITUTIONS) || is_relative(&function.value)
+        }
+        ComponentValue::Block(block) => is_relative(&block.value),
+        _ => false,
+    })
+}
+
+fn is_length(value: &ComponentValue) -> bool {
+    match value {
+        ComponentValue::Token(CSSToken::Dimension(_, _, unit)) => {
+            is_unit(unit, &ABSOLUTE_LENGTHS) || is_unit(unit, &RELATIVE_LENGTHS)
+        }
+        // 0 is the only length that doesn't need a unit
+        ComponentValue::Token(CSSToken::Number(number, _)) => *number == 0.0,
+        _ => false,
+    }
+}
+
+fn is_dimension(value: &ComponentValue, units: &[&str]) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Dimension(_, _, unit)) if is_unit(unit, units))
+}
+
+/**
+ * Whether a component value is of a type (without the angle brackets: length, color), None when the type isn't known
+ */
+pub fn matches(name: &str, value: &ComponentValue) -> Option<bool> {
+    let math = matches!(value, ComponentValue::Function(function) if is_math(&function.name));
+
+    let numeric = |matched: bool| Some(matched || math);
+
+    match name {
+        "length" => numeric(is_length(value)),
+        "percentage" => numeric(matches!(
+            value,
+            ComponentValue::Token(CSSToken::Percentage(_))
+        )),
+        "length-percentage" => numeric(
+            is_length(value) || matches!(value, ComponentValue::Token(CSSToken::Percentage(_))),
+        ),
+        "number" => numeric(matches!(value, ComponentValue::Token(CSSToken::Number(..)))),
+        "integer" => numeric(matches!(
+            value,
+            ComponentValue::Token(CSSToken::Number(_, NumericFlag::Integer))
+        )),
+        "angle" => numeric(is_dimension(value, &ANGLES)),
+        "time" => numeric(is_dimension(value, &TIMES)),
+        "frequency" => numeric(is_dimension(value, &FREQUENCIES)),
+        "resolution" => numeric(is_dimension(value, &RESOLUTIONS)),
+        "flex" => numeric(is_dimension(value, &["fr"])),
+        "color" => Some(color::is_valid(value)),
+        "url" => Some(
+            matches!(value, ComponentValue::Token(CSSToken::URL(_)))
+                || is_function(value, &["url", "src"]),
+        ),
+        "image" => Some(matches("url", value)? || is_function(value, &IMAGES)),
+        "string" => Some(matches!(value, ComponentValue::Token(CSSToken::String(_)))),
+        "ident" => Some(matches!(value, ComponentValue::Token(CSSToken::Ident(_)))),
+        "custom-ident" => Some(match value {
+            ComponentValue::Token(CSSToken::Ident(ident)) => {
+                !ident.eq_ignore_ascii_case("default") && !is_css_wide(std::slice::from_ref(value))
+            }
+            _ => false,
+        }),
+        "dashed-ident" => Some(
+            matches!(value, ComponentValue::Token(CSSToken::Ident(ident)) if ident.starts_with("--")),
+        ),
+        "transform-function" => Some(is_function(value, &TRANSFORMS)),
+        _ => None,
+    }
+}
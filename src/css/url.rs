@@ -0,0 +1,81 @@
+/**
+ * URLs
+ *
+ * Finds the urls in a stylesheet, written as url(file.png) or url("file.png"), so they can be read or rewritten. The
+ * urls of @import rules aren't included, as they load stylesheets rather than assets (see bundle.rs).
+ */
+use super::parse::tree::*;
+use super::token::tokens::CSSToken;
+
+fn is_whitespace(value: &ComponentValue) -> bool {
+    matches!(value, ComponentValue::Token(CSSToken::Whitespace))
+}
+
+/**
+ * The url of a url() function or a bare url token, or a string (which is what @import takes)
+ */
+pub fn value(value: &ComponentValue) -> Option<String> {
+    match value {
+        ComponentValue::Token(CSSToken::String(url) | CSSToken::URL(url)) => Some(url.clone()),
+        ComponentValue::Function(function) if function.name.eq_ignore_ascii_case("url") => {
+            match function.value.iter().find(|value| !is_whitespace(value)) {
+                Some(ComponentValue::Token(CSSToken::String(url))) => Some(url.clone()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Rewrites the urls in component values, leaving the ones rewrite returns None for
+fn values(values: &mut [ComponentValue], rewrite: &mut dyn FnMut(&str) -> Option<String>) {
+    for value in values.iter_mut() {
+        match value {
+            ComponentValue::Token(CSSToken::URL(url)) => {
+                if let Some(rewritten) = rewrite(url) {
+                    *url = rewritten;
+                }
+            }
+            ComponentValue::Function(function) if function.name.eq_ignore_ascii_case("url") => {
+                let rewritten = self::value(&ComponentValue::Function(function.clone()))
+                    .and_then(|url| rewrite(&url));
+
+                if let Some(rewritten) = rewritten {
+                    function.value = vec![ComponentValue::Token(CSSToken::String(rewritten))];
+                }
+            }
+            ComponentValue::Function(function) => self::values(&mut function.value, rewrite),
+            ComponentValue::Block(block) => self::values(&mut block.value, rewrite),
+            ComponentValue::Token(_) => (),
+        }
+    }
+}
+
+fn items(items: &mut [BlockItem], rewrite: &mut dyn FnMut(&str) -> Option<String>) {
+    for item in items.iter_mut() {
+        match item {
+            BlockItem::Declaration(declaration) => values(&mut declaration.value, rewrite),
+            BlockItem::Rule(rule) => rules(std::slice::from_mut(rule), rewrite),
+        }
+    }
+}
+
+/**
+ * Calls rewrite with every url in the rules, replacing the ones it returns a new url for
+ */
+pub fn rules(rules: &mut [Rule], rewrite: &mut dyn FnMut(&str) -> Option<String>) {
+    for rule in rules.iter_mut() {
+        match rule {
+            Rule::Qualified(rule) => items(&mut rule.block, rewrite),
+            Rule::At(rule) => {
+                if !rule.name.eq_ignore_ascii_case("import") {
+                    values(&mut rule.prelude, rewrite);
+                }
+
+                if let Some(block) = &mut rule.block {
+                    items(block, rewrite);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+/**
+ * CSS inlining for HTML email
+ *
+ * `minify email page.html` writes page.email.html next to the page, with the rules of its stylesheets (linked files and
+ * <style> elements) inlined into style attributes, since that's the only styling every email client understands. What
+ * can't be inlined, like @media rules and :hover, goes back in a single <style> element in the <head>. The result is
+ * minimized with the page's configuration. See css/inline.rs for what gets inlined and how.
+ *
+ * Remote stylesheets can't be read, so their <link> elements are left in place.
+ */
+use crate::bundle;
+use crate::config::Loader;
+use crate::css;
+use crate::html::dom::{self, Element, NodeData, ROOT};
+use crate::html::serialize;
+use crate::minimize;
+use crate::page;
+use std::fs;
+use std::path::Path;
+
+/**
+ * Inlines the stylesheets of a page into its elements, and writes the result next to it
+ */
+pub fn run(file: &str, loader: &mut Loader) -> Result<(), String> {
+    let path = bundle::normalize(Path::new(file));
+    let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+    let config = loader.config(&path).map_err(|e| e.to_string())?;
+
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let mut document = dom::parse(contents)
+        .map_err(|e| format!("Error parsing file {}!\n{}", path.display(), e))?;
+
+    let sources = page::sources(&document, &directory, true);
+    let elements: Vec<_> = sources.iter().filter_map(page::Source::element).collect();
+    let stylesheet = page::load(sources, &directory, loader)?;
+
+    let kept = css::inline::document(&mut document, &stylesheet);
+
+    for element in elements {
+        document.detach(element);
+    }
+
+    if !kept.rules.is_empty() {
+        let parent = (0..document.nodes.len())
+            .find(|id| document.name(*id) == Some("head"))
+            .or_else(|| (0..document.nodes.len()).find(|id| document.name(*id) == Some("html")))
+            .unwrap_or(ROOT);
+
+        let style = document.append(
+            parent,
+            NodeData::Element(Element {
+                name: String::from("style"),
+                attributes: Vec::new(),
+            }),
+        );
+        document.append(style, NodeData::Text(kept.to_string()));
+    }
+
+    let output = serialize::document(&document, &config)
+        .map_err(|e| format!("Error minimizing {}!\n{}", path.display(), e))?;
+
+    let stem = path.file_stem().map_or(String::from("page"), |stem| {
+        stem.to_string_lossy().to_string()
+    });
+    let destination = directory.join(format!("{}.email.html", stem));
+    fs::write(&destination, &output)
+        .map_err(|e| format!("Could not write {}: {}", destination.display(), e))?;
+
+    println!(
+        "Wrote {} ({})",
+        destination.display(),
+        minimize::size(output.len())
+    );
+    Ok(())
+}
@@ -0,0 +1,279 @@
+/**
+ * A simple document tree
+ *
+ * Nodes are stored in a flat list and refer to each other by index, the document itself is always node 0. The tree
+ * builder is a simplification of § 13.2.6 Tree construction: it handles void elements, implied end tags and stray
+ * end tags, but it does not synthesize missing <html>, <head> or <body> elements or reparent misnested content, so the
+ * tree mirrors the source as closely as possible.
+ */
+use super::elements::{closes_p, is_foreign, is_void};
+use super::error::ParseError;
+use super::token::{self, Attribute, HTMLToken};
+
+pub type NodeId = usize;
+
+pub const ROOT: NodeId = 0;
+
+#[derive(Debug, Clone)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<Attribute>,
+}
+
+#[derive(Debug, Clone)]
+pub enum NodeData {
+    Document,
+    Doctype(String),
+    Element(Element),
+    Text(String),
+    Comment(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub data: NodeData,
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub nodes: Vec<Node>,
+}
+
+impl Document {
+    pub fn new() -> Document {
+        Document {
+            nodes: vec![Node {
+                data: NodeData::Document,
+                parent: None,
+                children: Vec::new(),
+            }],
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn element(&self, id: NodeId) -> Option<&Element> {
+        match &self.nodes[id].data {
+            NodeData::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+
+    // The (lowercase) tag name of a node, if it's an element
+    pub fn name(&self, id: NodeId) -> Option<&str> {
+        self.element(id).map(|element| element.name.as_str())
+    }
+
+    // The value of an attribute, matching its name in any case ("" for attributes without a value)
+    pub fn attribute(&self, id: NodeId, name: &str) -> Option<&str> {
+        self.element(id)?.attributes.iter().find_map(|attribute| {
+            if attribute.name.eq_ignore_ascii_case(name) {
+                Some(attribute.value.as_deref().unwrap_or(""))
+            } else {
+                None
+            }
+        })
+    }
+
+    // The parent of a node, if it's an element
+    pub fn parent_element(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id]
+            .parent
+            .filter(|parent| self.element(*parent).is_some())
+    }
+
+    // The children of a node that are elements
+    pub fn element_children(&self, id: NodeId) -> Vec<NodeId> {
+        self.nodes[id]
+            .children
+            .iter()
+            .copied()
+            .filter(|child| self.element(*child).is_some())
+            .collect()
+    }
+
+    pub fn append(&mut self, parent: NodeId, data: NodeData) -> NodeId {
+        let id = self.nodes.len();
+
+        self.nodes.push(Node {
+            data,
+            parent: Some(parent),
+            children: Vec::new(),
+        });
+        self.nodes[parent].children.push(id);
+
+        id
+    }
+
+    // Sets the value of an attribute, adding it when the element doesn't have it
+    pub fn set_attribute(&mut self, id: NodeId, name: &str, value: &str) {
+        if let NodeData::Element(element) = &mut self.nodes[id].data {
+            match element
+                .attributes
+                .iter_mut()
+                .find(|attribute| attribute.name.eq_ignore_ascii_case(name))
+            {
+                Some(attribute) => attribute.value = Some(value.to_string()),
+                None => element.attributes.push(Attribute {
+                    name: name.to_ascii_lowercase(),
+                    value: Some(value.to_string()),
+                }),
+            }
+        }
+    }
+
+    // Takes a node out of the tree. It stays in the list, so the IDs of other nodes don't change.
+    pub fn detach(&mut self, id: NodeId) {
+        if let Some(parent) = self.nodes[id].parent.take() {
+            self.nodes[parent].children.retain(|child| *child != id);
+        }
+    }
+
+    // Whether any ancestor (or the node itself) matches the predicate
+    pub fn within(&self, id: NodeId, predicate: impl Fn(&str) -> bool) -> bool {
+        let mut current = Some(id);
+
+        while let Some(id) = current {
+            if self.name(id).is_some_and(&predicate) {
+                return true;
+            }
+
+            current = self.nodes[id].parent;
+        }
+
+        false
+    }
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Document::new()
+    }
+}
+
+// Pops the stack up to and including the nearest element with one of the names, unless a boundary element comes first
+fn close(document: &Document, stack: &mut Vec<NodeId>, names: &[&str], boundaries: &[&str]) {
+    for index in (1..stack.len()).rev() {
+        let name = document.name(stack[index]).unwrap_or("");
+
+        if names.contains(&name) {
+            stack.truncate(index);
+            return;
+        }
+
+        if boundaries.contains(&name) {
+            return;
+        }
+    }
+}
+
+// Closes any elements implicitly ended by the start of a new element
+fn implied_end_tags(document: &Document, stack: &mut Vec<NodeId>, name: &str) {
+    let current = *stack.last().unwrap();
+
+    if closes_p(name) && document.name(current) == Some("p") {
+        stack.pop();
+    }
+
+    match name {
+        "li" => close(document, stack, &["li"], &["ul", "ol", "menu"]),
+        "dt" | "dd" => close(document, stack, &["dt", "dd"], &["dl"]),
+        "option" => close(
+            document,
+            stack,
+            &["option"],
+            &["select", "datalist", "optgroup"],
+        ),
+        "optgroup" => close(document, stack, &["option", "optgroup"], &["select"]),
+        "tr" => close(
+            document,
+            stack,
+            &["tr"],
+            &["table", "thead", "tbody", "tfoot"],
+        ),
+        "td" | "th" => close(document, stack, &["td", "th"], &["tr", "table"]),
+        "thead" | "tbody" | "tfoot" => {
+            close(document, stack, &["thead", "tbody", "tfoot"], &["table"])
+        }
+        "rt" | "rp" => close(document, stack, &["rt", "rp"], &["ruby"]),
+        _ => (),
+    }
+}
+
+/**
+ * § 13.2.6 Tree construction
+ */
+pub fn build(tokens: Vec<HTMLToken>) -> Document {
+    let mut document = Document::new();
+
+    // Stack of open elements, with the document at the bottom
+    let mut stack: Vec<NodeId> = vec![ROOT];
+
+    for token in tokens {
+        let parent = *stack.last().unwrap();
+
+        match token {
+            HTMLToken::Doctype(doctype) => {
+                document.append(parent, NodeData::Doctype(doctype));
+            }
+
+            HTMLToken::Comment(comment) => {
+                document.append(parent, NodeData::Comment(comment));
+            }
+
+            HTMLToken::Text(text) => {
+                document.append(parent, NodeData::Text(text));
+            }
+
+            HTMLToken::StartTag(tag) => {
+                let foreign = stack
+                    .iter()
+                    .any(|id| document.name(*id).is_some_and(is_foreign));
+
+                if !foreign {
+                    implied_end_tags(&document, &mut stack, &tag.name);
+                }
+
+                let parent = *stack.last().unwrap();
+                let void =
+                    is_void(&tag.name) || (tag.self_closing && (foreign || is_foreign(&tag.name)));
+
+                let id = document.append(
+                    parent,
+                    NodeData::Element(Element {
+                        name: tag.name,
+                        attributes: tag.attributes,
+                    }),
+                );
+
+                if !void {
+                    stack.push(id);
+                }
+            }
+
+            // Pop up to the matching element, stray end tags are ignored
+            HTMLToken::EndTag(name) => {
+                if let Some(index) = stack
+                    .iter()
+                    .rposition(|id| document.name(*id) == Some(name.as_str()))
+                {
+                    stack.truncate(index);
+                }
+            }
+
+            HTMLToken::EOF => break,
+        }
+    }
+
+    document
+}
+
+pub fn parse(contents: String) -> Result<Document, ParseError> {
+    let tokens = token::tokenize(contents)?;
+
+    Ok(build(tokens))
+}
@@ -0,0 +1,169 @@
+/**
+ * Element categories used when building the tree and when deciding what is safe to minify
+ * https://html.spec.whatwg.org/multipage/syntax.html#elements-2
+ */
+// § 13.1.2 Void elements (never have contents or an end tag)
+const VOID: [&str; 15] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "keygen", "link", "meta", "param",
+    "source", "track", "wbr",
+];
+
+// Raw text and escapable raw text elements, whose contents are not markup
+const RAW_TEXT: [&str; 4] = ["script", "style", "textarea", "title"];
+
+// Elements inside which whitespace is significant
+const PREFORMATTED: [&str; 4] = ["pre", "textarea", "listing", "plaintext"];
+
+// Elements that never take part in an inline formatting context, so whitespace around them is insignificant
+const BLOCK: [&str; 56] = [
+    "address",
+    "article",
+    "aside",
+    "base",
+    "blockquote",
+    "body",
+    "caption",
+    "col",
+    "colgroup",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "menu",
+    "meta",
+    "nav",
+    "ol",
+    "optgroup",
+    "option",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "ul",
+    "search",
+];
+
+// § 2.3.2 Boolean attributes defined by the standard
+const BOOLEAN_ATTRIBUTES: [&str; 26] = [
+    "allowfullscreen",
+    "async",
+    "autofocus",
+    "autoplay",
+    "checked",
+    "controls",
+    "default",
+    "defer",
+    "disabled",
+    "formnovalidate",
+    "hidden",
+    "inert",
+    "ismap",
+    "itemscope",
+    "loop",
+    "multiple",
+    "muted",
+    "nomodule",
+    "novalidate",
+    "open",
+    "playsinline",
+    "readonly",
+    "required",
+    "reversed",
+    "selected",
+    "shadowrootdelegatesfocus",
+];
+
+// Start tags which close an open <p> element (§ 13.2.6.4.7 "in body")
+const CLOSES_P: [&str; 31] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+pub fn is_void(name: &str) -> bool {
+    VOID.contains(&name)
+}
+
+pub fn is_raw_text(name: &str) -> bool {
+    RAW_TEXT.contains(&name)
+}
+
+pub fn is_preformatted(name: &str) -> bool {
+    PREFORMATTED.contains(&name)
+}
+
+pub fn is_block(name: &str) -> bool {
+    BLOCK.contains(&name)
+}
+
+pub fn is_boolean_attribute(name: &str) -> bool {
+    BOOLEAN_ATTRIBUTES.contains(&name)
+}
+
+pub fn closes_p(name: &str) -> bool {
+    CLOSES_P.contains(&name)
+}
+
+// Elements whose children are in the SVG or MathML namespace, where self-closing tags are honored
+pub fn is_foreign(name: &str) -> bool {
+    name == "svg" || name == "math"
+}
@@ -0,0 +1,13 @@
+pub mod dom;
+pub mod elements;
+pub mod error;
+pub mod serialize;
+pub mod token;
+
+use crate::config::Config;
+
+pub fn minimize(contents: String, config: &Config) -> Result<String, error::ParseError> {
+    let document = dom::parse(contents)?;
+
+    serialize::document(&document, config)
+}
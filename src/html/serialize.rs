@@ -0,0 +1,381 @@
+/**
+ * Minimal HTML serialization
+ *
+ *  - Whitespace is collapsed, and dropped entirely where it can't affect rendering (between block level elements)
+ *  - Comments are removed, except for conditional comments
+ *  - Optional end tags are omitted (§ 13.1.2.4 Optional tags)
+ *  - Attribute quotes are removed where the value allows it, and boolean attributes are shortened
+ *  - The contents of <style> elements are minimized as CSS
+ */
+use super::dom::{Document, NodeData, NodeId, ROOT};
+use super::elements::{
+    closes_p, is_block, is_boolean_attribute, is_foreign, is_preformatted, is_void,
+};
+use super::error::ParseError;
+use super::token::{is_whitespace, Attribute};
+use crate::config::Config;
+use crate::css;
+
+// A child that will actually be written, after comments are dropped and text is collapsed
+enum Item {
+    Node(NodeId),
+    Text(String),
+}
+
+struct Serializer<'a> {
+    document: &'a Document,
+    config: &'a Config,
+    output: String,
+
+    // Whether the last thing written ends a line box or a space, in which case leading whitespace is redundant
+    trailing_space: bool,
+}
+
+// Collapses each run of whitespace into a single space
+fn collapse(text: &str) -> String {
+    let mut collapsed = String::new();
+    let mut space = false;
+
+    for ch in text.chars() {
+        if is_whitespace(ch) {
+            if !space {
+                collapsed.push(' ');
+            }
+            space = true;
+        } else {
+            collapsed.push(ch);
+            space = false;
+        }
+    }
+
+    collapsed
+}
+
+// Conditional comments (<!--[if IE]> ... <![endif]-->) are markup for old versions of Internet Explorer
+fn is_conditional(comment: &str) -> bool {
+    comment.starts_with("[if") || comment.starts_with("<![endif]") || comment.ends_with("<![endif]")
+}
+
+// § 13.1.2.3 Attributes: unquoted attribute values cannot contain these characters
+fn can_unquote(value: &str) -> bool {
+    !value.is_empty()
+        && !value
+            .chars()
+            .any(|ch| is_whitespace(ch) || "\"'=<>`".contains(ch))
+}
+
+// § 13.1.2.4 A <p> end tag can be omitted when its parent ends, but only for these parents
+fn p_closed_by_parent(parent: Option<&str>) -> bool {
+    match parent {
+        None => true,
+        Some(name) => is_block(name) && name != "p",
+    }
+}
+
+impl<'a> Serializer<'a> {
+    fn is_block_item(&self, item: &Item) -> bool {
+        match item {
+            Item::Node(id) => match &self.document.node(*id).data {
+                NodeData::Element(element) => is_block(&element.name),
+                _ => true,
+            },
+            Item::Text(_) => false,
+        }
+    }
+
+    fn item_name(&self, item: Option<&Item>) -> Option<&'a str> {
+        match item {
+            Some(Item::Node(id)) => self.document.name(*id),
+            _ => None,
+        }
+    }
+
+    // Whitespace is significant inside <pre> and friends, and in raw text
+    fn collapsible(&self, id: NodeId) -> bool {
+        match self.document.name(id) {
+            Some("script") | Some("style") => false,
+            _ => !self.document.within(id, is_preformatted),
+        }
+    }
+
+    // Builds the list of children that will be written
+    fn items(&self, id: NodeId) -> Result<Vec<Item>, ParseError> {
+        let name = self.document.name(id);
+        let collapsible = self.collapsible(id);
+
+        let mut items: Vec<Item> = Vec::new();
+        let mut text: Option<String> = None;
+
+        for child in &self.document.node(id).children {
+            match &self.document.node(*child).data {
+                // Text is accumulated, since removing a comment can leave adjacent text nodes
+                NodeData::Text(string) => text.get_or_insert_with(String::new).push_str(string),
+
+                NodeData::Comment(comment) if !is_conditional(comment) => (),
+
+                _ => {
+                    if let Some(text) = text.take() {
+                        items.push(Item::Text(text));
+                    }
+                    items.push(Item::Node(*child));
+                }
+            }
+        }
+
+        if let Some(text) = text.take() {
+            items.push(Item::Text(text));
+        }
+
+        // Embedded stylesheets
+        if name == Some("style") {
+            for item in items.iter_mut() {
+                if let Item::Text(text) = item {
+                    match css::minimize(text.clone(), self.config) {
+                        Ok(minimized) => *text = minimized.trim().to_string(),
+                        Err(e) => {
+                            return Err(ParseError {
+                                error_text: "Could not parse the contents of a <style> element",
+                                at: e.at,
+                            })
+                        }
+                    }
+                }
+            }
+        }
+
+        if !collapsible {
+            return Ok(items);
+        }
+
+        // Whitespace next to the edge of a block is never rendered
+        let parent_block = id == ROOT || name.is_some_and(is_block);
+        let count = items.len();
+
+        for index in 0..count {
+            let start = if index == 0 {
+                parent_block
+            } else {
+                self.is_block_item(&items[index - 1])
+            };
+
+            let end = if index == count - 1 {
+                parent_block
+            } else {
+                self.is_block_item(&items[index + 1])
+            };
+
+            if let Item::Text(text) = &mut items[index] {
+                let mut collapsed = collapse(text);
+
+                if start && collapsed.starts_with(' ') {
+                    collapsed.remove(0);
+                }
+
+                if end && collapsed.ends_with(' ') {
+                    collapsed.pop();
+                }
+
+                *text = collapsed;
+            }
+        }
+
+        items.retain(|item| match item {
+            Item::Text(text) => !text.is_empty(),
+            _ => true,
+        });
+
+        Ok(items)
+    }
+
+    // § 13.1.2.4 Optional tags
+    fn can_omit_end_tag(&self, name: &str, next: Option<&Item>, parent: Option<&str>) -> bool {
+        if !self.config.enabled("html-optional-tags") {
+            return false;
+        }
+
+        let next_name = self.item_name(next);
+        let followed_by = |names: &[&str]| next_name.is_some_and(|n| names.contains(&n));
+
+        match name {
+            "html" | "body" => match next {
+                Some(Item::Node(id)) => {
+                    !matches!(self.document.node(*id).data, NodeData::Comment(_))
+                }
+                _ => true,
+            },
+            "head" => next.is_none() || next_name.is_some(),
+            "li" => next.is_none() || followed_by(&["li"]),
+            "dt" => followed_by(&["dt", "dd"]),
+            "dd" => next.is_none() || followed_by(&["dt", "dd"]),
+            "p" => {
+                next_name.is_some_and(closes_p) || (next.is_none() && p_closed_by_parent(parent))
+            }
+            "rt" | "rp" => next.is_none() || followed_by(&["rt", "rp"]),
+            "optgroup" => next.is_none() || followed_by(&["optgroup"]),
+            "option" => next.is_none() || followed_by(&["option", "optgroup"]),
+            "thead" => followed_by(&["tbody", "tfoot"]),
+            "tbody" => next.is_none() || followed_by(&["tbody", "tfoot"]),
+            "tfoot" => next.is_none(),
+            "tr" => next.is_none() || followed_by(&["tr"]),
+            "td" | "th" => next.is_none() || followed_by(&["td", "th"]),
+            _ => false,
+        }
+    }
+
+    // Writes an attribute, returns whether the value was left unquoted
+    fn attribute(&mut self, attribute: &Attribute, foreign: bool) -> bool {
+        self.output.push(' ');
+        self.output.push_str(&attribute.name);
+
+        let shorten = self.config.enabled("html-attributes");
+
+        let value = match &attribute.value {
+            Some(value) if !value.is_empty() || !shorten => value,
+            _ => return false,
+        };
+
+        // hidden="until-found" is the one boolean attribute with a meaningful value
+        if shorten
+            && !foreign
+            && is_boolean_attribute(&attribute.name)
+            && !(attribute.name == "hidden" && value.eq_ignore_ascii_case("until-found"))
+        {
+            return false;
+        }
+
+        self.output.push('=');
+
+        if shorten && can_unquote(value) {
+            self.output.push_str(value);
+            return true;
+        }
+
+        // Use whichever quote doesn't appear in the value
+        if value.contains('"') && !value.contains('\'') {
+            self.output.push('\'');
+            self.output.push_str(value);
+            self.output.push('\'');
+        } else {
+            self.output.push('"');
+            self.output.push_str(&value.replace('"', "&quot;"));
+            self.output.push('"');
+        }
+
+        false
+    }
+
+    fn node(&mut self, id: NodeId, next: Option<&Item>, parent: NodeId) -> Result<(), ParseError> {
+        match &self.document.node(id).data {
+            NodeData::Document => self.children(id)?,
+
+            NodeData::Doctype(doctype) => {
+                self.output.push_str("<!doctype ");
+                if doctype.eq_ignore_ascii_case("html") {
+                    self.output.push_str("html");
+                } else {
+                    self.output.push_str(doctype);
+                }
+                self.output.push('>');
+                self.trailing_space = true;
+            }
+
+            NodeData::Comment(comment) => {
+                self.output.push_str("<!--");
+                self.output.push_str(comment);
+                self.output.push_str("-->");
+                self.trailing_space = true;
+            }
+
+            NodeData::Text(text) => self.output.push_str(text),
+
+            NodeData::Element(element) => {
+                let name = element.name.as_str();
+                let block = is_block(name);
+                let foreign = self.document.within(parent, is_foreign) || is_foreign(name);
+
+                if block {
+                    self.trailing_space = true;
+                }
+
+                self.output.push('<');
+                self.output.push_str(name);
+
+                let mut unquoted = false;
+                for attribute in &element.attributes {
+                    unquoted = self.attribute(attribute, foreign);
+                }
+
+                let children = &self.document.node(id).children;
+
+                // Childless foreign elements use the self-closing syntax
+                if foreign && children.is_empty() {
+                    self.output.push_str(if unquoted { " />" } else { "/>" });
+                    self.trailing_space = block;
+                    return Ok(());
+                }
+
+                self.output.push('>');
+
+                if is_void(name) {
+                    self.trailing_space = block || name == "br";
+                    return Ok(());
+                }
+
+                self.children(id)?;
+
+                if !self.can_omit_end_tag(name, next, self.document.name(parent)) {
+                    self.output.push_str("</");
+                    self.output.push_str(name);
+                    self.output.push('>');
+                }
+
+                if block {
+                    self.trailing_space = true;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn children(&mut self, id: NodeId) -> Result<(), ParseError> {
+        let items = self.items(id)?;
+        let collapsible = self.collapsible(id);
+
+        for (index, item) in items.iter().enumerate() {
+            match item {
+                Item::Text(text) => {
+                    let mut text = text.as_str();
+
+                    // Avoid doubling up spaces across inline element boundaries
+                    if collapsible && self.trailing_space && text.starts_with(' ') {
+                        text = &text[1..];
+                    }
+
+                    if let Some(last) = text.chars().last() {
+                        self.trailing_space = is_whitespace(last);
+                    }
+
+                    self.output.push_str(text);
+                }
+
+                Item::Node(child) => self.node(*child, items.get(index + 1), id)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn document(document: &Document, config: &Config) -> Result<String, ParseError> {
+    let mut serializer = Serializer {
+        document,
+        config,
+        output: String::new(),
+        trailing_space: true,
+    };
+
+    serializer.node(ROOT, None, ROOT)?;
+
+    Ok(serializer.output)
+}
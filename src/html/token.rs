@@ -0,0 +1,366 @@
+/**
+ * HTML Tokenizer
+ * https://html.spec.whatwg.org/multipage/parsing.html#tokenization
+ *
+ * This is not a complete implementation of the tokenizer state machine. Character references are left untouched
+ * (the minifier never needs to decode them) and the various script data escape states are collapsed into a single
+ * search for the matching end tag.
+ */
+use super::elements::is_raw_text;
+use super::error::ParseError;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone)]
+pub struct Attribute {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub name: String,
+    pub attributes: Vec<Attribute>,
+    pub self_closing: bool,
+}
+
+// Possible HTML tokens
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone)]
+pub enum HTMLToken {
+    Doctype(String),
+    StartTag(Tag),
+    EndTag(String),
+    Comment(String),
+    Text(String),
+    EOF,
+}
+
+// Looks ahead for a string (ASCII case-insensitive), consumes nothing
+pub fn lookahead(points: &mut Peekable<Chars>, string: &str) -> bool {
+    let mut cmp = points.clone();
+
+    for expected in string.chars() {
+        match cmp.next() {
+            Some(ch) if ch.eq_ignore_ascii_case(&expected) => (),
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+// Consumes a fixed number of code points
+fn skip(points: &mut Peekable<Chars>, position: &mut i32, count: usize) {
+    for _ in 0..count {
+        *position += 1;
+        points.next();
+    }
+}
+
+// U+0009 CHARACTER TABULATION, U+000A LINE FEED, U+000C FORM FEED, U+000D CARRIAGE RETURN, U+0020 SPACE
+pub fn is_whitespace(ch: char) -> bool {
+    ch == '\t' || ch == '\n' || ch == '\x0C' || ch == '\r' || ch == ' '
+}
+
+// "Consume as much whitespace as possible"
+fn whitespace(points: &mut Peekable<Chars>, position: &mut i32) {
+    while let Some(ch) = points.peek() {
+        if !is_whitespace(*ch) {
+            break;
+        }
+
+        *position += 1;
+        points.next();
+    }
+}
+
+// Consumes everything up to (and including) the terminator, returning what came before it
+fn until(
+    points: &mut Peekable<Chars>,
+    position: &mut i32,
+    terminator: &str,
+) -> Result<String, ParseError> {
+    let mut string = String::new();
+
+    loop {
+        if lookahead(points, terminator) {
+            skip(points, position, terminator.chars().count());
+            return Ok(string);
+        }
+
+        *position += 1;
+        match points.next() {
+            Some(ch) => string.push(ch),
+            None => {
+                return Err(ParseError {
+                    error_text: "Unexpected End Of File (EOF)",
+                    at: *position,
+                })
+            }
+        }
+    }
+}
+
+// § 13.2.5.32 Attribute value (double-quoted / single-quoted / unquoted) state
+fn attribute_value(points: &mut Peekable<Chars>, position: &mut i32) -> Result<String, ParseError> {
+    match points.peek() {
+        Some('"') => {
+            skip(points, position, 1);
+            until(points, position, "\"")
+        }
+        Some('\'') => {
+            skip(points, position, 1);
+            until(points, position, "'")
+        }
+        _ => {
+            let mut value = String::new();
+
+            while let Some(ch) = points.peek() {
+                if is_whitespace(*ch) || *ch == '>' {
+                    break;
+                }
+
+                *position += 1;
+                value.push(points.next().unwrap());
+            }
+
+            Ok(value)
+        }
+    }
+}
+
+// Consumes a tag name, lowercased
+fn tag_name(points: &mut Peekable<Chars>, position: &mut i32) -> String {
+    let mut name = String::new();
+
+    while let Some(ch) = points.peek() {
+        if is_whitespace(*ch) || *ch == '/' || *ch == '>' {
+            break;
+        }
+
+        *position += 1;
+        name.push(points.next().unwrap().to_ascii_lowercase());
+    }
+
+    name
+}
+
+// § 13.2.5.8 Tag name state, through to the end of the tag
+fn start_tag(points: &mut Peekable<Chars>, position: &mut i32) -> Result<Tag, ParseError> {
+    let name = tag_name(points, position);
+    let mut attributes: Vec<Attribute> = Vec::new();
+    let mut self_closing = false;
+
+    loop {
+        whitespace(points, position);
+
+        match points.peek() {
+            None => {
+                return Err(ParseError {
+                    error_text: "Unexpected End Of File (EOF) in tag",
+                    at: *position,
+                })
+            }
+
+            Some('>') => {
+                skip(points, position, 1);
+                break;
+            }
+
+            Some('/') => {
+                skip(points, position, 1);
+
+                if points.peek() == Some(&'>') {
+                    skip(points, position, 1);
+                    self_closing = true;
+                    break;
+                }
+            }
+
+            // § 13.2.5.33 Attribute name state
+            Some(_) => {
+                let mut attribute = String::new();
+
+                // An = at the start of the name is part of it
+                if points.peek() == Some(&'=') {
+                    *position += 1;
+                    attribute.push(points.next().unwrap());
+                }
+
+                while let Some(ch) = points.peek() {
+                    if is_whitespace(*ch) || *ch == '/' || *ch == '>' || *ch == '=' {
+                        break;
+                    }
+
+                    *position += 1;
+                    attribute.push(points.next().unwrap().to_ascii_lowercase());
+                }
+
+                whitespace(points, position);
+
+                let value = if points.peek() == Some(&'=') {
+                    skip(points, position, 1);
+                    whitespace(points, position);
+                    Some(attribute_value(points, position)?)
+                } else {
+                    None
+                };
+
+                // Duplicate attributes are dropped, the first one wins
+                if !attributes.iter().any(|a| a.name == attribute) {
+                    attributes.push(Attribute {
+                        name: attribute,
+                        value,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(Tag {
+        name,
+        attributes,
+        self_closing,
+    })
+}
+
+// Consumes the contents of a raw text or RCDATA element up to its end tag
+fn raw_text(points: &mut Peekable<Chars>, position: &mut i32, name: &str) -> String {
+    let end = format!("</{}", name);
+    let mut text = String::new();
+
+    loop {
+        if lookahead(points, end.as_str()) {
+            // The end tag has to actually end here (</scripts> is not </script>)
+            let mut after = points.clone();
+            after.nth(end.chars().count() - 1);
+
+            match after.peek() {
+                Some(ch) if is_whitespace(*ch) || *ch == '/' || *ch == '>' => return text,
+                None => return text,
+                _ => (),
+            }
+        }
+
+        *position += 1;
+        match points.next() {
+            Some(ch) => text.push(ch),
+            None => return text,
+        }
+    }
+}
+
+/**
+ * Consumes a single token from the stream
+ */
+pub fn parse(points: &mut Peekable<Chars>, position: &mut i32) -> Result<HTMLToken, ParseError> {
+    let next = match points.peek() {
+        Some(ch) => *ch,
+        None => return Ok(HTMLToken::EOF),
+    };
+
+    if next == '<' {
+        // Comments
+        if lookahead(points, "<!--") {
+            skip(points, position, 4);
+            return Ok(HTMLToken::Comment(until(points, position, "-->")?));
+        }
+
+        // Doctype
+        if lookahead(points, "<!doctype") {
+            skip(points, position, 9);
+            let doctype = until(points, position, ">")?;
+            return Ok(HTMLToken::Doctype(doctype.trim().to_string()));
+        }
+
+        // CDATA sections are kept verbatim
+        if lookahead(points, "<![CDATA[") {
+            skip(points, position, 9);
+            let data = until(points, position, "]]>")?;
+            return Ok(HTMLToken::Text(format!("<![CDATA[{}]]>", data)));
+        }
+
+        // Bogus comments (<!foo> and <?foo>)
+        if lookahead(points, "<!") || lookahead(points, "<?") {
+            skip(points, position, 2);
+            return Ok(HTMLToken::Comment(until(points, position, ">")?));
+        }
+
+        let mut lookahead = points.clone();
+        lookahead.next();
+        let second = lookahead.next();
+        let third = lookahead.next();
+
+        // End tag
+        if second == Some('/') && third.is_some_and(|ch| ch.is_ascii_alphabetic()) {
+            skip(points, position, 2);
+            let name = tag_name(points, position);
+            until(points, position, ">")?;
+
+            return Ok(HTMLToken::EndTag(name));
+        }
+
+        // Start tag
+        if second.is_some_and(|ch| ch.is_ascii_alphabetic()) {
+            skip(points, position, 1);
+            return Ok(HTMLToken::StartTag(start_tag(points, position)?));
+        }
+    }
+
+    // Everything else is text, up to the next thing that looks like markup
+    let mut text = String::new();
+
+    *position += 1;
+    text.push(points.next().unwrap());
+
+    while let Some(ch) = points.peek() {
+        if *ch == '<' {
+            break;
+        }
+
+        *position += 1;
+        text.push(points.next().unwrap());
+    }
+
+    Ok(HTMLToken::Text(text))
+}
+
+/**
+ * § 13.2.5 Tokenization
+ */
+pub fn tokenize(contents: String) -> Result<Vec<HTMLToken>, ParseError> {
+    // § 13.2.3.5 Preprocessing the input stream
+    let contents = contents.replace("\r\n", "\n").replace('\r', "\n");
+
+    let mut tokens: Vec<HTMLToken> = Vec::new();
+    let mut iter = contents.chars().peekable();
+
+    let mut position = 0;
+
+    loop {
+        let token = parse(&mut iter, &mut position)?;
+
+        match token {
+            HTMLToken::EOF => break,
+
+            // The contents of raw text elements are consumed as a single text token
+            HTMLToken::StartTag(ref tag) if is_raw_text(&tag.name) && !tag.self_closing => {
+                let name = tag.name.clone();
+                tokens.push(token);
+
+                let text = raw_text(&mut iter, &mut position, &name);
+                if !text.is_empty() {
+                    tokens.push(HTMLToken::Text(text));
+                }
+            }
+
+            _ => tokens.push(token),
+        }
+    }
+
+    // Add the EOF token from the end
+    tokens.push(HTMLToken::EOF);
+
+    Ok(tokens)
+}
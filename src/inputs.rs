@@ -0,0 +1,399 @@
+/**
+ * Input expansion
+ *
+ * Arguments can be files, directories (walked recursively) or glob patterns (*, ?, [abc] and ** for any number of
+ * directories), quoted so the shell leaves them alone. Files found by walking or matching are only kept if they can be
+ * minimized, and outputs from earlier runs (*.min.css, *.min.html, *.min.svg) are skipped.
+ *
+ * Without an output directory every file is minimized next to itself (main.css => main.min.css). With one, the
+ * directory tree below each argument is mirrored into it, keeping the file names. Both the directory and the suffix
+ * come from the configuration, which also lists paths to exclude when walking or matching. Files whose output would
+ * overwrite an input, or the output of another file, are reported and left out.
+ */
+use crate::bundle::normalize;
+use crate::config::{Config, Loader};
+use crate::minimize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// A file to minimize, where to write it and how
+pub struct Job {
+    pub input: String,
+    pub output: String,
+    pub config: Arc<Config>,
+}
+
+// Add a suffix to a file (main.css => main.min.css)
+fn min_file(file: &str, suffix: &str) -> String {
+    if suffix.is_empty() {
+        return file.to_string();
+    }
+
+    let mut split: Vec<&str> = file.split('.').collect();
+    split.insert(split.len() - 1, suffix);
+
+    split.join(".")
+}
+
+fn is_glob(component: &str) -> bool {
+    component.contains(['*', '?', '['])
+}
+
+// Outputs of earlier runs
+fn is_minimized(path: &Path) -> bool {
+    path.file_stem()
+        .is_some_and(|stem| stem.to_string_lossy().ends_with(".min"))
+}
+
+// Whether a file found by walking or matching should be minimized
+fn is_input(path: &Path) -> bool {
+    path.is_file() && minimize::extension(&path.to_string_lossy()).is_some() && !is_minimized(path)
+}
+
+// Matches a single path component (or any name) against a pattern
+pub fn matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| matches(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && matches(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let end = match pattern.iter().position(|ch| *ch == ']') {
+                Some(end) if !name.is_empty() => end,
+                _ => return false,
+            };
+
+            let (negated, set) = match pattern[1] {
+                '!' | '^' => (true, &pattern[2..end]),
+                _ => (false, &pattern[1..end]),
+            };
+
+            // Ranges like a-z
+            let mut found = false;
+            let mut index = 0;
+            while index < set.len() {
+                if index + 2 < set.len() && set[index + 1] == '-' {
+                    found = found || (set[index]..=set[index + 2]).contains(&name[0]);
+                    index += 3;
+                } else {
+                    found = found || set[index] == name[0];
+                    index += 1;
+                }
+            }
+
+            found != negated && matches(&pattern[end + 1..], &name[1..])
+        }
+        Some(ch) => name.first() == Some(ch) && matches(&pattern[1..], &name[1..]),
+    }
+}
+
+// Matches path components against a pattern's components, where ** matches any number of directories
+fn matches_path(pattern: &[Vec<char>], path: &[Vec<char>]) -> bool {
+    match pattern.first() {
+        None => path.is_empty(),
+        Some(first) if first[..] == ['*', '*'] => {
+            (0..=path.len()).any(|skip| matches_path(&pattern[1..], &path[skip..]))
+        }
+        Some(first) => {
+            !path.is_empty() && matches(first, &path[0]) && matches_path(&pattern[1..], &path[1..])
+        }
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    normalize(&env::current_dir().unwrap_or_default().join(path))
+}
+
+// Whether the configuration excludes a file. Patterns with a / are matched from the configuration's directory, others
+// against the name of the file or any directory it's in. Matching a directory excludes everything inside it.
+fn is_excluded(input: &Path, config: &Config) -> bool {
+    let input = absolute(input);
+    let relative = match input.strip_prefix(absolute(&config.root)) {
+        Ok(relative) => relative,
+        Err(_) => return false,
+    };
+
+    let components: Vec<Vec<char>> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().chars().collect())
+        .collect();
+
+    config.exclude.iter().any(|pattern| {
+        let pattern = pattern.trim_end_matches('/');
+
+        if pattern.contains('/') {
+            let pattern: Vec<Vec<char>> = pattern
+                .trim_start_matches('/')
+                .split('/')
+                .map(|component| component.chars().collect())
+                .collect();
+
+            (1..=components.len()).any(|length| matches_path(&pattern, &components[..length]))
+        } else {
+            let pattern: Vec<char> = pattern.chars().collect();
+            components
+                .iter()
+                .any(|component| matches(&pattern, component))
+        }
+    })
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+// Every directory below (and including) a directory, except hidden ones (like .git)
+fn directories(directory: &Path, found: &mut Vec<PathBuf>) {
+    found.push(directory.to_path_buf());
+
+    let mut entries: Vec<PathBuf> = match fs::read_dir(directory) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect(),
+        Err(_) => return,
+    };
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() && !is_hidden(&entry) {
+            directories(&entry, found);
+        }
+    }
+}
+
+// Every file below a directory that's accepted
+fn walk(directory: &Path, accept: fn(&Path) -> bool, found: &mut Vec<PathBuf>) {
+    let mut all: Vec<PathBuf> = Vec::new();
+    directories(directory, &mut all);
+
+    for directory in all {
+        let mut entries: Vec<PathBuf> = match fs::read_dir(&directory) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .collect(),
+            Err(_) => continue,
+        };
+        entries.sort();
+
+        found.extend(entries.into_iter().filter(|entry| accept(entry)));
+    }
+}
+
+// Expands a glob pattern into the accepted files, returning the directory the matches are relative to and the matches
+fn glob(pattern: &str, accept: fn(&Path) -> bool) -> (PathBuf, Vec<PathBuf>) {
+    let components: Vec<&str> = pattern.split('/').collect();
+    let literal = components.iter().take_while(|c| !is_glob(c)).count();
+
+    let root: PathBuf = if pattern.starts_with('/') {
+        Path::new("/").join(components[..literal].join("/"))
+    } else {
+        components[..literal].iter().collect()
+    };
+
+    let mut paths: Vec<PathBuf> = vec![root.clone()];
+
+    for component in &components[literal..] {
+        let mut next: Vec<PathBuf> = Vec::new();
+
+        for path in &paths {
+            let directory = if path.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                path.as_path()
+            };
+
+            if *component == "**" {
+                directories(directory, &mut next);
+                continue;
+            }
+
+            let pattern: Vec<char> = component.chars().collect();
+            let mut entries: Vec<PathBuf> = match fs::read_dir(directory) {
+                Ok(entries) => entries
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .collect(),
+                Err(_) => continue,
+            };
+            entries.sort();
+
+            for entry in entries {
+                let file_name = match entry.file_name() {
+                    Some(file_name) => file_name,
+                    None => continue,
+                };
+                let name: Vec<char> = file_name.to_string_lossy().chars().collect();
+
+                // Hidden files are only matched explicitly
+                let hidden = name.first() == Some(&'.') && pattern.first() != Some(&'.');
+
+                if !hidden && matches(&pattern, &name) {
+                    next.push(path.join(file_name));
+                }
+            }
+        }
+
+        paths = next;
+    }
+
+    let mut inputs: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if accept(&path) && !inputs.contains(&path) {
+            inputs.push(path);
+        }
+    }
+
+    (root, inputs)
+}
+
+fn job(input: &Path, root: &Path, config: Arc<Config>) -> Job {
+    let input = input.to_string_lossy().to_string();
+
+    let output = match &config.out_dir {
+        Some(out_dir) => {
+            let relative = Path::new(&input)
+                .strip_prefix(root)
+                .unwrap_or(Path::new(&input));
+            let output = out_dir.join(relative).to_string_lossy().to_string();
+
+            min_file(&output, config.suffix())
+        }
+        None => min_file(&input, config.suffix()),
+    };
+
+    Job {
+        input,
+        output,
+        config,
+    }
+}
+
+fn is_content(path: &Path) -> bool {
+    path.is_file() && !is_minimized(path)
+}
+
+/**
+ * Expands a file, directory or glob pattern into every file it matches, not just the ones that can be minimized
+ */
+pub fn files(pattern: &str) -> Vec<PathBuf> {
+    let path = Path::new(pattern);
+
+    if path.is_dir() {
+        let mut files: Vec<PathBuf> = Vec::new();
+        walk(path, is_content, &mut files);
+        files
+    } else if is_glob(pattern) {
+        glob(pattern, is_content).1
+    } else if path.is_file() {
+        vec![path.to_path_buf()]
+    } else {
+        Vec::new()
+    }
+}
+
+/**
+ * Expands the arguments into the files to minimize, along with any errors for arguments that didn't match anything
+ */
+pub fn expand(args: &[String], loader: &mut Loader) -> (Vec<Job>, Vec<String>) {
+    let mut jobs: Vec<Job> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+
+    for arg in args {
+        let path = Path::new(arg);
+
+        let (root, inputs) = if path.is_dir() {
+            let mut inputs: Vec<PathBuf> = Vec::new();
+            walk(path, is_input, &mut inputs);
+
+            (path.to_path_buf(), inputs)
+        } else if is_glob(arg) {
+            glob(arg, is_input)
+        } else if !path.exists() {
+            errors.push(format!("Could not find \"{}\"", arg));
+            continue;
+        } else if minimize::extension(arg).is_none() {
+            errors.push(format!(
+                "Invalid file extension in \"{}\". Make sure passed files has the .css, .html or .svg extension.",
+                arg
+            ));
+            continue;
+        } else {
+            let root = path.parent().unwrap_or(Path::new("")).to_path_buf();
+            (root, vec![path.to_path_buf()])
+        };
+
+        if inputs.is_empty() && !path.is_dir() {
+            errors.push(format!("No files match \"{}\"", arg));
+        }
+
+        // Files named explicitly are always minimized
+        let explicit = path.is_file();
+
+        for input in inputs {
+            let config = match loader.config(&input) {
+                Ok(config) => config,
+                Err(e) => {
+                    let error = e.to_string();
+                    if !errors.contains(&error) {
+                        errors.push(error);
+                    }
+                    continue;
+                }
+            };
+
+            if !explicit {
+                // Don't pick up the outputs of an earlier run
+                let suffix = format!(".{}", config.suffix());
+                let output = input
+                    .file_stem()
+                    .is_some_and(|stem| stem.to_string_lossy().ends_with(&suffix));
+                let inside_out_dir = config
+                    .out_dir
+                    .as_ref()
+                    .is_some_and(|out_dir| absolute(&input).starts_with(absolute(out_dir)));
+
+                if (output && suffix != ".") || inside_out_dir || is_excluded(&input, &config) {
+                    continue;
+                }
+            }
+
+            let job = job(&input, &root, config);
+
+            if !jobs.iter().any(|other| other.input == job.input) {
+                jobs.push(job);
+            }
+        }
+    }
+
+    // An output can't overwrite an input (like an output directory that's the input's directory, without a suffix), or
+    // another output
+    let inputs: Vec<PathBuf> = jobs
+        .iter()
+        .map(|job| absolute(Path::new(&job.input)))
+        .collect();
+    let mut checked: Vec<Job> = Vec::new();
+
+    for job in jobs {
+        let output = absolute(Path::new(&job.output));
+
+        if inputs.contains(&output) {
+            errors.push(format!(
+                "Minimizing \"{}\" would overwrite \"{}\". Set output.dir or output.suffix so outputs don't replace inputs.",
+                job.input, job.output
+            ));
+        } else if let Some(other) = checked
+            .iter()
+            .find(|other| absolute(Path::new(&other.output)) == output)
+        {
+            errors.push(format!(
+                "\"{}\" and \"{}\" would both be minimized to \"{}\"",
+                other.input, job.input, job.output
+            ));
+        } else {
+            checked.push(job);
+        }
+    }
+
+    (checked, errors)
+}
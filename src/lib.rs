@@ -0,0 +1,25 @@
+/**
+ * minify_web
+ *
+ * Everything the minify command is made of, so it can be used from other code (and tests) too. The command line in
+ * main.rs is a thin layer over minimize::all() and the run() function of each command (critical, email and style).
+ *
+ * Parsing lives in css::parse, html::dom and svg, and css::select matches selectors against a parsed HTML document.
+ * css::cascade works out the computed style of its elements.
+ */
+pub mod assets;
+pub mod bundle;
+pub mod config;
+pub mod critical;
+pub mod css;
+pub mod email;
+pub mod html;
+pub mod inputs;
+pub mod minimize;
+pub mod modules;
+pub mod page;
+pub mod purge;
+pub mod regex;
+pub mod style;
+pub mod svg;
+pub mod watch;
@@ -1,8 +1,35 @@
 use std::env;
+use std::path::{Path, PathBuf};
 use std::process;
 
-mod css;
-mod minimize;
+use minify_web::config::{self, Loader, Value};
+use minify_web::css::media::Viewport;
+use minify_web::{critical, email, inputs, minimize, style, watch};
+
+// Options taking a value, as --option value or --option=value, and the setting they change
+const VALUE_OPTIONS: [(&str, &str); 10] = [
+    ("--out-dir", "output.dir"),
+    ("--purge", "purge.content"),
+    ("--comments", "comments"),
+    ("--targets", "targets"),
+    ("--cdn", "assets.cdn"),
+    ("--inline-limit", "assets.inline-limit"),
+    ("--config", ""),
+    ("--set", ""),
+    ("--viewport", ""),
+    ("--css", ""),
+];
+
+// Prints the configuration that applies to a file or directory
+fn print_config(loader: &mut Loader, path: &str) {
+    match loader.config(Path::new(path)) {
+        Ok(config) => print!("{}", config),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -10,47 +37,193 @@ fn main() {
     // Display the help
     if args.len() < 2 {
         println!("minify");
-        println!("Quickly minimize CSS files\n");
+        println!("Quickly minimize CSS, HTML and SVG files\n");
+
+        println!("Specify one or more CSS, HTML or SVG files, directories or (quoted) glob patterns");
 
-        println!("Specify one or more CSS files");
+        println!("Options:");
+        println!(" --bundle           Inline local @import rules into the minimized CSS");
+        println!(" --mangle           Rename classes and IDs in CSS files, writing what they became to a .json file next to each output");
+        println!(" --modules          Make classes and IDs local to each CSS file (CSS Modules), writing the exported names to a .json file next to each output");
+        println!(" --assets           Copy the files CSS refers to with url() next to the output, with a hash of their contents in the name");
+        println!(" --watch            Keep minimizing files as they (or the files they depend on) change");
+        println!(" --out-dir <dir>    Write the minimized files into a directory, mirroring the input directories");
+        println!(" --purge <files>    Remove CSS rules that can't match anything in these HTML or template files (comma separated, globs allowed)");
+        println!(" --comments <kind>  Which comments to keep: none or license (/*! ... */)");
+        println!(" --targets <list>   The browsers to target, like \"chrome 100, safari 15\"");
+        println!(" --cdn <url>        With --assets, point urls at a CDN instead, like https://cdn.example.com/static");
+        println!(" --inline-limit <n> Inline files up to this many bytes that CSS refers to with url() as data: URIs");
+        println!(" --config <file>    Use this configuration file instead of looking for a minify.toml");
+        println!(" --set <key=value>  Change any setting from the configuration file, like passes.svg-groups=false\n");
+
+        println!("Commands:");
+        println!(" minify config --print [path]  Show the configuration that applies to a file or directory");
+        println!(" minify critical <page.html>   Split the page's stylesheets into page.critical.css, the rules it needs to render, and page.rest.css");
+        println!("   --viewport <size>           The screen size to render for, 1300x900 by default");
+        println!("   --css <files>               The stylesheets to split (comma separated), instead of the ones the page links to");
+        println!(" minify email <page.html>      Inline the page's CSS into style attributes for HTML email, writing page.email.html");
+        println!(" minify style <page.html> <selector>  Print the computed style of the elements a selector matches, with the same --viewport and --css\n");
 
         println!("Example:");
         println!(" minify main.css");
+        println!(" minify --bundle main.css");
+        println!(" minify --watch main.css index.html");
+        println!(" minify index.html");
+        println!(" minify icon.svg");
+        println!(" minify src/ --out-dir dist");
+        println!(" minify \"src/**/*.css\"");
 
         process::exit(0);
     }
 
-    for file in &args[1..] {
-        // Split the file into all it's parts
-        let mut split: Vec<&str> = file.split(".").collect();
+    // Options start with --, everything else is a file, directory or pattern
+    let mut watch = false;
+    let mut print = false;
+    let mut file: Option<PathBuf> = None;
+    let mut overrides: Vec<(String, Value)> = Vec::new();
+    let mut inputs: Vec<String> = Vec::new();
+    let mut viewport = Viewport::DEFAULT;
+    let mut stylesheets: Vec<String> = Vec::new();
 
-        // Get the extension
-        let ext = match split.pop() {
-            Some(x) => x,
-            None => {
-                eprintln!("Could not parse filename {}", file);
-                process::exit(1);
+    let command = match args[1].as_str() {
+        "config" | "critical" | "email" | "style" => Some(args[1].as_str()),
+        _ => None,
+    };
+    let mut iter = args[if command.is_some() { 2 } else { 1 }..].iter();
+
+    while let Some(arg) = iter.next() {
+        let option = VALUE_OPTIONS
+            .iter()
+            .find(|(name, _)| arg == name || arg.starts_with(&format!("{}=", name)));
+
+        if let Some((name, key)) = option {
+            let value = match arg.strip_prefix(&format!("{}=", name)) {
+                Some(value) => value.to_string(),
+                None => match iter.next() {
+                    Some(value) => value.clone(),
+                    None => {
+                        eprintln!("{} needs a value", name);
+                        process::exit(1);
+                    }
+                },
+            };
+
+            match *name {
+                "--config" => file = Some(PathBuf::from(value)),
+                "--set" => match value.split_once('=') {
+                    Some((key, value)) => {
+                        overrides.push((key.trim().to_string(), config::value(value.trim())))
+                    }
+                    None => {
+                        eprintln!("--set needs a key=value pair");
+                        process::exit(1);
+                    }
+                },
+                "--viewport" | "--css" if !matches!(command, Some("critical" | "style")) => {
+                    eprintln!("{} only works with minify critical and minify style", name);
+                    process::exit(1);
+                }
+                "--viewport" => match Viewport::parse(&value) {
+                    Some(size) => viewport = size,
+                    None => {
+                        eprintln!("--viewport needs a size like 1300x900");
+                        process::exit(1);
+                    }
+                },
+                "--css" => stylesheets.extend(
+                    value
+                        .split(',')
+                        .map(|file| file.trim().to_string())
+                        .filter(|file| !file.is_empty()),
+                ),
+                _ => overrides.push((key.to_string(), Value::String(value))),
             }
-        };
-
-        // Handle files by ext
-        let result = match ext {
-            "css" => minimize::css(file),
-            _ => {
-                eprintln!(
-                    "Invalid file extension in \"{}\". Make sure passed files has the .css extension.",
-                    file
-                );
+
+            continue;
+        }
+
+        match arg.as_str() {
+            "--bundle" => overrides.push((String::from("bundle"), Value::Boolean(true))),
+            "--mangle" => overrides.push((String::from("mangle.enabled"), Value::Boolean(true))),
+            "--modules" => overrides.push((String::from("modules.enabled"), Value::Boolean(true))),
+            "--assets" => overrides.push((String::from("assets.enabled"), Value::Boolean(true))),
+            "--watch" => watch = true,
+            "--print" if command == Some("config") => print = true,
+            _ if arg.starts_with("--") => {
+                eprintln!("Unknown option \"{}\"", arg);
                 process::exit(1);
             }
-        };
+            _ => inputs.push(arg.clone()),
+        }
+    }
 
-        match result {
-            Ok(_) => {}
-            Err(e) => {
-                eprint!("{}", e);
-                process::exit(1)
-            }
+    let mut loader = Loader::new(overrides, file);
+
+    if command == Some("critical") {
+        if inputs.len() != 1 {
+            eprintln!("Usage: minify critical <page.html> [--viewport 1300x900] [--css files]");
+            process::exit(1);
         }
+
+        if let Err(e) = critical::run(&inputs[0], &stylesheets, &viewport, &mut loader) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    if command == Some("email") {
+        if inputs.len() != 1 {
+            eprintln!("Usage: minify email <page.html>");
+            process::exit(1);
+        }
+
+        if let Err(e) = email::run(&inputs[0], &mut loader) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    if command == Some("style") {
+        if inputs.len() != 2 {
+            eprintln!(
+                "Usage: minify style <page.html> <selector> [--viewport 1300x900] [--css files]"
+            );
+            process::exit(1);
+        }
+
+        if let Err(e) = style::run(&inputs[0], &inputs[1], &stylesheets, &viewport, &mut loader) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    if command.is_some() {
+        if !print || inputs.len() > 1 {
+            eprintln!("Usage: minify config --print [path]");
+            process::exit(1);
+        }
+
+        print_config(
+            &mut loader,
+            inputs.first().map_or(".", |path| path.as_str()),
+        );
+        process::exit(0);
+    }
+
+    let (jobs, errors) = inputs::expand(&inputs, &mut loader);
+
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+
+    if watch {
+        watch::run(&jobs);
+    }
+
+    if !minimize::all(&jobs, errors.len()) {
+        process::exit(1);
     }
 }
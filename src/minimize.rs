@@ -1,29 +1,248 @@
+use std::fmt;
 use std::fs;
-use std::io::Error;
-use std::process;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
 
+use crate::assets;
+use crate::bundle;
 use crate::css;
+use crate::html;
+use crate::inputs::Job;
+use crate::modules;
+use crate::purge;
+use crate::svg;
 
-pub fn css(file: &str) -> Result<(), Error> {
-    let contents = match fs::read_to_string(file) {
-        Ok(str) => str,
-        Err(e) => return Err(e),
-    };
+pub enum Error {
+    Extension,
+    Io(io::Error),
+    Css(css::token::error::ParseError),
+    Html(html::error::ParseError),
+    Svg(svg::error::ParseError),
+    Bundle(bundle::Error),
+    Purge(String),
+    Modules(String),
+    Assets(String),
+
+    // A lint rule set to "error"
+    Lint(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Extension => write!(
+                f,
+                "Invalid file extension. Make sure passed files has the .css, .html or .svg extension."
+            ),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Css(e) => write!(f, "{}", e),
+            Error::Html(e) => write!(f, "{}", e),
+            Error::Svg(e) => write!(f, "{}", e),
+            Error::Bundle(e) => write!(f, "{}", e),
+            Error::Purge(e) => write!(f, "{}", e),
+            Error::Modules(e) => write!(f, "{}", e),
+            Error::Assets(e) => write!(f, "{}", e),
+            Error::Lint(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+// The extension of a file, which decides how it's minimized
+pub fn extension(file: &str) -> Option<&str> {
+    match file.rsplit('.').next() {
+        Some(ext @ ("css" | "html" | "htm" | "svg")) if file.contains('.') => Some(ext),
+        _ => None,
+    }
+}
+
+// Where a problem is in the input, as file:line:column
+fn location(job: &Job, source: Option<&str>, at: i32) -> String {
+    match source {
+        Some(source) => {
+            let (line, column) = css::token::location(source, at);
+            format!("{}:{}:{}", job.input, line, column)
+        }
+        None => format!("{} (position {})", job.input, at),
+    }
+}
+
+/**
+ * Minimizes a file based on its extension, returning the size before and after
+ */
+pub fn file(job: &Job) -> Result<(usize, usize), Error> {
+    let config = &job.config;
+
+    // Both would write their names to the same file, and mangling would change the exported ones. Checked before
+    // anything (like assets) is written.
+    if config.modules && config.mangle {
+        return Err(Error::Modules(String::from(
+            "CSS Modules and mangling can't be used together",
+        )));
+    }
+
+    let contents = fs::read_to_string(&job.input)?;
+    let original = contents.len();
+
+    // What classes and IDs were renamed to, when mangling or using CSS Modules
+    let mut names: Option<String> = None;
+
+    let optimized = match extension(&job.input) {
+        Some("css") => {
+            // Inlines local @import rules before minimizing
+            let mut stylesheet = if config.bundle {
+                bundle::stylesheet(&job.input, config).map_err(Error::Bundle)?
+            } else {
+                css::parse::stylesheet(contents.clone()).map_err(Error::Css)?
+            };
+
+            // Bundled stylesheets are made of several files, so positions can't be turned into lines in the input
+            let source = if config.bundle { None } else { Some(contents) };
+
+            let lints = [
+                ("invalid-declaration", css::grammar::problems(&stylesheet)),
+                ("invalid-font-face", css::font_face::problems(&stylesheet)),
+                (
+                    "invalid-property-rule",
+                    css::property::problems(&stylesheet),
+                ),
+                (
+                    "invalid-custom-property",
+                    css::property::mismatches(&stylesheet),
+                ),
+                (
+                    "undeclared-container",
+                    css::container::problems(&stylesheet),
+                ),
+            ];
 
-    let optimized = match css::minimize(contents) {
-        Ok(opt) => opt,
-        Err(e) => {
-            eprintln!("Error parsing file {}!", file);
-            eprintln!("{}", e);
-            process::exit(1);
+            for (rule, problems) in lints {
+                for (at, problem) in problems {
+                    let message =
+                        format!("{} in {}", problem, location(job, source.as_deref(), at));
+                    config.report(rule, message).map_err(Error::Lint)?;
+                }
+            }
+
+            if !config.content.is_empty() {
+                let used = purge::used(config).map_err(Error::Purge)?;
+                css::purge::stylesheet(&mut stylesheet, &used);
+            }
+
+            css::optimize(&mut stylesheet, config);
+
+            if config.assets || config.inline_limit.is_some() {
+                let (input, output) = (Path::new(&job.input), Path::new(&job.output));
+                assets::stylesheet(&mut stylesheet, input, output, config)
+                    .map_err(Error::Assets)?;
+            }
+
+            if config.modules {
+                let exports = modules::stylesheet(&mut stylesheet, Path::new(&job.input), config)
+                    .map_err(Error::Modules)?;
+                names = Some(exports.to_string());
+            }
+
+            if config.mangle {
+                let mangled =
+                    css::mangle::stylesheet(&mut stylesheet, &config.reserved, config.seed);
+                names = Some(mangled.to_string());
+            }
+
+            stylesheet.to_string()
         }
+        Some("html" | "htm") => html::minimize(contents, config).map_err(Error::Html)?,
+        Some("svg") => svg::minimize(contents, config).map_err(Error::Svg)?,
+        _ => return Err(Error::Extension),
     };
 
-    // Add min to file
-    let mut split: Vec<&str> = file.split(".").collect();
-    split.insert(split.len() - 1, "min");
+    if let Some(directory) = Path::new(&job.output).parent() {
+        if !directory.as_os_str().is_empty() {
+            fs::create_dir_all(directory)?;
+        }
+    }
+
+    fs::write(&job.output, &optimized)?;
+
+    if let Some(names) = names {
+        fs::write(format!("{}.json", job.output), names)?;
+    }
+
+    Ok((original, optimized.len()))
+}
+
+// Formats a number of bytes (1234 => 1.2 kB)
+pub fn size(bytes: usize) -> String {
+    if bytes < 1000 {
+        format!("{} B", bytes)
+    } else if bytes < 1_000_000 {
+        format!("{:.1} kB", bytes as f64 / 1000.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+    }
+}
+
+/**
+ * Minimizes all the files in parallel, printing errors as they happen and a summary at the end
+ *
+ * Returns whether every file was minimized.
+ */
+pub fn all(jobs: &[Job], failed: usize) -> bool {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<(usize, usize)>> = Mutex::new(Vec::new());
+    let failures = AtomicUsize::new(failed);
+
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(jobs.len());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while let Some(job) = jobs.get(next.fetch_add(1, Ordering::SeqCst)) {
+                    match file(job) {
+                        Ok(sizes) => results.lock().unwrap().push(sizes),
+                        Err(e) => {
+                            eprintln!("Error minimizing {}!\n{}", job.input, e);
+                            failures.fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    let results = results.into_inner().unwrap();
+    let failures = failures.into_inner();
+
+    let before: usize = results.iter().map(|(before, _)| before).sum();
+    let after: usize = results.iter().map(|(_, after)| after).sum();
+    let saved = before.saturating_sub(after);
+    let percent = if before > 0 {
+        saved as f64 / before as f64 * 100.0
+    } else {
+        0.0
+    };
 
-    let out_file = split.join(".");
+    println!(
+        "Minified {} file{}, {} failed. {} => {} (saved {}, {:.1}%)",
+        results.len(),
+        if results.len() == 1 { "" } else { "s" },
+        failures,
+        size(before),
+        size(after),
+        size(saved),
+        percent
+    );
 
-    fs::write(out_file, optimized)
+    failures == 0
 }
@@ -0,0 +1,85 @@
+/**
+ * CSS Modules files
+ *
+ * Runs css/modules.rs on a stylesheet, reading the modules its composes declarations refer to for the names they
+ * export. A module's hash comes from its path relative to the minify.toml that applies to it (or to the working
+ * directory, without one), so the names are the same on every machine and composed modules hash the same way as when
+ * they're minimized themselves.
+ */
+use crate::bundle;
+use crate::config::Config;
+use crate::css::{self, modules::Exports, parse::tree::Stylesheet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn absolute(path: &Path) -> PathBuf {
+    let current = env::current_dir().unwrap_or_default();
+    bundle::normalize(&current.join(path))
+}
+
+// The path the hash of a module is made from. Both paths are made absolute first, as the input can be given either way
+// and the configuration file is relative to the working directory.
+fn scope(path: &Path, config: &Config) -> String {
+    let path = absolute(path);
+    let base = match &config.file {
+        Some(file) => absolute(file.parent().unwrap_or(Path::new(""))),
+        None => absolute(Path::new("")),
+    };
+
+    let relative = path.strip_prefix(&base).unwrap_or(&path);
+
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn module(
+    stylesheet: &mut Stylesheet,
+    path: &Path,
+    config: &Config,
+    chain: &mut Vec<PathBuf>,
+) -> Result<Exports, String> {
+    let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+    css::modules::stylesheet(stylesheet, &scope(path, config), &mut |file| {
+        let other = bundle::normalize(&directory.join(file));
+
+        if chain.contains(&other) {
+            chain.push(other);
+            return Err(format!(
+                "composes cycle detected: {}",
+                chain
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect::<Vec<String>>()
+                    .join(" -> ")
+            ));
+        }
+
+        let contents = fs::read_to_string(&other)
+            .map_err(|e| format!("Could not read {}: {}", other.display(), e))?;
+        let mut composed = css::parse::stylesheet(contents)
+            .map_err(|e| format!("Error parsing file {}!\n{}", other.display(), e))?;
+
+        chain.push(other.clone());
+        let exports = module(&mut composed, &other, config, chain);
+        chain.pop();
+
+        exports
+    })
+}
+
+/**
+ * Makes the classes and IDs of a stylesheet local to it, returning the names it exports
+ */
+pub fn stylesheet(
+    stylesheet: &mut Stylesheet,
+    path: &Path,
+    config: &Config,
+) -> Result<Exports, String> {
+    let path = bundle::normalize(path);
+    module(stylesheet, &path, config, &mut vec![path.clone()])
+}
@@ -0,0 +1,170 @@
+/**
+ * The stylesheets of an HTML page
+ *
+ * Finds the stylesheets a page uses in the order they apply: local <link rel=stylesheet> files, and <style> elements
+ * when they're wanted. Linked files are bundled, and their urls rewritten to work from the page's directory. A media
+ * attribute applies to the whole stylesheet, as if its rules were inside @media.
+ */
+use crate::bundle;
+use crate::config::Loader;
+use crate::css::{self, parse::tree::*};
+use crate::html::dom::{Document, NodeData, NodeId};
+use std::path::{Path, PathBuf};
+
+enum Contents {
+    File(PathBuf),
+    Inline(String),
+}
+
+/**
+ * A stylesheet a page uses, with the media attribute of the element it came from
+ */
+pub struct Source {
+    contents: Contents,
+    media: Option<String>,
+    element: Option<NodeId>,
+}
+
+impl Source {
+    pub fn file(path: &Path) -> Source {
+        Source {
+            contents: Contents::File(bundle::normalize(path)),
+            media: None,
+            element: None,
+        }
+    }
+
+    // The <link> or <style> element the stylesheet came from
+    pub fn element(&self) -> Option<NodeId> {
+        self.element
+    }
+}
+
+/**
+ * The stylesheets a page uses, in order. Remote stylesheets are skipped with a warning.
+ */
+pub fn sources(document: &Document, directory: &Path, inline: bool) -> Vec<Source> {
+    let mut sources: Vec<Source> = Vec::new();
+
+    for id in 0..document.nodes.len() {
+        let media = document.attribute(id, "media").map(String::from);
+
+        match document.name(id) {
+            Some("style") if inline => {
+                let text: String = document
+                    .node(id)
+                    .children
+                    .iter()
+                    .filter_map(|child| match &document.node(*child).data {
+                        NodeData::Text(text) => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+
+                sources.push(Source {
+                    contents: Contents::Inline(text),
+                    media,
+                    element: Some(id),
+                });
+            }
+            Some("link") => {
+                // Alternate stylesheets aren't used until they're picked
+                let rel: Vec<String> = document
+                    .attribute(id, "rel")
+                    .unwrap_or("")
+                    .split_ascii_whitespace()
+                    .map(|rel| rel.to_ascii_lowercase())
+                    .collect();
+                if !rel.iter().any(|rel| rel == "stylesheet")
+                    || rel.iter().any(|rel| rel == "alternate")
+                {
+                    continue;
+                }
+
+                let href = match document.attribute(id, "href") {
+                    Some(href) => href,
+                    None => continue,
+                };
+
+                if !bundle::is_relative(href) {
+                    eprintln!("Skipping {}, only local stylesheets can be read", href);
+                    continue;
+                }
+
+                let file = href.split(['?', '#']).next().unwrap_or(href);
+                sources.push(Source {
+                    contents: Contents::File(bundle::normalize(&directory.join(file))),
+                    media,
+                    element: Some(id),
+                });
+            }
+            _ => (),
+        }
+    }
+
+    sources
+}
+
+// Parses the media query list of a media attribute
+fn media(media: &str) -> Option<Vec<ComponentValue>> {
+    let mut prelude = css::parse::component_values(media.to_string()).ok()?;
+    css::parse::consume::trim(&mut prelude);
+
+    Some(prelude)
+}
+
+/**
+ * Reads, bundles and joins the stylesheets
+ */
+pub fn load(
+    sources: Vec<Source>,
+    directory: &Path,
+    loader: &mut Loader,
+) -> Result<Stylesheet, String> {
+    let mut stylesheet = Stylesheet {
+        rules: Vec::new(),
+        comments: Vec::new(),
+    };
+
+    for source in sources {
+        let mut loaded = match &source.contents {
+            Contents::File(path) => {
+                let config = loader.config(path).map_err(|e| e.to_string())?;
+                let mut loaded = bundle::stylesheet(&path.to_string_lossy(), &config)
+                    .map_err(|e| e.to_string())?;
+
+                let from = path.parent().unwrap_or(Path::new(""));
+                bundle::relocate(&mut loaded.rules, from, directory);
+                loaded
+            }
+            Contents::Inline(text) => css::parse::stylesheet(text.clone())
+                .map_err(|e| format!("Error parsing a <style> element!\n{}", e))?,
+        };
+
+        match source.media.as_deref().and_then(media) {
+            Some(prelude) if !prelude.is_empty() => {
+                loaded.rules = vec![bundle::wrap("media", prelude, loaded.rules)]
+            }
+            _ => (),
+        }
+
+        stylesheet.rules.extend(loaded.rules);
+        stylesheet.comments.extend(loaded.comments);
+    }
+
+    // @charset and @import only work at the top, and only one @charset is needed
+    let mut charset = false;
+    stylesheet.rules.retain(|rule| match rule {
+        Rule::At(rule) if rule.name.eq_ignore_ascii_case("charset") => {
+            !std::mem::replace(&mut charset, true)
+        }
+        _ => true,
+    });
+    stylesheet.rules.sort_by_key(|rule| match rule {
+        Rule::At(rule) if rule.name.eq_ignore_ascii_case("charset") => 0,
+        Rule::At(rule) if rule.name.eq_ignore_ascii_case("import") => 1,
+        _ => 2,
+    });
+
+    Ok(stylesheet)
+}
@@ -0,0 +1,176 @@
+/**
+ * Purge content
+ *
+ * The class names, IDs, tag names and attribute names used by a set of HTML and template files, which decide what
+ * css/purge.rs keeps.
+ *
+ * HTML files are parsed, so tags and attributes are known exactly. Classes set by scripts or templates can't be, so the
+ * words in <script> elements and attribute values count as well. Any other file (a template in some other language, or
+ * JavaScript) is split into words, and every word counts as a class, ID, tag and attribute name. It's better to keep a
+ * rule that isn't needed than to lose one that is.
+ */
+use crate::config::{self, Config};
+use crate::html::dom::{self, Document, NodeData};
+use crate::inputs;
+use crate::regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+/**
+ * Names used by the content, along with the safelist
+ */
+pub struct Used {
+    classes: HashSet<String>,
+    ids: HashSet<String>,
+
+    // Tag and attribute names are lowercase
+    tags: HashSet<String>,
+    attributes: HashSet<String>,
+
+    safelist: Vec<Regex>,
+}
+
+impl Used {
+    fn new() -> Used {
+        Used {
+            classes: HashSet::new(),
+            ids: HashSet::new(),
+            // Always there, even if the content only has fragments of pages
+            tags: ["html", "body"].iter().map(|tag| tag.to_string()).collect(),
+            attributes: HashSet::new(),
+            safelist: Vec::new(),
+        }
+    }
+
+    // A name that could be anything
+    fn word(&mut self, word: &str) {
+        self.classes.insert(word.to_string());
+        self.ids.insert(word.to_string());
+        self.tags.insert(word.to_ascii_lowercase());
+        self.attributes.insert(word.to_ascii_lowercase());
+    }
+
+    // Every word in some text, as written and split into its parts (md:flex => md:flex, md and flex)
+    fn words(&mut self, text: &str) {
+        let separator = |ch: char| ch.is_whitespace() || "\"'`<>=(){},;".contains(ch);
+
+        for word in text.split(separator).filter(|word| !word.is_empty()) {
+            self.word(word);
+
+            for part in word.split(|ch: char| !(ch.is_alphanumeric() || ch == '-' || ch == '_')) {
+                if !part.is_empty() {
+                    self.word(part);
+                }
+            }
+        }
+    }
+
+    fn document(&mut self, document: &Document) {
+        for (id, node) in document.nodes.iter().enumerate() {
+            let element = match &node.data {
+                NodeData::Element(element) => element,
+                NodeData::Text(text) if document.within(id, |name| name == "script") => {
+                    self.words(text);
+                    continue;
+                }
+                _ => continue,
+            };
+
+            self.tags.insert(element.name.to_ascii_lowercase());
+
+            for attribute in &element.attributes {
+                let name = attribute.name.to_ascii_lowercase();
+                let value = attribute.value.as_deref().unwrap_or("");
+
+                match name.as_str() {
+                    "class" => self
+                        .classes
+                        .extend(value.split_ascii_whitespace().map(String::from)),
+                    "id" => {
+                        self.ids.insert(value.to_string());
+                    }
+                    _ => (),
+                }
+
+                self.attributes.insert(name);
+                self.words(value);
+            }
+        }
+    }
+
+    fn has(&self, names: &HashSet<String>, name: &str) -> bool {
+        names.contains(name) || self.safelist.iter().any(|regex| regex.is_match(name))
+    }
+
+    pub fn class(&self, name: &str) -> bool {
+        self.has(&self.classes, name)
+    }
+
+    pub fn id(&self, name: &str) -> bool {
+        self.has(&self.ids, name)
+    }
+
+    pub fn tag(&self, name: &str) -> bool {
+        self.has(&self.tags, &name.to_ascii_lowercase())
+    }
+
+    pub fn attribute(&self, name: &str) -> bool {
+        self.has(&self.attributes, &name.to_ascii_lowercase())
+    }
+}
+
+/**
+ * The content files for a configuration
+ */
+pub fn files(config: &Config) -> Vec<PathBuf> {
+    config
+        .content
+        .iter()
+        .flat_map(|pattern| inputs::files(pattern))
+        .collect()
+}
+
+/**
+ * Reads the content files, and the safelist
+ */
+pub fn used(config: &Config) -> Result<Used, String> {
+    let mut used = Used::new();
+
+    for pattern in &config.content {
+        let files = inputs::files(pattern);
+
+        // Purging against nothing would remove everything
+        if files.is_empty() {
+            return Err(format!("No content files match \"{}\"", pattern));
+        }
+
+        for file in files {
+            let contents = fs::read_to_string(&file)
+                .map_err(|e| format!("Could not read {}: {}", file.display(), e))?;
+            let html = file
+                .extension()
+                .is_some_and(|extension| extension == "html" || extension == "htm");
+
+            let document = if html {
+                dom::parse(contents.clone()).ok()
+            } else {
+                None
+            };
+
+            match document {
+                Some(document) => used.document(&document),
+                None => used.words(&contents),
+            }
+        }
+    }
+
+    for entry in &config.safelist {
+        match config::safelist_pattern(entry) {
+            Some(pattern) => used.safelist.push(Regex::new(pattern)?),
+            None => used.word(entry),
+        }
+    }
+
+    Ok(used)
+}
@@ -0,0 +1,338 @@
+/**
+ * Regular expressions
+ *
+ * A small backtracking matcher for the patterns settings take (like the purge safelist), supporting the common parts
+ * of the JavaScript syntax:
+ *
+ *  - Literals, . and escapes (\d, \w, \s and their negations, \. for a literal dot)
+ *  - Character classes ([a-z], [^0-9_])
+ *  - Groups and alternatives ((a|b), (?:a|b))
+ *  - Greedy quantifiers (*, +, ?, {n}, {n,}, {n,m})
+ *  - Anchors (^ and $)
+ *
+ * Like RegExp.test(), a pattern matches if it's found anywhere in the text, unless it's anchored.
+ */
+enum Node {
+    Char(char),
+    Any,
+
+    // Ranges, and whether the class is negated
+    Class(Vec<(char, char)>, bool),
+    Start,
+    End,
+
+    // Alternatives, each a sequence
+    Group(Vec<Vec<Node>>),
+    Repeat(Box<Node>, usize, Option<usize>),
+}
+
+const DIGIT: [(char, char); 1] = [('0', '9')];
+const WORD: [(char, char); 4] = [('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+const SPACE: [(char, char); 6] = [
+    (' ', ' '),
+    ('\t', '\t'),
+    ('\n', '\n'),
+    ('\r', '\r'),
+    ('\x0b', '\x0b'),
+    ('\x0c', '\x0c'),
+];
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek();
+        self.index += 1;
+        ch
+    }
+
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alternatives = vec![self.sequence()?];
+
+        while self.peek() == Some('|') {
+            self.index += 1;
+            alternatives.push(self.sequence()?);
+        }
+
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut sequence: Vec<Node> = Vec::new();
+
+        while let Some(ch) = self.peek() {
+            if ch == '|' || ch == ')' {
+                break;
+            }
+
+            let atom = self.atom()?;
+            let atom = self.quantifier(atom)?;
+            sequence.push(atom);
+        }
+
+        Ok(sequence)
+    }
+
+    // An escape, as a single character or a class
+    fn escape(&mut self) -> Result<Node, String> {
+        let class = |ranges: &[(char, char)], negated: bool| Node::Class(ranges.to_vec(), negated);
+
+        Ok(match self.next() {
+            Some('d') => class(&DIGIT, false),
+            Some('D') => class(&DIGIT, true),
+            Some('w') => class(&WORD, false),
+            Some('W') => class(&WORD, true),
+            Some('s') => class(&SPACE, false),
+            Some('S') => class(&SPACE, true),
+            Some('n') => Node::Char('\n'),
+            Some('t') => Node::Char('\t'),
+            Some(ch) if !ch.is_ascii_alphanumeric() => Node::Char(ch),
+            Some(ch) => return Err(format!("unsupported escape \\{}", ch)),
+            None => return Err(String::from("the pattern ends with \\")),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.index += 1;
+        }
+
+        let mut ranges: Vec<(char, char)> = Vec::new();
+        let mut first = true;
+
+        loop {
+            let ch = match self.next() {
+                Some(']') if !first => break,
+                Some(ch) => ch,
+                None => return Err(String::from("unclosed [")),
+            };
+            first = false;
+
+            let start = if ch == '\\' {
+                match self.escape()? {
+                    Node::Char(ch) => ch,
+                    Node::Class(class, false) => {
+                        ranges.extend(class);
+                        continue;
+                    }
+                    _ => return Err(String::from("negated classes can't be used inside []")),
+                }
+            } else {
+                ch
+            };
+
+            // A range, unless the - is the last character
+            if self.peek() == Some('-')
+                && self.chars.get(self.index + 1).is_some_and(|ch| *ch != ']')
+            {
+                self.index += 1;
+                let end = match self.next() {
+                    Some('\\') => match self.escape()? {
+                        Node::Char(ch) => ch,
+                        _ => return Err(String::from("invalid range in []")),
+                    },
+                    Some(ch) => ch,
+                    None => return Err(String::from("unclosed [")),
+                };
+
+                if end < start {
+                    return Err(format!("invalid range {}-{}", start, end));
+                }
+                ranges.push((start, end));
+            } else {
+                ranges.push((start, start));
+            }
+        }
+
+        Ok(Node::Class(ranges, negated))
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some('.') => Ok(Node::Any),
+            Some('^') => Ok(Node::Start),
+            Some('$') => Ok(Node::End),
+            Some('\\') => self.escape(),
+            Some('[') => self.class(),
+            Some('(') => {
+                // Non capturing groups are the same thing here
+                if self.peek() == Some('?') {
+                    if self.chars.get(self.index + 1) != Some(&':') {
+                        return Err(String::from("only (?: groups are supported"));
+                    }
+                    self.index += 2;
+                }
+
+                let alternatives = self.alternatives()?;
+                if self.next() != Some(')') {
+                    return Err(String::from("unclosed ("));
+                }
+
+                Ok(Node::Group(alternatives))
+            }
+            Some(ch @ ('*' | '+' | '?')) => Err(format!("nothing to repeat before {}", ch)),
+            Some(ch) => Ok(Node::Char(ch)),
+            None => Err(String::from("unexpected end")),
+        }
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.index;
+        while self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+            self.index += 1;
+        }
+
+        self.chars[start..self.index]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn quantifier(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                let start = self.index;
+                self.index += 1;
+
+                let min = self.number();
+                let max = if self.peek() == Some(',') {
+                    self.index += 1;
+                    self.number()
+                } else {
+                    min
+                };
+
+                match (min, self.next()) {
+                    (Some(min), Some('}')) if max.is_none_or(|max| max >= min) => {
+                        self.index -= 1;
+                        (min, max)
+                    }
+                    _ => {
+                        // Not a quantifier, so a literal {
+                        self.index = start;
+                        return Ok(atom);
+                    }
+                }
+            }
+            _ => return Ok(atom),
+        };
+        self.index += 1;
+
+        if matches!(atom, Node::Start | Node::End) {
+            return Err(String::from("anchors can't be repeated"));
+        }
+
+        Ok(Node::Repeat(Box::new(atom), min, max))
+    }
+}
+
+/**
+ * A compiled pattern
+ */
+pub struct Regex {
+    pattern: Node,
+}
+
+// Matches a sequence at a position, calling next with where it ends until next accepts
+fn sequence(
+    nodes: &[Node],
+    text: &[char],
+    position: usize,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    let (node, rest) = match nodes.split_first() {
+        Some(split) => split,
+        None => return next(position),
+    };
+
+    let single = |matched: bool| matched.then_some(position + 1);
+
+    let end = match node {
+        Node::Char(ch) => single(text.get(position) == Some(ch)),
+        Node::Any => single(text.get(position).is_some_and(|ch| *ch != '\n')),
+        Node::Class(ranges, negated) => single(text.get(position).is_some_and(|ch| {
+            ranges
+                .iter()
+                .any(|(start, end)| (start..=end).contains(&ch))
+                != *negated
+        })),
+        Node::Start => (position == 0).then_some(position),
+        Node::End => (position == text.len()).then_some(position),
+        Node::Group(alternatives) => {
+            return alternatives.iter().any(|alternative| {
+                sequence(alternative, text, position, &mut |end| {
+                    sequence(rest, text, end, next)
+                })
+            })
+        }
+        Node::Repeat(node, min, max) => {
+            return repeat(node, *min, *max, 0, text, position, &mut |end| {
+                sequence(rest, text, end, next)
+            })
+        }
+    };
+
+    match end {
+        Some(end) => sequence(rest, text, end, next),
+        None => false,
+    }
+}
+
+// Greedily matches a node as many times as it can, backing off until the rest matches
+fn repeat(
+    node: &Node,
+    min: usize,
+    max: Option<usize>,
+    count: usize,
+    text: &[char],
+    position: usize,
+    next: &mut dyn FnMut(usize) -> bool,
+) -> bool {
+    let more = max.is_none_or(|max| count < max)
+        && sequence(std::slice::from_ref(node), text, position, &mut |end| {
+            // Stop repeating something that matches nothing, once it's been matched enough times
+            (end != position || count < min) && repeat(node, min, max, count + 1, text, end, next)
+        });
+
+    more || (count >= min && next(position))
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            index: 0,
+        };
+
+        let alternatives = parser.alternatives()?;
+        if parser.index < parser.chars.len() {
+            return Err(String::from("unmatched )"));
+        }
+
+        Ok(Regex {
+            pattern: Node::Group(alternatives),
+        })
+    }
+
+    /**
+     * Whether the pattern is found anywhere in the text
+     */
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let pattern = std::slice::from_ref(&self.pattern);
+
+        (0..=text.len()).any(|start| sequence(pattern, &text, start, &mut |_| true))
+    }
+}
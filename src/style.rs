@@ -0,0 +1,100 @@
+/**
+ * Computed styles
+ *
+ * `minify style page.html "nav a"` prints the computed value of every property set or inherited by the elements a
+ * selector matches, the way a browser's developer tools would, without running one. The page's stylesheets (or the ones
+ * given with --css) are read as page.rs describes, <style> elements included, on top of a basic user-agent stylesheet.
+ * See css/cascade.rs for how values are worked out.
+ */
+use crate::bundle;
+use crate::config::Loader;
+use crate::css::cascade::{self, Cascade, Origin};
+use crate::css::{media::Viewport, select};
+use crate::html::dom::{self, Document, NodeId};
+use crate::page;
+use std::fs;
+use std::path::Path;
+
+// Describes an element by its name, ID and classes (a#top.button.large)
+fn describe(document: &Document, id: NodeId) -> String {
+    let mut description = document.name(id).unwrap_or("").to_string();
+
+    if let Some(element_id) = document.attribute(id, "id") {
+        description.push('#');
+        description.push_str(element_id);
+    }
+
+    for class in document
+        .attribute(id, "class")
+        .unwrap_or("")
+        .split_ascii_whitespace()
+    {
+        description.push('.');
+        description.push_str(class);
+    }
+
+    description
+}
+
+/**
+ * Prints the computed style of the elements of a page that match a selector
+ */
+pub fn run(
+    file: &str,
+    selector: &str,
+    stylesheets: &[String],
+    viewport: &Viewport,
+    loader: &mut Loader,
+) -> Result<(), String> {
+    let path = bundle::normalize(Path::new(file));
+    let directory = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let document = dom::parse(contents)
+        .map_err(|e| format!("Error parsing file {}!\n{}", path.display(), e))?;
+    let selectors = select::selectors(selector)?;
+
+    let sources = if stylesheets.is_empty() {
+        page::sources(&document, &directory, true)
+    } else {
+        stylesheets
+            .iter()
+            .map(|file| page::Source::file(Path::new(file)))
+            .collect()
+    };
+
+    let mut cascade = Cascade::new(*viewport);
+    cascade.add(&cascade::user_agent(), Origin::UserAgent);
+    cascade.add(&page::load(sources, &directory, loader)?, Origin::Author);
+
+    let elements = select::query(&document, &selectors);
+    if elements.is_empty() {
+        return Err(format!(
+            "Nothing in {} matches {}",
+            path.display(),
+            selector
+        ));
+    }
+
+    for (index, element) in elements.into_iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+
+        let mut ancestors = vec![describe(&document, element)];
+        let mut current = element;
+        while let Some(parent) = document.parent_element(current) {
+            ancestors.push(describe(&document, parent));
+            current = parent;
+        }
+        ancestors.reverse();
+        println!("{}", ancestors.join(" > "));
+
+        for (name, value) in cascade.computed(&document, element).properties() {
+            println!("  {}: {}", name, value);
+        }
+    }
+
+    Ok(())
+}
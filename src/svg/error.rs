@@ -0,0 +1,16 @@
+/**
+ * SVG (XML) Parse Error Type
+ */
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub error_text: &'static str,
+    pub at: i32,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.error_text, self.at)
+    }
+}
@@ -0,0 +1,17 @@
+pub mod error;
+pub mod number;
+pub mod optimize;
+pub mod path;
+pub mod serialize;
+pub mod transform;
+pub mod xml;
+
+use crate::config::Config;
+
+pub fn minimize(contents: String, config: &Config) -> Result<String, error::ParseError> {
+    let mut document = xml::parse(contents)?;
+
+    optimize::document(&mut document, config)?;
+
+    Ok(serialize::document(&document))
+}
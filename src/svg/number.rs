@@ -0,0 +1,182 @@
+/**
+ * Number parsing and minimal number formatting for SVG attributes
+ * https://www.w3.org/TR/SVG2/paths.html#PathDataBNF
+ */
+use super::xml::is_whitespace;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// Skips whitespace and at most one comma (comma-wsp)
+pub fn separator(points: &mut Peekable<Chars>) {
+    let mut comma = false;
+
+    while let Some(ch) = points.peek() {
+        if is_whitespace(*ch) {
+            points.next();
+        } else if *ch == ',' && !comma {
+            comma = true;
+            points.next();
+        } else {
+            break;
+        }
+    }
+}
+
+// Consumes a number (sign? digits? ("." digits)? exponent?), without a leading separator
+pub fn number(points: &mut Peekable<Chars>) -> Option<f64> {
+    let mut repr = String::new();
+
+    if let Some(ch) = points.peek() {
+        if *ch == '+' || *ch == '-' {
+            repr.push(points.next().unwrap());
+        }
+    }
+
+    let mut digits = false;
+    let mut decimal = false;
+
+    while let Some(ch) = points.peek() {
+        if ch.is_ascii_digit() {
+            digits = true;
+        } else if *ch == '.' && !decimal {
+            decimal = true;
+        } else {
+            break;
+        }
+
+        repr.push(points.next().unwrap());
+    }
+
+    if !digits {
+        return None;
+    }
+
+    // Only consume the exponent if it is followed by digits
+    if let Some(ch) = points.peek() {
+        if *ch == 'e' || *ch == 'E' {
+            let mut lookahead = points.clone();
+            lookahead.next();
+
+            let mut exponent = String::from("e");
+            if let Some(sign) = lookahead.peek() {
+                if *sign == '+' || *sign == '-' {
+                    exponent.push(lookahead.next().unwrap());
+                }
+            }
+
+            if lookahead.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+                while let Some(ch) = lookahead.peek() {
+                    if !ch.is_ascii_digit() {
+                        break;
+                    }
+                    exponent.push(lookahead.next().unwrap());
+                }
+
+                *points = lookahead;
+                repr.push_str(&exponent);
+            }
+        }
+    }
+
+    repr.parse::<f64>().ok()
+}
+
+// Parses a whitespace and/or comma separated list of numbers, None if anything else is in the list
+pub fn list(value: &str) -> Option<Vec<f64>> {
+    let mut points = value.chars().peekable();
+    let mut numbers: Vec<f64> = Vec::new();
+
+    separator(&mut points);
+
+    while points.peek().is_some() {
+        numbers.push(number(&mut points)?);
+        separator(&mut points);
+    }
+
+    Some(numbers)
+}
+
+/**
+ * Formats a number in as few characters as possible, without losing precision
+ *
+ *  0.5 => .5
+ *  -0.5 => -.5
+ *  10.0 => 10
+ *  1000000 => 1e6
+ *  0.00005 => 5e-5
+ */
+pub fn format(number: f64) -> String {
+    if number == 0.0 {
+        return String::from("0");
+    }
+
+    let mut repr = format!("{}", number);
+
+    if repr.starts_with("0.") {
+        repr.remove(0);
+    } else if repr.starts_with("-0.") {
+        repr.remove(1);
+    }
+
+    // Leading zeros after the decimal point can be written as a negative exponent (.00005 => 5e-5)
+    if let Some(fraction) = repr.trim_start_matches('-').strip_prefix('.') {
+        let zeros = fraction.len() - fraction.trim_start_matches('0').len();
+        let digits = &fraction[zeros..];
+        let sign = if number < 0.0 { "-" } else { "" };
+        let exponent = format!("{}{}e-{}", sign, digits, zeros + digits.len());
+
+        if exponent.len() < repr.len() {
+            repr = exponent;
+        }
+    }
+
+    // Trailing zeros can be written as an exponent
+    if !repr.contains('.') && !repr.contains('e') {
+        let zeros = repr.len() - repr.trim_end_matches('0').len();
+
+        if zeros > 2 {
+            let exponent = format!("{}e{}", &repr[..repr.len() - zeros], zeros);
+
+            if exponent.len() < repr.len() {
+                repr = exponent;
+            }
+        }
+    }
+
+    repr
+}
+
+// Joins numbers, only adding a separator where the next number can't start directly after the previous one
+pub fn join(output: &mut String, number: f64) {
+    let formatted = format(number);
+
+    // The digits (and decimal point) of the previous number, if the output ends with one
+    let previous = output
+        .rsplit(|ch: char| !(ch.is_ascii_digit() || ch == '.'))
+        .next()
+        .unwrap_or("");
+
+    let needs_separator = !previous.is_empty()
+        && match formatted.chars().next() {
+            Some('-') => false,
+            Some('.') => !previous.contains('.'),
+            _ => true,
+        };
+
+    if needs_separator {
+        output.push(' ');
+    }
+
+    output.push_str(&formatted);
+}
+
+// Formats a list of numbers
+pub fn format_list(numbers: &[f64]) -> String {
+    let mut output = String::new();
+
+    for number in numbers {
+        join(&mut output, *number);
+    }
+
+    output
+}
@@ -0,0 +1,355 @@
+/**
+ * SVG tree optimizations
+ *
+ *  - Comments, editor metadata and editor namespaced elements and attributes are removed
+ *  - Insignificant whitespace is removed
+ *  - Path data, transforms and numeric attributes are written with as few characters as possible
+ *  - Redundant groups are collapsed into their parent or their only child, unless a <style> element could select them
+ *  - <style> contents are minimized as CSS
+ *  - Unused namespace declarations are removed
+ */
+use super::error::ParseError;
+use super::number;
+use super::path;
+use super::transform;
+use super::xml::{escape, is_whitespace, unescape};
+use crate::config::Config;
+use crate::css;
+use crate::html::dom::{Document, NodeData, NodeId, ROOT};
+use crate::html::token::Attribute;
+
+// Namespace prefixes used by editors to store their own state
+const EDITOR_PREFIXES: [&str; 4] = ["inkscape", "sodipodi", "sketch", "serif"];
+
+// Elements whose text content is rendered (or otherwise meaningful), so whitespace is kept
+const TEXT_CONTENT: [&str; 7] = [
+    "text", "tspan", "textPath", "title", "desc", "style", "script",
+];
+
+// Attributes holding a single number
+const NUMERIC_ATTRIBUTES: [&str; 22] = [
+    "x",
+    "y",
+    "width",
+    "height",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "fx",
+    "fy",
+    "offset",
+    "opacity",
+    "fill-opacity",
+    "stroke-opacity",
+    "stop-opacity",
+    "stroke-width",
+    "stroke-miterlimit",
+];
+
+// Attributes which prevent a group from being merged into its child
+const UNMOVABLE_ATTRIBUTES: [&str; 7] = [
+    "id",
+    "class",
+    "style",
+    "clip-path",
+    "mask",
+    "filter",
+    "systemLanguage",
+];
+
+// The namespace prefix of a qualified name (inkscape:label => inkscape)
+fn prefix(name: &str) -> Option<&str> {
+    name.find(':').map(|index| &name[..index])
+}
+
+fn is_editor(name: &str) -> bool {
+    name == "metadata" || prefix(name).is_some_and(|prefix| EDITOR_PREFIXES.contains(&prefix))
+}
+
+// The XML declaration can be dropped as long as it doesn't declare an encoding other than UTF-8
+fn is_redundant_declaration(text: &str) -> bool {
+    text.starts_with("<?xml ")
+        && match text.find("encoding=") {
+            None => true,
+            Some(index) => {
+                let encoding = text[index + 9..].to_ascii_lowercase();
+                encoding.starts_with("\"utf-8\"") || encoding.starts_with("'utf-8'")
+            }
+        }
+}
+
+// Minimizes a single attribute, None removes it
+fn attribute(attribute: Attribute, config: &Config) -> Option<Attribute> {
+    let name = attribute.name;
+    let value = attribute.value.unwrap_or_default();
+
+    if prefix(&name).is_some_and(|prefix| EDITOR_PREFIXES.contains(&prefix)) {
+        return None;
+    }
+
+    if !config.enabled("svg-numbers") {
+        return Some(Attribute {
+            name,
+            value: Some(value),
+        });
+    }
+
+    let minimized = match name.as_str() {
+        "d" => path::minimize(&value),
+
+        // An empty transform does nothing
+        "transform" | "gradientTransform" | "patternTransform" => match transform::minimize(&value)
+        {
+            Some(transform) if transform.is_empty() => return None,
+            transform => transform,
+        },
+
+        "viewBox" | "points" => number::list(&value).map(|numbers| number::format_list(&numbers)),
+        _ if NUMERIC_ATTRIBUTES.contains(&name.as_str()) => match number::list(&value) {
+            Some(numbers) if numbers.len() == 1 => Some(number::format(numbers[0])),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    Some(Attribute {
+        name,
+        value: Some(minimized.unwrap_or(value)),
+    })
+}
+
+// Minimizes the contents of a <style> element as CSS
+fn style(document: &mut Document, id: NodeId, config: &Config) -> Result<(), ParseError> {
+    let mut contents = String::new();
+
+    for child in &document.nodes[id].children {
+        if let NodeData::Text(text) = &document.nodes[*child].data {
+            // CDATA sections were escaped while parsing
+            contents.push_str(&unescape(text));
+        }
+    }
+
+    let minimized = match css::minimize(contents, config) {
+        Ok(minimized) => minimized.trim().to_string(),
+        Err(e) => {
+            return Err(ParseError {
+                error_text: "Could not parse the contents of a <style> element",
+                at: e.at,
+            })
+        }
+    };
+
+    // Use a CDATA section if escaping would be longer
+    let text = if minimized.contains('<') || minimized.contains('&') {
+        format!(
+            "<![CDATA[{}]]>",
+            minimized.replace("]]>", "]]]]><![CDATA[>")
+        )
+    } else {
+        escape(&minimized)
+    };
+
+    document.nodes[id].children.clear();
+    if !text.is_empty() {
+        document.append(id, NodeData::Text(text));
+    }
+
+    Ok(())
+}
+
+// Tries to merge a group into its only child, returning the child if successful
+fn merge_group(document: &mut Document, group: NodeId) -> Option<NodeId> {
+    let children = &document.nodes[group].children;
+    if children.len() != 1 {
+        return None;
+    }
+
+    let child = children[0];
+    let group_attributes = document.element(group)?.attributes.clone();
+    let child_element = document.element(child)?;
+
+    let child_has = |name: &str| child_element.attributes.iter().any(|a| a.name == name);
+
+    for attribute in &group_attributes {
+        if UNMOVABLE_ATTRIBUTES.contains(&attribute.name.as_str())
+            || attribute.name.starts_with("on")
+            || (attribute.name != "transform" && child_has(&attribute.name))
+        {
+            return None;
+        }
+    }
+
+    // The group's transform would change the coordinate system of the child's clip path, mask or filter
+    let transformed = group_attributes.iter().any(|a| a.name == "transform");
+    if transformed && (child_has("clip-path") || child_has("mask") || child_has("filter")) {
+        return None;
+    }
+
+    if let NodeData::Element(element) = &mut document.nodes[child].data {
+        let mut attributes = group_attributes;
+
+        // The group transform applies before the child's own
+        let own = element
+            .attributes
+            .iter()
+            .position(|a| a.name == "transform");
+        let group_transform = attributes.iter_mut().find(|a| a.name == "transform");
+
+        if let (Some(own), Some(transform)) = (own, group_transform) {
+            let own = element.attributes.remove(own);
+            transform.value = Some(format!(
+                "{} {}",
+                transform.value.as_deref().unwrap_or(""),
+                own.value.as_deref().unwrap_or("")
+            ));
+        }
+
+        attributes.append(&mut element.attributes);
+        element.attributes = attributes;
+    }
+
+    Some(child)
+}
+
+fn optimize(
+    document: &mut Document,
+    id: NodeId,
+    groups: bool,
+    config: &Config,
+) -> Result<(), ParseError> {
+    let name = document.name(id).unwrap_or("").to_string();
+
+    if name == "style" {
+        return style(document, id, config);
+    }
+
+    let preserve = document.within(id, |name| TEXT_CONTENT.contains(&name));
+    let children = document.nodes[id].children.clone();
+    let mut kept: Vec<NodeId> = Vec::new();
+
+    for child in children {
+        match &document.nodes[child].data {
+            NodeData::Comment(_) => continue,
+
+            NodeData::Doctype(doctype) => {
+                // Only keep the doctype if it declares entities
+                if doctype.contains('[') {
+                    kept.push(child);
+                }
+                continue;
+            }
+
+            NodeData::Text(text) => {
+                let blank = text.chars().all(is_whitespace);
+
+                if (!blank || preserve) && !is_redundant_declaration(text) {
+                    kept.push(child);
+                }
+                continue;
+            }
+
+            NodeData::Element(element) if is_editor(&element.name) => continue,
+
+            _ => (),
+        }
+
+        if let NodeData::Element(element) = &mut document.nodes[child].data {
+            let attributes = std::mem::take(&mut element.attributes);
+            element.attributes = attributes
+                .into_iter()
+                .filter_map(|a| attribute(a, config))
+                .collect();
+        }
+
+        optimize(document, child, groups, config)?;
+
+        let element = document.element(child).unwrap();
+        let empty = document.nodes[child].children.is_empty();
+
+        match element.name.as_str() {
+            _ if !groups => kept.push(child),
+
+            // Groups without attributes can be replaced by their children (except where the children are alternatives)
+            "g" if element.attributes.is_empty() && name != "switch" => {
+                kept.extend(document.nodes[child].children.clone());
+            }
+
+            // Empty groups and definitions render nothing
+            "g" | "defs" if empty && element.attributes.iter().all(|a| a.name != "id") => (),
+
+            "g" if name != "switch" => match merge_group(document, child) {
+                Some(merged) => kept.push(merged),
+                None => kept.push(child),
+            },
+
+            _ => kept.push(child),
+        }
+    }
+
+    for child in &kept {
+        document.nodes[*child].parent = Some(id);
+    }
+    document.nodes[id].children = kept;
+
+    Ok(())
+}
+
+// Collects the namespace prefixes used by elements and attributes
+fn used_prefixes(document: &Document, id: NodeId, prefixes: &mut Vec<String>) {
+    if let Some(element) = document.element(id) {
+        let names =
+            std::iter::once(&element.name).chain(element.attributes.iter().map(|a| &a.name));
+
+        for name in names {
+            if let Some(prefix) = prefix(name) {
+                if prefix != "xmlns" && !prefixes.iter().any(|p| p == prefix) {
+                    prefixes.push(prefix.to_string());
+                }
+            }
+        }
+    }
+
+    for child in &document.nodes[id].children {
+        used_prefixes(document, *child, prefixes);
+    }
+}
+
+fn remove_unused_namespaces(document: &mut Document, prefixes: &[String]) {
+    for node in document.nodes.iter_mut() {
+        if let NodeData::Element(element) = &mut node.data {
+            element
+                .attributes
+                .retain(|attribute| match attribute.name.strip_prefix("xmlns:") {
+                    Some(prefix) => prefixes.iter().any(|p| p == prefix),
+                    None => true,
+                });
+        }
+    }
+}
+
+pub fn document(document: &mut Document, config: &Config) -> Result<(), ParseError> {
+    // Selectors like g > path or :empty depend on the groups being there
+    let styled = document
+        .nodes
+        .iter()
+        .any(|node| matches!(&node.data, NodeData::Element(element) if element.name == "style"));
+
+    optimize(
+        document,
+        ROOT,
+        config.enabled("svg-groups") && !styled,
+        config,
+    )?;
+
+    let mut prefixes: Vec<String> = Vec::new();
+    used_prefixes(document, ROOT, &mut prefixes);
+    remove_unused_namespaces(document, &prefixes);
+
+    Ok(())
+}
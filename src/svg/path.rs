@@ -0,0 +1,134 @@
+/**
+ * Path data minification
+ * https://www.w3.org/TR/SVG2/paths.html#PathData
+ *
+ * Commands are kept as written (absolute or relative), so the result renders identically. Only the way the data is
+ * written changes: numbers are formatted minimally, separators are dropped wherever possible and repeated command
+ * letters are left implicit.
+ */
+use super::number::{join, number, separator};
+use super::xml::is_whitespace;
+use std::iter::Peekable;
+use std::str::Chars;
+
+pub struct Segment {
+    pub command: char,
+    pub arguments: Vec<f64>,
+}
+
+// The number of arguments each command takes
+fn arity(command: char) -> Option<usize> {
+    match command.to_ascii_lowercase() {
+        'z' => Some(0),
+        'h' | 'v' => Some(1),
+        'm' | 'l' | 't' => Some(2),
+        's' | 'q' => Some(4),
+        'c' => Some(6),
+        'a' => Some(7),
+        _ => None,
+    }
+}
+
+// Arc flags are a single 0 or 1, and may be written without a separator (a1 1 0 0110 10)
+fn flag(points: &mut Peekable<Chars>) -> Option<f64> {
+    match points.next() {
+        Some('0') => Some(0.0),
+        Some('1') => Some(1.0),
+        _ => None,
+    }
+}
+
+/**
+ * Parses path data into segments, None if the data is invalid
+ *
+ * Repeated arguments are split into separate segments, so "M0 0 10 10" is a moveto followed by an implicit lineto.
+ */
+pub fn parse(data: &str) -> Option<Vec<Segment>> {
+    let mut points = data.chars().peekable();
+    let mut segments: Vec<Segment> = Vec::new();
+
+    while points.peek().is_some_and(|ch| is_whitespace(*ch)) {
+        points.next();
+    }
+
+    while let Some(command) = points.next() {
+        let count = arity(command)?;
+
+        // Repeat the command for as long as there are arguments
+        let mut repeated = command;
+        loop {
+            separator(&mut points);
+
+            let mut arguments: Vec<f64> = Vec::new();
+            for index in 0..count {
+                let argument = if repeated.eq_ignore_ascii_case(&'a') && (index == 3 || index == 4)
+                {
+                    flag(&mut points)?
+                } else {
+                    number(&mut points)?
+                };
+
+                arguments.push(argument);
+                separator(&mut points);
+            }
+
+            segments.push(Segment {
+                command: repeated,
+                arguments,
+            });
+
+            // Subsequent pairs after a moveto are linetos
+            repeated = match repeated {
+                'M' => 'L',
+                'm' => 'l',
+                other => other,
+            };
+
+            let more = points
+                .peek()
+                .is_some_and(|ch| ch.is_ascii_digit() || "+-.".contains(*ch));
+
+            if count == 0 || !more {
+                break;
+            }
+        }
+    }
+
+    Some(segments)
+}
+
+// Serializes segments as compactly as possible
+pub fn serialize(segments: &[Segment]) -> String {
+    let mut output = String::new();
+    let mut previous: Option<char> = None;
+
+    for segment in segments {
+        // The command letter can be left out when it would be implied anyway
+        let implied = match (previous, segment.command) {
+            (Some('M'), 'L') | (Some('m'), 'l') => true,
+            (Some(previous), command) => previous == command && !"MmZz".contains(command),
+            _ => false,
+        };
+
+        if !implied {
+            output.push(segment.command);
+        }
+
+        for argument in &segment.arguments {
+            join(&mut output, *argument);
+        }
+
+        previous = Some(match segment.command {
+            // A lineto following a moveto keeps the moveto "current" for implying more linetos
+            'L' if previous == Some('M') && implied => 'M',
+            'l' if previous == Some('m') && implied => 'm',
+            command => command,
+        });
+    }
+
+    output
+}
+
+pub fn minimize(data: &str) -> Option<String> {
+    parse(data).map(|segments| serialize(&segments))
+}
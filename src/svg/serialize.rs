@@ -0,0 +1,77 @@
+/**
+ * XML serialization
+ */
+use crate::html::dom::{Document, NodeData, NodeId, ROOT};
+
+fn node(document: &Document, id: NodeId, output: &mut String) {
+    match &document.node(id).data {
+        NodeData::Document => {
+            for child in &document.node(id).children {
+                node(document, *child, output);
+            }
+        }
+
+        NodeData::Doctype(doctype) => {
+            output.push_str("<!DOCTYPE ");
+            output.push_str(doctype);
+            output.push('>');
+        }
+
+        NodeData::Comment(comment) => {
+            output.push_str("<!--");
+            output.push_str(comment);
+            output.push_str("-->");
+        }
+
+        NodeData::Text(text) => output.push_str(text),
+
+        NodeData::Element(element) => {
+            output.push('<');
+            output.push_str(&element.name);
+
+            for attribute in &element.attributes {
+                let value = attribute.value.as_deref().unwrap_or("");
+
+                output.push(' ');
+                output.push_str(&attribute.name);
+
+                // Values are stored as written, so they may contain the other kind of quote
+                if value.contains('"') && !value.contains('\'') {
+                    output.push_str("='");
+                    output.push_str(value);
+                    output.push('\'');
+                } else {
+                    output.push_str("=\"");
+                    output.push_str(&value.replace('"', "&quot;"));
+                    output.push('"');
+                }
+            }
+
+            let children = &document.node(id).children;
+
+            // Empty elements use the empty-element tag
+            if children.is_empty() {
+                output.push_str("/>");
+                return;
+            }
+
+            output.push('>');
+
+            for child in children {
+                node(document, *child, output);
+            }
+
+            output.push_str("</");
+            output.push_str(&element.name);
+            output.push('>');
+        }
+    }
+}
+
+pub fn document(document: &Document) -> String {
+    let mut output = String::new();
+
+    node(document, ROOT, &mut output);
+
+    output
+}
@@ -0,0 +1,114 @@
+/**
+ * Transform list minification
+ * https://www.w3.org/TR/css-transforms-1/#svg-syntax
+ */
+use super::number::{format_list, number, separator};
+
+pub struct Transform {
+    pub name: String,
+    pub arguments: Vec<f64>,
+}
+
+impl Transform {
+    // Transforms that have no effect
+    fn is_identity(&self) -> bool {
+        let arguments = self.arguments.as_slice();
+
+        match self.name.as_str() {
+            "translate" | "skewX" | "skewY" => arguments.iter().all(|a| *a == 0.0),
+            "scale" => arguments.iter().all(|a| *a == 1.0),
+            "rotate" => arguments.first() == Some(&0.0),
+            "matrix" => arguments == [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            _ => false,
+        }
+    }
+
+    // Drops arguments equal to their defaults
+    fn shorten(&mut self) {
+        match self.name.as_str() {
+            "translate" if self.arguments.len() == 2 && self.arguments[1] == 0.0 => {
+                self.arguments.pop();
+            }
+            "scale" if self.arguments.len() == 2 && self.arguments[0] == self.arguments[1] => {
+                self.arguments.pop();
+            }
+            "rotate" if self.arguments.len() == 3 && self.arguments[1..] == [0.0, 0.0] => {
+                self.arguments.truncate(1);
+            }
+            _ => (),
+        }
+    }
+}
+
+// The number of arguments each transform function may take
+fn valid(name: &str, count: usize) -> bool {
+    match name {
+        "matrix" => count == 6,
+        "translate" | "scale" => count == 1 || count == 2,
+        "rotate" => count == 1 || count == 3,
+        "skewX" | "skewY" => count == 1,
+        _ => false,
+    }
+}
+
+/**
+ * Parses a transform list, None if the list is invalid
+ */
+pub fn parse(value: &str) -> Option<Vec<Transform>> {
+    let mut points = value.chars().peekable();
+    let mut transforms: Vec<Transform> = Vec::new();
+
+    separator(&mut points);
+
+    while points.peek().is_some() {
+        let mut name = String::new();
+        while let Some(ch) = points.peek() {
+            if !ch.is_ascii_alphabetic() {
+                break;
+            }
+            name.push(points.next().unwrap());
+        }
+
+        separator(&mut points);
+        if points.next() != Some('(') {
+            return None;
+        }
+        separator(&mut points);
+
+        let mut arguments: Vec<f64> = Vec::new();
+        while points.peek() != Some(&')') {
+            arguments.push(number(&mut points)?);
+            separator(&mut points);
+        }
+        points.next();
+
+        if !valid(&name, arguments.len()) {
+            return None;
+        }
+
+        transforms.push(Transform { name, arguments });
+        separator(&mut points);
+    }
+
+    Some(transforms)
+}
+
+pub fn serialize(transforms: &[Transform]) -> String {
+    transforms
+        .iter()
+        .map(|transform| format!("{}({})", transform.name, format_list(&transform.arguments)))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Minimizes a transform list, an empty string means the transform can be removed
+pub fn minimize(value: &str) -> Option<String> {
+    let mut transforms = parse(value)?;
+
+    transforms.retain(|transform| !transform.is_identity());
+    for transform in transforms.iter_mut() {
+        transform.shorten();
+    }
+
+    Some(serialize(&transforms))
+}
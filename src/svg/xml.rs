@@ -0,0 +1,323 @@
+/**
+ * XML Parser
+ * https://www.w3.org/TR/xml/
+ *
+ * Parses well-formed XML into the same document tree used for HTML. Like the HTML tokenizer, character and entity
+ * references are left as written: text and attribute values are stored exactly as they appear in the source (CDATA
+ * sections are escaped into ordinary text), so they can be written back out unchanged.
+ */
+use super::error::ParseError;
+use crate::html::dom::{Document, Element, NodeData, NodeId, ROOT};
+use crate::html::token::Attribute;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// Looks ahead for a string, consumes nothing
+fn lookahead(points: &mut Peekable<Chars>, string: &str) -> bool {
+    let mut cmp = points.clone();
+
+    for expected in string.chars() {
+        if cmp.next() != Some(expected) {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn skip(points: &mut Peekable<Chars>, position: &mut i32, count: usize) {
+    for _ in 0..count {
+        *position += 1;
+        points.next();
+    }
+}
+
+// § 2.3 S ::= (#x20 | #x9 | #xD | #xA)+
+pub fn is_whitespace(ch: char) -> bool {
+    ch == ' ' || ch == '\t' || ch == '\r' || ch == '\n'
+}
+
+fn whitespace(points: &mut Peekable<Chars>, position: &mut i32) {
+    while let Some(ch) = points.peek() {
+        if !is_whitespace(*ch) {
+            break;
+        }
+
+        *position += 1;
+        points.next();
+    }
+}
+
+// Consumes everything up to (and including) the terminator, returning what came before it
+fn until(
+    points: &mut Peekable<Chars>,
+    position: &mut i32,
+    terminator: &str,
+) -> Result<String, ParseError> {
+    let mut string = String::new();
+
+    loop {
+        if lookahead(points, terminator) {
+            skip(points, position, terminator.chars().count());
+            return Ok(string);
+        }
+
+        *position += 1;
+        match points.next() {
+            Some(ch) => string.push(ch),
+            None => {
+                return Err(ParseError {
+                    error_text: "Unexpected End Of File (EOF)",
+                    at: *position,
+                })
+            }
+        }
+    }
+}
+
+// § 2.3 Name
+fn name(points: &mut Peekable<Chars>, position: &mut i32) -> Result<String, ParseError> {
+    let mut name = String::new();
+
+    while let Some(ch) = points.peek() {
+        if is_whitespace(*ch) || "/>=\"'<".contains(*ch) {
+            break;
+        }
+
+        *position += 1;
+        name.push(points.next().unwrap());
+    }
+
+    if name.is_empty() {
+        return Err(ParseError {
+            error_text: "Expected a name",
+            at: *position,
+        });
+    }
+
+    Ok(name)
+}
+
+// § 2.8 doctypedecl, including any internal subset
+fn doctype(points: &mut Peekable<Chars>, position: &mut i32) -> Result<String, ParseError> {
+    let mut doctype = String::new();
+    let mut depth = 0;
+
+    loop {
+        *position += 1;
+        match points.next() {
+            None => {
+                return Err(ParseError {
+                    error_text: "Unexpected End Of File (EOF) in doctype",
+                    at: *position,
+                })
+            }
+            Some('>') if depth == 0 => return Ok(doctype.trim().to_string()),
+            Some(ch) => {
+                if ch == '[' {
+                    depth += 1;
+                } else if ch == ']' {
+                    depth -= 1;
+                }
+
+                doctype.push(ch);
+            }
+        }
+    }
+}
+
+// § 3.1 Start-tags, returns the element and whether it was an empty-element tag
+fn start_tag(
+    points: &mut Peekable<Chars>,
+    position: &mut i32,
+) -> Result<(Element, bool), ParseError> {
+    let name = name(points, position)?;
+    let mut attributes: Vec<Attribute> = Vec::new();
+
+    loop {
+        whitespace(points, position);
+
+        if lookahead(points, "/>") {
+            skip(points, position, 2);
+            return Ok((Element { name, attributes }, true));
+        }
+
+        if lookahead(points, ">") {
+            skip(points, position, 1);
+            return Ok((Element { name, attributes }, false));
+        }
+
+        // Attribute ::= Name Eq AttValue
+        let attribute = self::name(points, position)?;
+
+        whitespace(points, position);
+        if !lookahead(points, "=") {
+            return Err(ParseError {
+                error_text: "Expected = after attribute name",
+                at: *position,
+            });
+        }
+        skip(points, position, 1);
+        whitespace(points, position);
+
+        let quote = match points.peek() {
+            Some('"') => "\"",
+            Some('\'') => "'",
+            _ => {
+                return Err(ParseError {
+                    error_text: "Attribute values must be quoted",
+                    at: *position,
+                })
+            }
+        };
+        skip(points, position, 1);
+
+        let value = until(points, position, quote)?;
+
+        if attributes.iter().any(|a| a.name == attribute) {
+            return Err(ParseError {
+                error_text: "Duplicate attribute",
+                at: *position,
+            });
+        }
+
+        attributes.push(Attribute {
+            name: attribute,
+            value: Some(value),
+        });
+    }
+}
+
+// Escapes text so it can be written as character data (> only needs escaping as part of ]]>)
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace("]]>", "]]&gt;")
+}
+
+// Resolves the predefined entities and character references in character data
+pub fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut rest = text;
+
+    while let Some(index) = rest.find('&') {
+        unescaped.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+
+        let entity = &rest[1..end];
+        let resolved = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32),
+            _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(std::char::from_u32),
+            _ => None,
+        };
+
+        match resolved {
+            Some(ch) => {
+                unescaped.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/**
+ * Parses an XML document
+ */
+pub fn parse(contents: String) -> Result<Document, ParseError> {
+    // § 2.11 End-of-Line Handling
+    let contents = contents.replace("\r\n", "\n").replace('\r', "\n");
+
+    let mut document = Document::new();
+    let mut iter = contents.chars().peekable();
+    let points = &mut iter;
+    let mut position = 0;
+
+    // Stack of open elements, with the document at the bottom
+    let mut stack: Vec<NodeId> = vec![ROOT];
+
+    while points.peek().is_some() {
+        let parent = *stack.last().unwrap();
+
+        if lookahead(points, "<!--") {
+            skip(points, &mut position, 4);
+            let comment = until(points, &mut position, "-->")?;
+            document.append(parent, NodeData::Comment(comment));
+        } else if lookahead(points, "<![CDATA[") {
+            skip(points, &mut position, 9);
+            let data = until(points, &mut position, "]]>")?;
+            document.append(parent, NodeData::Text(escape(&data)));
+        } else if lookahead(points, "<!DOCTYPE") {
+            skip(points, &mut position, 9);
+            let doctype = doctype(points, &mut position)?;
+            document.append(parent, NodeData::Doctype(doctype));
+        } else if lookahead(points, "<?") {
+            // Processing instructions (including the XML declaration) are kept verbatim
+            skip(points, &mut position, 2);
+            let instruction = until(points, &mut position, "?>")?;
+            document.append(parent, NodeData::Text(format!("<?{}?>", instruction)));
+        } else if lookahead(points, "</") {
+            skip(points, &mut position, 2);
+            let name = name(points, &mut position)?;
+            whitespace(points, &mut position);
+            until(points, &mut position, ">")?;
+
+            if stack.len() == 1 || document.name(parent) != Some(name.as_str()) {
+                return Err(ParseError {
+                    error_text: "End tag does not match the open element",
+                    at: position,
+                });
+            }
+
+            stack.pop();
+        } else if lookahead(points, "<") {
+            skip(points, &mut position, 1);
+            let (element, empty) = start_tag(points, &mut position)?;
+            let id = document.append(parent, NodeData::Element(element));
+
+            if !empty {
+                stack.push(id);
+            }
+        } else {
+            let mut text = String::new();
+
+            while let Some(ch) = points.peek() {
+                if *ch == '<' {
+                    break;
+                }
+
+                position += 1;
+                text.push(points.next().unwrap());
+            }
+
+            document.append(parent, NodeData::Text(text));
+        }
+    }
+
+    if stack.len() > 1 {
+        return Err(ParseError {
+            error_text: "Unexpected End Of File (EOF), an element was not closed",
+            at: position,
+        });
+    }
+
+    Ok(document)
+}
@@ -0,0 +1,363 @@
+/**
+ * Watch mode
+ *
+ * Every input is minified once, then the inputs and everything they depend on (imported stylesheets and local url()s)
+ * are watched. When a file changes, only the inputs depending on it are minified again. Changes are debounced, so an
+ * editor writing several files at once causes a single rebuild.
+ *
+ * On Linux the parent directories are watched with inotify (directories rather than the files themselves, as many
+ * editors save by replacing the file), through one instance that stays open between rebuilds, so files saved during a
+ * rebuild are seen once it's done. Elsewhere modification times are polled.
+ */
+use crate::bundle::{self, normalize};
+use crate::inputs::Job;
+use crate::minimize;
+use crate::purge;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// How long to wait for more changes before rebuilding
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+// An input and the files it depends on
+struct Entry<'a> {
+    job: &'a Job,
+    path: PathBuf,
+    dependencies: Vec<PathBuf>,
+}
+
+impl Entry<'_> {
+    fn new(job: &Job) -> Entry<'_> {
+        let dependencies = match minimize::extension(&job.input) {
+            // Purging depends on the content files too
+            Some("css") => bundle::dependencies(&job.input)
+                .into_iter()
+                .chain(purge::files(&job.config).iter().map(|file| normalize(file)))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Entry {
+            job,
+            path: normalize(Path::new(&job.input)),
+            dependencies,
+        }
+    }
+
+    fn affected_by(&self, changed: &[PathBuf]) -> bool {
+        changed
+            .iter()
+            .any(|path| *path == self.path || self.dependencies.contains(path))
+    }
+}
+
+// Minifies a file, printing how long it took or what went wrong
+fn build(job: &Job) {
+    let start = Instant::now();
+
+    match minimize::file(job) {
+        Ok(_) => println!(
+            "Minified {} in {}ms",
+            job.input,
+            start.elapsed().as_millis()
+        ),
+        Err(e) => {
+            eprintln!("Error minimizing {}!", job.input);
+            eprintln!("{}", e);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{normalize, DEBOUNCE};
+    use std::ffi::CString;
+    use std::os::raw::{c_char, c_int, c_short, c_ulong, c_void};
+    use std::path::{Path, PathBuf};
+
+    // <sys/inotify.h>
+    const IN_CLOEXEC: c_int = 0o2000000;
+    const IN_MODIFY: u32 = 0x2;
+    const IN_CLOSE_WRITE: u32 = 0x8;
+    const IN_MOVED_TO: u32 = 0x80;
+    const IN_CREATE: u32 = 0x100;
+    const IN_DELETE: u32 = 0x200;
+
+    // <poll.h>
+    const POLLIN: c_short = 0x1;
+
+    #[repr(C)]
+    struct PollFd {
+        fd: c_int,
+        events: c_short,
+        revents: c_short,
+    }
+
+    extern "C" {
+        fn inotify_init1(flags: c_int) -> c_int;
+        fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int;
+        fn poll(fds: *mut PollFd, nfds: c_ulong, timeout: c_int) -> c_int;
+        fn read(fd: c_int, buf: *mut c_void, count: usize) -> isize;
+        fn close(fd: c_int) -> c_int;
+    }
+
+    // Waits for events, returning false on timeout (-1 waits forever)
+    fn ready(fd: c_int, timeout: c_int) -> bool {
+        let mut poll_fd = PollFd {
+            fd,
+            events: POLLIN,
+            revents: 0,
+        };
+
+        unsafe { poll(&mut poll_fd, 1, timeout) > 0 }
+    }
+
+    // Reads the pending events, as the changed paths
+    fn events(fd: c_int, directories: &[(c_int, PathBuf)], changed: &mut Vec<PathBuf>) {
+        let mut buffer = [0u8; 4096];
+        let length = unsafe { read(fd, buffer.as_mut_ptr() as *mut c_void, buffer.len()) };
+        if length <= 0 {
+            return;
+        }
+
+        // struct inotify_event { int wd; uint32_t mask; uint32_t cookie; uint32_t len; char name[]; }
+        let buffer = &buffer[..length as usize];
+        let mut offset = 0;
+
+        while offset + 16 <= buffer.len() {
+            let field = |index: usize| {
+                let start = offset + index * 4;
+                [
+                    buffer[start],
+                    buffer[start + 1],
+                    buffer[start + 2],
+                    buffer[start + 3],
+                ]
+            };
+
+            let wd = i32::from_ne_bytes(field(0));
+            let len = u32::from_ne_bytes(field(3)) as usize;
+
+            let name = &buffer[offset + 16..(offset + 16 + len).min(buffer.len())];
+            let name = String::from_utf8_lossy(name);
+            let name = name.trim_end_matches('\0');
+
+            if let Some((_, directory)) = directories.iter().find(|(watch, _)| *watch == wd) {
+                let path = normalize(&directory.join(name));
+
+                if !changed.contains(&path) {
+                    changed.push(path);
+                }
+            }
+
+            offset += 16 + len;
+        }
+    }
+
+    /**
+     * An inotify instance, kept open between rebuilds so changes made during one aren't missed
+     */
+    pub struct Watcher {
+        fd: c_int,
+        directories: Vec<(c_int, PathBuf)>,
+    }
+
+    impl Watcher {
+        pub fn new() -> Watcher {
+            let fd = unsafe { inotify_init1(IN_CLOEXEC) };
+            if fd < 0 {
+                eprintln!("Could not start watching files (inotify_init1 failed)");
+                std::process::exit(1);
+            }
+
+            Watcher {
+                fd,
+                directories: Vec::new(),
+            }
+        }
+
+        /**
+         * Starts watching the directories of the files that aren't watched yet
+         */
+        pub fn watch(&mut self, files: &[PathBuf]) {
+            for file in files {
+                let directory = match file.parent() {
+                    Some(directory) if directory != Path::new("") => directory.to_path_buf(),
+                    _ => PathBuf::new(),
+                };
+
+                if self
+                    .directories
+                    .iter()
+                    .any(|(_, watched)| *watched == directory)
+                {
+                    continue;
+                }
+
+                let name = if directory == Path::new("") {
+                    Path::new(".")
+                } else {
+                    directory.as_path()
+                };
+
+                let path = match CString::new(name.to_string_lossy().as_bytes()) {
+                    Ok(path) => path,
+                    Err(_) => continue,
+                };
+
+                let mask = IN_MODIFY | IN_CLOSE_WRITE | IN_MOVED_TO | IN_CREATE | IN_DELETE;
+                let wd = unsafe { inotify_add_watch(self.fd, path.as_ptr(), mask) };
+
+                // Directories that don't exist (yet) can't be watched
+                if wd >= 0 {
+                    self.directories.push((wd, directory));
+                }
+            }
+        }
+
+        /**
+         * Blocks until some of the files change, returning the changed files
+         */
+        pub fn wait(&mut self, files: &[PathBuf]) -> Vec<PathBuf> {
+            self.watch(files);
+
+            let mut changed: Vec<PathBuf> = Vec::new();
+
+            while !changed.iter().any(|path| files.contains(path)) {
+                ready(self.fd, -1);
+                events(self.fd, &self.directories, &mut changed);
+
+                // Keep collecting until things settle down
+                while ready(self.fd, DEBOUNCE.as_millis() as c_int) {
+                    events(self.fd, &self.directories, &mut changed);
+                }
+            }
+
+            changed.retain(|path| files.contains(path));
+            changed
+        }
+    }
+
+    impl Drop for Watcher {
+        fn drop(&mut self) {
+            unsafe {
+                close(self.fd);
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use super::DEBOUNCE;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::thread;
+    use std::time::SystemTime;
+
+    fn modified(file: &PathBuf) -> Option<SystemTime> {
+        fs::metadata(file)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /**
+     * The modification times of the files, kept between rebuilds so changes made during one aren't missed
+     */
+    pub struct Watcher {
+        times: Vec<(PathBuf, Option<SystemTime>)>,
+    }
+
+    impl Watcher {
+        pub fn new() -> Watcher {
+            Watcher { times: Vec::new() }
+        }
+
+        /**
+         * Starts watching the files that aren't watched yet
+         */
+        pub fn watch(&mut self, files: &[PathBuf]) {
+            for file in files {
+                if !self.times.iter().any(|(watched, _)| watched == file) {
+                    self.times.push((file.clone(), modified(file)));
+                }
+            }
+        }
+
+        /**
+         * Blocks until some of the files change, returning the changed files
+         */
+        pub fn wait(&mut self, files: &[PathBuf]) -> Vec<PathBuf> {
+            self.watch(files);
+
+            loop {
+                let mut changed: Vec<PathBuf> = Vec::new();
+
+                for (file, time) in self.times.iter_mut() {
+                    let now = modified(file);
+
+                    if now != *time {
+                        *time = now;
+
+                        if files.contains(file) {
+                            changed.push(file.clone());
+                        }
+                    }
+                }
+
+                if !changed.is_empty() {
+                    // Let any other writes finish
+                    thread::sleep(DEBOUNCE);
+                    return changed;
+                }
+
+                thread::sleep(DEBOUNCE * 2);
+            }
+        }
+    }
+}
+
+// Every input and dependency of the entries
+fn watched(entries: &[Entry]) -> Vec<PathBuf> {
+    let mut watched: Vec<PathBuf> = Vec::new();
+
+    for entry in entries {
+        for path in std::iter::once(&entry.path).chain(&entry.dependencies) {
+            if !watched.contains(path) {
+                watched.push(path.clone());
+            }
+        }
+    }
+
+    watched
+}
+
+/**
+ * Minifies the files, then keeps minifying them as they (or their dependencies) change. Never returns.
+ */
+pub fn run(jobs: &[Job]) -> ! {
+    let mut entries: Vec<Entry> = jobs.iter().map(Entry::new).collect();
+
+    // Watching starts before the first build, so nothing saved during it is missed
+    let mut watcher = platform::Watcher::new();
+    watcher.watch(&watched(&entries));
+
+    for entry in &entries {
+        build(entry.job);
+    }
+
+    println!("Watching for changes...");
+
+    loop {
+        // Dependencies added since the last change are watched from here on
+        let changed = watcher.wait(&watched(&entries));
+
+        for entry in entries.iter_mut() {
+            if entry.affected_by(&changed) {
+                // Imports may have been added or removed
+                *entry = Entry::new(entry.job);
+                build(entry.job);
+            }
+        }
+    }
+}